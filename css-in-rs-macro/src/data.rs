@@ -1,9 +1,14 @@
 use syn::parse::{Parse, ParseStream};
 
+pub mod options;
 pub mod rules;
 pub mod signature;
 
+/// Name of the generated field which contains all classnames, separated by spaces
+pub const ROOT_ALL: &str = "root_all";
+
 pub struct Style {
+    pub options: options::Options,
     pub signature: signature::Signature,
     pub rules: rules::RuleList,
 }
@@ -22,24 +27,73 @@ impl Style {
 
         result
     }
+
+    /// The names defined by at-rules like `@keyframes fade_in`, together with
+    /// whether they are dashed idents
+    pub fn get_local_names(&self) -> Vec<(syn::Ident, bool)> {
+        let mut names = Default::default();
+        self.rules.collect_local_names(&mut names);
+
+        names
+            .into_iter()
+            .map(|(name, (span, dashed))| (syn::Ident::new(&name, span), dashed))
+            .collect()
+    }
 }
 
 impl Parse for Style {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let options = options::Options::from_attributes(&attrs)?;
         let signature = input.parse::<signature::Signature>()?;
 
         let content;
         syn::braced!(content in input);
         let rules = content.parse::<rules::RuleList>()?;
+        if options.strict {
+            rules.check_at_rules()?;
+            rules.check_pseudo_elements()?;
+        }
+        if options.deny_important {
+            rules.check_important()?;
+        }
+        if !options.allow_unknown_values {
+            rules.check_values()?;
+        }
+        rules.variants()?;
 
-        let style = Style { signature, rules };
+        let mut classnames = Default::default();
+        rules.collect_classnames(&mut classnames);
+        if let Some(span) = classnames.get(ROOT_ALL) {
+            return Err(syn::Error::new(
+                *span,
+                format!("`{ROOT_ALL}` is reserved for the field containing all classes"),
+            ));
+        }
+
+        let mut local_names = Default::default();
+        rules.collect_local_names(&mut local_names);
+        for (name, (span, _)) in local_names {
+            if name == ROOT_ALL || classnames.contains_key(&name) {
+                return Err(syn::Error::new(
+                    span,
+                    format!("`{name}` is already the name of a field"),
+                ));
+            }
+        }
+
+        let style = Style {
+            options,
+            signature,
+            rules,
+        };
         Ok(style)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use quote::quote;
+    use quote::{quote, ToTokens};
 
     use crate::data::rules::{
         header::{Header, Part},
@@ -79,6 +133,15 @@ mod tests {
                     Part::ClassName(s) => {
                         write!(result, "classname'{}'", s)
                     }
+                    Part::Name(expr) => {
+                        write!(result, "name'{}'", expr)
+                    }
+                    Part::Reference { classes, field } => {
+                        write!(result, "reference'{}::{}'", classes, field)
+                    }
+                    Part::LocalName(s) => {
+                        write!(result, "local'{}'", s)
+                    }
                 }
                 .unwrap();
             }
@@ -100,7 +163,7 @@ mod tests {
         {
             let rule = rules[2];
             let entries = match &rule.body {
-                crate::data::rules::RuleBody::Normal { entries } => entries,
+                crate::data::rules::RuleBody::Normal { entries, .. } => entries,
                 _ => unreachable!(),
             };
             assert_eq!(entries.len(), 2);
@@ -114,4 +177,775 @@ mod tests {
             assert_eq!(entry.property, "12%");
         }
     }
+
+    #[test]
+    fn templates() {
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                @template badge(color) {
+                    color: color,
+                    padding: "2px",
+                },
+                badge_red = badge("red"),
+                "@media print" {
+                    badge_blue = badge("blue"),
+                },
+            }
+        };
+
+        let style = syn::parse2::<Style>(input).unwrap();
+        let classnames: Vec<String> = style
+            .get_classnames()
+            .iter()
+            .map(|ident| ident.to_string())
+            .collect();
+        assert_eq!(classnames, ["badge_blue", "badge_red"]);
+
+        let rules: Vec<&Rule> = style.rules.rules.iter().collect();
+        assert_eq!(rules.len(), 2);
+
+        let entries = match &rules[0].body {
+            crate::data::rules::RuleBody::Normal { entries, .. } => entries,
+            _ => unreachable!(),
+        };
+        let values: Vec<String> = entries
+            .iter()
+            .map(|entry| entry.value.to_token_stream().to_string())
+            .collect();
+        assert_eq!(values, ["\"red\"", "\"2px\""]);
+
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                @template badge(color) {
+                    color: color,
+                },
+                badge_red = badge("red", "blue"),
+            }
+        };
+        assert!(syn::parse2::<Style>(input).is_err());
+    }
+
+    #[test]
+    fn options() {
+        let input = quote! {
+            #[atomic]
+            (_theme: MyTheme) -> MyClasses {
+                my_class {
+                    color: "red",
+                },
+            }
+        };
+        let style = syn::parse2::<Style>(input).unwrap();
+        assert!(style.options.atomic);
+        assert!(!style.options.compact);
+
+        let input = quote! {
+            #[compact]
+            #[atomic]
+            (_theme: MyTheme) -> MyClasses {}
+        };
+        let style = syn::parse2::<Style>(input).unwrap();
+        assert!(style.options.atomic && style.options.compact);
+
+        let input = quote! {
+            #[preview]
+            #[dedup]
+            (_theme: MyTheme) -> MyClasses {}
+        };
+        let style = syn::parse2::<Style>(input).unwrap();
+        assert!(style.options.preview && style.options.dedup);
+
+        let input = quote! {
+            #[overrides("Button")]
+            (_theme: MyTheme) -> MyClasses {}
+        };
+        let style = syn::parse2::<Style>(input).unwrap();
+        assert_eq!(style.options.overrides.unwrap().value(), "Button");
+
+        let input = quote! {
+            #[priority(-10)]
+            (_theme: MyTheme) -> MyClasses {}
+        };
+        let style = syn::parse2::<Style>(input).unwrap();
+        assert_eq!(style.options.priority, Some(-10));
+
+        let input = quote! {
+            #[atomic(yes)]
+            (_theme: MyTheme) -> MyClasses {}
+        };
+        assert!(syn::parse2::<Style>(input).is_err());
+
+        let input = quote! {
+            #[unknown]
+            (_theme: MyTheme) -> MyClasses {}
+        };
+        assert!(syn::parse2::<Style>(input).is_err());
+    }
+
+    #[test]
+    fn strict() {
+        let rules = quote! {
+            "@media print" {
+                "@document url(https://example.com)" {
+                    "body" {
+                        color: "black",
+                    },
+                },
+            },
+        };
+
+        let input = quote!((_theme: MyTheme) -> MyClasses { #rules });
+        assert!(syn::parse2::<Style>(input).is_ok());
+
+        let input = quote!(#[strict] (_theme: MyTheme) -> MyClasses { #rules });
+        let err = syn::parse2::<Style>(input).err().unwrap();
+        assert!(err.to_string().starts_with("Unknown at-rule `@document`"));
+
+        let input = quote! {
+            #[strict]
+            (_theme: MyTheme) -> MyClasses {
+                @media(QUERY) {
+                    "@supports (display: grid)" {},
+                },
+            }
+        };
+        assert!(syn::parse2::<Style>(input).is_ok());
+
+        let rules = quote! {
+            "@media print" {
+                "a.link::after, a.link:is(:hover, :focus)::before" {
+                    color: "black",
+                },
+            },
+        };
+        let input = quote!((_theme: MyTheme) -> MyClasses { #rules });
+        assert!(syn::parse2::<Style>(input).is_ok());
+
+        let input = quote!(#[strict] (_theme: MyTheme) -> MyClasses { #rules });
+        let err = syn::parse2::<Style>(input).err().unwrap();
+        assert!(err
+            .to_string()
+            .starts_with("`::before` and `::after` are not rendered"));
+
+        let input = quote! {
+            #[strict]
+            (_theme: MyTheme) -> MyClasses {
+                "a.link::after" {
+                    content: "\"\"",
+                },
+            }
+        };
+        assert!(syn::parse2::<Style>(input).is_ok());
+    }
+
+    #[test]
+    fn deny_important() {
+        let values = [
+            quote!(important("red")),
+            quote!(css_in_rs::values::important(theme.color)),
+            quote!("red !important"),
+            quote!(format!("{} !important", theme.color)),
+        ];
+        for value in values {
+            let rules = quote! {
+                button {
+                    hover {
+                        color: #value,
+                    },
+                },
+            };
+
+            let input = quote!((_theme: MyTheme) -> MyClasses { #rules });
+            assert!(syn::parse2::<Style>(input).is_ok());
+
+            let input = quote!(#[deny_important] (_theme: MyTheme) -> MyClasses { #rules });
+            let err = syn::parse2::<Style>(input).err().unwrap();
+            assert!(err.to_string().contains("`!important` is denied"));
+        }
+    }
+
+    #[test]
+    fn conditional() {
+        let input = quote! {
+            (theme: MyTheme) -> MyClasses {
+                @if theme.compact {
+                    item {
+                        padding: "2px",
+                    },
+                } @else if theme.dense {
+                    "@media print" {
+                        icon {
+                            margin: 0,
+                        },
+                    },
+                } @else {
+                    label = badge("red"),
+                },
+                @template badge(color) {
+                    color: color,
+                },
+            }
+        };
+
+        let style = syn::parse2::<Style>(input).unwrap();
+        let classnames = style
+            .get_classnames()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(classnames, ["icon", "item", "label"]);
+
+        let crate::data::rules::RuleBody::Conditional(conditional) = &style.rules.rules[0].body
+        else {
+            unreachable!()
+        };
+        let sources = conditional
+            .branches
+            .iter()
+            .map(|branch| branch.source.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sources,
+            ["@if theme . compact", "@else if theme . dense", "@else"]
+        );
+        assert!(conditional.branches[2].condition.is_none());
+    }
+
+    #[test]
+    fn repetition() {
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                @for i in 0..2 {
+                    p_{i} {
+                        padding: format!("{}px", i * 4),
+                    },
+                    "@media print" {
+                        @for j in 1..=2 {
+                            "div.m{i}_{j}" {
+                                margin: j,
+                            },
+                        },
+                    },
+                },
+            }
+        };
+
+        let style = syn::parse2::<Style>(input).unwrap();
+        let classnames = style
+            .get_classnames()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(classnames, ["m0_1", "m0_2", "m1_1", "m1_2", "p_0", "p_1"]);
+
+        let crate::data::rules::RuleBody::Normal { entries, .. } = &style.rules.rules[2].body
+        else {
+            unreachable!()
+        };
+        assert_eq!(
+            entries[0].value.to_token_stream().to_string(),
+            "format ! (\"{}px\" , 1 * 4)"
+        );
+
+        let input = quote!((_theme: MyTheme) -> MyClasses { @for i in 1..1 { a_{i} {} } });
+        let err = syn::parse2::<Style>(input).err().unwrap();
+        assert_eq!(err.to_string(), "The range of `@for` is empty");
+    }
+
+    #[test]
+    fn variants() {
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                button {
+                    variants {
+                        size: { small { padding: "2px" }, large {} },
+                    },
+                },
+                chip {
+                    variants {
+                        size: { large {}, small { hover { color: "red" } } },
+                    },
+                },
+            }
+        };
+        let style = syn::parse2::<Style>(input).unwrap();
+        let classnames = style
+            .get_classnames()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            classnames,
+            [
+                "button",
+                "button_size_large",
+                "button_size_small",
+                "chip",
+                "chip_size_large",
+                "chip_size_small"
+            ]
+        );
+        let variants = style.rules.variants().unwrap();
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants[1].1.dimensions[0].enum_ident(), "Size");
+
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                button {
+                    variants {
+                        size: { small {}, #[default] large {} },
+                        tone: { neutral {}, danger {} },
+                        (size: small, tone: danger) { font_weight: "bold" },
+                    },
+                },
+            }
+        };
+        let style = syn::parse2::<Style>(input).unwrap();
+        let classnames = style.get_classnames();
+        assert!(classnames
+            .iter()
+            .any(|classname| classname == "button_size_small_tone_danger"));
+        let variants = style.rules.variants().unwrap();
+        let dimensions = &variants[0].1.dimensions;
+        assert_eq!(dimensions[0].default.as_ref().unwrap(), "large");
+        assert!(dimensions[1].default.is_none());
+        assert_eq!(variants[0].1.compounds[0].selection.len(), 2);
+
+        let errors = [
+            (
+                quote!("div.a" { variants { size: { small {} } } }),
+                "Variants can only be declared in rules selecting a single class",
+            ),
+            (
+                quote!("@media print" { a { variants { size: { small {} } } } }),
+                "Variants can only be declared in rules at the top level",
+            ),
+            (
+                quote!(a { variants { size: { small {}, small {} } } }),
+                "Variant `small` of `size` is declared twice",
+            ),
+            (
+                quote!(a { variants { size: { small {} } } }, b { variants { size: { large {} } } }),
+                "`size` is declared with different variants by another rule, but both share \
+                 the enum `Size`",
+            ),
+            (
+                quote!(a { variants { size: { #[default] small {}, #[default] large {} } } }),
+                "`size` has more than one default variant",
+            ),
+            (
+                quote!(a { variants { size: { small {} }, (size: small, tone: danger) {} } }),
+                "Unknown dimension `tone`",
+            ),
+            (
+                quote!(a { variants { size: { small {} }, tone: { danger {} }, (size: large, tone: danger) {} } }),
+                "`size` has no variant `large`",
+            ),
+            (
+                quote!(a { variants { size: { small {} }, (size: small) {} } }),
+                "Compound variants select at least two dimensions",
+            ),
+        ];
+        for (rules, message) in errors {
+            let input = quote!((_theme: MyTheme) -> MyClasses { #rules });
+            let err = syn::parse2::<Style>(input).err().unwrap();
+            assert_eq!(err.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn unknown_values() {
+        let rules = quote! {
+            button {
+                display: "inline flex",
+                hover {
+                    display: "fex",
+                },
+            },
+        };
+
+        let input = quote!((_theme: MyTheme) -> MyClasses { #rules });
+        let err = syn::parse2::<Style>(input).err().unwrap();
+        assert!(err
+            .to_string()
+            .contains("Unknown value `fex` for `display`"));
+
+        let input = quote!(#[allow_unknown_values] (_theme: MyTheme) -> MyClasses { #rules });
+        assert!(syn::parse2::<Style>(input).is_ok());
+    }
+
+    #[test]
+    fn repeated_declarations() {
+        let input = quote! {
+            #[atomic]
+            (_theme: MyTheme) -> MyClasses {
+                grid {
+                    display: ["flex", "grid"],
+                    color: "red",
+                },
+            }
+        };
+        let style = syn::parse2::<Style>(input).unwrap();
+        let entries = match &style.rules.rules[0].body {
+            crate::data::rules::RuleBody::Normal { entries, .. } => entries,
+            _ => unreachable!(),
+        };
+        assert_eq!(entries[0].values().count(), 2);
+        // Repeated declarations must stay in order, so they are not atomic
+        assert_eq!(entries[0].literal_value(), None);
+        assert_eq!(entries[1].literal_value().as_deref(), Some("red"));
+
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                grid {
+                    display: [],
+                },
+            }
+        };
+        assert!(syn::parse2::<Style>(input).is_err());
+
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                grid {
+                    display: "flex",
+                    hover {
+                        color: "red",
+                        color: "red",
+                    },
+                },
+            }
+        };
+        assert!(syn::parse2::<Style>(input).is_ok());
+
+        let invalid = [
+            quote! {
+                grid {
+                    display: "flex",
+                    gap: "4px",
+                    display: "grid",
+                },
+            },
+            quote! {
+                grid {
+                    hover {
+                        color: "red",
+                        color: ["blue", "red"],
+                    },
+                },
+            },
+            quote! {
+                @template stack(gap) {
+                    gap: gap,
+                    gap: "4px",
+                },
+            },
+        ];
+        for rules in invalid {
+            let input = quote! {
+                (_theme: MyTheme) -> MyClasses {
+                    #rules
+                }
+            };
+            let err = syn::parse2::<Style>(input).err().unwrap();
+            assert!(err
+                .to_string()
+                .contains("is already declared with a different value"));
+        }
+    }
+
+    #[test]
+    fn references() {
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                ".toolbar > {icons::IconClasses::icon}" {
+                    width: "24px",
+                },
+            }
+        };
+        let style = syn::parse2::<Style>(input).unwrap();
+        let header = &style.rules.rules[0].header;
+        match &header.parts[3] {
+            Part::Reference { classes, field } => {
+                assert_eq!(classes.to_string(), "icons :: IconClasses");
+                assert_eq!(field, "icon");
+            }
+            part => panic!("Unexpected part {part:?}"),
+        }
+
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                ".toolbar > {icon}" {
+                    width: "24px",
+                },
+            }
+        };
+        assert!(syn::parse2::<Style>(input).is_err());
+    }
+
+    #[test]
+    fn media_directive() {
+        let input = quote! {
+            (theme: MyTheme) -> MyClasses {
+                @media(theme.breakpoints.up(Md)) {
+                    sidebar {
+                        display: "block",
+                    },
+                },
+            }
+        };
+        let style = syn::parse2::<Style>(input).unwrap();
+        let header = &style.rules.rules[0].header;
+        assert!(header.at_rule);
+        assert_eq!(header.source, "@media(theme . breakpoints . up (Md))");
+        assert_eq!(style.get_classnames(), ["sidebar"]);
+
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                @container(SIDEBAR) {},
+            }
+        };
+        let style = syn::parse2::<Style>(input).unwrap();
+        assert_eq!(style.rules.rules[0].header.source, "@container(SIDEBAR)");
+
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                @supports(display_grid) {},
+            }
+        };
+        assert!(syn::parse2::<Style>(input).is_err());
+    }
+
+    #[test]
+    fn motion_shorthands() {
+        let input = quote! {
+            #[strict]
+            (_theme: MyTheme) -> MyClasses {
+                "@motion-reduce" {
+                    spinner {
+                        animation: "none",
+                    },
+                },
+            }
+        };
+        let style = syn::parse2::<Style>(input).unwrap();
+        let header = &style.rules.rules[0].header;
+        assert_eq!(header.source, "@motion-reduce");
+        assert_eq!(header.at_rule_keyword(), Some("@media"));
+        assert!(header.check_at_rule().is_ok());
+    }
+
+    #[test]
+    fn print_shorthand() {
+        let input = quote! {
+            #[strict]
+            (_theme: MyTheme) -> MyClasses {
+                @print {
+                    toolbar {
+                        display: "none",
+                    },
+                },
+                "@print" {},
+            }
+        };
+        let style = syn::parse2::<Style>(input).unwrap();
+        for rule in &style.rules.rules {
+            assert_eq!(rule.header.source, "@print");
+            assert_eq!(rule.header.at_rule_keyword(), Some("@media"));
+        }
+        assert_eq!(style.get_classnames(), ["toolbar"]);
+
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                @print(screen) {},
+            }
+        };
+        assert!(syn::parse2::<Style>(input).is_err());
+    }
+
+    #[test]
+    fn declaration_at_rules() {
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                "@font-face" {
+                    font_family: "Inter",
+                },
+                @property(ANGLE) {
+                    syntax: "'<angle>'",
+                },
+                spinner {
+                    --(ANGLE): "45deg",
+                },
+            }
+        };
+        let style = syn::parse2::<Style>(input).unwrap();
+        let rules = &style.rules.rules;
+        for rule in rules {
+            assert!(matches!(
+                rule.body,
+                crate::data::rules::RuleBody::Normal { .. }
+            ));
+        }
+        let entry = match &rules[2].body {
+            crate::data::rules::RuleBody::Normal { entries, .. } => &entries[0],
+            _ => unreachable!(),
+        };
+        assert_eq!(entry.property, "--(ANGLE)");
+        assert!(entry.custom_property.is_some());
+        assert_eq!(entry.literal_value(), None);
+
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                "@font-face" {
+                    disabled {
+                        color: "gray",
+                    },
+                },
+            }
+        };
+        assert!(syn::parse2::<Style>(input).is_err());
+    }
+
+    #[test]
+    fn local_names() {
+        let input = quote! {
+            #[strict]
+            (_theme: MyTheme) -> MyClasses {
+                @keyframes pulse {
+                    "50%" {
+                        opacity: "0.5",
+                    },
+                },
+                "@media print" {
+                    @font-palette-values brand {
+                        font_family: "Inter",
+                    },
+                },
+                list {
+                    animation: pulse,
+                },
+            }
+        };
+        let style = syn::parse2::<Style>(input).unwrap();
+        let header = &style.rules.rules[0].header;
+        assert_eq!(header.source, "@keyframes pulse");
+        assert_eq!(header.at_rule_keyword(), Some("@keyframes"));
+        assert_eq!(style.get_classnames(), ["list"]);
+
+        let names = style.get_local_names();
+        let names: Vec<_> = names
+            .iter()
+            .map(|(ident, dashed)| (ident.to_string(), *dashed))
+            .collect();
+        assert_eq!(
+            names,
+            [("brand".to_owned(), true), ("pulse".to_owned(), false)]
+        );
+
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                @counter-style list {},
+                list {},
+            }
+        };
+        assert!(syn::parse2::<Style>(input).is_err());
+    }
+
+    #[test]
+    fn states() {
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                checkbox {
+                    margin: "4px",
+                    checked {
+                        color: "green",
+                    },
+                    disabled {
+                        opacity: "0.5",
+                    },
+                },
+            }
+        };
+        let style = syn::parse2::<Style>(input).unwrap();
+        let (entries, states) = match &style.rules.rules[0].body {
+            crate::data::rules::RuleBody::Normal {
+                entries, states, ..
+            } => (entries, states),
+            _ => unreachable!(),
+        };
+        assert_eq!(entries.len(), 1);
+        let pseudo_classes: Vec<&str> = states.iter().map(|s| s.pseudo_class.as_str()).collect();
+        assert_eq!(pseudo_classes, ["checked", "disabled"]);
+
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                checkbox {
+                    hovered {
+                        color: "green",
+                    },
+                },
+            }
+        };
+        assert!(syn::parse2::<Style>(input).is_err());
+    }
+
+    #[test]
+    fn pseudo_class_shorthand() {
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                link:focus_visible {
+                    outline: "2px solid",
+                },
+                "@media print" {
+                    link:hover:visited {
+                        color: "black",
+                    },
+                },
+            }
+        };
+        let style = syn::parse2::<Style>(input).unwrap();
+        let rules = &style.rules.rules;
+        assert_eq!(rules[0].header.source, "link:focus-visible");
+        assert_eq!(rules[0].header.parts.len(), 3);
+        assert_eq!(style.get_classnames().len(), 1);
+
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                link:hovr {
+                    color: "red",
+                },
+            }
+        };
+        let err = syn::parse2::<Style>(input).err().unwrap();
+        assert!(err.to_string().starts_with("Unknown pseudo-class `hovr`"));
+    }
+
+    #[test]
+    fn selector_lists() {
+        let input = quote! {
+            (_theme: MyTheme) -> MyClasses {
+                "button.primary, a.primary:is(.x, .y)" {
+                    color: "white",
+                },
+            }
+        };
+        let style = syn::parse2::<Style>(input).unwrap();
+        let classnames: Vec<String> = style
+            .get_classnames()
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(classnames, ["primary", "x", "y"]);
+
+        for selector in ["button, , input", "button,", ", input"] {
+            let input = quote! {
+                (_theme: MyTheme) -> MyClasses {
+                    #selector {
+                        color: "white",
+                    },
+                }
+            };
+            let err = syn::parse2::<Style>(input).err().unwrap();
+            assert!(err.to_string().starts_with("Empty selector"));
+        }
+    }
 }
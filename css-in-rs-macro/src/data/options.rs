@@ -0,0 +1,81 @@
+/// Options for a single `make_styles!` invocation. They are given as outer
+/// attributes in front of the signature, i.e. `#[atomic] (theme: T) -> C { ... }`
+#[derive(Default)]
+pub struct Options {
+    /// Split literal declarations of simple class rules into atomic classes
+    pub atomic: bool,
+    /// Emit a static template instead of formatting code, see `#[compact]`
+    pub compact: bool,
+    /// Reject at-rules which are not known to the macro, see `#[strict]`
+    pub strict: bool,
+    /// Reject declarations marked as `!important`, see `#[deny_important]`
+    pub deny_important: bool,
+    /// Share rules with identical declarations between structs, see `#[dedup]`
+    pub dedup: bool,
+    /// Document the expected markup on the generated struct, see `#[preview]`
+    pub preview: bool,
+    /// The key under which the theme can override the classes, see `#[overrides(...)]`
+    pub overrides: Option<syn::LitStr>,
+    /// The order in which the styles are emitted, see `#[priority(...)]`
+    pub priority: Option<i32>,
+    /// Do not warn about rules overriding each other, see `#[allow_conflicts]`
+    pub allow_conflicts: bool,
+    /// Accept unknown keywords of enumerated properties, see `#[allow_unknown_values]`
+    pub allow_unknown_values: bool,
+}
+
+const KNOWN_OPTIONS: &str =
+    "`allow_conflicts`, `allow_unknown_values`, `atomic`, `compact`, `dedup`, `deny_important`, `overrides(...)`, `preview`, `priority(...)`, `strict`";
+
+impl Options {
+    pub fn from_attributes(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut options = Options::default();
+
+        for attr in attrs {
+            let path = attr.path();
+            if path.is_ident("atomic") {
+                attr.meta.require_path_only()?;
+                options.atomic = true;
+            } else if path.is_ident("compact") {
+                attr.meta.require_path_only()?;
+                options.compact = true;
+            } else if path.is_ident("dedup") {
+                attr.meta.require_path_only()?;
+                options.dedup = true;
+            } else if path.is_ident("strict") {
+                attr.meta.require_path_only()?;
+                options.strict = true;
+            } else if path.is_ident("deny_important") {
+                attr.meta.require_path_only()?;
+                options.deny_important = true;
+            } else if path.is_ident("preview") {
+                attr.meta.require_path_only()?;
+                options.preview = true;
+            } else if path.is_ident("allow_conflicts") {
+                attr.meta.require_path_only()?;
+                options.allow_conflicts = true;
+            } else if path.is_ident("allow_unknown_values") {
+                attr.meta.require_path_only()?;
+                options.allow_unknown_values = true;
+            } else if path.is_ident("overrides") {
+                options.overrides = Some(attr.parse_args::<syn::LitStr>()?);
+            } else if path.is_ident("priority") {
+                options.priority = Some(attr.parse_args_with(parse_priority)?);
+            } else {
+                return Err(syn::Error::new_spanned(
+                    path,
+                    format!("Unknown option. Supported options: {KNOWN_OPTIONS}"),
+                ));
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// Parses a possibly negative integer like `-10`
+fn parse_priority(input: syn::parse::ParseStream) -> syn::Result<i32> {
+    let negative = input.parse::<Option<syn::Token![-]>>()?.is_some();
+    let value = input.parse::<syn::LitInt>()?.base10_parse::<i32>()?;
+    Ok(if negative { -value } else { value })
+}
@@ -9,8 +9,16 @@ use syn::{
 
 use crate::output::{Output, ToOutput};
 
+mod calc;
+pub mod conditional;
 pub mod entry;
+mod grid_areas;
 pub mod header;
+mod keywords;
+pub mod repetition;
+pub mod state;
+pub mod template;
+pub mod variants;
 
 pub enum RuleBody {
     AtRule {
@@ -18,7 +26,15 @@ pub enum RuleBody {
     },
     Normal {
         entries: Punctuated<entry::Entry, syn::token::Comma>,
+        states: Vec<state::StateBlock>,
+        /// Only declared by rules at the top level, see [variants]
+        variants: variants::Variants,
     },
+    /// Only used during parsing. Will be replaced by the expanded entries
+    /// once all templates are known
+    Template(template::Instantiation),
+    /// `@if ... { ... } @else { ... }`, evaluated while generating
+    Conditional(conditional::Conditional),
 }
 
 impl RuleBody {
@@ -29,8 +45,70 @@ impl RuleBody {
                     child.collect_classnames(result);
                 }
             }
+            RuleBody::Normal { variants, .. } => {
+                for rule in variants.rules() {
+                    rule.collect_classnames(result);
+                }
+            }
+            RuleBody::Template(_) => unreachable!("Templates are expanded while parsing"),
+            RuleBody::Conditional(conditional) => {
+                for rule in conditional.rules() {
+                    rule.collect_classnames(result);
+                }
+            }
+        }
+    }
+
+    fn collect_local_names(&self, result: &mut BTreeMap<String, (Span, bool)>) {
+        match self {
+            RuleBody::AtRule { children } => {
+                for child in children {
+                    child.collect_local_names(result);
+                }
+            }
+            RuleBody::Conditional(conditional) => {
+                for rule in conditional.rules() {
+                    rule.collect_local_names(result);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn expand_templates(&mut self, templates: &[template::Template]) -> syn::Result<()> {
+        match self {
+            RuleBody::AtRule { children } => {
+                for child in children {
+                    child.body.expand_templates(templates)?;
+                }
+            }
             RuleBody::Normal { .. } => {}
+            RuleBody::Conditional(conditional) => {
+                for branch in &mut conditional.branches {
+                    for rule in branch.rules.iter_mut() {
+                        rule.body.expand_templates(templates)?;
+                    }
+                }
+            }
+            RuleBody::Template(instantiation) => {
+                let name = &instantiation.template;
+                let Some(template) = templates.iter().find(|t| t.name == *name) else {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        format!("Unknown template `{name}`"),
+                    ));
+                };
+
+                let entries = template.instantiate(instantiation)?;
+                *self = RuleBody::Normal {
+                    entries,
+                    states: Vec::new(),
+                    variants: variants::Variants::default(),
+                };
+            }
         }
+
+        Ok(())
     }
 }
 
@@ -44,6 +122,109 @@ impl Rule {
         self.header.collect_classnames(result);
         self.body.collect_classnames(result);
     }
+
+    fn collect_local_names(&self, result: &mut BTreeMap<String, (Span, bool)>) {
+        self.header.collect_local_names(result);
+        self.body.collect_local_names(result);
+    }
+
+    fn check_at_rules(&self) -> syn::Result<()> {
+        self.header.check_at_rule()?;
+        match &self.body {
+            RuleBody::AtRule { children } => children.iter().try_for_each(Rule::check_at_rules),
+            RuleBody::Conditional(conditional) => {
+                conditional.rules().try_for_each(Rule::check_at_rules)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Strict mode: Fails if this rule creates a `::before` or `::after`
+    /// pseudo-element without declaring its `content`
+    fn check_pseudo_elements(&self) -> syn::Result<()> {
+        match &self.body {
+            RuleBody::AtRule { children } => {
+                children.iter().try_for_each(Rule::check_pseudo_elements)
+            }
+            RuleBody::Conditional(conditional) => conditional
+                .rules()
+                .try_for_each(Rule::check_pseudo_elements),
+            RuleBody::Normal { entries, .. } => {
+                let has_content = entries.iter().any(|entry| entry.property == "content");
+                if !has_content && self.header.has_generated_content() {
+                    return Err(syn::Error::new(
+                        self.header.span,
+                        "`::before` and `::after` are not rendered without `content`. \
+                         Declare it, i.e. using `css_in_rs::values::content`",
+                    ));
+                }
+                Ok(())
+            }
+            RuleBody::Template(_) => unreachable!("Templates are expanded while parsing"),
+        }
+    }
+
+    fn check_values(&self) -> syn::Result<()> {
+        let entries = match &self.body {
+            RuleBody::AtRule { children } => {
+                return children.iter().try_for_each(Rule::check_values);
+            }
+            RuleBody::Conditional(conditional) => {
+                return conditional.rules().try_for_each(Rule::check_values);
+            }
+            RuleBody::Normal {
+                entries,
+                states,
+                variants,
+            } => {
+                for rule in variants.rules() {
+                    rule.check_values()?;
+                }
+                entries
+                    .iter()
+                    .chain(states.iter().flat_map(|state| &state.entries))
+            }
+            RuleBody::Template(_) => unreachable!("Templates are expanded while parsing"),
+        };
+
+        entries
+            .into_iter()
+            .try_for_each(entry::Entry::check_values)
+    }
+
+    fn check_important(&self) -> syn::Result<()> {
+        let entries = match &self.body {
+            RuleBody::AtRule { children } => {
+                return children.iter().try_for_each(Rule::check_important);
+            }
+            RuleBody::Conditional(conditional) => {
+                return conditional.rules().try_for_each(Rule::check_important);
+            }
+            RuleBody::Normal {
+                entries,
+                states,
+                variants,
+            } => {
+                for rule in variants.rules() {
+                    rule.check_important()?;
+                }
+                entries
+                    .iter()
+                    .chain(states.iter().flat_map(|state| &state.entries))
+            }
+            RuleBody::Template(_) => unreachable!("Templates are expanded while parsing"),
+        };
+
+        for entry in entries {
+            if let Some(span) = entry.find_important() {
+                return Err(syn::Error::new(
+                    span,
+                    "`!important` is denied by `#[deny_important]`",
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 pub struct RuleList {
@@ -56,24 +237,122 @@ impl RuleList {
             rule.collect_classnames(result);
         }
     }
+
+    /// Collects the names defined by at-rules like `@keyframes fade_in`,
+    /// see [header::Header::collect_local_names]
+    pub fn collect_local_names(&self, result: &mut BTreeMap<String, (Span, bool)>) {
+        for rule in &self.rules {
+            rule.collect_local_names(result);
+        }
+    }
+
+    /// Strict mode: Fails on the first at-rule which is not known
+    pub fn check_at_rules(&self) -> syn::Result<()> {
+        self.rules.iter().try_for_each(Rule::check_at_rules)
+    }
+
+    /// Fails on the first `::before` or `::after` rule without `content`, see `#[strict]`
+    pub fn check_pseudo_elements(&self) -> syn::Result<()> {
+        self.rules.iter().try_for_each(Rule::check_pseudo_elements)
+    }
+
+    /// Fails on the first unknown keyword of a property with enumerated
+    /// values, unless `#[allow_unknown_values]` is given
+    pub fn check_values(&self) -> syn::Result<()> {
+        self.rules.iter().try_for_each(Rule::check_values)
+    }
+
+    /// The classes of rules declaring variants, together with the dimensions.
+    /// Fails if a class declares variants twice, or if dimensions of the same
+    /// name have different variants, since they share the enum.
+    pub fn variants(&self) -> syn::Result<Vec<(syn::Ident, &variants::Variants)>> {
+        let mut result = Vec::<(syn::Ident, &variants::Variants)>::new();
+        for rule in &self.rules {
+            let (Some(classname), RuleBody::Normal { variants, .. }) =
+                (rule.header.single_classname(), &rule.body)
+            else {
+                continue;
+            };
+            if variants.is_empty() {
+                continue;
+            }
+
+            if result.iter().any(|(other, _)| other == classname) {
+                return Err(syn::Error::new(
+                    rule.header.span,
+                    format!("The variants of `{classname}` are already declared"),
+                ));
+            }
+            for dimension in &variants.dimensions {
+                let declared = result.iter().flat_map(|(_, other)| &other.dimensions);
+                let conflicting = declared
+                    .filter(|other| other.name == dimension.name)
+                    .any(|other| !other.same_variants(dimension));
+                if conflicting {
+                    return Err(syn::Error::new(
+                        dimension.name.span(),
+                        format!(
+                            "`{}` is declared with different variants by another rule, but \
+                             both share the enum `{}`",
+                            dimension.name,
+                            dimension.enum_ident()
+                        ),
+                    ));
+                }
+            }
+
+            let ident = syn::Ident::new(classname, rule.header.span);
+            result.push((ident, variants));
+        }
+        Ok(result)
+    }
+
+    /// Fails on the first declaration marked as `!important`, see `#[deny_important]`
+    pub fn check_important(&self) -> syn::Result<()> {
+        self.rules.iter().try_for_each(Rule::check_important)
+    }
 }
 
 impl Parse for Rule {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        if conditional::Conditional::peek(input) {
+            let span = input.span();
+            let conditional = input.parse::<conditional::Conditional>()?;
+            let header = header::Header {
+                parts: Vec::new(),
+                span,
+                at_rule: false,
+                source: "@if".to_owned(),
+            };
+            let body = RuleBody::Conditional(conditional);
+            return Ok(Rule { header, body });
+        }
+
         let header = input.parse::<header::Header>()?;
 
+        if input.peek(Token![=]) {
+            let eq = input.parse::<Token![=]>()?;
+            if header.at_rule {
+                return Err(syn::Error::new(
+                    eq.span,
+                    "At-rules cannot be instantiated from a template",
+                ));
+            }
+
+            let instantiation = input.parse::<template::Instantiation>()?;
+            let body = RuleBody::Template(instantiation);
+            return Ok(Rule { header, body });
+        }
+
         let content;
         syn::braced!(content in input);
 
-        let body = match header.at_rule {
+        let body = match header.at_rule && !header.has_declarations() {
             true => {
-                let children = content.parse_terminated(Rule::parse, Token![,])?;
+                let children = parse_rules(&content)?;
                 RuleBody::AtRule { children }
             }
-            false => {
-                let entries = content.parse_terminated(entry::Entry::parse, Token![,])?;
-                RuleBody::Normal { entries }
-            }
+            false => parse_declarations(&content, &header)?,
         };
 
         let rule = Rule { header, body };
@@ -82,9 +361,106 @@ impl Parse for Rule {
     }
 }
 
+/// Parses the declarations, state blocks and variants of a rule with the
+/// given header
+fn parse_declarations(content: ParseStream, header: &header::Header) -> syn::Result<RuleBody> {
+    let mut entries = Punctuated::new();
+    let mut states = Vec::new();
+    let mut declared = variants::Variants::default();
+    while !content.is_empty() {
+        if variants::peek(content) {
+            let span = content.span();
+            let classname = match header.single_classname() {
+                Some(classname) if declared.is_empty() => classname,
+                Some(_) => return Err(syn::Error::new(span, "Variants are declared twice")),
+                None => {
+                    return Err(syn::Error::new(
+                        span,
+                        "Variants can only be declared in rules selecting a single class",
+                    ))
+                }
+            };
+            declared = variants::parse(content, classname)?;
+        } else if state::StateBlock::peek(content) {
+            states.push(content.parse::<state::StateBlock>()?);
+        } else {
+            entries.push(content.parse::<entry::Entry>()?);
+        }
+
+        if content.is_empty() {
+            break;
+        }
+        content.parse::<Token![,]>()?;
+    }
+    entry::check_duplicates(&entries)?;
+    if let (Some(state), true) = (states.first(), header.at_rule) {
+        return Err(syn::Error::new(
+            state.span,
+            "State blocks cannot be used in at-rules",
+        ));
+    }
+    Ok(RuleBody::Normal {
+        entries,
+        states,
+        variants: declared,
+    })
+}
+
+/// Fails if the rule declares variants, which are only supported at the top level
+fn check_top_level(rule: &Rule) -> syn::Result<()> {
+    match &rule.body {
+        RuleBody::Normal { variants, .. } if !variants.is_empty() => Err(syn::Error::new(
+            rule.header.span,
+            "Variants can only be declared in rules at the top level",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Parses a comma-separated list of rules, unrolling `@for` loops
+fn parse_rules(input: ParseStream) -> syn::Result<Punctuated<Rule, syn::token::Comma>> {
+    let mut rules = Punctuated::new();
+    while !input.is_empty() {
+        if repetition::Repetition::peek(input) {
+            rules.extend(input.parse::<repetition::Repetition>()?.expand()?);
+        } else {
+            let rule = input.parse::<Rule>()?;
+            check_top_level(&rule)?;
+            rules.push(rule);
+        }
+
+        if input.is_empty() {
+            break;
+        }
+        input.parse::<Token![,]>()?;
+    }
+    Ok(rules)
+}
+
 impl Parse for RuleList {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let rules = input.parse_terminated(Rule::parse, Token![,])?;
+        let mut rules = Punctuated::new();
+        let mut templates = Vec::new();
+
+        while !input.is_empty() {
+            if template::Template::peek(input) {
+                templates.push(input.parse::<template::Template>()?);
+            } else if repetition::Repetition::peek(input) {
+                rules.extend(input.parse::<repetition::Repetition>()?.expand()?);
+            } else {
+                rules.push(input.parse::<Rule>()?);
+            }
+
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+
+        for rule in rules.iter_mut() {
+            rule.body.expand_templates(&templates)?;
+        }
+
         Ok(RuleList { rules })
     }
 }
@@ -97,28 +473,134 @@ impl ToOutput for RuleBody {
                     child.append(result);
                 }
             }
-            RuleBody::Normal { entries } => {
+            RuleBody::Normal { entries, .. } => {
                 for entry in entries {
                     entry.append(result);
                 }
             }
+            RuleBody::Template(_) => unreachable!("Templates are expanded while parsing"),
+            RuleBody::Conditional(conditional) => conditional.append(result),
         }
     }
 }
 
+impl Rule {
+    /// Appends a rule for every state block, i.e. `.checkbox:checked { ... }`
+    fn append_states(&self, result: &mut Output) {
+        let RuleBody::Normal { states, .. } = &self.body else {
+            return;
+        };
+
+        for state in states {
+            self.header
+                .append_with_pseudo_class(result, &state.pseudo_class, state.span);
+            result.push_str(" {\n");
+            for entry in &state.entries {
+                entry.append(result);
+            }
+            result.push_str("}\n");
+        }
+    }
+
+    /// Atomic mode: Literal declarations of rules selecting exactly one class
+    /// become atomic classes. Only the other declarations remain in the rule.
+    /// Returns `false` if the rule cannot be split this way.
+    fn append_atomic(&self, result: &mut Output) -> bool {
+        let (Some(classname), RuleBody::Normal { entries, .. }) =
+            (self.header.single_classname(), &self.body)
+        else {
+            return false;
+        };
+
+        let mut remaining = Vec::new();
+        for entry in entries {
+            match entry.literal_value() {
+                Some(value) => result.push_atom(classname, &entry.property, &value),
+                None => remaining.push(entry),
+            }
+        }
+
+        if !remaining.is_empty() || result.has_overrides() {
+            self.header.append(result);
+            result.push_str(" {\n");
+            for entry in remaining {
+                entry.append(result);
+            }
+            result.push_overrides(classname);
+            result.push_str("}\n");
+        }
+        self.append_states(result);
+
+        true
+    }
+
+    /// Dedup mode: Rules selecting exactly one class, which only contain
+    /// literal declarations, become shared classes. Returns `false` if the
+    /// rule cannot be shared.
+    fn append_shared(&self, result: &mut Output) -> bool {
+        let (Some(classname), RuleBody::Normal { entries, .. }) =
+            (self.header.single_classname(), &self.body)
+        else {
+            return false;
+        };
+
+        let declarations = entries
+            .iter()
+            .map(|entry| Some((entry.property.as_str(), entry.literal_value()?)))
+            .collect::<Option<Vec<_>>>();
+        let Some(declarations) = declarations else {
+            return false;
+        };
+        if declarations.is_empty() || result.has_overrides() {
+            return false;
+        }
+
+        result.push_shared_rule(classname, &declarations);
+        self.append_states(result);
+
+        true
+    }
+}
+
 impl ToOutput for Rule {
     fn append(&self, result: &mut Output) {
+        if let RuleBody::Conditional(conditional) = &self.body {
+            return conditional.append(result);
+        }
+
         self.header.append(result);
-        result.format_str.push_str(" {{\n");
+        result.push_str(" {\n");
         self.body.append(result);
-        result.format_str.push_str("}}\n");
+        if let Some(classname) = self.header.single_classname() {
+            result.push_overrides(classname);
+        }
+        result.push_str("}\n");
+        self.append_states(result);
+    }
+}
+
+impl Rule {
+    /// Appends a rule at the top level, which may be split up in atomic or
+    /// dedup mode, followed by its variants
+    fn append_top_level(&self, result: &mut Output) {
+        let appended = (result.atomic && self.append_atomic(result))
+            || (result.dedup && self.append_shared(result));
+        if !appended {
+            self.append(result);
+        }
+
+        if let RuleBody::Normal { variants, .. } = &self.body {
+            for rule in variants.rules() {
+                rule.append_top_level(result);
+            }
+        }
     }
 }
 
 impl ToOutput for RuleList {
     fn append(&self, result: &mut Output) {
         for rule in &self.rules {
-            rule.append(result);
+            rule.append_top_level(result);
         }
     }
 }
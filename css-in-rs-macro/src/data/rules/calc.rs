@@ -0,0 +1,280 @@
+//! Validation of `calc()` within string literals, so malformed arithmetic like
+//! `calc(100%-64px)` is caught at compile time. See `#[allow_unknown_values]`.
+
+/// Functions whose arguments are calculations, like the one of `calc()`
+const MATH_FUNCTIONS: &[&str] = &["calc", "min", "max", "clamp"];
+
+/// Returns why the first `calc()` of the value is malformed, if any
+pub fn malformed_calc(value: &str) -> Option<String> {
+    let chars = value.chars().collect::<Vec<_>>();
+    let mut parser = Parser {
+        chars: &chars,
+        pos: 0,
+    };
+
+    while let Some(ch) = parser.peek() {
+        match ch {
+            // `calc(` within a string, i.e. of `content`, is not a calculation
+            '"' | '\'' => parser.skip_string(ch),
+            ch if is_ident_char(ch) => {
+                let name = parser.ident();
+                if parser.peek() == Some('(') && is_calc(&name) {
+                    parser.pos += 1;
+                    if let Err(err) = parser.sum(false) {
+                        return Some(err);
+                    }
+                }
+            }
+            _ => parser.pos += 1,
+        }
+    }
+    None
+}
+
+/// `calc`, optionally with a vendor prefix like `-webkit-calc`
+fn is_calc(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name == "calc" || (name.starts_with('-') && name.ends_with("-calc"))
+}
+
+fn is_ident_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '-' || ch == '_'
+}
+
+struct Parser<'a> {
+    chars: &'a [char],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    /// Returns whether there has been any whitespace
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    fn skip_string(&mut self, quote: char) {
+        self.pos += 1;
+        while let Some(ch) = self.peek() {
+            self.pos += 1;
+            match ch {
+                '\\' => self.pos += 1,
+                ch if ch == quote => return,
+                _ => {}
+            }
+        }
+    }
+
+    /// Skips the arguments of a function which is not a calculation, like
+    /// `var(...)`, including the closing parenthesis
+    fn skip_arguments(&mut self) -> Result<(), String> {
+        let mut depth = 1;
+        while let Some(ch) = self.peek() {
+            self.pos += 1;
+            match ch {
+                '(' => depth += 1,
+                ')' if depth == 1 => return Ok(()),
+                ')' => depth -= 1,
+                '"' | '\'' => {
+                    self.pos -= 1;
+                    self.skip_string(ch);
+                }
+                _ => {}
+            }
+        }
+        Err(unclosed())
+    }
+
+    fn ident(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(is_ident_char) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// The word starting at the current position, for error messages
+    fn word(&self) -> String {
+        let rest = &self.chars[self.pos..];
+        let len = rest
+            .iter()
+            .position(|ch| ch.is_whitespace() || *ch == ')')
+            .unwrap_or(rest.len());
+        rest[..len.max(1)].iter().collect()
+    }
+
+    /// Parses a calculation up to the closing parenthesis, which is consumed.
+    /// With `arguments`, several calculations are separated by commas, like
+    /// the ones of `clamp()`.
+    fn sum(&mut self, arguments: bool) -> Result<(), String> {
+        loop {
+            self.skip_whitespace();
+            self.operand()?;
+
+            let space_before = self.skip_whitespace();
+            match self.peek() {
+                None => return Err(unclosed()),
+                Some(')') => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                Some(',') if arguments => self.pos += 1,
+                Some(op @ ('+' | '-')) => {
+                    let space_after = self.peek_at(1).is_some_and(char::is_whitespace);
+                    if !space_before || !space_after {
+                        return Err(format!(
+                            "`{op}` needs spaces on both sides, like `100% {op} 8px`"
+                        ));
+                    }
+                    self.pos += 1;
+                }
+                Some('*' | '/') => self.pos += 1,
+                Some(_) => {
+                    return Err(format!("An operator is missing before `{}`", self.word()));
+                }
+            }
+        }
+    }
+
+    fn operand(&mut self) -> Result<(), String> {
+        let Some(ch) = self.peek() else {
+            return Err(unclosed());
+        };
+
+        let starts_number =
+            |ch: Option<char>| ch.is_some_and(|ch| ch.is_ascii_digit() || ch == '.');
+        match ch {
+            '(' => {
+                self.pos += 1;
+                self.sum(false)
+            }
+            ch if starts_number(Some(ch)) => {
+                self.number();
+                Ok(())
+            }
+            '+' | '-' if starts_number(self.peek_at(1)) => {
+                self.pos += 1;
+                self.number();
+                Ok(())
+            }
+            ch if is_ident_char(ch) => {
+                let name = self.ident();
+                if self.peek() != Some('(') {
+                    // A keyword like `infinity`
+                    return Ok(());
+                }
+
+                self.pos += 1;
+                let name = name.to_ascii_lowercase();
+                if MATH_FUNCTIONS.contains(&name.as_str()) || is_calc(&name) {
+                    self.sum(true)
+                } else {
+                    self.skip_arguments()
+                }
+            }
+            ')' => Err("A value is missing before `)`".to_owned()),
+            _ => Err(format!("Expected a value, found `{}`", self.word())),
+        }
+    }
+
+    /// Parses a number with an optional exponent and unit, like `1.5em`
+    fn number(&mut self) {
+        while self
+            .peek()
+            .is_some_and(|ch| ch.is_ascii_digit() || ch == '.')
+        {
+            self.pos += 1;
+        }
+
+        let exponent = match (self.peek(), self.peek_at(1), self.peek_at(2)) {
+            (Some('e' | 'E'), Some(digit), _) if digit.is_ascii_digit() => 1,
+            (Some('e' | 'E'), Some('+' | '-'), Some(digit)) if digit.is_ascii_digit() => 2,
+            _ => 0,
+        };
+        if exponent > 0 {
+            self.pos += exponent;
+            while self.peek().is_some_and(|ch| ch.is_ascii_digit()) {
+                self.pos += 1;
+            }
+        }
+
+        // Units only consist of letters, so `64px-8px` is a unit followed by `-`
+        if self.peek() == Some('%') {
+            self.pos += 1;
+        } else {
+            while self.peek().is_some_and(|ch| ch.is_ascii_alphabetic()) {
+                self.pos += 1;
+            }
+        }
+    }
+}
+
+fn unclosed() -> String {
+    "A closing parenthesis is missing".to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::malformed_calc;
+
+    #[test]
+    fn valid() {
+        assert_eq!(malformed_calc("calc(100% - 64px)"), None);
+        assert_eq!(malformed_calc("calc(-5px + 1em * 2)"), None);
+        assert_eq!(malformed_calc("calc((100vw - 2rem) / 3)"), None);
+        assert_eq!(
+            malformed_calc("calc(100% - var(--header-height, 64px))"),
+            None
+        );
+        assert_eq!(
+            malformed_calc("clamp(1rem, calc(2.5vw + 1e-1rem), 2rem)"),
+            None
+        );
+        assert_eq!(malformed_calc("calc(min(10px, 5vw) * -1)"), None);
+        assert_eq!(
+            malformed_calc("-webkit-calc(1px + 2px) calc(infinity * 1px)"),
+            None
+        );
+        assert_eq!(malformed_calc("\"calc(1px-2px)\""), None);
+        assert_eq!(malformed_calc("1px solid red"), None);
+    }
+
+    #[test]
+    fn malformed() {
+        let spaces = "`-` needs spaces on both sides, like `100% - 8px`";
+        assert_eq!(malformed_calc("calc(100%-64px)").unwrap(), spaces);
+        assert_eq!(malformed_calc("calc(64px-8px)").unwrap(), spaces);
+        assert_eq!(malformed_calc("calc(64px -8px)").unwrap(), spaces);
+        assert_eq!(
+            malformed_calc("calc(1px +2px)").unwrap(),
+            "`+` needs spaces on both sides, like `100% + 8px`"
+        );
+        assert_eq!(
+            malformed_calc("calc(1px 2px)").unwrap(),
+            "An operator is missing before `2px`"
+        );
+        assert_eq!(
+            malformed_calc("calc(1px * )").unwrap(),
+            "A value is missing before `)`"
+        );
+        assert_eq!(
+            malformed_calc("calc((1px + 2px)").unwrap(),
+            "A closing parenthesis is missing"
+        );
+        assert_eq!(
+            malformed_calc("max(1px, calc(2px+3px))").unwrap(),
+            "`+` needs spaces on both sides, like `100% + 8px`"
+        );
+    }
+}
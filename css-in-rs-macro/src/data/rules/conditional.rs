@@ -0,0 +1,98 @@
+use quote::ToTokens;
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Token,
+};
+
+use super::Rule;
+use crate::output::{Output, ToOutput};
+
+/// Rules which are only generated if a condition holds, i.e.
+/// `@if theme.compact { ... } @else { ... }`. The conditions are evaluated
+/// whenever the CSS is generated.
+pub struct Conditional {
+    pub branches: Vec<Branch>,
+}
+
+pub struct Branch {
+    /// `None` for the final `@else`
+    pub condition: Option<syn::Expr>,
+    /// The branch as written by the user, i.e. `@if theme.compact` or `@else`
+    pub source: String,
+    pub rules: Punctuated<Rule, syn::token::Comma>,
+}
+
+impl Conditional {
+    /// Whether the input starts with `@if`
+    pub fn peek(input: ParseStream) -> bool {
+        input.peek(Token![@]) && input.peek2(Token![if])
+    }
+
+    /// The rules of all branches
+    pub fn rules(&self) -> impl Iterator<Item = &Rule> {
+        self.branches.iter().flat_map(|branch| &branch.rules)
+    }
+}
+
+impl Parse for Conditional {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<Token![@]>()?;
+        input.parse::<Token![if]>()?;
+
+        let mut branches = Vec::new();
+        let mut keyword = "@if";
+        loop {
+            let condition = input.call(syn::Expr::parse_without_eager_brace)?;
+            let source = format!("{keyword} {}", condition.to_token_stream());
+
+            let content;
+            syn::braced!(content in input);
+            let rules = super::parse_rules(&content)?;
+            branches.push(Branch {
+                condition: Some(condition),
+                source,
+                rules,
+            });
+
+            if !(input.peek(Token![@]) && input.peek2(Token![else])) {
+                break;
+            }
+            input.parse::<Token![@]>()?;
+            input.parse::<Token![else]>()?;
+
+            if input.parse::<Option<Token![if]>>()?.is_none() {
+                let content;
+                syn::braced!(content in input);
+                let rules = super::parse_rules(&content)?;
+                branches.push(Branch {
+                    condition: None,
+                    source: "@else".to_owned(),
+                    rules,
+                });
+                break;
+            }
+            keyword = "@else if";
+        }
+
+        Ok(Conditional { branches })
+    }
+}
+
+impl ToOutput for Branch {
+    fn append(&self, result: &mut Output) {
+        for rule in &self.rules {
+            rule.append(result);
+        }
+    }
+}
+
+impl ToOutput for Conditional {
+    fn append(&self, result: &mut Output) {
+        let branches = self
+            .branches
+            .iter()
+            .map(|branch| (branch.condition.as_ref(), branch as &dyn ToOutput));
+        result.push_conditional(branches);
+    }
+}
@@ -1,18 +1,190 @@
-use quote::{quote, ToTokens};
-use syn::parse::{Parse, ParseStream};
+use proc_macro2::{Delimiter, Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{
+    parse::{Parse, ParseStream},
+    Token,
+};
 
 use crate::output::{Output, ToOutput};
 
+#[derive(Clone)]
 pub struct Entry {
     pub property: String,
+    /// The name of a custom property which is computed at runtime, i.e. the
+    /// `MY_ANGLE` in `--(MY_ANGLE): "45deg"`. `property` is `--(MY_ANGLE)` then.
+    pub custom_property: Option<syn::Expr>,
     pub value: syn::Expr,
+    /// Values of repeated declarations following the one of `value`, i.e. the
+    /// `"grid"` in `display: ["flex", "grid"]`. Browsers use the last one they
+    /// support, so the earlier ones serve as fallbacks.
+    pub repeated: Vec<syn::Expr>,
+    /// The span of the property
+    pub span: Span,
+}
+
+impl Entry {
+    /// Returns the value if it is a literal which can be evaluated at compile
+    /// time, formatted the same way as it would be at runtime. Always `None`
+    /// for computed custom properties and repeated declarations.
+    pub fn literal_value(&self) -> Option<String> {
+        if self.custom_property.is_some() || !self.repeated.is_empty() {
+            return None;
+        }
+
+        literal(&self.value)
+    }
+
+    /// All values, in the order of their declarations
+    pub fn values(&self) -> impl Iterator<Item = &syn::Expr> {
+        core::iter::once(&self.value).chain(&self.repeated)
+    }
+}
+
+impl Entry {
+    /// Fails if a string literal contains an unknown keyword of a property with
+    /// enumerated values, i.e. `display: "fex"`, a malformed `calc()` or
+    /// areas of `grid-template-areas` which are not rectangular
+    pub fn check_values(&self) -> syn::Result<()> {
+        for value in self.values() {
+            let syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(lit),
+                ..
+            }) = value
+            else {
+                continue;
+            };
+
+            let value = lit.value();
+            if let Some((unknown, keywords)) =
+                super::keywords::unknown_keyword(&self.property, &value)
+            {
+                return Err(syn::Error::new(
+                    lit.span(),
+                    format!(
+                        "Unknown value `{unknown}` for `{}`. Known values: {}. Use \
+                         `#[allow_unknown_values]` for values the macro does not know yet",
+                        self.property,
+                        keywords.join(", ")
+                    ),
+                ));
+            }
+            if self.property == "grid-template-areas" {
+                if let Some(err) = super::grid_areas::invalid_grid_areas(&value) {
+                    return Err(syn::Error::new(
+                        lit.span(),
+                        format!(
+                            "Invalid `grid-template-areas`: {err}. Use \
+                             `#[allow_unknown_values]` if the value is valid nevertheless"
+                        ),
+                    ));
+                }
+            }
+            if let Some(err) = super::calc::malformed_calc(&value) {
+                return Err(syn::Error::new(
+                    lit.span(),
+                    format!(
+                        "Malformed `calc()`: {err}. Use `#[allow_unknown_values]` if the \
+                         value is valid nevertheless"
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fails if a property is declared twice with different values, which is
+/// usually a copy-paste mistake. Fallback values are declared explicitly
+/// instead, i.e. `display: ["flex", "grid"]`.
+pub fn check_duplicates<'a>(entries: impl IntoIterator<Item = &'a Entry>) -> syn::Result<()> {
+    let values = |entry: &Entry| {
+        entry
+            .values()
+            .map(|value| value.to_token_stream().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    let mut seen = Vec::<&Entry>::new();
+    for entry in entries {
+        let earlier = seen.iter().find(|other| other.property == entry.property);
+        if earlier.is_some_and(|other| values(other) != values(entry)) {
+            return Err(syn::Error::new(
+                entry.span,
+                format!(
+                    "`{}` is already declared with a different value. Remove one of the \
+                     declarations, or use `[...]` to declare fallback values",
+                    entry.property
+                ),
+            ));
+        }
+        seen.push(entry);
+    }
+    Ok(())
+}
+
+fn literal(value: &syn::Expr) -> Option<String> {
+    let syn::Expr::Lit(lit) = value else {
+        return None;
+    };
+
+    match &lit.lit {
+        syn::Lit::Str(s) => Some(s.value()),
+        syn::Lit::Int(i) if i.suffix().is_empty() => Some(i.base10_digits().to_owned()),
+        syn::Lit::Bool(b) => Some(b.value.to_string()),
+        _ => None,
+    }
+}
+
+impl Entry {
+    /// Returns the span of a value marked as `!important`, either using
+    /// `important(...)` or inside a string literal
+    pub fn find_important(&self) -> Option<Span> {
+        self.values()
+            .find_map(|value| find_important(value.to_token_stream()))
+    }
+}
+
+fn find_important(tokens: TokenStream) -> Option<Span> {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Group(group) => {
+                if let Some(span) = find_important(group.stream()) {
+                    return Some(span);
+                }
+            }
+            TokenTree::Ident(ident) if ident == "important" => {
+                if let Some(TokenTree::Group(args)) = tokens.peek() {
+                    if args.delimiter() == Delimiter::Parenthesis {
+                        return Some(ident.span());
+                    }
+                }
+            }
+            TokenTree::Literal(lit) if lit.to_string().contains("!important") => {
+                return Some(lit.span());
+            }
+            _ => {}
+        }
+    }
+    None
 }
 
 impl Parse for Entry {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut custom_property = None;
+        let span = input.span();
         let property = {
-            if let Some(property) = input.parse::<syn::LitStr>().ok() {
+            if let Ok(property) = input.parse::<syn::LitStr>() {
                 property.value()
+            } else if input.peek(Token![-]) && input.peek2(Token![-]) {
+                input.parse::<Token![-]>()?;
+                input.parse::<Token![-]>()?;
+                let name;
+                syn::parenthesized!(name in input);
+                let name = name.parse::<syn::Expr>()?;
+                let property = format!("--({})", name.to_token_stream());
+                custom_property = Some(name);
+                property
             } else {
                 let property = input.parse::<syn::Ident>()?;
                 property.to_string().replace('_', "-")
@@ -20,9 +192,32 @@ impl Parse for Entry {
         };
 
         input.parse::<syn::token::Colon>()?;
-        let value = input.parse::<syn::Expr>()?;
 
-        let entry = Entry { property, value };
+        // Repeated declarations, i.e. `display: ["flex", "grid"]`
+        let (value, repeated) = if input.peek(syn::token::Bracket) {
+            let content;
+            let bracket = syn::bracketed!(content in input);
+            let mut values = content
+                .parse_terminated(syn::Expr::parse, Token![,])?
+                .into_iter();
+            let Some(value) = values.next() else {
+                return Err(syn::Error::new(
+                    bracket.span.join(),
+                    "Expected at least one value",
+                ));
+            };
+            (value, values.collect())
+        } else {
+            (input.parse::<syn::Expr>()?, Vec::new())
+        };
+
+        let entry = Entry {
+            property,
+            custom_property,
+            value,
+            repeated,
+            span,
+        };
 
         Ok(entry)
     }
@@ -30,10 +225,11 @@ impl Parse for Entry {
 
 impl ToOutput for Entry {
     fn append(&self, result: &mut Output) {
-        let property = &self.property;
-        let value = &self.value;
-
-        result.format_str.push_str("  {}: {};\n");
-        quote!(, #property, #value).to_tokens(&mut result.params);
+        for value in self.values() {
+            match &self.custom_property {
+                Some(name) => result.push_custom_declaration(name, value),
+                None => result.push_declaration(&self.property, value, literal(value)),
+            }
+        }
     }
 }
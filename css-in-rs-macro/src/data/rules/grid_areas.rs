@@ -0,0 +1,131 @@
+//! Validation of `grid-template-areas`, so areas which are not rectangular or
+//! rows of different lengths are caught at compile time. See
+//! `#[allow_unknown_values]`.
+
+/// Returns why the areas of a `grid-template-areas` value are invalid, if any.
+/// Values without rows, like `none`, are not checked.
+pub fn invalid_grid_areas(value: &str) -> Option<String> {
+    let rows = match rows(value) {
+        Ok(rows) => rows,
+        Err(err) => return Some(err),
+    };
+    let first = rows.first()?;
+
+    for (index, row) in rows.iter().enumerate() {
+        if row.is_empty() {
+            return Some(format!("Row {} has no cells", index + 1));
+        }
+        if row.len() != first.len() {
+            return Some(format!(
+                "Row {} has {} columns, but row 1 has {}. Use `.` for empty cells",
+                index + 1,
+                row.len(),
+                first.len()
+            ));
+        }
+        if let Some(cell) = row.iter().flatten().find(|cell| !is_area_name(cell)) {
+            return Some(format!("`{cell}` is not a valid area name"));
+        }
+    }
+
+    let mut names = Vec::<&str>::new();
+    for name in rows.iter().flatten().flatten() {
+        if !names.contains(name) {
+            names.push(name);
+        }
+    }
+    for name in names {
+        let cells = rows.iter().enumerate().flat_map(|(row, cells)| {
+            cells
+                .iter()
+                .enumerate()
+                .filter(|(_, cell)| *cell == &Some(name))
+                .map(move |(column, _)| (row, column))
+        });
+        let (mut top, mut left, mut bottom, mut right) = (usize::MAX, usize::MAX, 0, 0);
+        let mut count = 0;
+        for (row, column) in cells {
+            top = top.min(row);
+            bottom = bottom.max(row);
+            left = left.min(column);
+            right = right.max(column);
+            count += 1;
+        }
+        // Every cell has a single name, so the area is a rectangle iff it
+        // covers its whole bounding box
+        if count != (bottom - top + 1) * (right - left + 1) {
+            return Some(format!("The area `{name}` is not a rectangle"));
+        }
+    }
+    None
+}
+
+/// Splits the quoted rows into their cells. Empty cells, i.e. `.` or `...`,
+/// are `None`.
+fn rows(value: &str) -> Result<Vec<Vec<Option<&str>>>, String> {
+    let mut rows = Vec::new();
+    let mut rest = value;
+    while let Some(start) = rest.find(['"', '\'']) {
+        let quote = rest[start..].chars().next().unwrap();
+        let row = &rest[start + 1..];
+        let Some(end) = row.find(quote) else {
+            return Err("A closing quote is missing".to_owned());
+        };
+
+        let cells = row[..end]
+            .split_whitespace()
+            .map(|cell| Some(cell).filter(|cell| cell.chars().any(|ch| ch != '.')))
+            .collect();
+        rows.push(cells);
+        rest = &row[end + 1..];
+    }
+    Ok(rows)
+}
+
+fn is_area_name(name: &str) -> bool {
+    name.chars()
+        .all(|ch| ch.is_alphanumeric() || ch == '-' || ch == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::invalid_grid_areas;
+
+    #[test]
+    fn valid() {
+        assert_eq!(invalid_grid_areas("none"), None);
+        assert_eq!(
+            invalid_grid_areas(r#""header header" "sidebar main" "footer footer""#),
+            None
+        );
+        assert_eq!(invalid_grid_areas("'a a .' 'a a b'\n'... c b'"), None);
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            invalid_grid_areas(r#""header header" "sidebar main main""#).unwrap(),
+            "Row 2 has 3 columns, but row 1 has 2. Use `.` for empty cells"
+        );
+        assert_eq!(
+            invalid_grid_areas(r#""a a" "a b""#).unwrap(),
+            "The area `a` is not a rectangle"
+        );
+        assert_eq!(
+            invalid_grid_areas(r#""a b a""#).unwrap(),
+            "The area `a` is not a rectangle"
+        );
+        assert_eq!(
+            invalid_grid_areas(r#""a b" """#).unwrap(),
+            "Row 2 has no cells"
+        );
+        assert_eq!(
+            invalid_grid_areas(r#""a #b""#).unwrap(),
+            "`#b` is not a valid area name"
+        );
+        assert_eq!(
+            invalid_grid_areas(r#""a b"#).unwrap(),
+            "A closing quote is missing"
+        );
+    }
+}
@@ -1,14 +1,30 @@
 use std::collections::{btree_map::Entry, BTreeMap};
 
-use proc_macro2::Span;
-use syn::parse::{Parse, ParseStream};
+use proc_macro2::{Span, TokenStream};
+use quote::ToTokens;
+use syn::{
+    parse::{Parse, ParseStream},
+    Token,
+};
 
+use super::state;
 use crate::output::{Output, ToOutput};
 
 #[derive(Debug)]
 pub enum Part {
     Raw(String),
     ClassName(String),
+    /// An expression evaluating to an identifier or a media query, i.e. the
+    /// `animations::PULSE` in `@keyframes(animations::PULSE)`
+    Name(TokenStream),
+    /// A class of another struct, i.e. `{IconClasses::icon}`
+    Reference {
+        classes: TokenStream,
+        field: syn::Ident,
+    },
+    /// A name defined by an at-rule, which is renamed like a class, i.e. the
+    /// `fade_in` in `@keyframes fade_in`
+    LocalName(String),
 }
 
 impl Part {
@@ -22,26 +38,108 @@ pub struct Header {
     pub parts: Vec<Part>,
     pub span: Span,
     pub at_rule: bool,
+    /// The header as written by the user, i.e. `red_text` or `div.red_text`
+    pub source: String,
 }
 
 impl Header {
     pub fn collect_classnames(&self, result: &mut BTreeMap<String, Span>) {
         for part in &self.parts {
             match part {
-                Part::Raw(_) => {}
+                Part::Raw(_) | Part::Name(_) | Part::Reference { .. } | Part::LocalName(_) => {}
                 Part::ClassName(classname) => {
                     let classname = classname.to_string();
-                    match result.entry(classname) {
-                        Entry::Vacant(vac) => {
-                            vac.insert(self.span.clone());
-                        }
-                        _ => {}
+                    if let Entry::Vacant(vac) = result.entry(classname) {
+                        vac.insert(self.span);
                     }
                 }
             }
         }
     }
 
+    /// Collects the names defined by this at-rule, together with whether they
+    /// are dashed idents like the names of `@font-palette-values`
+    pub fn collect_local_names(&self, result: &mut BTreeMap<String, (Span, bool)>) {
+        for pair in self.parts.windows(2) {
+            if let [Part::Raw(raw), Part::LocalName(name)] = pair {
+                let dashed = raw.ends_with("--");
+                result.entry(name.clone()).or_insert((self.span, dashed));
+            }
+        }
+    }
+
+    /// Returns the classname if this header selects exactly one class, i.e. `.red_text`
+    pub fn single_classname(&self) -> Option<&str> {
+        match self.parts.as_slice() {
+            [Part::Raw(dot), Part::ClassName(classname)] if dot == "." => Some(classname),
+            _ => None,
+        }
+    }
+
+    /// The keyword of an at-rule, i.e. `@media`
+    pub fn at_rule_keyword(&self) -> Option<&str> {
+        if !self.at_rule {
+            return None;
+        }
+
+        // Not taken from the source, which may be a shorthand like `@motion-safe`
+        let Some(Part::Raw(prelude)) = self.parts.first() else {
+            return None;
+        };
+        let mut words = prelude.split(|ch: char| ch.is_whitespace() || ch == '(');
+        words.next()
+    }
+
+    /// Whether this is an at-rule containing declarations instead of other
+    /// rules, i.e. `@font-face`
+    pub fn has_declarations(&self) -> bool {
+        self.at_rule_keyword()
+            .is_some_and(|keyword| DECLARATION_AT_RULES.contains(&keyword))
+    }
+
+    /// Whether a selector of the list ends with the `::before` or `::after`
+    /// pseudo-element
+    pub fn has_generated_content(&self) -> bool {
+        if self.at_rule {
+            return false;
+        }
+
+        let mut depth = 0;
+        let mut selectors = Vec::new();
+        let mut start = 0;
+        for (idx, ch) in self.source.char_indices() {
+            match ch {
+                '(' | '[' => depth += 1,
+                ')' | ']' => depth -= 1,
+                ',' if depth == 0 => {
+                    selectors.push(&self.source[start..idx]);
+                    start = idx + 1;
+                }
+                _ => {}
+            }
+        }
+        selectors.push(&self.source[start..]);
+
+        selectors.iter().any(|selector| {
+            let selector = selector.trim_end();
+            selector.ends_with(":before") || selector.ends_with(":after")
+        })
+    }
+
+    /// Strict mode: Fails if this is an at-rule which is not known
+    pub fn check_at_rule(&self) -> syn::Result<()> {
+        match self.at_rule_keyword() {
+            Some(keyword) if !KNOWN_AT_RULES.contains(&keyword) => Err(syn::Error::new(
+                self.span,
+                format!(
+                    "Unknown at-rule `{keyword}`. Supported at-rules in strict mode: {}",
+                    KNOWN_AT_RULES.join(", ")
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+
     pub fn push_char(&mut self, ch: char) {
         if let Some(Part::Raw(r)) = self.parts.last_mut() {
             r.push(ch);
@@ -55,7 +153,9 @@ impl Header {
 mod parse {
     use proc_macro2::Span;
 
-    use super::{Header, Part};
+    use quote::ToTokens;
+
+    use super::{Header, Part, AT_RULE_SHORTHANDS};
 
     type ParseResult<'a, T> = nom::IResult<&'a str, T>;
 
@@ -67,10 +167,18 @@ mod parse {
             parts: Default::default(),
             span,
             at_rule,
+            source: src.to_owned(),
         };
+        if let Some((_, expanded)) = AT_RULE_SHORTHANDS.iter().find(|(name, _)| *name == src) {
+            src = expanded;
+        }
 
         loop {
             let (remaining, part) = parse_part(src, !at_rule).ok()?;
+            let part = match part {
+                RawPart::Part(part) => part,
+                RawPart::Reference(reference) => parse_reference(reference, span)?,
+            };
             if part.is_class() {
                 header.push_char('.');
             }
@@ -85,7 +193,7 @@ mod parse {
         Some(header)
     }
 
-    fn parse_classname(src: &str) -> ParseResult<&str> {
+    fn parse_classname(src: &str) -> ParseResult<'_, &str> {
         let (src, _) = nom::bytes::complete::tag(".")(src)?;
         let (src, classname) =
             nom::bytes::complete::take_while1(|ch: char| ch.is_ascii_alphanumeric() || ch == '_')(
@@ -101,38 +209,236 @@ mod parse {
         Ok((src, classname))
     }
 
-    fn parse_part(src: &str, handle_dots: bool) -> ParseResult<Part> {
+    enum RawPart<'a> {
+        Part(Part),
+        /// The content of `{...}`, which still has to be parsed as a path
+        Reference(&'a str),
+    }
+
+    fn parse_part(src: &str, handle_dots: bool) -> ParseResult<'_, RawPart<'_>> {
         if handle_dots && src.starts_with('.') {
             let (src, classname) = parse_classname(src)?;
             let part = Part::ClassName(classname.to_string());
-            return Ok((src, part));
+            return Ok((src, RawPart::Part(part)));
+        }
+
+        if handle_dots && src.starts_with('{') {
+            let (src, reference) = nom::sequence::delimited(
+                nom::bytes::complete::tag("{"),
+                nom::bytes::complete::take_until("}"),
+                nom::bytes::complete::tag("}"),
+            )(src)?;
+            return Ok((src, RawPart::Reference(reference)));
         }
 
         let (src, chunk) =
-            nom::bytes::complete::take_while1(|ch: char| !handle_dots || ch != '.')(src)?;
+            nom::bytes::complete::take_while1(|ch: char| !handle_dots || (ch != '.' && ch != '{'))(
+                src,
+            )?;
         let part = Part::Raw(chunk.to_string());
-        Ok((src, part))
+        Ok((src, RawPart::Part(part)))
+    }
+
+    /// Parses a reference like `IconClasses::icon` into the type and the field
+    fn parse_reference(reference: &str, span: Span) -> Option<Part> {
+        let lit = syn::LitStr::new(reference.trim(), span);
+        let mut path = lit.parse::<syn::Path>().ok()?;
+
+        let field = path.segments.pop()?.into_value();
+        if path.segments.is_empty() || !field.arguments.is_none() {
+            return None;
+        }
+        path.segments.pop_punct();
+
+        let part = Part::Reference {
+            classes: path.to_token_stream(),
+            field: field.ident,
+        };
+        Some(part)
+    }
+}
+
+/// At-rules whose prelude can be computed at runtime, i.e. `@media(...)`
+const DIRECTIVES: &[&str] = &["keyframes", "media", "container", "layer", "property"];
+
+/// At-rules defining a name, which is renamed like a class if it is given as
+/// an identifier, i.e. `@keyframes fade_in`. The names of
+/// `@font-palette-values` are dashed idents.
+const NAMED_AT_RULES: &[&str] = &["keyframes", "counter-style", "font-palette-values"];
+
+/// At-rules which are accepted in strict mode, see `#[strict]`
+const KNOWN_AT_RULES: &[&str] = &[
+    "@container",
+    "@counter-style",
+    "@font-face",
+    "@font-feature-values",
+    "@font-palette-values",
+    "@keyframes",
+    "@layer",
+    "@media",
+    "@page",
+    "@property",
+    "@scope",
+    "@starting-style",
+    "@supports",
+];
+
+/// At-rules which are replaced by a media query, see "Reduced motion",
+/// "Print styles" and "Dark mode" in the documentation of `make_styles!`
+const AT_RULE_SHORTHANDS: &[(&str, &str)] = &[
+    (
+        "@motion-safe",
+        "@media (prefers-reduced-motion: no-preference)",
+    ),
+    ("@motion-reduce", "@media (prefers-reduced-motion: reduce)"),
+    ("@print", "@media print"),
+    ("@dark", "@media (prefers-color-scheme: dark)"),
+];
+
+/// At-rules which contain declarations instead of other rules
+const DECLARATION_AT_RULES: &[&str] = &[
+    "@property",
+    "@font-face",
+    "@counter-style",
+    "@font-palette-values",
+];
+
+impl Header {
+    /// Parses a directive like `@keyframes(animations::PULSE)` or
+    /// `@media(theme.breakpoints.up(Md))`, a shorthand like `@print`, or an
+    /// at-rule with a local name like `@keyframes fade_in`
+    fn parse_directive(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<Token![@]>()?;
+        let ident = input.parse::<syn::Ident>()?;
+        let span = ident.span();
+
+        // Keywords like `counter-style` are split into several identifiers
+        let mut keyword = ident.to_string();
+        while input.peek(Token![-]) && input.peek2(syn::Ident) {
+            input.parse::<Token![-]>()?;
+            keyword.push('-');
+            keyword.push_str(&input.parse::<syn::Ident>()?.to_string());
+        }
+
+        if NAMED_AT_RULES.contains(&keyword.as_str()) && input.peek(syn::Ident) {
+            let name = input.parse::<syn::Ident>()?;
+            let raw = match keyword == "font-palette-values" {
+                true => format!("@{keyword} --"),
+                false => format!("@{keyword} "),
+            };
+            return Ok(Header {
+                parts: vec![Part::Raw(raw), Part::LocalName(name.to_string())],
+                span: name.span(),
+                at_rule: true,
+                source: format!("@{keyword} {name}"),
+            });
+        }
+
+        let source = format!("@{keyword}");
+        let shorthand = AT_RULE_SHORTHANDS.iter().find(|(name, _)| *name == source);
+        if let Some((_, expanded)) = shorthand.filter(|_| !input.peek(syn::token::Paren)) {
+            return Ok(Header {
+                parts: vec![Part::Raw((*expanded).to_owned())],
+                span,
+                at_rule: true,
+                source,
+            });
+        }
+
+        if !DIRECTIVES.contains(&keyword.as_str()) {
+            return Err(syn::Error::new(
+                span,
+                "Unknown directive. Expected `@template`, `@print`, `@dark`, \
+                 `@keyframes(...)`, `@media(...)`, `@container(...)`, `@layer(...)`, \
+                 `@property(...)`, or `@keyframes`, `@counter-style` or \
+                 `@font-palette-values` followed by a name",
+            ));
+        }
+
+        let name;
+        syn::parenthesized!(name in input);
+        let name = name.parse::<syn::Expr>()?;
+
+        let source = format!("@{keyword}({})", name.to_token_stream());
+        let raw = match keyword == "property" {
+            true => "@property --".to_owned(),
+            false => format!("@{keyword} "),
+        };
+        let header = Header {
+            parts: vec![Part::Raw(raw), Part::Name(name.to_token_stream())],
+            span,
+            at_rule: true,
+            source,
+        };
+        Ok(header)
+    }
+}
+
+/// Whether a selector list like `button, , input` contains an empty selector.
+/// Commas within parentheses or brackets, i.e. in `:is(a, b)`, do not
+/// separate selectors. The preludes of at-rules are not checked.
+fn has_empty_selector(src: &str) -> bool {
+    if src.trim_start().starts_with('@') {
+        return false;
+    }
+
+    let mut depth = 0;
+    let mut selector_start = 0;
+    for (idx, ch) in src.char_indices() {
+        match ch {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                if src[selector_start..idx].trim().is_empty() {
+                    return true;
+                }
+                selector_start = idx + 1;
+            }
+            _ => {}
+        }
     }
+    selector_start > 0 && src[selector_start..].trim().is_empty()
 }
 
 impl Parse for Header {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![@]) {
+            return Header::parse_directive(input);
+        }
+
         let header = if let Ok(ident) = input.parse::<syn::Ident>() {
-            let parts = vec![
+            let mut parts = vec![
                 Part::Raw(".".to_owned()),
                 Part::ClassName(ident.to_string()),
             ];
             let span = ident.span();
             let at_rule = false;
+            let mut source = ident.to_string();
+
+            // Pseudo-class shorthand, i.e. `red_text:hover`
+            while input.peek(Token![:]) && !input.peek(Token![::]) {
+                input.parse::<Token![:]>()?;
+                let pseudo_class = state::pseudo_class(&input.parse::<syn::Ident>()?)?;
+                parts.push(Part::Raw(format!(":{pseudo_class}")));
+                source.push(':');
+                source.push_str(&pseudo_class);
+            }
+
             Header {
                 parts,
                 span,
                 at_rule,
+                source,
             }
         } else {
             let source = input.parse::<syn::LitStr>()?;
 
-            if let Some(sel) = parse::parse(&source.value(), source.span()) {
+            if has_empty_selector(&source.value()) {
+                return Err(syn::Error::new(
+                    source.span(),
+                    "Empty selector in the list. Remove the superfluous comma",
+                ));
+            } else if let Some(sel) = parse::parse(&source.value(), source.span()) {
                 return Ok(sel);
             } else {
                 return Err(syn::Error::new(
@@ -150,13 +456,53 @@ impl ToOutput for Part {
     fn append(&self, result: &mut Output) {
         match self {
             Part::Raw(s) => result.push_str(s),
-            Part::ClassName(s) => result.push_classname(s),
+            Part::ClassName(s) | Part::LocalName(s) => result.push_classname(s),
+            Part::Name(expr) => result.push_interpolated(expr.clone()),
+            Part::Reference { classes, field } => result.push_reference(classes, field),
         }
     }
 }
 
+impl Header {
+    /// Appends the header with a pseudo-class at the end of every selector of
+    /// the list, i.e. `div.red_text:checked, span.red_text:checked`
+    pub fn append_with_pseudo_class(&self, result: &mut Output, pseudo_class: &str, span: Span) {
+        let suffix = format!(":{pseudo_class}");
+        let source = format!("{}{suffix}", self.source);
+        result.push_source_comment(&source, span);
+
+        let mut depth = 0;
+        for part in &self.parts {
+            let Part::Raw(raw) = part else {
+                part.append(result);
+                continue;
+            };
+
+            let mut chunk = String::new();
+            for ch in raw.chars() {
+                match ch {
+                    '(' | '[' => depth += 1,
+                    ')' | ']' => depth -= 1,
+                    ',' if depth == 0 => {
+                        let end = chunk.trim_end().len();
+                        chunk.insert_str(end, &suffix);
+                    }
+                    _ => {}
+                }
+                chunk.push(ch);
+            }
+            result.push_str(&chunk);
+        }
+        result.push_str(&suffix);
+    }
+}
+
 impl ToOutput for Header {
     fn append(&self, result: &mut Output) {
+        if let Some(keyword) = self.at_rule_keyword() {
+            result.push_at_rule(keyword);
+        }
+        result.push_source_comment(&self.source, self.span);
         for part in &self.parts {
             part.append(result);
         }
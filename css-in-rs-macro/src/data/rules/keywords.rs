@@ -0,0 +1,212 @@
+//! The keywords of properties with enumerated values, so typos like
+//! `display: "fex"` are caught at compile time. See `#[allow_unknown_values]`.
+
+/// Keywords which are valid for every property
+const GLOBAL_KEYWORDS: &[&str] = &["inherit", "initial", "unset", "revert", "revert-layer"];
+
+/// `overlay` is a legacy alias of `auto`, which browsers still accept
+const OVERFLOW: &[&str] = &["visible", "hidden", "clip", "scroll", "auto", "overlay"];
+
+/// Properties and their keywords. Values may consist of several keywords,
+/// i.e. `display: "inline flex"`.
+const PROPERTIES: &[(&str, &[&str])] = &[
+    ("backface-visibility", &["visible", "hidden"]),
+    ("border-collapse", &["collapse", "separate"]),
+    ("box-decoration-break", &["slice", "clone"]),
+    ("box-sizing", &["content-box", "border-box"]),
+    (
+        "clear",
+        &[
+            "none",
+            "left",
+            "right",
+            "both",
+            "inline-start",
+            "inline-end",
+        ],
+    ),
+    ("direction", &["ltr", "rtl"]),
+    (
+        "display",
+        &[
+            "none",
+            "contents",
+            "block",
+            "inline",
+            "inline-block",
+            "flow",
+            "flow-root",
+            "flex",
+            "inline-flex",
+            "grid",
+            "inline-grid",
+            "list-item",
+            "run-in",
+            "math",
+            "ruby",
+            "ruby-base",
+            "ruby-text",
+            "ruby-base-container",
+            "ruby-text-container",
+            "table",
+            "inline-table",
+            "table-caption",
+            "table-cell",
+            "table-column",
+            "table-column-group",
+            "table-footer-group",
+            "table-header-group",
+            "table-row",
+            "table-row-group",
+        ],
+    ),
+    (
+        "flex-direction",
+        &["row", "row-reverse", "column", "column-reverse"],
+    ),
+    ("flex-wrap", &["nowrap", "wrap", "wrap-reverse"]),
+    (
+        "float",
+        &["none", "left", "right", "inline-start", "inline-end"],
+    ),
+    ("font-style", &["normal", "italic", "oblique"]),
+    ("isolation", &["auto", "isolate"]),
+    ("list-style-position", &["inside", "outside"]),
+    (
+        "object-fit",
+        &["fill", "contain", "cover", "none", "scale-down"],
+    ),
+    ("overflow", OVERFLOW),
+    ("overflow-block", OVERFLOW),
+    ("overflow-inline", OVERFLOW),
+    ("overflow-x", OVERFLOW),
+    ("overflow-y", OVERFLOW),
+    (
+        "pointer-events",
+        &[
+            "auto",
+            "none",
+            "visiblepainted",
+            "visiblefill",
+            "visiblestroke",
+            "visible",
+            "painted",
+            "fill",
+            "stroke",
+            "all",
+            "bounding-box",
+        ],
+    ),
+    (
+        "position",
+        &["static", "relative", "absolute", "fixed", "sticky"],
+    ),
+    (
+        "resize",
+        &["none", "both", "horizontal", "vertical", "block", "inline"],
+    ),
+    ("table-layout", &["auto", "fixed"]),
+    (
+        "text-align",
+        &[
+            "start",
+            "end",
+            "left",
+            "right",
+            "center",
+            "justify",
+            "justify-all",
+            "match-parent",
+        ],
+    ),
+    ("text-overflow", &["clip", "ellipsis"]),
+    (
+        "text-transform",
+        &[
+            "none",
+            "capitalize",
+            "uppercase",
+            "lowercase",
+            "full-width",
+            "full-size-kana",
+            "math-auto",
+        ],
+    ),
+    ("user-select", &["auto", "text", "none", "contain", "all"]),
+    ("visibility", &["visible", "hidden", "collapse"]),
+    // The shorthand of CSS Text 4 combines the keywords of
+    // `white-space-collapse`, `text-wrap-mode` and `white-space-trim`
+    (
+        "white-space",
+        &[
+            "normal",
+            "nowrap",
+            "pre",
+            "pre-wrap",
+            "pre-line",
+            "break-spaces",
+            "collapse",
+            "discard",
+            "preserve",
+            "preserve-breaks",
+            "preserve-spaces",
+            "wrap",
+            "none",
+            "discard-before",
+            "discard-after",
+            "discard-inner",
+        ],
+    ),
+];
+
+/// Returns the first unknown keyword of the value together with the known
+/// ones, if the property has enumerated values. Computed values (`var(...)`),
+/// vendor-prefixed keywords, numbers and strings are not checked.
+pub fn unknown_keyword<'a>(
+    property: &str,
+    value: &'a str,
+) -> Option<(&'a str, &'static [&'static str])> {
+    let (_, keywords) = PROPERTIES.iter().find(|(name, _)| *name == property)?;
+    let value = value.trim().trim_end_matches("!important");
+    if value.contains('(') {
+        return None;
+    }
+
+    let is_known = |word: &str| {
+        let word = word.to_ascii_lowercase();
+        let word = word.as_str();
+        word.starts_with(|ch: char| !ch.is_ascii_alphabetic())
+            || keywords.contains(&word)
+            || GLOBAL_KEYWORDS.contains(&word)
+    };
+    let unknown = value.split_whitespace().find(|word| !is_known(word))?;
+    Some((unknown, keywords))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unknown_keyword;
+
+    #[test]
+    fn keywords() {
+        assert_eq!(unknown_keyword("display", "flex"), None);
+        assert_eq!(unknown_keyword("display", "inline flex"), None);
+        assert_eq!(unknown_keyword("display", "-webkit-box"), None);
+        assert_eq!(unknown_keyword("display", "none !important"), None);
+        assert_eq!(unknown_keyword("display", "var(--display)"), None);
+        assert_eq!(unknown_keyword("position", "INHERIT"), None);
+        assert_eq!(unknown_keyword("font-style", "oblique 10deg"), None);
+        assert_eq!(unknown_keyword("color", "rde"), None);
+        assert_eq!(unknown_keyword("overflow-y", "overlay"), None);
+        assert_eq!(unknown_keyword("white-space", "preserve-breaks wrap"), None);
+        assert_eq!(unknown_keyword("white-space", "break-spaces nowrap"), None);
+        assert_eq!(
+            unknown_keyword("white-space", "collapse discard-after"),
+            None
+        );
+
+        let (unknown, keywords) = unknown_keyword("display", "inline fex").unwrap();
+        assert_eq!(unknown, "fex");
+        assert!(keywords.contains(&"flex"));
+    }
+}
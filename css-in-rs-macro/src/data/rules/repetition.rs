@@ -0,0 +1,130 @@
+use proc_macro2::{Delimiter, Group, Literal, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{
+    parse::{Parse, ParseStream, Parser},
+    punctuated::Punctuated,
+    Token,
+};
+
+use super::Rule;
+
+/// Rules which are repeated for every number of a range, i.e.
+/// `@for i in 1..=12 { col_{i} { ... } }`. The loop is unrolled while
+/// parsing, so every iteration gets classes of its own.
+pub struct Repetition {
+    pub var: syn::Ident,
+    pub start: u64,
+    /// Inclusive
+    pub end: u64,
+    pub body: TokenStream,
+}
+
+impl Repetition {
+    /// Whether the input starts with `@for`
+    pub fn peek(input: ParseStream) -> bool {
+        input.peek(Token![@]) && input.peek2(Token![for])
+    }
+
+    /// Parses the body once for every number of the range
+    pub fn expand(&self) -> syn::Result<Punctuated<Rule, syn::token::Comma>> {
+        let mut rules = Punctuated::new();
+        for number in self.start..=self.end {
+            let body = self.substitute(self.body.clone(), number);
+            rules.extend(super::parse_rules.parse2(body)?);
+        }
+        Ok(rules)
+    }
+
+    /// Replaces `name_{i}` by `name_3`, `{i}` within string literals by `3`,
+    /// and every other usage of `i` by `3`
+    fn substitute(&self, tokens: TokenStream, number: u64) -> TokenStream {
+        let placeholder = format!("{{{}}}", self.var);
+        let mut result = Vec::<TokenTree>::new();
+        for token in tokens {
+            match token {
+                TokenTree::Group(group)
+                    if group.delimiter() == Delimiter::Brace && self.is_placeholder(&group) =>
+                {
+                    if let Some(TokenTree::Ident(prefix)) = result.last() {
+                        let ident = syn::Ident::new(&format!("{prefix}{number}"), prefix.span());
+                        *result.last_mut().unwrap() = TokenTree::Ident(ident);
+                    } else {
+                        result.push(TokenTree::Group(group));
+                    }
+                }
+                TokenTree::Group(group) => {
+                    let stream = self.substitute(group.stream(), number);
+                    let mut substituted = Group::new(group.delimiter(), stream);
+                    substituted.set_span(group.span());
+                    result.push(TokenTree::Group(substituted));
+                }
+                TokenTree::Ident(ident) if ident == self.var => {
+                    let mut literal = Literal::u64_unsuffixed(number);
+                    literal.set_span(ident.span());
+                    result.push(TokenTree::Literal(literal));
+                }
+                TokenTree::Literal(literal) => {
+                    let lit = syn::parse2::<syn::LitStr>(literal.to_token_stream());
+                    match lit {
+                        Ok(lit) if lit.value().contains(&placeholder) => {
+                            let value = lit.value().replace(&placeholder, &number.to_string());
+                            let lit = syn::LitStr::new(&value, lit.span());
+                            result.extend(lit.to_token_stream());
+                        }
+                        _ => result.push(TokenTree::Literal(literal)),
+                    }
+                }
+                token => result.push(token),
+            }
+        }
+        result.into_iter().collect()
+    }
+
+    /// Whether the group is `{i}`
+    fn is_placeholder(&self, group: &Group) -> bool {
+        let mut tokens = group.stream().into_iter();
+        matches!(
+            (tokens.next(), tokens.next()),
+            (Some(TokenTree::Ident(ident)), None) if ident == self.var
+        )
+    }
+}
+
+impl Parse for Repetition {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<Token![@]>()?;
+        input.parse::<Token![for]>()?;
+        let var = input.parse::<syn::Ident>()?;
+        input.parse::<Token![in]>()?;
+
+        let start = input.parse::<syn::LitInt>()?;
+        let inclusive = match input.parse::<Option<Token![..=]>>()? {
+            Some(_) => true,
+            None => {
+                input.parse::<Token![..]>()?;
+                false
+            }
+        };
+        let end = input.parse::<syn::LitInt>()?;
+
+        let first = start.base10_parse::<u64>()?;
+        let last = match inclusive {
+            true => Some(end.base10_parse::<u64>()?),
+            false => end.base10_parse::<u64>()?.checked_sub(1),
+        };
+        let Some(last) = last.filter(|last| *last >= first) else {
+            return Err(syn::Error::new(end.span(), "The range of `@for` is empty"));
+        };
+
+        let content;
+        syn::braced!(content in input);
+        let body = content.parse::<TokenStream>()?;
+
+        Ok(Repetition {
+            var,
+            start: first,
+            end: last,
+            body,
+        })
+    }
+}
@@ -0,0 +1,93 @@
+use proc_macro2::Span;
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Token,
+};
+
+use super::entry::Entry;
+
+/// Pseudo-classes which can be used without quoting the selector, either as
+/// nested blocks or in headers like `red_text:hover`. Underscores become
+/// dashes, i.e. `focus_visible` is `:focus-visible`.
+const PSEUDO_CLASSES: &[&str] = &[
+    "active",
+    "checked",
+    "default",
+    "disabled",
+    "empty",
+    "enabled",
+    "first_child",
+    "first_of_type",
+    "focus",
+    "focus_visible",
+    "focus_within",
+    "hover",
+    "indeterminate",
+    "invalid",
+    "last_child",
+    "last_of_type",
+    "link",
+    "only_child",
+    "optional",
+    "placeholder_shown",
+    "read_only",
+    "read_write",
+    "required",
+    "target",
+    "user_invalid",
+    "valid",
+    "visited",
+];
+
+/// Validates the name of a pseudo-class and converts it to CSS, i.e.
+/// `focus_visible` to `focus-visible`
+pub fn pseudo_class(ident: &syn::Ident) -> syn::Result<String> {
+    if PSEUDO_CLASSES.iter().any(|name| ident == name) {
+        return Ok(ident.to_string().replace('_', "-"));
+    }
+
+    let known = PSEUDO_CLASSES
+        .iter()
+        .map(|name| format!("`{name}`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Err(syn::Error::new(
+        ident.span(),
+        format!("Unknown pseudo-class `{ident}`. Quote the selector or use one of {known}"),
+    ))
+}
+
+/// Declarations inside of a rule which only apply in a certain state, i.e.
+/// `checked { ... }` or `hover { ... }`. They generate a rule of their own
+/// with the pseudo-class appended to the selector: `.checkbox:checked { ... }`
+pub struct StateBlock {
+    pub pseudo_class: String,
+    pub span: Span,
+    pub entries: Punctuated<Entry, syn::token::Comma>,
+}
+
+impl StateBlock {
+    /// Whether the input starts with an identifier followed by a block
+    pub fn peek(input: ParseStream) -> bool {
+        input.peek(syn::Ident) && input.peek2(syn::token::Brace)
+    }
+}
+
+impl Parse for StateBlock {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let state = input.parse::<syn::Ident>()?;
+        let pseudo_class = pseudo_class(&state)?;
+
+        let content;
+        syn::braced!(content in input);
+        let entries = content.parse_terminated(Entry::parse, Token![,])?;
+        super::entry::check_duplicates(&entries)?;
+
+        Ok(StateBlock {
+            pseudo_class,
+            span: state.span(),
+            entries,
+        })
+    }
+}
@@ -0,0 +1,130 @@
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    visit_mut::VisitMut,
+    Token,
+};
+
+use super::entry::Entry;
+
+/// A parametrized list of declarations which can be instantiated multiple
+/// times, i.e. `@template badge(color) { color: color, padding: "2px" }`
+pub struct Template {
+    pub name: syn::Ident,
+    pub params: Punctuated<syn::Ident, syn::token::Comma>,
+    pub entries: Punctuated<Entry, syn::token::Comma>,
+}
+
+/// The usage of a template inside a rule, i.e. `badge_red = badge("red")`
+pub struct Instantiation {
+    pub template: syn::Ident,
+    pub args: Punctuated<syn::Expr, syn::token::Comma>,
+}
+
+impl Template {
+    /// Whether the input starts with `@template`
+    pub fn peek(input: ParseStream) -> bool {
+        let fork = input.fork();
+        fork.parse::<Token![@]>().is_ok()
+            && fork
+                .parse::<syn::Ident>()
+                .is_ok_and(|keyword| keyword == "template")
+    }
+
+    pub fn instantiate(
+        &self,
+        instantiation: &Instantiation,
+    ) -> syn::Result<Punctuated<Entry, syn::token::Comma>> {
+        if self.params.len() != instantiation.args.len() {
+            return Err(syn::Error::new(
+                instantiation.template.span(),
+                format!(
+                    "Template `{}` expects {} argument(s), but {} were given",
+                    self.name,
+                    self.params.len(),
+                    instantiation.args.len()
+                ),
+            ));
+        }
+
+        let mut substitution = Substitution {
+            params: self.params.iter().collect(),
+            args: instantiation.args.iter().collect(),
+        };
+
+        let mut entries = self.entries.clone();
+        for entry in entries.iter_mut() {
+            substitution.visit_expr_mut(&mut entry.value);
+            for value in &mut entry.repeated {
+                substitution.visit_expr_mut(value);
+            }
+        }
+
+        Ok(entries)
+    }
+}
+
+/// Replaces every usage of a template parameter by the corresponding argument
+struct Substitution<'a> {
+    params: Vec<&'a syn::Ident>,
+    args: Vec<&'a syn::Expr>,
+}
+
+impl<'a> VisitMut for Substitution<'a> {
+    fn visit_expr_mut(&mut self, expr: &mut syn::Expr) {
+        if let syn::Expr::Path(path) = expr {
+            if let (None, Some(ident)) = (&path.qself, path.path.get_ident()) {
+                if let Some(idx) = self.params.iter().position(|param| *param == ident) {
+                    *expr = self.args[idx].clone();
+                    return;
+                }
+            }
+        }
+
+        syn::visit_mut::visit_expr_mut(self, expr);
+    }
+}
+
+impl Parse for Template {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<Token![@]>()?;
+        let keyword = input.parse::<syn::Ident>()?;
+        if keyword != "template" {
+            return Err(syn::Error::new(
+                keyword.span(),
+                "Unknown directive. Expected `@template`",
+            ));
+        }
+
+        let name = input.parse::<syn::Ident>()?;
+
+        let params;
+        syn::parenthesized!(params in input);
+        let params = params.parse_terminated(syn::Ident::parse, Token![,])?;
+
+        let content;
+        syn::braced!(content in input);
+        let entries = content.parse_terminated(Entry::parse, Token![,])?;
+        super::entry::check_duplicates(&entries)?;
+
+        let template = Template {
+            name,
+            params,
+            entries,
+        };
+        Ok(template)
+    }
+}
+
+impl Parse for Instantiation {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let template = input.parse::<syn::Ident>()?;
+
+        let args;
+        syn::parenthesized!(args in input);
+        let args = args.parse_terminated(syn::Expr::parse, Token![,])?;
+
+        let instantiation = Instantiation { template, args };
+        Ok(instantiation)
+    }
+}
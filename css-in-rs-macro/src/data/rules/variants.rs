@@ -0,0 +1,367 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{parse::ParseStream, Token};
+
+use super::{header, Rule, RuleBody};
+
+/// The variants of a rule, i.e. the content of
+/// `variants { size: { small { ... }, large { ... } }, (size: small, tone: danger) { ... } }`
+#[derive(Default)]
+pub struct Variants {
+    pub dimensions: Vec<Dimension>,
+    pub compounds: Vec<Compound>,
+}
+
+/// A set of alternative styles of a rule, i.e. the `size` in
+/// `variants { size: { small { ... }, large { ... } } }`. Every variant is a
+/// class of its own, named like `button_size_small`.
+pub struct Dimension {
+    pub name: syn::Ident,
+    pub variants: Vec<Variant>,
+    /// The variant marked with `#[default]`
+    pub default: Option<syn::Ident>,
+}
+
+pub struct Variant {
+    pub name: syn::Ident,
+    pub rule: Rule,
+}
+
+/// Declarations which are added if several variants are selected together,
+/// i.e. `(size: small, tone: danger) { ... }`. The class is named like
+/// `button_size_small_tone_danger`.
+pub struct Compound {
+    /// The dimensions together with the selected variant
+    pub selection: Vec<(syn::Ident, syn::Ident)>,
+    pub rule: Rule,
+}
+
+/// Whether the input starts with `variants { ... }`
+pub fn peek(input: ParseStream) -> bool {
+    let fork = input.fork();
+    fork.parse::<syn::Ident>()
+        .is_ok_and(|keyword| keyword == "variants")
+        && fork.peek(syn::token::Brace)
+}
+
+/// Parses `variants { ... }` of the rule selecting the given class
+pub fn parse(input: ParseStream, classname: &str) -> syn::Result<Variants> {
+    input.parse::<syn::Ident>()?;
+    let content;
+    syn::braced!(content in input);
+
+    let mut result = Variants::default();
+    while !content.is_empty() {
+        if content.peek(syn::token::Paren) {
+            let compound = parse_compound(&content, classname, &result.dimensions)?;
+            result.compounds.push(compound);
+        } else {
+            result
+                .dimensions
+                .push(parse_dimension(&content, classname)?);
+        }
+
+        if content.is_empty() {
+            break;
+        }
+        content.parse::<Token![,]>()?;
+    }
+
+    Ok(result)
+}
+
+/// Parses `size: { small { ... }, large { ... } }`
+fn parse_dimension(input: ParseStream, classname: &str) -> syn::Result<Dimension> {
+    let name = input.parse::<syn::Ident>()?;
+    input.parse::<Token![:]>()?;
+
+    let body;
+    syn::braced!(body in input);
+    let mut variants = Vec::new();
+    let mut default = None;
+    while !body.is_empty() {
+        let attrs = body.call(syn::Attribute::parse_outer)?;
+        let variant = body.parse::<syn::Ident>()?;
+        if variants.iter().any(|other: &Variant| other.name == variant) {
+            return Err(syn::Error::new(
+                variant.span(),
+                format!("Variant `{variant}` of `{name}` is declared twice"),
+            ));
+        }
+        for attr in attrs {
+            if !attr.path().is_ident("default") {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "Only `#[default]` is supported for variants",
+                ));
+            }
+            attr.meta.require_path_only()?;
+            if default.is_some() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    format!("`{name}` has more than one default variant"),
+                ));
+            }
+            default = Some(variant.clone());
+        }
+
+        let header = class_header(format!("{classname}_{name}_{variant}"), variant.span());
+        let rule = parse_rule(&body, header)?;
+        variants.push(Variant {
+            name: variant,
+            rule,
+        });
+
+        if body.is_empty() {
+            break;
+        }
+        body.parse::<Token![,]>()?;
+    }
+
+    if variants.is_empty() {
+        return Err(syn::Error::new(
+            name.span(),
+            format!("`{name}` has no variants"),
+        ));
+    }
+    Ok(Dimension {
+        name,
+        variants,
+        default,
+    })
+}
+
+/// Parses `(size: small, tone: danger) { ... }`. The dimensions have to be
+/// declared before.
+fn parse_compound(
+    input: ParseStream,
+    classname: &str,
+    dimensions: &[Dimension],
+) -> syn::Result<Compound> {
+    let content;
+    let paren = syn::parenthesized!(content in input);
+    let pairs = content.parse_terminated(
+        |input| {
+            let dimension = input.parse::<syn::Ident>()?;
+            input.parse::<Token![:]>()?;
+            Ok((dimension, input.parse::<syn::Ident>()?))
+        },
+        Token![,],
+    )?;
+    let selection = pairs.into_iter().collect::<Vec<_>>();
+
+    for (idx, (name, variant)) in selection.iter().enumerate() {
+        let Some(dimension) = dimensions.iter().find(|dimension| dimension.name == *name) else {
+            return Err(syn::Error::new(
+                name.span(),
+                format!("Unknown dimension `{name}`"),
+            ));
+        };
+        if !dimension
+            .variants
+            .iter()
+            .any(|other| other.name == *variant)
+        {
+            return Err(syn::Error::new(
+                variant.span(),
+                format!("`{name}` has no variant `{variant}`"),
+            ));
+        }
+        if selection[..idx].iter().any(|(other, _)| other == name) {
+            return Err(syn::Error::new(
+                name.span(),
+                format!("`{name}` is selected twice"),
+            ));
+        }
+    }
+    if selection.len() < 2 {
+        return Err(syn::Error::new(
+            paren.span.join(),
+            "Compound variants select at least two dimensions",
+        ));
+    }
+
+    let suffix = selection
+        .iter()
+        .map(|(name, variant)| format!("_{name}_{variant}"))
+        .collect::<String>();
+    let header = class_header(format!("{classname}{suffix}"), paren.span.join());
+    let rule = parse_rule(input, header)?;
+    Ok(Compound { selection, rule })
+}
+
+/// Parses the declarations of a variant or compound variant
+fn parse_rule(input: ParseStream, header: header::Header) -> syn::Result<Rule> {
+    let declarations;
+    syn::braced!(declarations in input);
+    let body = super::parse_declarations(&declarations, &header)?;
+    if matches!(&body, RuleBody::Normal { variants, .. } if !variants.is_empty()) {
+        return Err(syn::Error::new(header.span, "Variants cannot be nested"));
+    }
+    Ok(Rule { header, body })
+}
+
+/// The header selecting the class of a variant, i.e. `.button_size_small`
+fn class_header(name: String, span: Span) -> header::Header {
+    header::Header {
+        parts: vec![
+            header::Part::Raw(".".to_owned()),
+            header::Part::ClassName(name.clone()),
+        ],
+        span,
+        at_rule: false,
+        source: name,
+    }
+}
+
+/// `Small` for `small`
+fn variant_ident(variant: &syn::Ident) -> syn::Ident {
+    let name = variant
+        .to_string()
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect::<String>();
+    syn::Ident::new(&name, variant.span())
+}
+
+impl Variants {
+    pub fn is_empty(&self) -> bool {
+        self.dimensions.is_empty()
+    }
+
+    /// The rules of the variants, followed by the rules of the compound variants
+    pub fn rules(&self) -> impl Iterator<Item = &Rule> {
+        let variants = self
+            .dimensions
+            .iter()
+            .flat_map(|dimension| &dimension.variants)
+            .map(|variant| &variant.rule);
+        variants.chain(self.compounds.iter().map(|compound| &compound.rule))
+    }
+}
+
+impl Dimension {
+    /// The name of the generated enum, i.e. `Size` for `size`
+    pub fn enum_ident(&self) -> syn::Ident {
+        variant_ident(&self.name)
+    }
+
+    /// Whether another dimension of the same name has the same variants and
+    /// default, so both can share the enum
+    pub fn same_variants(&self, other: &Dimension) -> bool {
+        let names = |dimension: &Dimension| {
+            let mut names = dimension
+                .variants
+                .iter()
+                .map(|variant| variant.name.to_string())
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+        names(self) == names(other) && self.default == other.default
+    }
+
+    /// `pub enum Size { Small, Large }`, which implements `Default` if a
+    /// variant is marked with `#[default]`
+    pub fn enum_tokens(&self, vis: &syn::Visibility, classes: &syn::Ident) -> TokenStream {
+        let ident = self.enum_ident();
+        let variants = self.variants.iter().map(|variant| {
+            let attr = (self.default.as_ref() == Some(&variant.name)).then(|| quote!(#[default]));
+            let variant = variant_ident(&variant.name);
+            quote!(#attr #variant)
+        });
+        let default = self.default.as_ref().map(|_| quote!(Default,));
+        let doc = format!(" Variants of `{}`, see [{classes}]", self.name);
+        quote! {
+            #[doc = #doc]
+            #[derive(Clone, Copy, Debug, #default PartialEq, Eq, Hash)]
+            #[allow(dead_code)]
+            #vis enum #ident {
+                #(#variants,)*
+            }
+        }
+    }
+
+    /// The class of the selected variant
+    pub fn select_tokens(&self, classname: &syn::Ident) -> TokenStream {
+        let ident = self.enum_ident();
+        let param = &self.name;
+        let arms = self.variants.iter().map(|variant| {
+            let variant_ident = variant_ident(&variant.name);
+            let field = format_ident!("{classname}_{param}_{}", variant.name);
+            quote!(#ident::#variant_ident => self.#field.as_str(),)
+        });
+        quote! {
+            ::core::option::Option::Some(match #param {
+                #(#arms)*
+            })
+        }
+    }
+}
+
+/// `pub fn button(&self, size: Size, tone: Tone) -> String`, and
+/// `button_variants` returning the classes without allocating
+pub fn method_tokens(classname: &syn::Ident, variants: &Variants, span: Span) -> TokenStream {
+    let params = variants
+        .dimensions
+        .iter()
+        .map(|dimension| {
+            let param = &dimension.name;
+            let ident = dimension.enum_ident();
+            quote!(#param: #ident)
+        })
+        .collect::<Vec<_>>();
+    let args = variants.dimensions.iter().map(|dimension| &dimension.name);
+    let selections = variants
+        .dimensions
+        .iter()
+        .map(|dimension| dimension.select_tokens(classname));
+    let compounds = variants.compounds.iter().map(|compound| {
+        let conditions = compound.selection.iter().map(|(name, variant)| {
+            let ident = variant_ident(name);
+            let variant = variant_ident(variant);
+            quote!(#name == #ident::#variant)
+        });
+        let suffix = compound
+            .selection
+            .iter()
+            .map(|(name, variant)| format!("_{name}_{variant}"))
+            .collect::<String>();
+        let field = format_ident!("{classname}{suffix}");
+        quote!((#(#conditions)&&*).then_some(self.#field.as_str()))
+    });
+    let count = 1 + variants.dimensions.len() + variants.compounds.len();
+
+    let doc = format!(
+        " Returns the classes of `{classname}` together with the classes of the given variants"
+    );
+    let variants_doc = format!(
+        " Like [Self::{classname}], but borrows the classes instead of allocating, \
+         i.e. for `css_in_rs::classes!`"
+    );
+    let method = syn::Ident::new(&classname.to_string(), span);
+    let variants_method = format_ident!("{classname}_variants", span = span);
+    quote! {
+        #[doc = #doc]
+        #[allow(dead_code)]
+        pub fn #method(&self, #(#params),*) -> String {
+            self.#variants_method(#(#args),*).to_string()
+        }
+
+        #[doc = #variants_doc]
+        #[allow(dead_code)]
+        pub fn #variants_method(&self, #(#params),*) -> ::css_in_rs::class_list::VariantClasses<'_, #count> {
+            ::css_in_rs::class_list::VariantClasses::new([
+                ::core::option::Option::Some(self.#classname.as_str()),
+                #(#selections,)*
+                #(#compounds,)*
+            ])
+        }
+    }
+}
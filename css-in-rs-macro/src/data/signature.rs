@@ -3,6 +3,8 @@ use syn::parse::{Parse, ParseStream};
 pub struct Signature {
     pub theme_varname: syn::Ident,
     pub theme_type: syn::Ident,
+    /// Visibility of the generated struct, i.e. `-> pub MyClasses`
+    pub vis: syn::Visibility,
     pub classname: syn::Ident,
 }
 
@@ -20,11 +22,13 @@ impl Parse for Signature {
         };
 
         input.parse::<syn::token::RArrow>()?;
+        let vis = input.parse::<syn::Visibility>()?;
         let classname = input.parse::<syn::Ident>()?;
 
         let signature = Signature {
             theme_varname,
             theme_type,
+            vis,
             classname,
         };
         Ok(signature)
@@ -0,0 +1,285 @@
+use proc_macro2::{Delimiter, Group, Punct, Spacing, Span, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+
+use crate::data::rules::RuleList;
+
+/// The input of `define_styles_fragment!`, i.e. `pub button_base { ... }`
+pub struct Fragment {
+    attrs: Vec<syn::Attribute>,
+    vis: syn::Visibility,
+    ident: syn::Ident,
+    rules: TokenStream,
+}
+
+impl Parse for Fragment {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let vis = input.parse::<syn::Visibility>()?;
+        let ident = input.parse::<syn::Ident>()?;
+
+        let content;
+        syn::braced!(content in input);
+        let rules = content.parse::<TokenStream>()?;
+
+        // Fragments including other fragments can only be checked once they are resolved
+        if find_include(rules.clone()).is_none() {
+            syn::parse2::<RuleList>(rules.clone())?;
+        }
+
+        Ok(Fragment {
+            attrs,
+            vis,
+            ident,
+            rules,
+        })
+    }
+}
+
+/// The fragment becomes a macro which passes its rules to `make_styles!`,
+/// since a proc macro cannot read the tokens of items defined elsewhere
+impl ToTokens for Fragment {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let attrs = &self.attrs;
+        let ident = &self.ident;
+        let rules = dollar_crate(self.rules.clone());
+
+        let (export, reexport) = match &self.vis {
+            syn::Visibility::Public(_) => (Some(quote!(#[macro_export])), None),
+            syn::Visibility::Inherited => (None, None),
+            vis => (None, Some(quote!(#vis use #ident;))),
+        };
+
+        let ts = quote! {
+            #(#attrs)*
+            #export
+            macro_rules! #ident {
+                ($($input:tt)*) => {
+                    ::css_in_rs::make_styles! { @fragment { #rules } $($input)* }
+                };
+            }
+            #reexport
+        };
+        ts.to_tokens(tokens);
+    }
+}
+
+/// Replaces `crate` by `$crate`, so paths like `crate::base` refer to the
+/// crate of the fragment, even if it is included by another crate
+fn dollar_crate(tokens: TokenStream) -> TokenStream {
+    let mut result = TokenStream::new();
+    for token in tokens {
+        match token {
+            TokenTree::Group(group) => {
+                let mut replaced = Group::new(group.delimiter(), dollar_crate(group.stream()));
+                replaced.set_span(group.span());
+                result.extend([TokenTree::Group(replaced)]);
+            }
+            TokenTree::Ident(ident) if ident == "crate" => {
+                let dollar = Punct::new('$', Spacing::Alone);
+                result.extend([TokenTree::Punct(dollar), TokenTree::Ident(ident)]);
+            }
+            token => result.extend([token]),
+        }
+    }
+    result
+}
+
+/// The input of `make_styles!` once the next fragment is resolved
+pub enum Expansion {
+    /// No `@include` is left, so the input can be parsed
+    Style(TokenStream),
+    /// The fragment of the next `@include` has to be looked up by invoking it
+    Include(TokenStream),
+}
+
+/// Resolves the `@include` of the fragment given as `@fragment { ... }` at the
+/// start of the input, and looks up the fragment of the next one, if any.
+/// Fragments are resolved in order, one per expansion.
+pub fn resolve(input: TokenStream) -> syn::Result<Expansion> {
+    let tokens = input.into_iter().collect::<Vec<_>>();
+    let fragment = match tokens.as_slice() {
+        [TokenTree::Punct(at), TokenTree::Ident(keyword), TokenTree::Group(rules), ..]
+            if at.as_char() == '@'
+                && keyword == "fragment"
+                && rules.delimiter() == Delimiter::Brace =>
+        {
+            Some((keyword.span(), rules.stream()))
+        }
+        _ => None,
+    };
+
+    let input = match fragment {
+        Some((span, rules)) => {
+            let mut rules = Some(rules);
+            let input = replace_include(tokens.into_iter().skip(3).collect(), &mut rules);
+            if rules.is_some() {
+                return Err(syn::Error::new(
+                    span,
+                    "`@fragment` is only given by fragments of `@include`",
+                ));
+            }
+            input
+        }
+        None => tokens.into_iter().collect(),
+    };
+
+    Ok(match find_include(input.clone()) {
+        Some(path) => Expansion::Include(quote!(#path! { #input })),
+        None => Expansion::Style(input),
+    })
+}
+
+/// Returns the path of the first `@include path`
+fn find_include(tokens: TokenStream) -> Option<TokenStream> {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    for (idx, token) in tokens.iter().enumerate() {
+        if let Some(path) = include_at(&tokens, idx) {
+            return Some(path.iter().cloned().collect());
+        }
+        if let TokenTree::Group(group) = token {
+            if let Some(path) = find_include(group.stream()) {
+                return Some(path);
+            }
+        }
+    }
+    None
+}
+
+/// Returns the tokens of the path if `@include path` starts at the given index
+fn include_at(tokens: &[TokenTree], idx: usize) -> Option<&[TokenTree]> {
+    let (Some(TokenTree::Punct(at)), Some(TokenTree::Ident(keyword))) =
+        (tokens.get(idx), tokens.get(idx + 1))
+    else {
+        return None;
+    };
+    if at.as_char() != '@' || keyword != "include" {
+        return None;
+    }
+
+    let path = &tokens[idx + 2..];
+    let end = path
+        .iter()
+        .position(|token| matches!(token, TokenTree::Punct(comma) if comma.as_char() == ','))
+        .unwrap_or(path.len());
+    Some(&path[..end])
+}
+
+/// Replaces the first `@include` by the given rules
+fn replace_include(tokens: TokenStream, rules: &mut Option<TokenStream>) -> TokenStream {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut result = Vec::with_capacity(tokens.len());
+    let mut idx = 0;
+    while idx < tokens.len() {
+        if rules.is_some() {
+            if let Some(path) = include_at(&tokens, idx) {
+                let span = tokens[idx + 1].span();
+                let mut fragment = rules.take().unwrap().into_iter().collect::<Vec<_>>();
+                if matches!(fragment.last(), Some(TokenTree::Punct(comma)) if comma.as_char() == ',')
+                {
+                    fragment.pop();
+                }
+
+                idx += 2 + path.len();
+                // The comma after an empty fragment would be superfluous
+                if fragment.is_empty() {
+                    idx += 1;
+                }
+                result.extend(fragment.into_iter().map(|token| respan(token, span)));
+                continue;
+            }
+        }
+
+        let token = match &tokens[idx] {
+            TokenTree::Group(group) if rules.is_some() => {
+                let mut replaced =
+                    Group::new(group.delimiter(), replace_include(group.stream(), rules));
+                replaced.set_span(group.span());
+                TokenTree::Group(replaced)
+            }
+            token => token.clone(),
+        };
+        result.push(token);
+        idx += 1;
+    }
+    result.into_iter().collect()
+}
+
+/// Moves the tokens of a fragment to the `@include`. They are written within
+/// the macro of the fragment, so the variables of `make_styles!`, like the
+/// theme, would not be visible otherwise. `$crate` has to keep its span to
+/// refer to the crate of the fragment.
+fn respan(token: TokenTree, span: Span) -> TokenTree {
+    match token {
+        TokenTree::Ident(ident) if ident == "$crate" => TokenTree::Ident(ident),
+        TokenTree::Group(group) => {
+            let stream = group.stream().into_iter().map(|token| respan(token, span));
+            let mut respanned = Group::new(group.delimiter(), stream.collect());
+            respanned.set_span(span);
+            TokenTree::Group(respanned)
+        }
+        mut token => {
+            token.set_span(span);
+            token
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+
+    use super::{resolve, Expansion};
+
+    fn resolved(input: proc_macro2::TokenStream) -> String {
+        match resolve(input).unwrap() {
+            Expansion::Style(style) => format!("style: {style}"),
+            Expansion::Include(include) => format!("include: {include}"),
+        }
+    }
+
+    #[test]
+    fn includes() {
+        let input = quote! {
+            (theme: MyTheme) -> MyClasses {
+                @include design::base,
+                "@media print" {
+                    @include extra
+                },
+            }
+        };
+        let expected = quote!(design::base! { #input });
+        assert_eq!(resolved(input), format!("include: {expected}"));
+
+        let input = quote! {
+            @fragment { base { color: "red" }, }
+            (theme: MyTheme) -> MyClasses {
+                @include design::base,
+                "@media print" {
+                    @include extra
+                },
+            }
+        };
+        let expected = quote! {
+            extra! {
+                (theme: MyTheme) -> MyClasses {
+                    base { color: "red" },
+                    "@media print" {
+                        @include extra
+                    },
+                }
+            }
+        };
+        assert_eq!(resolved(input), format!("include: {expected}"));
+
+        let input = quote! {
+            @fragment {}
+            (theme: MyTheme) -> MyClasses {
+                @include empty,
+                other {},
+            }
+        };
+        let expected = quote!((theme: MyTheme) -> MyClasses { other {}, });
+        assert_eq!(resolved(input), format!("style: {expected}"));
+    }
+}
@@ -0,0 +1,161 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream},
+    Token,
+};
+
+use crate::{
+    data::{
+        options::Options,
+        rules::{
+            header::{Header, Part},
+            Rule, RuleBody, RuleList,
+        },
+    },
+    output::{Output, ToOutput},
+};
+
+/// The input of `keyframes!`, i.e. `pub PULSE = "pulse" { "from" { ... } }`
+pub struct Keyframes {
+    attrs: Vec<syn::Attribute>,
+    vis: syn::Visibility,
+    ident: syn::Ident,
+    name: syn::LitStr,
+    steps: RuleList,
+}
+
+impl Keyframes {
+    /// Steps select keyframes, not classes. Other structs cannot be referenced
+    /// either, since there is no provider to look them up
+    fn check_step(step: &Rule) -> syn::Result<()> {
+        let header = &step.header;
+        let is_plain = |part: &Part| matches!(part, Part::Raw(_));
+        if header.at_rule || !header.parts.iter().all(is_plain) {
+            return Err(syn::Error::new(
+                header.span,
+                "Expected a keyframe selector like \"from\", \"to\" or \"50%\"",
+            ));
+        }
+
+        if let RuleBody::Normal { states, .. } = &step.body {
+            if let Some(state) = states.first() {
+                return Err(syn::Error::new(
+                    state.span,
+                    "State blocks cannot be used in keyframes",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Parse for Keyframes {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let vis = input.parse::<syn::Visibility>()?;
+        let ident = input.parse::<syn::Ident>()?;
+        input.parse::<Token![=]>()?;
+        let name = input.parse::<syn::LitStr>()?;
+
+        let content;
+        syn::braced!(content in input);
+        let steps = content.parse::<RuleList>()?;
+        for step in &steps.rules {
+            Keyframes::check_step(step)?;
+        }
+
+        let keyframes = Keyframes {
+            attrs,
+            vis,
+            ident,
+            name,
+            steps,
+        };
+        Ok(keyframes)
+    }
+}
+
+impl ToTokens for Keyframes {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let Keyframes {
+            attrs,
+            vis,
+            ident,
+            name,
+            steps,
+        } = self;
+
+        let name = quote!(concat!(env!("CARGO_PKG_NAME"), "-", #name));
+        let header = Header {
+            parts: vec![
+                Part::Raw("@keyframes ".to_owned()),
+                Part::Name(name.clone()),
+            ],
+            span: ident.span(),
+            at_rule: true,
+            source: format!("@keyframes({})", self.name.value()),
+        };
+
+        let mut output = Output::new(ident, &[], &Options::default());
+        output.has_context = false;
+        header.append(&mut output);
+        output.push_str(" {\n");
+        steps.append(&mut output);
+        output.push_str("}\n");
+
+        let ts = quote! {
+            #(#attrs)*
+            #vis const #ident: ::css_in_rs::Keyframes = {
+                fn generate(css: &mut String) {
+                    use ::core::fmt::Write;
+                    #output
+                }
+                ::css_in_rs::Keyframes::new(#name, generate)
+            };
+        };
+        ts.to_tokens(tokens);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+
+    use super::Keyframes;
+
+    #[test]
+    fn steps() {
+        let input = quote! {
+            pub PULSE = "pulse" {
+                "from" {
+                    opacity: "0",
+                },
+                "50%" {
+                    opacity: OPACITY,
+                },
+            }
+        };
+        assert!(syn::parse2::<Keyframes>(input).is_ok());
+
+        let input = quote! {
+            PULSE = "pulse" {
+                from {
+                    opacity: "0",
+                },
+            }
+        };
+        let err = syn::parse2::<Keyframes>(input).err().unwrap();
+        assert!(err.to_string().starts_with("Expected a keyframe selector"));
+
+        let input = quote! {
+            PULSE = "pulse" {
+                "{IconClasses::icon}" {
+                    opacity: "0",
+                },
+            }
+        };
+        assert!(syn::parse2::<Keyframes>(input).is_err());
+    }
+}
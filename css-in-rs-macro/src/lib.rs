@@ -4,8 +4,13 @@ use proc_macro::TokenStream;
 use quote::ToTokens;
 
 mod data;
+mod fragment;
+mod keyframes;
+mod lint;
 mod output;
+mod preview;
 mod result;
+mod theme;
 
 /// Introduces dynamic CSS code which can be injected.
 ///
@@ -44,21 +49,985 @@ mod result;
 /// struct MyClasses {
 ///     pub text: String,
 ///     pub header: String,
+///     pub root_all: String, // "css-17 css-18"; all classes, separated by spaces
 /// }
 ///
 /// impl ::css_in_rs::Classes for MyClasses {
 ///     # type Theme = css_in_rs::EmptyTheme;
-///     # fn generate(_a: &css_in_rs::EmptyTheme, _b: &mut String, _c: &mut u64) {}
-///     # fn new(_: u64) -> Self { todo!() }
+///     # fn generate(_a: &Self::Theme, _b: &mut String, _c: &mut css_in_rs::GenerateContext<Self::Theme>) {}
+///     # fn new(_: &str, _: u64) -> Self { todo!() }
 ///     /* ... */
 /// }
 /// ```
 /// You can inject this style into the DOM using a `StyleProvider` (see
 /// css-in-rs crate). It will hand you a `MyClasses` instance with uniquely
 /// generated classnames (usually something like `css-17`).
+///
+/// In debug builds, every generated rule is preceded by a comment pointing to
+/// its definition, i.e. `/* MyClasses::text @ src/button.rs:29 */`.
+///
+/// # Values
+/// A value can be any Rust expression implementing `Display`, so constants
+/// shared with layout code can be used directly. Expressions which are more
+/// than a literal, a path or a call can be wrapped in parentheses or braces:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{testing::render_classes_to_string, EmptyTheme};
+/// const SIDEBAR_WIDTH: u32 = 240;
+/// const BRAND_COLOR: &str = "#ff0066";
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> LayoutClasses {
+///         sidebar {
+///             width: { format!("{}px", SIDEBAR_WIDTH) },
+///             color: (BRAND_COLOR),
+///             z_index: if SIDEBAR_WIDTH > 200 { 10 } else { 1 },
+///         },
+///     }
+/// }
+///
+/// let css = render_classes_to_string::<LayoutClasses>(&EmptyTheme);
+/// assert_eq!(
+///     css,
+///     ".css-0 {\n  width: 240px;\n  color: #ff0066;\n  z-index: 10;\n}\n"
+/// );
+/// ```
+///
+/// Computed design tokens can be exposed as methods of the theme and called in
+/// value position, i.e. `theme.spacing(3)` or
+/// `theme.palette.primary.darken(0.2)`. The values are computed again whenever
+/// the theme changes:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::testing::render_classes_to_string;
+/// #[derive(Clone)]
+/// struct MyTheme {
+///     pub unit: u32,
+/// }
+/// impl MyTheme {
+///     fn spacing(&self, factor: u32) -> String {
+///         format!("{}px", self.unit * factor)
+///     }
+/// }
+/// impl css_in_rs::Theme for MyTheme {
+///     # fn fast_cmp(&self, other: &MyTheme) -> bool { false }
+/// }
+///
+/// make_styles! {
+///     (theme: MyTheme) -> CardClasses {
+///         card {
+///             padding: theme.spacing(3),
+///         },
+///     }
+/// }
+///
+/// let css = render_classes_to_string::<CardClasses>(&MyTheme { unit: 8 });
+/// assert_eq!(css, ".css-0 {\n  padding: 24px;\n}\n");
+/// ```
+///
+/// # Templates
+/// Families of similar rules can share a template. A template is defined once
+/// using `@template name(params...) { ... }` and instantiated with `= name(args...)`
+/// instead of a rule body. Each instantiation generates its own rule; parameters
+/// are substituted by the given arguments:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::EmptyTheme;
+/// make_styles! {
+///     (_theme: EmptyTheme) -> BadgeClasses {
+///         @template badge(color, background) {
+///             color: color,
+///             background_color: background,
+///             padding: "2px 4px",
+///         },
+///         badge_danger = badge("white", "red"),
+///         badge_info = badge("black", "lightblue"),
+///         "span.badge_small" = badge("gray", "white"),
+///     }
+/// }
+/// ```
+///
+/// # Loops
+/// Numbered rules, i.e. of grid systems or spacing utilities, can be generated
+/// using `@for i in 1..=12 { ... }`. The loop is unrolled at compile time:
+/// `name_{i}` becomes a class like `name_3`, `{i}` is replaced within quoted
+/// selectors, and `i` can be used as a number within values:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{CssFormat, EmptyTheme, StyleProvider};
+/// make_styles! {
+///     (_theme: EmptyTheme) -> GridClasses {
+///         @for i in 1..=3 {
+///             col_{i} {
+///                 width: format!("{}%", i * 100 / 3),
+///             },
+///             "li > .col_{i}:first-child" {
+///                 margin_left: 0,
+///             },
+///         },
+///     }
+/// }
+///
+/// let style_provider = StyleProvider::new_headless(EmptyTheme);
+/// style_provider.set_format(CssFormat::Minified);
+/// let classes = style_provider.add_classes::<GridClasses>();
+/// assert_eq!(classes.col_3, "css-2");
+/// assert!(style_provider
+///     .current_css()
+///     .starts_with(".css-0{width:33%}li>.css-0:first-child{margin-left:0}"));
+/// ```
+///
+/// # Fragments
+/// Rules defined using [define_styles_fragment!] are inserted using
+/// `@include path::to::fragment`, i.e. to share them across crates.
+///
+/// # Fallback values
+/// A property can be declared multiple times by giving an array of values.
+/// The declarations are emitted in order, so browsers use the last value they
+/// support and ignore the others. Declaring a property twice with different
+/// values is rejected instead, since that is usually a copy-paste mistake:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{testing::render_classes_to_string, EmptyTheme};
+/// make_styles! {
+///     (_theme: EmptyTheme) -> LayoutClasses {
+///         grid {
+///             display: ["flex", "grid"],
+///             height: ["100vh", "100dvh"],
+///         },
+///     }
+/// }
+///
+/// let css = render_classes_to_string::<LayoutClasses>(&EmptyTheme);
+/// assert_eq!(
+///     css,
+///     ".css-0 {\n  display: flex;\n  display: grid;\n  height: 100vh;\n  height: 100dvh;\n}\n"
+/// );
+/// ```
+///
+/// # Selector lists
+/// A rule can apply to a comma-separated list of selectors. The declarations
+/// are only emitted once, and a classname which appears in multiple selectors
+/// still becomes a single field. Nested pseudo-class blocks (see below) are
+/// appended to every selector of the list:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{CssFormat, EmptyTheme, StyleProvider};
+/// make_styles! {
+///     (_theme: EmptyTheme) -> ButtonClasses {
+///         "button.primary, input[type=submit].primary" {
+///             color: "white",
+///             hover {
+///                 color: "yellow",
+///             },
+///         },
+///     }
+/// }
+///
+/// let provider = StyleProvider::new_headless(EmptyTheme);
+/// provider.set_format(CssFormat::Minified);
+/// let classes = provider.add_classes::<ButtonClasses>();
+/// assert_eq!(classes.primary, "css-0");
+/// assert_eq!(
+///     provider.current_css(),
+///     "button.css-0,input[type=submit].css-0{color:white}\
+///      button.css-0:hover,input[type=submit].css-0:hover{color:yellow}"
+/// );
+/// ```
+///
+/// # Referencing other classes
+/// Selectors can reference the classes of other structs using `{Type::field}`,
+/// i.e. to adjust child components depending on their parent. These structs
+/// have to use the same theme. They are mounted automatically before the
+/// referencing style. To use a struct from another module, make it public
+/// using `-> pub Name`:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{CssFormat, EmptyTheme, StyleProvider};
+/// mod icon {
+///     # use css_in_rs_macro::make_styles;
+///     # use css_in_rs::EmptyTheme;
+///     make_styles! {
+///         (_theme: EmptyTheme) -> pub IconClasses {
+///             icon {
+///                 width: "16px",
+///             },
+///         }
+///     }
+/// }
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> ToolbarClasses {
+///         ".toolbar > {icon::IconClasses::icon}" {
+///             width: "24px",
+///         },
+///     }
+/// }
+///
+/// let provider = StyleProvider::new_headless(EmptyTheme);
+/// provider.set_format(CssFormat::Minified);
+/// let toolbar = provider.add_classes::<ToolbarClasses>();
+/// assert_eq!(toolbar.toolbar, "css-1");
+/// assert_eq!(provider.current_css(), ".css-0{width:16px}.css-1>.css-0{width:24px}");
+/// ```
+///
+/// # Selectors
+/// Code which expects selectors instead of classnames, i.e. `querySelector` or
+/// test harnesses, can use `selector`. It returns `None` if there is no class
+/// with the given name. Atomic classes are not part of the selector:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{EmptyTheme, StyleProvider};
+/// make_styles! {
+///     #[atomic]
+///     (_theme: EmptyTheme) -> MyClasses {
+///         root {
+///             color: "red",
+///         },
+///     }
+/// }
+///
+/// let provider = StyleProvider::new_headless(EmptyTheme);
+/// let classes = provider.add_classes::<MyClasses>();
+/// assert_eq!(classes.selector("root").as_deref(), Some(".css-0"));
+/// assert_eq!(classes.selector("typo"), None);
+/// ```
+///
+/// # Markup preview
+/// With the `#[preview]` option, the documentation of the generated struct
+/// shows sample markup for every selector containing classes. This way, the
+/// docs of component libraries show which markup the styles expect, without
+/// maintaining it by hand. For the following styles, the docs show
+/// `<div class="card">` containing `<h2 class="title"></h2>`:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::EmptyTheme;
+/// make_styles! {
+///     #[preview]
+///     (_theme: EmptyTheme) -> pub CardClasses {
+///         ".card > h2.title" {
+///             margin: "0",
+///         },
+///     }
+/// }
+/// ```
+///
+/// # Priority
+/// Styles are emitted in the order in which they are mounted, so the cascade
+/// would depend on which component is rendered first. With the
+/// `#[priority(...)]` option, styles with a lower priority are emitted before
+/// the ones with a higher priority, i.e. base styles before component styles
+/// before utility overrides. The default priority is `0`; styles with the same
+/// priority keep their mount order:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{CssFormat, EmptyTheme, StyleProvider};
+/// make_styles! {
+///     #[priority(10)]
+///     (_theme: EmptyTheme) -> UtilityClasses {
+///         hidden {
+///             display: "none",
+///         },
+///     }
+/// }
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> ButtonClasses {
+///         button {
+///             display: "inline-block",
+///         },
+///     }
+/// }
+///
+/// let provider = StyleProvider::new_headless(EmptyTheme);
+/// provider.set_format(CssFormat::Minified);
+/// provider.add_classes::<UtilityClasses>();
+/// provider.add_classes::<ButtonClasses>();
+/// assert_eq!(
+///     provider.current_css(),
+///     ".css-1{display:inline-block}.css-0{display:none}"
+/// );
+/// ```
+///
+/// # Serde
+/// With the `serde` feature of `css_in_rs`, the generated structs implement
+/// `Serialize` and `Deserialize`, i.e. to send the classnames to a web worker
+/// or a LiveView server, or to embed them into JSON-driven templates. They are
+/// serialized as maps from the names of the classes (and `root_all`) to
+/// their classnames.
+///
+/// # Keyframes
+/// Keyframes and counters are global identifiers. To share them between
+/// multiple structs, declare their names using `css_in_rs::shared_names!`.
+/// Keyframes with such a name are defined using `@keyframes(NAME) { ... }`;
+/// the name itself can be used in any value. Alternatively, [keyframes!]
+/// defines keyframes independently of any struct.
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::EmptyTheme;
+/// css_in_rs::shared_names! {
+///     pub FADE_IN = "fade-in";
+/// }
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> DialogClasses {
+///         @keyframes(FADE_IN) {
+///             "from" {
+///                 opacity: "0",
+///             },
+///         },
+///         dialog {
+///             animation: format!("{FADE_IN} 0.2s"),
+///         },
+///     }
+/// }
+/// ```
+///
+/// Names which are only used by one struct can be defined right in the
+/// at-rule instead, i.e. `@keyframes pulse { ... }`. This works for
+/// `@counter-style` and `@font-palette-values` as well. Like classes, these
+/// names are renamed to avoid collisions. The struct gets a field with the
+/// generated name, and within the styles, the name is a local variable:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{CssFormat, EmptyTheme, StyleProvider};
+/// make_styles! {
+///     (_theme: EmptyTheme) -> ListClasses {
+///         @keyframes pulse {
+///             "50%" {
+///                 opacity: "0.5",
+///             },
+///         },
+///         @counter-style bullets {
+///             system: "cyclic",
+///             symbols: "'*'",
+///         },
+///         list {
+///             list_style_type: bullets,
+///             animation: format!("{pulse} 2s infinite"),
+///         },
+///     }
+/// }
+///
+/// let provider = StyleProvider::new_headless(EmptyTheme);
+/// provider.set_format(CssFormat::Minified);
+/// let classes = provider.add_classes::<ListClasses>();
+/// assert_eq!(classes.pulse, "css-2");
+/// assert_eq!(
+///     provider.current_css(),
+///     "@keyframes css-2{50%{opacity:0.5}}@counter-style css-1{system:cyclic;symbols:'*'}\
+///      .css-0{list-style-type:css-1;animation:css-2 2s infinite}"
+/// );
+/// ```
+///
+/// # Conditional rules
+/// Rules within `@if condition { ... }` are only generated if the condition
+/// holds. Further branches can follow using `@else if condition { ... }` and
+/// `@else { ... }`. The conditions are evaluated whenever the CSS is generated,
+/// so the rules follow theme updates. Classes of all branches are fields of the
+/// struct, so the classnames stay the same. Within branches, atomic and shared
+/// classes are not created, see `#[atomic]` and `#[dedup]`:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{CssFormat, StyleProvider};
+/// #[derive(Clone)]
+/// struct MyTheme {
+///     pub compact: bool,
+/// }
+/// impl css_in_rs::Theme for MyTheme {
+///     # fn fast_cmp(&self, other: &MyTheme) -> bool { false }
+/// }
+///
+/// make_styles! {
+///     (theme: MyTheme) -> ListClasses {
+///         item {
+///             display: "flex",
+///         },
+///         @if theme.compact {
+///             item {
+///                 padding: "2px",
+///             },
+///         } @else {
+///             item {
+///                 padding: "8px",
+///             },
+///             icon {
+///                 margin: "4px",
+///             },
+///         },
+///     }
+/// }
+///
+/// let style_provider = StyleProvider::new_headless(MyTheme { compact: true });
+/// style_provider.set_format(CssFormat::Minified);
+/// let classes = style_provider.add_classes::<ListClasses>();
+/// assert_eq!(classes.icon, "css-0");
+/// assert_eq!(style_provider.current_css(), ".css-1{display:flex}.css-1{padding:2px}");
+///
+/// style_provider.update_theme(MyTheme { compact: false });
+/// assert_eq!(
+///     style_provider.current_css(),
+///     ".css-1{display:flex}.css-1{padding:8px}.css-0{margin:4px}"
+/// );
+/// ```
+///
+/// # Media queries
+/// Media queries can be computed at runtime using `@media(...)`, i.e. from
+/// the breakpoints of the theme (see `css_in_rs::breakpoints`):
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::EmptyTheme;
+/// make_styles! {
+///     (_theme: EmptyTheme) -> CardClasses {
+///         @media(format!("(min-width: {}px)", 600)) {
+///             card {
+///                 padding: "16px",
+///             },
+///         },
+///     }
+/// }
+/// ```
+///
+/// # Reduced motion
+/// `"@motion-safe"` is a shorthand for
+/// `"@media (prefers-reduced-motion: no-preference)"` and `"@motion-reduce"`
+/// for `"@media (prefers-reduced-motion: reduce)"`, so animations only run
+/// for users who have not asked to reduce motion. At runtime, the preference
+/// is available from `css_in_rs::motion`.
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{CssFormat, EmptyTheme, StyleProvider};
+/// make_styles! {
+///     (_theme: EmptyTheme) -> SpinnerClasses {
+///         "@motion-safe" {
+///             spinner {
+///                 animation: "spin 1s linear infinite",
+///             },
+///         },
+///         "@motion-reduce" {
+///             spinner {
+///                 opacity: "0.5",
+///             },
+///         },
+///     }
+/// }
+///
+/// let provider = StyleProvider::new_headless(EmptyTheme);
+/// provider.set_format(CssFormat::Minified);
+/// provider.add_classes::<SpinnerClasses>();
+/// assert_eq!(
+///     provider.current_css(),
+///     "@media (prefers-reduced-motion: no-preference){.css-0{animation:spin 1s linear infinite}}\
+///      @media (prefers-reduced-motion: reduce){.css-0{opacity:0.5}}"
+/// );
+/// ```
+///
+/// # Print styles
+/// `@print { ... }` is a shorthand for `"@media print" { ... }`, so components
+/// can bundle their print overrides. The classes within get the same
+/// classnames as everywhere else:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{CssFormat, EmptyTheme, StyleProvider};
+/// make_styles! {
+///     (_theme: EmptyTheme) -> ToolbarClasses {
+///         toolbar {
+///             display: "flex",
+///         },
+///         @print {
+///             toolbar {
+///                 display: "none",
+///             },
+///         },
+///     }
+/// }
+///
+/// let provider = StyleProvider::new_headless(EmptyTheme);
+/// provider.set_format(CssFormat::Minified);
+/// provider.add_classes::<ToolbarClasses>();
+/// assert_eq!(
+///     provider.current_css(),
+///     ".css-0{display:flex}@media print{.css-0{display:none}}"
+/// );
+/// ```
+///
+/// # Dark mode
+/// `@dark { ... }` is a shorthand for
+/// `"@media (prefers-color-scheme: dark)" { ... }`. Depending on the
+/// `StyleProvider`, the rules within can apply below an ancestor selector like
+/// `.dark` instead, see `css_in_rs::dark_mode`:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::EmptyTheme;
+/// make_styles! {
+///     (_theme: EmptyTheme) -> CardClasses {
+///         card {
+///             color: "black",
+///         },
+///         @dark {
+///             card {
+///                 color: "white",
+///             },
+///         },
+///     }
+/// }
+/// ```
+///
+/// # Custom properties
+/// Custom properties can be registered using `@property`, so they are typed
+/// and can be animated. `@font-face` and `@counter-style` rules contain
+/// declarations as well. Custom property names are global, so they should be
+/// declared using `css_in_rs::shared_names!`. `@property(NAME)` registers
+/// `--NAME`, and `--(NAME): value` sets it within a rule:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{CssFormat, EmptyTheme, StyleProvider};
+/// css_in_rs::shared_names! {
+///     ANGLE = "angle";
+/// }
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> SpinnerClasses {
+///         @property(ANGLE) {
+///             syntax: "'<angle>'",
+///             inherits: false,
+///             initial_value: "0deg",
+///         },
+///         spinner {
+///             --(ANGLE): "45deg",
+///             transform: format!("rotate(var(--{ANGLE}))"),
+///         },
+///     }
+/// }
+///
+/// let provider = StyleProvider::new_headless(EmptyTheme);
+/// provider.set_format(CssFormat::Minified);
+/// provider.add_classes::<SpinnerClasses>();
+/// assert_eq!(
+///     provider.current_css(),
+///     "@property --css-in-rs-macro-angle{syntax:'<angle>';inherits:false;initial-value:0deg}\
+///      .css-0{--css-in-rs-macro-angle:45deg;transform:rotate(var(--css-in-rs-macro-angle))}"
+/// );
+/// ```
+///
+/// # Container queries
+/// `@container` rules work like `@media` rules, but depend on the size of a
+/// containing element instead of the viewport. Containers are declared with
+/// `container_type` (and optionally `container_name`). Like keyframes, container
+/// names are global and can be shared using `css_in_rs::shared_names!`;
+/// `@container(...)` computes the prelude at runtime:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{CssFormat, EmptyTheme, StyleProvider};
+/// css_in_rs::shared_names! {
+///     SIDEBAR = "sidebar";
+/// }
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> SidebarClasses {
+///         sidebar {
+///             container_type: "inline-size",
+///             container_name: SIDEBAR,
+///         },
+///         "@container (min-width: 400px)" {
+///             entry {
+///                 display: "flex",
+///             },
+///         },
+///         @container(format!("{SIDEBAR} (min-width: 600px)")) {
+///             entry {
+///                 gap: "8px",
+///             },
+///         },
+///     }
+/// }
+///
+/// let provider = StyleProvider::new_headless(EmptyTheme);
+/// provider.set_format(CssFormat::Minified);
+/// provider.add_classes::<SidebarClasses>();
+/// assert!(provider
+///     .current_css()
+///     .ends_with("@container css-in-rs-macro-sidebar (min-width: 600px){.css-0{gap:8px}}"));
+/// ```
+///
+/// # Cascade layers
+/// Rules can be put into cascade layers using `"@layer name" { ... }`, or
+/// `@layer(...)` if the name is computed. The order of the layers is declared
+/// by the `StyleProvider`, see `StyleProviderBuilder::layers`:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::EmptyTheme;
+/// const LIBRARY: &str = "my-library";
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> ChipClasses {
+///         @layer(LIBRARY) {
+///             chip {
+///                 border_radius: "16px",
+///             },
+///         },
+///     }
+/// }
+/// ```
+///
+/// # Pseudo-classes
+/// Styles for states like `hover` or `checked` can be nested into a rule as
+/// blocks. They generate a rule with the corresponding pseudo-class appended
+/// to the selector. Alternatively, the pseudo-class can be appended to a
+/// classname directly, as in `link:focus_visible`, without quoting the whole
+/// selector. Underscores become dashes. Only well-known pseudo-classes are
+/// accepted, so typos are reported by the macro; other selectors can still
+/// be written as strings:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{CssFormat, EmptyTheme, StyleProvider};
+/// make_styles! {
+///     (_theme: EmptyTheme) -> InputClasses {
+///         input {
+///             border_color: "gray",
+///             user_invalid {
+///                 border_color: "red",
+///             },
+///             disabled {
+///                 opacity: "0.5",
+///             },
+///         },
+///         input:hover:enabled {
+///             border_color: "black",
+///         },
+///     }
+/// }
+///
+/// let provider = StyleProvider::new_headless(EmptyTheme);
+/// provider.set_format(CssFormat::Minified);
+/// provider.add_classes::<InputClasses>();
+/// assert_eq!(
+///     provider.current_css(),
+///     ".css-0{border-color:gray}.css-0:user-invalid{border-color:red}.css-0:disabled{opacity:0.5}\
+///      .css-0:hover:enabled{border-color:black}"
+/// );
+/// ```
+///
+/// # Variants
+/// A rule selecting a single class can declare alternative styles using
+/// `variants { dimension: { variant { ... }, ... }, ... }`. Every variant
+/// becomes a class named like `button_size_small`, following the rule. For
+/// every dimension, an enum like `Size` is generated next to the struct, and
+/// a method named after the class returns its classnames together with the
+/// ones of the selected variants. A second method like `button_variants`
+/// borrows them instead of allocating a `String`, i.e. for `css_in_rs::classes!`.
+/// Dimensions of the same name share the enum, so they need the same variants:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{CssFormat, EmptyTheme, StyleProvider};
+/// make_styles! {
+///     (_theme: EmptyTheme) -> ButtonClasses {
+///         button {
+///             border: "none",
+///             variants {
+///                 size: {
+///                     small { padding: "2px" },
+///                     large { padding: "8px" },
+///                 },
+///                 tone: {
+///                     neutral { color: "gray" },
+///                     danger { color: "red" },
+///                 },
+///             },
+///         },
+///     }
+/// }
+///
+/// let style_provider = StyleProvider::new_headless(EmptyTheme);
+/// style_provider.set_format(CssFormat::Minified);
+/// let classes = style_provider.add_classes::<ButtonClasses>();
+/// assert_eq!(classes.button(Size::Small, Tone::Danger), "css-0 css-2 css-3");
+/// assert_eq!(classes.button_size_small, "css-2");
+/// assert_eq!(
+///     style_provider.current_css(),
+///     ".css-0{border:none}.css-2{padding:2px}.css-1{padding:8px}\
+///      .css-4{color:gray}.css-3{color:red}"
+/// );
+/// ```
+///
+/// Compound variants like `(size: small, tone: danger) { ... }` add
+/// declarations which only apply if all of the given variants are selected.
+/// They follow the dimensions they refer to and become a class named like
+/// `button_size_small_tone_danger`, generated after all variants. Marking a
+/// variant with `#[default]` makes the enum implement `Default`:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{CssFormat, EmptyTheme, StyleProvider};
+/// make_styles! {
+///     (_theme: EmptyTheme) -> ButtonClasses {
+///         button {
+///             variants {
+///                 size: {
+///                     small { padding: "2px" },
+///                     #[default]
+///                     large { padding: "8px" },
+///                 },
+///                 tone: {
+///                     #[default]
+///                     neutral { color: "gray" },
+///                     danger { color: "red" },
+///                 },
+///                 (size: small, tone: danger) { font_weight: "bold" },
+///             },
+///         },
+///     }
+/// }
+///
+/// let style_provider = StyleProvider::new_headless(EmptyTheme);
+/// style_provider.set_format(CssFormat::Minified);
+/// let classes = style_provider.add_classes::<ButtonClasses>();
+/// assert_eq!(classes.button(Size::Small, Tone::Danger), "css-0 css-2 css-4 css-3");
+/// assert_eq!(classes.button(Size::default(), Tone::default()), "css-0 css-1 css-5");
+/// assert_eq!(
+///     style_provider.current_css(),
+///     ".css-2{padding:2px}.css-1{padding:8px}\
+///      .css-5{color:gray}.css-4{color:red}.css-3{font-weight:bold}"
+/// );
+/// ```
+///
+/// # Atomic mode
+/// With the `#[atomic]` option, every declaration with a literal value inside
+/// a rule selecting exactly one class (like `text { ... }` or `".text" { ... }`)
+/// becomes an atomic class instead, i.e. `.css-a-3f2c9e01b7d4 { color: red; }`.
+/// Atomic classes are named after their declaration, so they are shared by all
+/// classes using the same declaration and only mounted once per `StyleProvider`.
+/// Like other classnames, they start with the prefix of the `StyleProvider`.
+/// This keeps the stylesheet small if many components use similar styles.
+///
+/// The generated classnames contain the atomic classes. Declarations with
+/// computed values and all other rules are generated as usual:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{EmptyTheme, StyleProvider};
+/// make_styles! {
+///     #[atomic]
+///     (_theme: EmptyTheme) -> ButtonClasses {
+///         primary {
+///             color: "white",
+///             padding: "4px",
+///             margin: format!("{}px", 2 * 4),
+///         },
+///         secondary {
+///             padding: "4px",
+///         },
+///     }
+/// }
+///
+/// let provider = StyleProvider::new_headless(EmptyTheme);
+/// let classes = provider.add_classes::<ButtonClasses>();
+/// let atoms = classes.primary.split(' ').skip(1).collect::<Vec<_>>();
+/// assert_eq!(atoms.len(), 2);
+/// assert!(classes.secondary.ends_with(atoms[1])); // shares `padding: 4px`
+/// ```
+///
+/// # Dedup mode
+/// With the `#[dedup]` option, every rule selecting exactly one class which
+/// only contains literal declarations becomes a shared class instead, i.e.
+/// `.css-r-8d0e2a61f4c3 { color: red; padding: 4px; }`. Like atomic classes, shared
+/// classes are named after their declarations, so two structs with identical
+/// rules share one class, which is only mounted once per `StyleProvider`.
+/// Design systems often repeat the same rules in many components; this way,
+/// each of them is only in the stylesheet once:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{EmptyTheme, StyleProvider};
+/// make_styles! {
+///     #[dedup]
+///     (_theme: EmptyTheme) -> ButtonClasses {
+///         label {
+///             color: "white",
+///             padding: "4px",
+///         },
+///     }
+/// }
+///
+/// make_styles! {
+///     #[dedup]
+///     (_theme: EmptyTheme) -> ChipClasses {
+///         label {
+///             color: "white",
+///             padding: "4px",
+///         },
+///     }
+/// }
+///
+/// let provider = StyleProvider::new_headless(EmptyTheme);
+/// let button = provider.add_classes::<ButtonClasses>();
+/// let chip = provider.add_classes::<ChipClasses>();
+/// let shared = button.label.split(' ').nth(1).unwrap();
+/// assert!(chip.label.ends_with(shared));
+/// assert_eq!(provider.current_css().matches(shared).count(), 1);
+/// ```
+///
+/// # Strict mode
+/// At-rules are passed through as written, so stylesheets can use at-rules
+/// the macro does not know about. With the `#[strict]` option, only
+/// well-known at-rules like `@media`, `@supports` or `@container` are
+/// accepted; others (including vendor-prefixed ones and typos) are reported
+/// at their prelude. Rules for `::before` and `::after`, which are not rendered
+/// without `content`, have to declare it. Component libraries can use it to
+/// enforce stricter rules than the applications using them:
+/// ```compile_fail
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::EmptyTheme;
+/// make_styles! {
+///     #[strict]
+///     (_theme: EmptyTheme) -> MyClasses {
+///         "@meida print" {
+///             text {
+///                 color: "black",
+///             },
+///         },
+///     }
+/// }
+/// ```
+///
+/// # Keyword validation
+/// String literals of properties with enumerated values, like `display`,
+/// `position` or `overflow`, are checked against the keywords of the
+/// property, so typos fail to compile. Values containing functions like
+/// `var(...)` and vendor-prefixed keywords are accepted. For values the macro
+/// does not know yet, the check is disabled using `#[allow_unknown_values]`:
+/// ```compile_fail
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::EmptyTheme;
+/// make_styles! {
+///     (_theme: EmptyTheme) -> MyClasses {
+///         row {
+///             display: "fex",
+///         },
+///     }
+/// }
+/// ```
+///
+/// The arithmetic of `calc()` in string literals is checked as well. `+` and
+/// `-` need spaces on both sides, otherwise the browser drops the declaration:
+/// ```compile_fail
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::EmptyTheme;
+/// make_styles! {
+///     (_theme: EmptyTheme) -> MyClasses {
+///         content {
+///             height: "calc(100%-64px)",
+///         },
+///     }
+/// }
+/// ```
+///
+/// Likewise, the areas of `grid-template-areas` have to be rectangles, and
+/// all rows need the same number of columns:
+/// ```compile_fail
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::EmptyTheme;
+/// make_styles! {
+///     (_theme: EmptyTheme) -> MyClasses {
+///         layout {
+///             display: "grid",
+///             grid_template_areas: r#"
+///                 "header header"
+///                 "sidebar main"
+///                 "main main"
+///             "#,
+///         },
+///     }
+/// }
+/// ```
+///
+/// # Denying `!important`
+/// Declarations are marked as `!important` using `css_in_rs::values::important`.
+/// With the `#[deny_important]` option, the macro rejects them, as well as
+/// values containing `!important` literally:
+/// ```compile_fail
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{values::important, EmptyTheme};
+/// make_styles! {
+///     #[deny_important]
+///     (_theme: EmptyTheme) -> MyClasses {
+///         text {
+///             color: important("red"),
+///         },
+///     }
+/// }
+/// ```
+///
+/// # Conflicting rules
+/// The macro warns about rules which are overridden by other rules of the
+/// same invocation: A rule whose properties are all set by an earlier rule
+/// with a higher specificity, i.e. `.link` after `"nav .link"`, only applies
+/// where the other one does not match. Rules for the same selector which set
+/// a property to different values are reported as well, since only the later
+/// value applies. Rules for states like `.link:hover` are expected to override
+/// the rule without the state. If the overrides are intended, the warnings
+/// are silenced using `#[allow_conflicts]`:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::EmptyTheme;
+/// make_styles! {
+///     #[allow_conflicts]
+///     (_theme: EmptyTheme) -> NavClasses {
+///         "nav .link" {
+///             color: "gray",
+///         },
+///         link {
+///             color: "blue",
+///         },
+///     }
+/// }
+/// ```
+///
+/// # Overrides by the theme
+/// With `#[overrides("Key")]`, the theme can override the declarations of
+/// every class, see `css_in_rs::overrides`. This is meant for component
+/// libraries: Apps can adjust the styles of the library's components without
+/// forking them. The theme has to implement `OverridableTheme`.
+///
+/// # Compact mode
+/// By default, the styles are generated by a single `write!` command with
+/// one parameter per declaration and classname. For large stylesheets, this
+/// adds up to a lot of code, which matters for wasm bundles. With the
+/// `#[compact]` option, a static template is emitted instead and rendered by
+/// a small function shared by all structs. Literal values become part of the
+/// template. The generated CSS is the same:
+/// ```
+/// # use css_in_rs_macro::make_styles;
+/// # use css_in_rs::{testing::render_classes_to_string, EmptyTheme};
+/// make_styles! {
+///     #[compact]
+///     (_theme: EmptyTheme) -> CompactClasses {
+///         "div.card > {LargeClasses::card}" {
+///             padding: "4px",
+///             margin: format!("{}px", 2 * 4),
+///         },
+///     }
+/// }
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> LargeClasses {
+///         card {
+///             padding: "4px",
+///         },
+///     }
+/// }
+///
+/// let css = render_classes_to_string::<CompactClasses>(&EmptyTheme);
+/// assert_eq!(css, "div.css-1 > .css-0 {\n  padding: 4px;\n  margin: 8px;\n}\n");
+/// ```
 #[proc_macro]
 pub fn make_styles(input: TokenStream) -> TokenStream {
-    let style = syn::parse_macro_input!(input as data::Style);
+    let input = match fragment::resolve(input.into()) {
+        Ok(fragment::Expansion::Style(input)) => input,
+        Ok(fragment::Expansion::Include(include)) => return TokenStream::from(include),
+        Err(err) => return TokenStream::from(err.to_compile_error()),
+    };
+    let style = match syn::parse2::<data::Style>(input) {
+        Ok(style) => style,
+        Err(err) => return TokenStream::from(err.to_compile_error()),
+    };
 
     let result = result::Result::new(style);
     let expanded = result.to_token_stream();
@@ -67,6 +1036,134 @@ pub fn make_styles(input: TokenStream) -> TokenStream {
     TokenStream::from(expanded)
 }
 
+/// Defines keyframes outside of [make_styles!], so animations can live in
+/// shared utility modules. The result is a `Keyframes` constant, which is
+/// mounted using `StyleProvider::add_keyframes`. Like the names declared with
+/// `shared_names!`, the name is prefixed by the name of the crate. It is
+/// emitted if the constant is used as a value, i.e. for `animation-name`.
+///
+/// Steps are written like rules of [make_styles!], but only contain
+/// declarations. Values can be arbitrary expressions.
+///
+/// # Example
+/// ```
+/// # use css_in_rs::{keyframes, make_styles, EmptyTheme, StyleProvider};
+/// keyframes! {
+///     /// Fades elements in
+///     pub FADE_IN = "fade-in" {
+///         "from" {
+///             opacity: "0",
+///         },
+///         "to" {
+///             opacity: format!("{}", 1),
+///         },
+///     }
+/// }
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> DialogClasses {
+///         dialog {
+///             animation: format!("{FADE_IN} 0.2s"),
+///         },
+///     }
+/// }
+///
+/// let style_provider = StyleProvider::new_headless(EmptyTheme);
+/// style_provider.set_format(css_in_rs::CssFormat::Minified);
+/// style_provider.add_keyframes(&FADE_IN);
+/// style_provider.add_classes::<DialogClasses>();
+/// assert_eq!(
+///     style_provider.current_css(),
+///     "@keyframes css-in-rs-macro-fade-in{from{opacity:0}to{opacity:1}}\
+///      .css-0{animation:css-in-rs-macro-fade-in 0.2s}"
+/// );
+/// ```
+#[proc_macro]
+pub fn keyframes(input: TokenStream) -> TokenStream {
+    let keyframes = syn::parse_macro_input!(input as keyframes::Keyframes);
+    TokenStream::from(keyframes.to_token_stream())
+}
+
+/// Defines rules which can be included by [make_styles!] invocations of
+/// other modules or crates using `@include path::to::fragment`, so design
+/// systems can ship partial styles. The rules are inserted in place of the
+/// `@include`, so their classes become fields of the including struct and are
+/// named like its other classes. Values may use the theme variable of the
+/// including [make_styles!].
+///
+/// The fragment is a macro. Public fragments are exported at the root of the
+/// crate, i.e. `my_design::button_base`. Other fragments can only be included
+/// after their definition, like other macros. Fragments can include other
+/// fragments; within fragments, `crate::` refers to the crate of the fragment.
+///
+/// # Example
+/// ```
+/// # use css_in_rs::{define_styles_fragment, make_styles, CssFormat, EmptyTheme, StyleProvider};
+/// define_styles_fragment! {
+///     /// Resets the styles of buttons
+///     button_base {
+///         button {
+///             border: "none",
+///         },
+///     }
+/// }
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> ToolbarClasses {
+///         @include button_base,
+///         button {
+///             color: "red",
+///         },
+///     }
+/// }
+///
+/// let style_provider = StyleProvider::new_headless(EmptyTheme);
+/// style_provider.set_format(CssFormat::Minified);
+/// let classes = style_provider.add_classes::<ToolbarClasses>();
+/// assert_eq!(classes.button, "css-0");
+/// assert_eq!(style_provider.current_css(), ".css-0{border:none}.css-0{color:red}");
+/// ```
+#[proc_macro]
+pub fn define_styles_fragment(input: TokenStream) -> TokenStream {
+    let fragment = syn::parse_macro_input!(input as fragment::Fragment);
+    TokenStream::from(fragment.to_token_stream())
+}
+
+/// Implements `css_in_rs::Theme` for a type which implements `PartialEq`.
+/// Besides comparing themes with `==`, the implementation fingerprints the
+/// names and types of the fields (see `Theme::SCHEMA`), so the hooks of
+/// `css-in-rs` recognize changed theme types during hot reloading.
+///
+/// # Example
+/// ```
+/// # use css_in_rs::{make_styles, CssFormat, StyleProvider, Theme};
+/// #[derive(Clone, PartialEq, Theme)]
+/// struct MyTheme {
+///     primary_color: String,
+/// }
+///
+/// make_styles! {
+///     (theme: MyTheme) -> MyClasses {
+///         button {
+///             color: theme.primary_color,
+///         },
+///     }
+/// }
+///
+/// let theme = MyTheme { primary_color: "red".to_string() };
+/// assert!(theme.fast_cmp(&theme.clone()));
+///
+/// let style_provider = StyleProvider::new_headless(theme);
+/// style_provider.set_format(CssFormat::Minified);
+/// style_provider.add_classes::<MyClasses>();
+/// assert_eq!(style_provider.current_css(), ".css-0{color:red}");
+/// ```
+#[proc_macro_derive(Theme)]
+pub fn derive_theme(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    TokenStream::from(theme::Theme::new(input).to_token_stream())
+}
+
 #[cfg(test)]
 mod tests {
     use quote::{quote, ToTokens};
@@ -107,37 +1204,121 @@ mod tests {
 
         let result = result.to_token_stream().to_string();
 
+        let comment = |label: &str| {
+            quote! {
+                , if cfg!(debug_assertions) {
+                    concat!("/* ", #label, " @ ", file!(), ":", line!(), " */\n")
+                } else {
+                    ""
+                }
+            }
+        };
+        let red_color = comment("MyClasses::div.red_color");
+        let blue_color = comment("MyClasses::div.blue_color");
+        let media = comment("MyClasses::@media (hover: none)");
+        let body = comment("MyClasses::body *");
+        let some_class = comment("MyClasses::some_class");
+
         let expected = quote! {
-            struct MyClasses {
-                pub blue_color: String,
-                pub red_color: String,
-                pub some_class: String,
+            ::css_in_rs::classes_struct! {
+                struct MyClasses {
+                    pub blue_color: String,
+                    pub red_color: String,
+                    pub some_class: String,
+                    #[allow(dead_code)]
+                    pub root_all: String,
+                }
+            }
+
+            impl MyClasses {
+                /// Returns the selector of the class with the given name, i.e.
+                /// `.css-12`, or `None` if there is no such class
+                #[allow(dead_code)]
+                pub fn selector(&self, class: &str) -> Option<String> {
+                    let classnames = match class {
+                        "blue_color" => &self.blue_color,
+                        "red_color" => &self.red_color,
+                        "some_class" => &self.some_class,
+                        _ => return None,
+                    };
+                    // Skips the atomic classes, see `#[atomic]`
+                    let classname = classnames.split(' ').next().unwrap_or_default();
+                    Some(format!(".{classname}"))
+                }
             }
 
             impl ::css_in_rs::Classes for MyClasses {
                 type Theme = MyTheme;
+                const INFO: &'static ::css_in_rs::graph::ClassesInfo =
+                    &::css_in_rs::graph::ClassesInfo {
+                        theme_fields: &[],
+                        at_rules: &["@media"],
+                        dependencies: &[],
+                    };
+                const CSS_CAPACITY_HINT: usize = 550usize;
+                const CLASS_NAMES: &'static [&'static str] = &["blue_color", "red_color", "some_class"];
+                const CLASS_LOCATIONS: &'static [::css_in_rs::lookup::SourceLocation] = &[
+                    ::css_in_rs::lookup::SourceLocation { file: file!(), line: line!(), column: column!(), },
+                    ::css_in_rs::lookup::SourceLocation { file: file!(), line: line!(), column: column!(), },
+                    ::css_in_rs::lookup::SourceLocation { file: file!(), line: line!(), column: column!(), }
+                ];
+                const READS_THEME: bool = false;
 
-                fn generate(theme: &Self::Theme, css: &mut String, counter: &mut u64) {
+                fn generate(
+                    theme: &Self::Theme,
+                    css: &mut String,
+                    ctx: &mut ::css_in_rs::GenerateContext<Self::Theme>,
+                ) {
                     use ::core::fmt::Write;
-                    let start = *counter;
+                    let start = ctx.reserve(3u64);
                     let _ = write!(
                         css,
-                        "div.css-{} {{\n  {}: {};\n}}\ndiv.css-{} {{\n  {}: {};\n}}\n@media (hover: none) {{\nbody * {{\n  {}: {};\n}}\n.css-{} {{\n}}\n}}\n",
-                        start + 1u64, "color", "red", start + 0u64, "color", "blue",
-                        "pointer-events", "none", start + 2u64
+                        "{}div.{}-{} {{\n  {}: {};\n}}\n{}div.{}-{} {{\n  {}: {};\n}}\n{}@media (hover: none) {{\n{}body * {{\n  {}: {};\n}}\n{}.{}-{} {{\n}}\n}}\n"
+                        #red_color, ctx.prefix(), start + 1u64, "color", "red"
+                        #blue_color, ctx.prefix(), start + 0u64, "color", "blue"
+                        #media #body, "pointer-events", "none"
+                        #some_class, ctx.prefix(), start + 2u64
                     );
-                    *counter = start + 3u64;
                 }
-                fn new(start: u64) -> Self {
+                fn new(prefix: &str, start: u64) -> Self {
                     Self {
-                        blue_color: format!("css-{}", start + 0u64),
-                        red_color: format!("css-{}", start + 1u64),
-                        some_class: format!("css-{}", start + 2u64),
+                        blue_color: format!("{prefix}-{}", start + 0u64, prefix = prefix),
+                        red_color: format!("{prefix}-{}", start + 1u64, prefix = prefix),
+                        some_class: format!("{prefix}-{}", start + 2u64, prefix = prefix),
+                        root_all: format!("{prefix}-{} {prefix}-{} {prefix}-{}", start + 0u64, start + 1u64, start + 2u64, prefix = prefix),
                     }
                 }
+                fn iter(&self) -> impl ::core::iter::Iterator<Item = (&'static str, &str)> {
+                    Self::CLASS_NAMES.iter().copied().zip(self.root_all.split(' '))
+                }
             }
         };
 
         assert_eq!(result, expected.to_string());
     }
+
+    #[test]
+    fn theme_fields() {
+        let input = quote! {
+            (theme: MyTheme) -> MyClasses {
+                @media(theme.breakpoints.up(Md)) {
+                    text {
+                        color: theme.palette.primary,
+                        margin: format!("{}px", theme.spacing * 2),
+                        padding: theme.gap(3),
+                        border_color: theme.palette.primary.darken(0.2),
+                    },
+                },
+            }
+        };
+
+        let style = syn::parse2::<Style>(input).unwrap();
+        let result = result::Result::new(style).to_token_stream().to_string();
+
+        let expected = quote! {
+            theme_fields: &["breakpoints", "gap()", "palette.primary", "spacing"],
+            at_rules: &["@media"],
+        };
+        assert!(result.contains(&expected.to_string()));
+    }
 }
@@ -0,0 +1,535 @@
+//! Compile-time checks for rules of one `make_styles!` invocation which
+//! override each other, so these bugs show up without opening the devtools.
+//! They are reported as warnings, see [Warning], and can be silenced using
+//! `#[allow_conflicts]`.
+
+use std::collections::BTreeSet;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned, ToTokens};
+
+use crate::{
+    data::rules::{entry::Entry, header::Header, Rule, RuleBody, RuleList},
+    preview::{selector, split_selectors},
+};
+
+/// Pseudo-elements which can be written with a single colon
+const LEGACY_PSEUDO_ELEMENTS: &[&str] = &["before", "after", "first-line", "first-letter"];
+
+/// A warning pointing to a span. Proc macros cannot emit warnings on stable
+/// Rust, so it is emitted as the usage of a deprecated item.
+pub struct Warning {
+    span: Span,
+    message: String,
+}
+
+impl ToTokens for Warning {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let message = &self.message;
+        let usage = quote_spanned!(self.span => let _ = make_styles_warning;);
+        let ts = quote! {
+            const _: () = {
+                #[deprecated(note = #message)]
+                #[allow(non_camel_case_types)]
+                struct make_styles_warning;
+                #usage
+            };
+        };
+        ts.to_tokens(tokens);
+    }
+}
+
+/// Warns about rules which are overridden by earlier ones within the same
+/// at-rules:
+/// - A rule is shadowed if an earlier rule for the same element sets all of
+///   its properties with a higher specificity. Earlier rules which only add
+///   pseudo-classes, i.e. `.button:hover` for `.button`, are intended to win.
+/// - Rules for the same selector conflict if they set the same property to
+///   different values. Only the later one applies.
+pub fn check(rules: &RuleList) -> Vec<Warning> {
+    let mut sites = Vec::new();
+    for rule in &rules.rules {
+        collect_sites(rule, &mut Vec::new(), &mut sites);
+    }
+
+    let mut warnings = Vec::new();
+    for (idx, later) in sites.iter().enumerate() {
+        let earlier = sites[..idx]
+            .iter()
+            .filter(|site| site.context == later.context)
+            .collect::<Vec<_>>();
+
+        if let Some(warning) = shadowed(later, &earlier) {
+            warnings.push(warning);
+        }
+        for entry in &later.entries {
+            if let Some(warning) = conflict(later, entry, &earlier) {
+                warnings.push(warning);
+            }
+        }
+    }
+    warnings
+}
+
+/// A rule which contains declarations
+struct Site<'a> {
+    /// The preludes of the surrounding at-rules
+    context: Vec<&'a str>,
+    header: &'a Header,
+    entries: Vec<&'a Entry>,
+    selectors: Vec<Selector>,
+}
+
+fn collect_sites<'a>(rule: &'a Rule, context: &mut Vec<&'a str>, sites: &mut Vec<Site<'a>>) {
+    match &rule.body {
+        RuleBody::AtRule { children } => {
+            // The steps of keyframes are no selectors
+            if rule.header.at_rule_keyword() == Some("@keyframes") {
+                return;
+            }
+
+            context.push(&rule.header.source);
+            for child in children {
+                collect_sites(child, context, sites);
+            }
+            context.pop();
+        }
+        RuleBody::Conditional(conditional) => {
+            // Rules of different branches do not apply at the same time
+            for branch in &conditional.branches {
+                context.push(&branch.source);
+                for child in &branch.rules {
+                    collect_sites(child, context, sites);
+                }
+                context.pop();
+            }
+        }
+        RuleBody::Normal { entries, .. } if !rule.header.at_rule => {
+            let selectors = split_selectors(&selector(&rule.header))
+                .into_iter()
+                .map(Selector::parse)
+                .collect();
+            sites.push(Site {
+                context: context.clone(),
+                header: &rule.header,
+                entries: entries.iter().collect(),
+                selectors,
+            });
+        }
+        _ => {}
+    }
+}
+
+fn shadowed(later: &Site, earlier: &[&Site]) -> Option<Warning> {
+    if later.entries.is_empty() {
+        return None;
+    }
+
+    let properties = |site: &Site| {
+        site.entries
+            .iter()
+            .map(|entry| entry.property.clone())
+            .collect::<BTreeSet<_>>()
+    };
+    let later_properties = properties(later);
+
+    for site in earlier {
+        if !later_properties.is_subset(&properties(site)) {
+            continue;
+        }
+
+        // Every selector of the later rule has to be shadowed
+        let pairs = later
+            .selectors
+            .iter()
+            .map(|selector| {
+                let shadowing = site
+                    .selectors
+                    .iter()
+                    .find(|other| other.shadows(selector))?;
+                Some((shadowing, selector))
+            })
+            .collect::<Option<Vec<_>>>();
+        let Some(&(shadowing, selector)) = pairs.as_ref().and_then(|pairs| pairs.first()) else {
+            continue;
+        };
+
+        let message = format!(
+            "Every property of `{}` is set by the earlier rule `{}` with a higher \
+             specificity, so this rule is overridden wherever both match. Increase its \
+             specificity if it is meant to win, or use `#[allow_conflicts]`",
+            selector.text, shadowing.text
+        );
+        return Some(Warning {
+            span: later.header.span,
+            message,
+        });
+    }
+    None
+}
+
+fn conflict(later: &Site, entry: &Entry, earlier: &[&Site]) -> Option<Warning> {
+    let values = |entry: &Entry| {
+        entry
+            .values()
+            .map(|value| value.to_token_stream().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    for site in earlier {
+        let Some(selector) = later.selectors.iter().find(|selector| {
+            site.selectors
+                .iter()
+                .any(|other| other.text == selector.text)
+        }) else {
+            continue;
+        };
+
+        let conflicting = site
+            .entries
+            .iter()
+            .any(|other| other.property == entry.property && values(other) != values(entry));
+        if conflicting {
+            let message = format!(
+                "`{}` is set to a different value for `{}` by an earlier rule, which is \
+                 overridden by this one. Merge the rules, use `[...]` for fallback values, \
+                 or use `#[allow_conflicts]`",
+                entry.property, selector.text
+            );
+            return Some(Warning {
+                span: entry.span,
+                message,
+            });
+        }
+    }
+    None
+}
+
+/// A single selector of a selector list
+#[derive(Debug, PartialEq)]
+struct Selector {
+    /// The selector without redundant whitespace
+    text: String,
+    specificity: (u32, u32, u32),
+    /// The classes of the element the selector applies to, i.e. of its last
+    /// compound selector
+    subject: Vec<String>,
+    /// The pseudo-element of the last compound selector, i.e. `before`
+    pseudo_element: Option<String>,
+    /// The selector without the pseudo-classes of its last compound selector,
+    /// i.e. `.button` for `.button:hover`, and their number
+    stateless: (String, usize),
+}
+
+impl Selector {
+    fn parse(src: &str) -> Self {
+        let text = src.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        // The last compound selector starts after the last combinator
+        let mut depth = 0;
+        let mut start = 0;
+        for (idx, ch) in text.char_indices() {
+            match ch {
+                '(' | '[' => depth += 1,
+                ')' | ']' => depth -= 1,
+                ' ' | '>' | '+' | '~' if depth == 0 => start = idx + 1,
+                _ => {}
+            }
+        }
+
+        let mut subject = Vec::new();
+        let mut pseudo_element = None;
+        let mut stateless = text[..start].to_owned();
+        let mut states = 0;
+        for token in tokens(&text[start..]) {
+            match &token {
+                Token::Class(name) => subject.push(name.clone()),
+                Token::PseudoElement(name) => pseudo_element = Some(name.clone()),
+                Token::PseudoClass { .. } => {
+                    states += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            stateless.push_str(&token.text());
+        }
+
+        Self {
+            specificity: specificity(&text),
+            text,
+            subject,
+            pseudo_element,
+            stateless: (stateless, states),
+        }
+    }
+
+    /// Whether this selector overrides every declaration of `other` it
+    /// shares, on the elements both of them apply to
+    fn shadows(&self, other: &Selector) -> bool {
+        let same_element = self.pseudo_element == other.pseudo_element
+            && self
+                .subject
+                .iter()
+                .any(|class| other.subject.contains(class));
+        let is_state =
+            self.stateless.0 == other.stateless.0 && self.stateless.1 > other.stateless.1;
+        same_element && !is_state && self.specificity > other.specificity
+    }
+}
+
+#[derive(Debug)]
+enum Token {
+    Id(String),
+    Class(String),
+    Attribute(String),
+    PseudoClass {
+        name: String,
+        args: Option<String>,
+    },
+    PseudoElement(String),
+    Type(String),
+    /// Anything else, i.e. `*` or a combinator
+    Other(char),
+}
+
+impl Token {
+    fn text(&self) -> String {
+        match self {
+            Token::Id(name) => format!("#{name}"),
+            Token::Class(name) => format!(".{name}"),
+            Token::Attribute(attr) => format!("[{attr}]"),
+            Token::PseudoClass { name, args: None } => format!(":{name}"),
+            Token::PseudoClass {
+                name,
+                args: Some(args),
+            } => format!(":{name}({args})"),
+            Token::PseudoElement(name) => format!("::{name}"),
+            Token::Type(name) => name.clone(),
+            Token::Other(ch) => ch.to_string(),
+        }
+    }
+}
+
+fn tokens(selector: &str) -> Vec<Token> {
+    let chars = selector.chars().collect::<Vec<_>>();
+    let ident = |start: usize| {
+        let len = chars[start..]
+            .iter()
+            .take_while(|ch| ch.is_alphanumeric() || **ch == '-' || **ch == '_')
+            .count();
+        (
+            chars[start..start + len].iter().collect::<String>(),
+            start + len,
+        )
+    };
+    // Returns the content of the group starting at `start` and its end
+    let group = |start: usize| {
+        let mut depth = 0;
+        for (idx, ch) in chars.iter().enumerate().skip(start) {
+            match ch {
+                '(' | '[' => depth += 1,
+                ')' | ']' => depth -= 1,
+                _ => continue,
+            }
+            if depth == 0 {
+                return (chars[start + 1..idx].iter().collect::<String>(), idx + 1);
+            }
+        }
+        (chars[start + 1..].iter().collect(), chars.len())
+    };
+
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    while idx < chars.len() {
+        let token = match chars[idx] {
+            '#' => {
+                let (name, end) = ident(idx + 1);
+                idx = end;
+                Token::Id(name)
+            }
+            '.' => {
+                let (name, end) = ident(idx + 1);
+                idx = end;
+                Token::Class(name)
+            }
+            '[' => {
+                let (attr, end) = group(idx);
+                idx = end;
+                Token::Attribute(attr)
+            }
+            ':' => {
+                let double = chars.get(idx + 1) == Some(&':');
+                let (name, end) = ident(idx + if double { 2 } else { 1 });
+                idx = end;
+                let mut args = None;
+                if chars.get(idx) == Some(&'(') {
+                    let (content, end) = group(idx);
+                    args = Some(content);
+                    idx = end;
+                }
+
+                match double || LEGACY_PSEUDO_ELEMENTS.contains(&name.as_str()) {
+                    true => Token::PseudoElement(name),
+                    false => Token::PseudoClass { name, args },
+                }
+            }
+            ch if ch.is_alphabetic() || ch == '_' || ch == '-' => {
+                let (name, end) = ident(idx);
+                idx = end;
+                Token::Type(name)
+            }
+            ch => {
+                idx += 1;
+                Token::Other(ch)
+            }
+        };
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// The specificity of a selector as (ids, classes, types)
+fn specificity(selector: &str) -> (u32, u32, u32) {
+    let mut result = (0, 0, 0);
+    for token in tokens(selector) {
+        match token {
+            Token::Id(_) => result.0 += 1,
+            Token::Class(_) | Token::Attribute(_) => result.1 += 1,
+            Token::PseudoClass { name, args } => match (name.as_str(), args) {
+                ("where", _) => {}
+                ("is" | "not" | "has", Some(args)) => {
+                    let max = split_selectors(&args)
+                        .into_iter()
+                        .map(specificity)
+                        .max()
+                        .unwrap_or_default();
+                    result = (result.0 + max.0, result.1 + max.1, result.2 + max.2);
+                }
+                _ => result.1 += 1,
+            },
+            Token::Type(_) | Token::PseudoElement(_) => result.2 += 1,
+            Token::Other(_) => {}
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+
+    use super::{check, specificity, Selector};
+    use crate::data::Style;
+
+    fn messages(input: proc_macro2::TokenStream) -> Vec<String> {
+        let style = syn::parse2::<Style>(input).unwrap();
+        check(&style.rules)
+            .into_iter()
+            .map(|warning| warning.message)
+            .collect()
+    }
+
+    #[test]
+    fn specificities() {
+        assert_eq!(specificity(".a"), (0, 1, 0));
+        assert_eq!(specificity("nav > ul li.a:hover::before"), (0, 2, 4));
+        assert_eq!(specificity("#main .a[disabled]"), (1, 2, 0));
+        assert_eq!(specificity(":is(#a, .b) :where(.c) :not(.d, p)"), (1, 1, 0));
+
+        let selector = Selector::parse("nav  .link.active:hover::after");
+        assert_eq!(selector.text, "nav .link.active:hover::after");
+        assert_eq!(selector.subject, ["link", "active"]);
+        assert_eq!(selector.pseudo_element.as_deref(), Some("after"));
+        assert_eq!(
+            selector.stateless,
+            ("nav .link.active::after".to_owned(), 1)
+        );
+    }
+
+    #[test]
+    fn shadowed() {
+        let warnings = messages(quote! {
+            (_theme: MyTheme) -> MyClasses {
+                "nav .link" {
+                    color: "gray",
+                },
+                link {
+                    color: "blue",
+                },
+            }
+        });
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0]
+            .starts_with("Every property of `.link` is set by the earlier rule `nav .link`"));
+
+        // States, other elements, other at-rules and additional properties
+        let warnings = messages(quote! {
+            (_theme: MyTheme) -> MyClasses {
+                "nav .link" {
+                    color: "gray",
+                },
+                "nav .link:hover" {
+                    color: "black",
+                },
+                "nav .link" {
+                    color: "gray",
+                },
+                "nav .link::after" {
+                    color: "gray",
+                },
+                link {
+                    color: "blue",
+                    margin: "0",
+                },
+                "@media print" {
+                    link {
+                        color: "black",
+                    },
+                },
+                "{OtherClasses::link}" {},
+            }
+        });
+        assert!(warnings.is_empty(), "{warnings:?}");
+    }
+
+    #[test]
+    fn conflicts() {
+        let warnings = messages(quote! {
+            (_theme: MyTheme) -> MyClasses {
+                "a, .link" {
+                    color: "gray",
+                    margin: "0",
+                },
+                "@media print" {
+                    link {
+                        color: "blue",
+                    },
+                },
+                ".link" {
+                    color: "blue",
+                    margin: "0",
+                },
+            }
+        });
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("`color` is set to a different value for `.link`"));
+    }
+
+    #[test]
+    fn branches() {
+        let warnings = messages(quote! {
+            (theme: MyTheme) -> MyClasses {
+                @if theme.compact {
+                    link {
+                        padding: "2px",
+                    },
+                } @else {
+                    link {
+                        padding: "8px",
+                    },
+                },
+            }
+        });
+        assert!(warnings.is_empty(), "{warnings:?}");
+    }
+}
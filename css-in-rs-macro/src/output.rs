@@ -1,16 +1,49 @@
 use std::collections::BTreeMap;
 
-use proc_macro2::TokenStream;
-use quote::{quote, ToTokens};
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned, ToTokens};
+
+use crate::data::options::Options;
 
 pub struct Output {
     pub format_str: String,
     pub params: TokenStream,
+    /// Whether literal declarations should be split into atomic classes
+    pub atomic: bool,
+    /// Whether rules with only literal declarations become shared classes
+    pub dedup: bool,
+    /// Whether the generated code has access to a `GenerateContext`, which is
+    /// not the case in `keyframes!`
+    pub has_context: bool,
     map: BTreeMap<String, u64>,
+    struct_name: String,
+    /// Maps classnames to the names of their atomic classes
+    atoms: BTreeMap<String, Vec<String>>,
+    /// The rules of all atomic and shared classes
+    atom_rules: Vec<String>,
+    /// Other structs whose classes are referenced in selectors
+    dependencies: Vec<TokenStream>,
+    /// The kinds of used at-rules, i.e. `@media`
+    at_rules: Vec<String>,
+    /// Whether to emit a static template instead of a `write!` command
+    compact: bool,
+    /// The same output as `format_str` and `params`, used in compact mode
+    pieces: Vec<Piece>,
+    /// The theme variable and the component key, see `#[overrides(...)]`
+    overrides: Option<(syn::Ident, syn::LitStr)>,
+}
+
+/// A part of the template emitted in compact mode
+enum Piece {
+    Str(String),
+    /// The class with the given id
+    Class(u64),
+    /// An expression implementing `Display` and the guessed size of its output
+    Value(TokenStream, usize),
 }
 
 impl Output {
-    pub fn new(classnames: &[syn::Ident]) -> Self {
+    pub fn new(struct_name: &syn::Ident, classnames: &[syn::Ident], options: &Options) -> Self {
         let mut map = BTreeMap::default();
         for ident in classnames {
             let idx = map.len() as u64;
@@ -21,26 +54,371 @@ impl Output {
         Self {
             format_str: Default::default(),
             params: Default::default(),
+            atomic: options.atomic,
+            dedup: options.dedup,
+            has_context: true,
             map,
+            struct_name: struct_name.to_string(),
+            atoms: Default::default(),
+            atom_rules: Default::default(),
+            dependencies: Default::default(),
+            at_rules: Default::default(),
+            compact: options.compact,
+            pieces: Default::default(),
+            overrides: None,
+        }
+    }
+
+    /// Lets the theme override the declarations of every class, see `#[overrides(...)]`
+    pub fn enable_overrides(&mut self, theme_var: &syn::Ident, component: &syn::LitStr) {
+        self.overrides = Some((theme_var.clone(), component.clone()));
+    }
+
+    pub fn has_overrides(&self) -> bool {
+        self.overrides.is_some()
+    }
+
+    /// Adds the declarations which the theme overrides for the given class.
+    /// Does nothing unless overrides are enabled.
+    pub fn push_overrides(&mut self, classname: &str) {
+        let Some((theme_var, component)) = &self.overrides else {
+            return;
+        };
+
+        let policy = self.sanitize_policy();
+        let value = quote! {
+            ::css_in_rs::overrides::OverridableTheme::components(#theme_var)
+                .declarations(#component, #classname)
+                .sanitize(#policy)
+        };
+        self.push_value(value);
+    }
+
+    fn push_piece(&mut self, piece: Piece) {
+        match (self.pieces.last_mut(), piece) {
+            (Some(Piece::Str(last)), Piece::Str(s)) => last.push_str(&s),
+            (_, piece) => self.pieces.push(piece),
+        }
+    }
+
+    /// Adds a value which is only known at runtime
+    pub fn push_value(&mut self, value: TokenStream) {
+        self.push_value_with_estimate(value, VALUE_ESTIMATE);
+    }
+
+    /// Like [Output::push_value] if the size of the value can be guessed better
+    fn push_value_with_estimate(&mut self, value: TokenStream, estimate: usize) {
+        self.format_str.push_str("{}");
+        quote!(, #value).to_tokens(&mut self.params);
+        self.push_piece(Piece::Value(value, estimate));
+    }
+
+    /// Adds a value which is only known at runtime and checked by the
+    /// `SanitizePolicy` of the provider, see `css_in_rs::sanitize`
+    pub fn push_interpolated(&mut self, value: TokenStream) {
+        let value = self.interpolated(value);
+        self.push_value(value);
+    }
+
+    fn interpolated(&self, value: TokenStream) -> TokenStream {
+        let policy = self.sanitize_policy();
+        quote!(::css_in_rs::sanitize::interpolate(&(#value), #policy))
+    }
+
+    /// The `SanitizePolicy` of the provider, or the default one without a context
+    fn sanitize_policy(&self) -> TokenStream {
+        match self.has_context {
+            true => quote!(ctx.sanitize_policy()),
+            false => quote!(::css_in_rs::sanitize::SanitizePolicy::default()),
+        }
+    }
+
+    /// Adds rules which are only emitted if their condition holds, see `@if`.
+    /// Every branch is rendered into a string of its own; the branch without
+    /// a condition is the final `@else`. Atomic and shared classes are not
+    /// created within branches, since they are emitted unconditionally.
+    pub fn push_conditional<'a>(
+        &mut self,
+        branches: impl IntoIterator<Item = (Option<&'a syn::Expr>, &'a dyn ToOutput)>,
+    ) {
+        let mut chain = TokenStream::new();
+        let mut has_else = false;
+        for (condition, content) in branches {
+            let mut branch = Output {
+                format_str: Default::default(),
+                params: Default::default(),
+                atomic: false,
+                dedup: false,
+                has_context: self.has_context,
+                map: self.map.clone(),
+                struct_name: self.struct_name.clone(),
+                atoms: Default::default(),
+                atom_rules: Default::default(),
+                // Moved, so the bindings of dependencies stay the same
+                dependencies: std::mem::take(&mut self.dependencies),
+                at_rules: std::mem::take(&mut self.at_rules),
+                compact: false,
+                pieces: Default::default(),
+                overrides: self.overrides.clone(),
+            };
+            content.append(&mut branch);
+            self.dependencies = branch.dependencies;
+            self.at_rules = branch.at_rules;
+
+            let format_str = &branch.format_str;
+            let params = &branch.params;
+            let css = quote! {{
+                let mut css = ::std::string::String::new();
+                let _ = write!(css, #format_str #params);
+                css
+            }};
+            match condition {
+                Some(condition) => quote!(if #condition #css else).to_tokens(&mut chain),
+                None => {
+                    css.to_tokens(&mut chain);
+                    has_else = true;
+                    break;
+                }
+            }
+        }
+        if !has_else {
+            quote!({ ::std::string::String::new() }).to_tokens(&mut chain);
+        }
+
+        self.push_value(chain);
+    }
+
+    /// Adds a declaration like `color: red;`. If the value is a literal, it is
+    /// given as `literal`, so it can be inlined into the template.
+    pub fn push_declaration(&mut self, property: &str, value: &syn::Expr, literal: Option<String>) {
+        let value = match literal {
+            Some(_) => value.to_token_stream(),
+            None => self.interpolated(value.to_token_stream()),
+        };
+        self.format_str.push_str("  {}: {};\n");
+        quote!(, #property, #value).to_tokens(&mut self.params);
+
+        self.push_piece(Piece::Str(format!("  {property}: ")));
+        match literal {
+            Some(literal) => self.push_piece(Piece::Str(literal)),
+            None => self.push_piece(Piece::Value(value, VALUE_ESTIMATE)),
+        }
+        self.push_piece(Piece::Str(";\n".to_owned()));
+    }
+
+    /// Adds a declaration of a custom property whose name is computed at
+    /// runtime, i.e. `--my-angle: 45deg;`
+    pub fn push_custom_declaration(&mut self, name: &syn::Expr, value: &syn::Expr) {
+        self.push_str("  --");
+        self.push_interpolated(name.to_token_stream());
+        self.push_str(": ");
+        self.push_interpolated(value.to_token_stream());
+        self.push_str(";\n");
+    }
+
+    /// Adds an atomic class for the given declaration to the given class. Atomic
+    /// classes are named after a hash of their declaration, so identical
+    /// declarations share the same class, even across different structs. The
+    /// name lacks the prefix of the provider, which is added at runtime.
+    pub fn push_atom(&mut self, classname: &str, property: &str, value: &str) {
+        let declaration = format!("{property}: {value}");
+        let name = format!("a-{:012x}", fnv1a(&declaration) & 0xffff_ffff_ffff);
+        self.push_hashed_class(classname, name, &format!("  {declaration};\n"));
+    }
+
+    /// Adds a class with all the given declarations to the given class, see
+    /// `#[dedup]`. Like atomic classes, it is named after a hash of its
+    /// declarations, so identical rules share the same class.
+    pub fn push_shared_rule(&mut self, classname: &str, declarations: &[(&str, String)]) {
+        let body = declarations
+            .iter()
+            .map(|(property, value)| format!("  {property}: {value};\n"))
+            .collect::<String>();
+        let name = format!("r-{:012x}", fnv1a(&body) & 0xffff_ffff_ffff);
+        self.push_hashed_class(classname, name, &body);
+    }
+
+    fn push_hashed_class(&mut self, classname: &str, name: String, body: &str) {
+        let rule = format!(".{name} {{\n{body}}}\n");
+        if !self.atom_rules.contains(&rule) {
+            self.atom_rules.push(rule);
+        }
+
+        let atoms = self.atoms.entry(classname.to_owned()).or_default();
+        if !atoms.contains(&name) {
+            atoms.push(name);
         }
     }
 
+    /// The names of the atomic and shared classes which are part of the given class
+    pub fn atoms_of(&self, classname: &str) -> Option<&[String]> {
+        self.atoms.get(classname).map(Vec::as_slice)
+    }
+
+    pub fn atom_rules(&self) -> &[String] {
+        &self.atom_rules
+    }
+
     pub fn push_str(&mut self, s: &str) {
+        self.push_piece(Piece::Str(s.to_owned()));
         let s = s.replace('{', "{{");
         let s = s.replace('}', "}}");
         self.format_str.push_str(&s);
     }
 
+    /// Adds a comment like `/* MyClasses::red_text @ src/button.rs:42 */`
+    /// pointing to the given span. It will only be emitted in debug builds.
+    pub fn push_source_comment(&mut self, source: &str, span: Span) {
+        // `*/` would terminate the comment early
+        let label = format!("{}::{}", self.struct_name, source.replace("*/", "* /"));
+        let file = quote_spanned!(span => file!());
+        let line = quote_spanned!(span => line!());
+
+        let estimate = "/*  @ : */\n".len() + label.len() + LOCATION_ESTIMATE;
+        self.push_value_with_estimate(
+            quote! {
+                if cfg!(debug_assertions) {
+                    concat!("/* ", #label, " @ ", #file, ":", #line, " */\n")
+                } else {
+                    ""
+                }
+            },
+            estimate,
+        );
+    }
+
+    /// Adds a class selector for a class of another struct, i.e. `{IconClasses::icon}`
+    pub fn push_reference(&mut self, classes: &TokenStream, field: &syn::Ident) {
+        let key = classes.to_string();
+        let idx = match self
+            .dependencies
+            .iter()
+            .position(|dep| dep.to_string() == key)
+        {
+            Some(idx) => idx,
+            None => {
+                self.dependencies.push(classes.clone());
+                self.dependencies.len() - 1
+            }
+        };
+        let binding = Self::dependency_binding(idx);
+
+        // Only the first classname, in case there are atomic classes as well
+        self.push_str(".");
+        self.push_value(quote!(#binding.#field.split(' ').next().unwrap_or_default()));
+    }
+
+    pub fn dependencies(&self) -> &[TokenStream] {
+        &self.dependencies
+    }
+
+    /// Records the usage of an at-rule, i.e. `@media`
+    pub fn push_at_rule(&mut self, keyword: &str) {
+        if !self.at_rules.iter().any(|at_rule| at_rule == keyword) {
+            self.at_rules.push(keyword.to_owned());
+        }
+    }
+
+    pub fn at_rules(&self) -> &[String] {
+        &self.at_rules
+    }
+
+    /// Returns the fields of the theme which are read by the generated code,
+    /// i.e. `palette.primary` for `theme.palette.primary` or `breakpoints`
+    /// for `theme.breakpoints.up(Md)`. Methods of the theme itself are listed
+    /// with parentheses, i.e. `spacing()` for `theme.spacing(3)`
+    pub fn theme_fields(&self, theme_var: &syn::Ident) -> Vec<String> {
+        let mut fields = Vec::new();
+        collect_theme_fields(self.params.clone(), theme_var, &mut fields);
+        fields.sort();
+        fields.dedup();
+        fields
+    }
+
+    /// Whether the generated code uses the theme at all, see `Classes::READS_THEME`
+    pub fn reads_theme(&self, theme_var: &syn::Ident) -> bool {
+        fn contains(tokens: TokenStream, theme_var: &syn::Ident) -> bool {
+            tokens.into_iter().any(|token| match token {
+                proc_macro2::TokenTree::Group(group) => contains(group.stream(), theme_var),
+                proc_macro2::TokenTree::Ident(ident) => ident == *theme_var,
+                _ => false,
+            })
+        }
+
+        contains(self.params.clone(), theme_var)
+    }
+
+    /// The name of the variable holding the classes of the given dependency
+    pub fn dependency_binding(idx: usize) -> syn::Ident {
+        quote::format_ident!("dependency_{}", idx)
+    }
+
     pub fn push_classname(&mut self, name: &str) {
         let id = *self.map.get(name).unwrap();
 
-        self.format_str.push_str("css-{}");
-        quote!(, start + #id).to_tokens(&mut self.params);
+        self.format_str.push_str("{}-{}");
+        quote!(, ctx.prefix(), start + #id).to_tokens(&mut self.params);
+        self.push_piece(Piece::Class(id));
+    }
+}
+
+/// Guessed size of a classname like `css-42` in bytes
+const CLASSNAME_ESTIMATE: usize = 8;
+/// Guessed size of a value which is only known at runtime in bytes
+const VALUE_ESTIMATE: usize = 16;
+/// Guessed size of a location like `src/components/button.rs:42` in bytes
+const LOCATION_ESTIMATE: usize = 48;
+
+impl Output {
+    /// Estimates the size of the generated CSS in bytes, see `Classes::CSS_CAPACITY_HINT`.
+    /// Literal parts are counted exactly, the other ones are guessed, so this
+    /// is no upper bound.
+    pub fn css_capacity_hint(&self) -> usize {
+        self.pieces
+            .iter()
+            .map(|piece| match piece {
+                Piece::Str(s) => s.len(),
+                Piece::Class(_) => CLASSNAME_ESTIMATE,
+                Piece::Value(_, estimate) => *estimate,
+            })
+            .sum()
+    }
+
+    /// Compact mode: The output is a static template which is rendered by a
+    /// function shared by all structs, so less code is generated per rule
+    fn compact_to_tokens(&self, tokens: &mut TokenStream) {
+        let mut values = Vec::new();
+        let pieces = self.pieces.iter().map(|piece| match piece {
+            Piece::Str(s) => quote!(::css_in_rs::template::Piece::Str(#s)),
+            Piece::Class(id) => quote!(::css_in_rs::template::Piece::Class(#id)),
+            Piece::Value(value, _) => {
+                let idx = values.len();
+                values.push(value);
+                quote!(::css_in_rs::template::Piece::Value(#idx))
+            }
+        });
+        let pieces = pieces.collect::<Vec<_>>();
+
+        let ts = quote! {
+            const PIECES: &[::css_in_rs::template::Piece] = &[#(#pieces),*];
+            ::css_in_rs::template::render(
+                css,
+                PIECES,
+                ctx.prefix(),
+                start,
+                &[#(&(#values) as &dyn ::core::fmt::Display),*],
+            );
+        };
+        ts.to_tokens(tokens);
     }
 }
 
 impl ToTokens for Output {
     fn to_tokens(&self, tokens: &mut TokenStream) {
+        if self.compact {
+            return self.compact_to_tokens(tokens);
+        }
+
         let format_str = &self.format_str;
         let params = &self.params;
 
@@ -51,6 +429,53 @@ impl ToTokens for Output {
     }
 }
 
+fn collect_theme_fields(tokens: TokenStream, theme_var: &syn::Ident, fields: &mut Vec<String>) {
+    use proc_macro2::TokenTree;
+
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut idx = 0;
+    while idx < tokens.len() {
+        match &tokens[idx] {
+            TokenTree::Group(group) => collect_theme_fields(group.stream(), theme_var, fields),
+            TokenTree::Ident(ident) if ident == theme_var => {
+                // Follow `.field` as long as it is not a method call
+                let mut path = Vec::new();
+                while let (Some(TokenTree::Punct(dot)), Some(TokenTree::Ident(field))) =
+                    (tokens.get(idx + 1), tokens.get(idx + 2))
+                {
+                    if dot.as_char() != '.' {
+                        break;
+                    }
+                    if let Some(TokenTree::Group(args)) = tokens.get(idx + 3) {
+                        if args.delimiter() == proc_macro2::Delimiter::Parenthesis {
+                            if path.is_empty() {
+                                path.push(format!("{field}()"));
+                            }
+                            break;
+                        }
+                    }
+                    path.push(field.to_string());
+                    idx += 2;
+                }
+                if !path.is_empty() {
+                    fields.push(path.join("."));
+                }
+            }
+            _ => {}
+        }
+        idx += 1;
+    }
+}
+
+pub(crate) fn fnv1a(s: &str) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for byte in s.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
 /// Essentially, the `make_styles` creates one huge `write!` command.
 /// It consists of one format string and many params. Whenever we want
 /// to add something to this output, the need to add it to both the
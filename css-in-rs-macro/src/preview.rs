@@ -0,0 +1,278 @@
+use crate::data::rules::{
+    header::{Header, Part},
+    Rule, RuleBody, RuleList,
+};
+
+/// The documentation generated by `#[preview]`: Sample markup for every
+/// selector containing classes, i.e. for `.card > h2.title`:
+/// ```html
+/// <div class="card">
+///   <h2 class="title"></h2>
+/// </div>
+/// ```
+pub fn docs(rules: &RuleList) -> Vec<String> {
+    let mut snippets = Vec::new();
+    for rule in &rules.rules {
+        collect_snippets(rule, &mut snippets);
+    }
+
+    if snippets.is_empty() {
+        return Vec::new();
+    }
+
+    let mut docs = vec![
+        " # Markup".to_owned(),
+        " The markup expected by the styles. Classes are named after the fields".to_owned(),
+        " of this struct:".to_owned(),
+        " ```html".to_owned(),
+    ];
+    for snippet in snippets {
+        docs.extend(snippet.lines().map(|line| format!(" {line}")));
+    }
+    docs.push(" ```".to_owned());
+    docs
+}
+
+fn collect_snippets(rule: &Rule, snippets: &mut Vec<String>) {
+    if let RuleBody::AtRule { children } = &rule.body {
+        for child in children {
+            collect_snippets(child, snippets);
+        }
+        return;
+    }
+    if let RuleBody::Conditional(conditional) = &rule.body {
+        for child in conditional.rules() {
+            collect_snippets(child, snippets);
+        }
+        return;
+    }
+
+    if rule.header.at_rule {
+        return;
+    }
+
+    for selector in split_selectors(&selector(&rule.header)) {
+        let levels = levels(selector);
+        let has_classes = levels.iter().flatten().any(|el| !el.classes.is_empty());
+        if !has_classes {
+            continue;
+        }
+
+        let mut snippet = String::new();
+        render(&levels, 0, &mut snippet);
+        if !snippets.contains(&snippet) {
+            snippets.push(snippet);
+        }
+    }
+}
+
+/// The selector of the header with the classnames as written by the user
+pub fn selector(header: &Header) -> String {
+    let mut selector = String::new();
+    for part in &header.parts {
+        match part {
+            Part::Raw(raw) => selector.push_str(raw),
+            Part::ClassName(name) | Part::LocalName(name) => selector.push_str(name),
+            Part::Reference { field, .. } => {
+                selector.push('.');
+                selector.push_str(&field.to_string());
+            }
+            Part::Name(_) => {}
+        }
+    }
+    selector
+}
+
+/// Splits a selector list at the commas which are not nested
+pub fn split_selectors(src: &str) -> Vec<&str> {
+    let mut depth = 0;
+    let mut start = 0;
+    let mut selectors = Vec::new();
+    for (idx, ch) in src.char_indices() {
+        match ch {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                selectors.push(&src[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    selectors.push(&src[start..]);
+    selectors
+}
+
+#[derive(Default)]
+struct Element {
+    tag: String,
+    classes: Vec<String>,
+    attrs: Vec<String>,
+}
+
+/// Splits a selector at its combinators. Every level contains sibling
+/// elements; the next level is nested in the last element of the previous one.
+fn levels(selector: &str) -> Vec<Vec<Element>> {
+    let mut levels = vec![Vec::new()];
+    let mut compound = String::new();
+    let mut sibling = false;
+    let mut depth = 0;
+
+    let mut finish = |compound: &mut String, sibling: bool| {
+        if compound.is_empty() {
+            return;
+        }
+        if !sibling && !levels.last().unwrap().is_empty() {
+            levels.push(Vec::new());
+        }
+        levels.last_mut().unwrap().push(element(compound));
+        compound.clear();
+    };
+
+    for ch in selector.chars() {
+        match ch {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            _ => {}
+        }
+        match ch {
+            '+' | '~' if depth == 0 => {
+                finish(&mut compound, sibling);
+                sibling = true;
+            }
+            '>' if depth == 0 => {
+                finish(&mut compound, sibling);
+                sibling = false;
+            }
+            ch if ch.is_whitespace() && depth == 0 => {
+                if !compound.is_empty() {
+                    finish(&mut compound, sibling);
+                    sibling = false;
+                }
+            }
+            ch => compound.push(ch),
+        }
+    }
+    finish(&mut compound, sibling);
+    levels
+}
+
+/// Parses a compound selector like `input.field[type="text"]:hover`
+fn element(compound: &str) -> Element {
+    let is_name = |ch: char| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_';
+    let tag_len = compound.find(|ch| !is_name(ch)).unwrap_or(compound.len());
+    let mut element = Element {
+        tag: match &compound[..tag_len] {
+            "" => "div".to_owned(),
+            tag => tag.to_owned(),
+        },
+        ..Default::default()
+    };
+
+    let mut rest = &compound[tag_len..];
+    while let Some(ch) = rest.chars().next() {
+        rest = &rest[ch.len_utf8()..];
+        let name_len = rest.find(|ch| !is_name(ch)).unwrap_or(rest.len());
+        match ch {
+            '.' => element.classes.push(rest[..name_len].to_owned()),
+            '#' => element.attrs.push(format!("id=\"{}\"", &rest[..name_len])),
+            '[' => {
+                let end = rest.find(']').unwrap_or(rest.len());
+                element.attrs.push(attribute(&rest[..end]));
+                rest = &rest[end..];
+                continue;
+            }
+            '(' => {
+                // Arguments of pseudo-classes, i.e. `:not(.active)`
+                let end = rest.find(')').unwrap_or(rest.len());
+                rest = &rest[end..];
+                continue;
+            }
+            _ => continue,
+        }
+        rest = &rest[name_len..];
+    }
+
+    element
+}
+
+/// Turns an attribute selector like `type="text"` or `disabled` into an attribute
+fn attribute(selector: &str) -> String {
+    let Some((name, value)) = selector.split_once('=') else {
+        return selector.trim().to_owned();
+    };
+
+    let name = name.trim_end_matches(['~', '|', '^', '$', '*']).trim();
+    let value = value.trim().trim_matches(['"', '\'']);
+    format!("{name}=\"{value}\"")
+}
+
+/// Elements which have no closing tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+fn render(levels: &[Vec<Element>], depth: usize, out: &mut String) {
+    let Some((siblings, nested)) = levels.split_first() else {
+        return;
+    };
+
+    let indent = "  ".repeat(depth);
+    for (idx, element) in siblings.iter().enumerate() {
+        let mut open = format!("{indent}<{}", element.tag);
+        if !element.classes.is_empty() {
+            open.push_str(&format!(" class=\"{}\"", element.classes.join(" ")));
+        }
+        for attr in &element.attrs {
+            open.push(' ');
+            open.push_str(attr);
+        }
+        open.push('>');
+
+        if VOID_ELEMENTS.contains(&element.tag.as_str()) {
+            out.push_str(&format!("{open}\n"));
+        } else if idx + 1 == siblings.len() && !nested.is_empty() {
+            out.push_str(&format!("{open}\n"));
+            render(nested, depth + 1, out);
+            out.push_str(&format!("{indent}</{}>\n", element.tag));
+        } else {
+            out.push_str(&format!("{open}</{}>\n", element.tag));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+
+    use crate::data::rules::RuleList;
+
+    #[test]
+    fn markup() {
+        let input = quote! {
+            card {
+                padding: "4px",
+            },
+            ".card > h2.title:hover, .card input.field[type=\"text\"] + label" {
+                color: "red",
+            },
+            "@media print" {
+                card {
+                    padding: "0",
+                },
+            },
+            "button" {
+                margin: "0",
+            },
+        };
+        let rules = syn::parse2::<RuleList>(input).unwrap();
+        let docs = super::docs(&rules);
+        assert_eq!(
+            docs[4..].join("\n"),
+            " <div class=\"card\"></div>\n \
+             <div class=\"card\">\n   <h2 class=\"title\"></h2>\n </div>\n \
+             <div class=\"card\">\n   <input class=\"field\" type=\"text\">\n   \
+             <label></label>\n </div>\n ```"
+        );
+    }
+}
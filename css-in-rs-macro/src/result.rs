@@ -2,13 +2,82 @@ use proc_macro2::TokenStream;
 use quote::{quote, quote_spanned, ToTokens, TokenStreamExt};
 
 use crate::{
-    data::{signature::Signature, Style},
+    data::{rules::variants, signature::Signature, Style},
+    lint::Warning,
     output::{Output, ToOutput},
 };
 
 struct ClassDefinition<'a> {
+    /// Lines of the documentation of the struct
+    docs: Vec<String>,
     classnames: &'a [syn::Ident],
+    /// See [Style::get_local_names]
+    local_names: &'a [(syn::Ident, bool)],
+    vis: &'a syn::Visibility,
     classname: &'a syn::Ident,
+    /// See [crate::data::rules::RuleList::variants]
+    variants: Vec<(syn::Ident, &'a variants::Variants)>,
+}
+
+impl<'a> ClassDefinition<'a> {
+    /// The enums of the variant dimensions and a method per rule with
+    /// variants, i.e. `fn button(&self, size: Size) -> String`
+    fn variants(&self) -> Option<TokenStream> {
+        if self.variants.is_empty() {
+            return None;
+        }
+
+        let mut enums = Vec::new();
+        let mut declared = Vec::new();
+        for dimension in self.variants.iter().flat_map(|(_, variants)| &variants.dimensions) {
+            if !declared.contains(&&dimension.name) {
+                declared.push(&dimension.name);
+                enums.push(dimension.enum_tokens(self.vis, self.classname));
+            }
+        }
+
+        let classname = self.classname;
+        let methods = self
+            .variants
+            .iter()
+            .map(|(ident, variants)| variants::method_tokens(ident, variants, ident.span()));
+        let ts = quote! {
+            #(#enums)*
+
+            impl #classname {
+                #(#methods)*
+            }
+        };
+        Some(ts)
+    }
+
+    /// `fn selector(&self, field: &str) -> Option<String>`, returning i.e. `.css-12`
+    fn selector_fn(&self) -> Option<TokenStream> {
+        if self.classnames.is_empty() {
+            return None;
+        }
+
+        let classname = self.classname;
+        let fields = self.classnames.iter().map(|ident| ident.to_string());
+        let idents = self.classnames;
+        let ts = quote! {
+            impl #classname {
+                /// Returns the selector of the class with the given name, i.e.
+                /// `.css-12`, or `None` if there is no such class
+                #[allow(dead_code)]
+                pub fn selector(&self, class: &str) -> Option<String> {
+                    let classnames = match class {
+                        #(#fields => &self.#idents,)*
+                        _ => return None,
+                    };
+                    // Skips the atomic classes, see `#[atomic]`
+                    let classname = classnames.split(' ').next().unwrap_or_default();
+                    Some(format!(".{classname}"))
+                }
+            }
+        };
+        Some(ts)
+    }
 }
 
 impl<'a> ToTokens for ClassDefinition<'a> {
@@ -20,23 +89,39 @@ impl<'a> ToTokens for ClassDefinition<'a> {
                 pub #ident: String,
             )
         }));
+        decls.append_all(self.local_names.iter().map(|(ident, _)| {
+            quote_spanned!(ident.span() =>
+                pub #ident: String,
+            )
+        }));
 
         let classname = self.classname;
-        let header = quote_spanned!(classname.span() => struct #classname);
-        let result = quote!(
+        let vis = self.vis;
+        let docs = &self.docs;
+        let header = quote_spanned!(classname.span() => #vis struct #classname);
+        // Wrapped, so derives can depend on the features of `css_in_rs`
+        let result = quote!(::css_in_rs::classes_struct! {
+            #(#[doc = #docs])*
             #header {
                 #decls
+                #[allow(dead_code)]
+                pub root_all: String,
             }
-        );
+        });
 
-        result.to_tokens(tokens)
+        result.to_tokens(tokens);
+        self.selector_fn().to_tokens(tokens);
+        self.variants().to_tokens(tokens);
     }
 }
 
 struct TraitImpl<'a> {
     signature: &'a Signature,
     classnames: &'a [syn::Ident],
+    local_names: &'a [(syn::Ident, bool)],
     output: &'a Output,
+    /// See `#[priority(...)]`
+    priority: Option<i32>,
 }
 
 impl<'a> ToTokens for TraitImpl<'a> {
@@ -47,33 +132,184 @@ impl<'a> ToTokens for TraitImpl<'a> {
         let output = self.output;
 
         let number_of_classes = self.classnames.len() as u64;
+        // Local names are numbered after the classes
+        let number_of_names = number_of_classes + self.local_names.len() as u64;
+        let local_names = self
+            .local_names
+            .iter()
+            .enumerate()
+            .map(|(idx, (ident, dashed))| {
+                let idx = number_of_classes + idx as u64;
+                let format_str = match dashed {
+                    true => "--{prefix}-{}",
+                    false => "{prefix}-{}",
+                };
+                (
+                    ident,
+                    quote!(format!(#format_str, start + #idx, prefix = prefix)),
+                )
+            })
+            .collect::<Vec<_>>();
+        let setup_local_names = local_names
+            .iter()
+            .map(|(ident, value)| quote!(#ident: #value,));
+        // Bound in `generate`, so the names can be used in values
+        let local_name_bindings = local_names.iter().map(|(ident, value)| {
+            quote! {
+                #[allow(unused_variables)]
+                let #ident = {
+                    let prefix = ctx.prefix();
+                    #value
+                };
+            }
+        });
         let setup_classnames =
             self.classnames
                 .iter()
                 .enumerate()
                 .map(|(idx, ident)| -> TokenStream {
                     let idx = idx as u64;
+                    let format_str = match output.atoms_of(&ident.to_string()) {
+                        Some(atoms) => {
+                            let atoms = atoms.iter().map(|atom| format!("{{prefix}}-{atom}"));
+                            format!("{{prefix}}-{{}} {}", atoms.collect::<Vec<_>>().join(" "))
+                        }
+                        None => "{prefix}-{}".to_owned(),
+                    };
                     quote! {
-                        #ident: format!("css-{}", start + #idx),
+                        #ident: format!(#format_str, start + #idx, prefix = prefix),
                     }
                 });
 
+        let atom_rules = output.atom_rules();
+        let atoms = (!atom_rules.is_empty()).then(|| {
+            quote! {
+                const ATOMS: &'static [&'static str] = &[#(#atom_rules),*];
+            }
+        });
+
+        let dependencies = output.dependencies();
+        let add_dependencies = (!dependencies.is_empty()).then(|| {
+            quote! {
+                fn add_dependencies(provider: &::css_in_rs::StyleProvider<Self::Theme>) {
+                    #(provider.add_classes::<#dependencies>();)*
+                }
+            }
+        });
+        let theme_fields = output.theme_fields(theme_var);
+        let at_rules = output.at_rules();
+        let info = (!theme_fields.is_empty() || !at_rules.is_empty() || !dependencies.is_empty())
+            .then(|| {
+                quote! {
+                    const INFO: &'static ::css_in_rs::graph::ClassesInfo =
+                        &::css_in_rs::graph::ClassesInfo {
+                            theme_fields: &[#(#theme_fields),*],
+                            at_rules: &[#(#at_rules),*],
+                            dependencies: &[#(::core::any::type_name::<#dependencies>),*],
+                        };
+                }
+            });
+
+        let css_estimate = output.css_capacity_hint();
+        let css_estimate = (css_estimate > 0).then(|| {
+            quote! {
+                const CSS_CAPACITY_HINT: usize = #css_estimate;
+            }
+        });
+
+        let class_names = self.classnames.iter().map(|ident| ident.to_string());
+        let class_names = (!self.classnames.is_empty()).then(|| {
+            quote! {
+                const CLASS_NAMES: &'static [&'static str] = &[#(#class_names),*];
+            }
+        });
+
+        let class_locations = self.classnames.iter().map(|ident| {
+            quote_spanned! {ident.span() =>
+                ::css_in_rs::lookup::SourceLocation {
+                    file: file!(),
+                    line: line!(),
+                    column: column!(),
+                }
+            }
+        });
+        let class_locations = (!self.classnames.is_empty()).then(|| {
+            quote! {
+                const CLASS_LOCATIONS: &'static [::css_in_rs::lookup::SourceLocation] =
+                    &[#(#class_locations),*];
+            }
+        });
+
+        let priority = self.priority.map(|priority| {
+            quote! {
+                const PRIORITY: i32 = #priority;
+            }
+        });
+
+        let reads_theme = (!output.reads_theme(theme_var)).then(|| {
+            quote! {
+                const READS_THEME: bool = false;
+            }
+        });
+
+        let dependency_bindings = dependencies.iter().enumerate().map(|(idx, dependency)| {
+            let binding = Output::dependency_binding(idx);
+            quote!(let #binding = ctx.classes_of::<#dependency>();)
+        });
+
+        // Computed from the counter instead of the other fields. Reading them
+        // would suppress the warnings for unused classes
+        let setup_all = if self.classnames.is_empty() {
+            quote!(String::new())
+        } else {
+            let format_str = vec!["{prefix}-{}"; self.classnames.len()].join(" ");
+            let params = (0..number_of_classes).map(|idx| quote!(, start + #idx));
+            quote!(format!(#format_str #(#params)*, prefix = prefix))
+        };
+
+        // Based on `root_all` for the same reason
+        let iter = (!self.classnames.is_empty()).then(|| {
+            quote! {
+                fn iter(&self) -> impl ::core::iter::Iterator<Item = (&'static str, &str)> {
+                    Self::CLASS_NAMES.iter().copied().zip(self.root_all.split(' '))
+                }
+            }
+        });
+
         let ts = quote! {
             impl ::css_in_rs::Classes for #classname {
                 type Theme = #theme_type;
+                #atoms
+                #info
+                #css_estimate
+                #class_names
+                #class_locations
+                #priority
+                #reads_theme
+
+                #add_dependencies
 
-                fn generate(#theme_var: &Self::Theme, css: &mut String, counter: &mut u64) {
+                fn generate(
+                    #theme_var: &Self::Theme,
+                    css: &mut String,
+                    ctx: &mut ::css_in_rs::GenerateContext<Self::Theme>,
+                ) {
                     use ::core::fmt::Write;
-                    let start = *counter;
+                    let start = ctx.reserve(#number_of_names);
+                    #(#dependency_bindings)*
+                    #(#local_name_bindings)*
                     #output
-                    *counter = start + #number_of_classes;
                 }
 
-                fn new(start: u64) -> Self {
+                fn new(prefix: &str, start: u64) -> Self {
                     Self {
                         #(#setup_classnames)*
+                        #(#setup_local_names)*
+                        root_all: #setup_all,
                     }
                 }
+
+                #iter
             }
         };
         ts.to_tokens(tokens)
@@ -83,20 +319,38 @@ impl<'a> ToTokens for TraitImpl<'a> {
 pub struct Result {
     style: Style,
     classnames: Vec<syn::Ident>,
+    local_names: Vec<(syn::Ident, bool)>,
     output: Output,
+    warnings: Vec<Warning>,
 }
 
 impl Result {
     pub fn new(style: Style) -> Self {
         let classnames = style.get_classnames();
-        let mut output = Output::new(&classnames);
+        let local_names = style.get_local_names();
+        let idents = classnames
+            .iter()
+            .chain(local_names.iter().map(|(ident, _)| ident))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut output = Output::new(&style.signature.classname, &idents, &style.options);
+        if let Some(component) = &style.options.overrides {
+            output.enable_overrides(&style.signature.theme_varname, component);
+        }
 
         style.rules.append(&mut output);
 
+        let warnings = match style.options.allow_conflicts {
+            true => Vec::new(),
+            false => crate::lint::check(&style.rules),
+        };
+
         Self {
             style,
             classnames,
+            local_names,
             output,
+            warnings,
         }
     }
 }
@@ -105,20 +359,32 @@ impl ToTokens for Result {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let signature = &self.style.signature;
         let classnames = &self.classnames;
+        let docs = match self.style.options.preview {
+            true => crate::preview::docs(&self.style.rules),
+            false => Vec::new(),
+        };
         let class_def = ClassDefinition {
+            docs,
             classnames,
+            local_names: &self.local_names,
+            vis: &signature.vis,
             classname: &signature.classname,
+            variants: self.style.rules.variants().unwrap_or_default(),
         };
 
         let trait_impl = TraitImpl {
             signature,
             classnames,
+            local_names: &self.local_names,
             output: &self.output,
+            priority: self.style.options.priority,
         };
 
+        let warnings = &self.warnings;
         let ts = quote! {
             #class_def
             #trait_impl
+            #(#warnings)*
         };
         ts.to_tokens(tokens);
     }
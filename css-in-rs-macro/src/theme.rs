@@ -0,0 +1,79 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+
+use crate::output::fnv1a;
+
+/// The input of `#[derive(Theme)]`
+pub struct Theme {
+    input: syn::DeriveInput,
+}
+
+impl Theme {
+    pub fn new(input: syn::DeriveInput) -> Self {
+        Self { input }
+    }
+
+    /// Hashes the names and types of all fields, so the fingerprint changes
+    /// whenever the layout of the theme might change. Attributes other than
+    /// `repr` are ignored; changing a doc comment does not need a new provider
+    fn schema(&self) -> u64 {
+        let mut schema = String::new();
+        for attr in &self.input.attrs {
+            if attr.path().is_ident("repr") {
+                schema += &attr.to_token_stream().to_string();
+            }
+        }
+        schema += &self.input.generics.to_token_stream().to_string();
+
+        match &self.input.data {
+            syn::Data::Struct(data) => push_fields(&mut schema, &data.fields),
+            syn::Data::Enum(data) => {
+                for variant in &data.variants {
+                    schema += &variant.ident.to_string();
+                    if let Some((_, discriminant)) = &variant.discriminant {
+                        schema += "=";
+                        schema += &discriminant.to_token_stream().to_string();
+                    }
+                    schema += "{";
+                    push_fields(&mut schema, &variant.fields);
+                    schema += "}";
+                }
+            }
+            syn::Data::Union(data) => {
+                push_fields(&mut schema, &syn::Fields::Named(data.fields.clone()))
+            }
+        }
+
+        fnv1a(&schema)
+    }
+}
+
+impl ToTokens for Theme {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let ident = &self.input.ident;
+        let (impl_generics, ty_generics, where_clause) = self.input.generics.split_for_impl();
+        let schema = self.schema();
+
+        tokens.extend(quote! {
+            impl #impl_generics ::css_in_rs::Theme for #ident #ty_generics #where_clause {
+                const SCHEMA: u64 = #schema;
+
+                fn fast_cmp(&self, other: &Self) -> bool {
+                    self == other
+                }
+            }
+        });
+    }
+}
+
+fn push_fields(schema: &mut String, fields: &syn::Fields) {
+    for (idx, field) in fields.iter().enumerate() {
+        match &field.ident {
+            Some(ident) => *schema += &ident.to_string(),
+            None => *schema += &idx.to_string(),
+        }
+        *schema += ":";
+        *schema += &field.ty.to_token_stream().to_string();
+        *schema += ";";
+    }
+}
@@ -1,9 +1,37 @@
+#[cfg(feature = "dioxus")]
+pub mod document;
+pub mod memory;
 #[cfg(feature = "web-sys")]
 pub mod web;
 
-use crate::Theme;
+use crate::{Error, GenerateContext, Theme};
 
-pub type CssGeneratorFn<T> = fn(&T, &mut String, &mut u64) -> ();
+pub type CssGeneratorFn<T> = fn(&T, &mut String, &mut GenerateContext<T>) -> ();
+
+/// Where an external stylesheet is linked, relative to the generated styles,
+/// see [StyleProvider::link_external](crate::StyleProvider::link_external).
+/// Stylesheets with the same position keep the order in which they have been
+/// linked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LinkPosition {
+    /// Before the generated styles, so they override the external ones with
+    /// the same specificity, i.e. for a CSS reset or a component library
+    #[default]
+    Before,
+    /// After the generated styles, so the external ones take precedence
+    After,
+}
+
+/// A part of the styles of a [StyleProvider](crate::StyleProvider), see
+/// [Backend::replace_chunks]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StyleChunk {
+    /// The type name of the [Classes](crate::Classes) generating the styles,
+    /// or `None` for styles shared by all of them, like keyframes, font faces
+    /// or raw stylesheets
+    pub name: Option<&'static str>,
+    pub css: String,
+}
 
 /// css-in-rs is backend agnostic. The default backend is based on web_sys,
 /// but other backends are possible (i.e. just insert css into a string, for
@@ -12,10 +40,76 @@ pub trait Backend<T: Theme>: 'static {
     /// Replaces all styles managed by this backend by the given CSS string
     fn replace_all(&mut self, css: String);
 
+    /// Replaces all styles managed by this backend by the given chunks, in
+    /// order. Only used if
+    /// [StyleProviderBuilder::chunked](crate::StyleProviderBuilder::chunked)
+    /// is enabled, in which case nothing is appended. By default, the chunks
+    /// are concatenated and passed to [Backend::replace_all].
+    fn replace_chunks(&mut self, chunks: Vec<StyleChunk>) {
+        self.replace_all(chunks.into_iter().map(|chunk| chunk.css).collect());
+    }
+
+    /// Appends the given CSS string to the styles managed by this backend
+    fn append(&mut self, css: &str);
+
+    /// Returns all styles currently managed by this backend
+    fn current_css(&self) -> &str;
+
     /// Runs a given css generator and add the generated styles. The `generator`
     /// function is expected to append new rules to the given `String`. It may
     /// be empty, in which case the new style is to be returned. Alternatively,
     /// the backend may choose to put in all existing rules, in which case the
     /// new rules are to be appended.
-    fn run_css_generator(&mut self, generator: CssGeneratorFn<T>, theme: &T, counter: &mut u64);
+    fn run_css_generator(
+        &mut self,
+        generator: CssGeneratorFn<T>,
+        theme: &T,
+        ctx: &mut GenerateContext<T>,
+    );
+
+    /// Creates an empty backend for a scoped [StyleProvider](crate::StyleProvider),
+    /// see [StyleProvider::scoped](crate::StyleProvider::scoped). Its styles
+    /// should be mounted next to ours, i.e. in a `style` element of the same
+    /// document.
+    fn new_scope(&self) -> Box<dyn Backend<T>>;
+
+    /// Called after the styles of a new [Classes](crate::Classes) type have been
+    /// added. `name` is the type name of the classes. Backends may use it to take
+    /// over styles which have been inlined ahead of time, see
+    /// [CriticalCss](crate::extract::CriticalCss).
+    fn mounted(&mut self, name: &'static str) {
+        let _ = name;
+    }
+
+    /// Whether the backend has deferred changes which [Backend::flush] still
+    /// has to write. The [StyleProvider](crate::StyleProvider) flushes them
+    /// once per animation frame, so many changes in one frame cause only one
+    /// style recalculation.
+    fn needs_flush(&self) -> bool {
+        false
+    }
+
+    /// Writes the deferred changes, see [Backend::needs_flush]
+    fn flush(&mut self) {}
+
+    /// Loads the external stylesheet at `url`, i.e. using a
+    /// `<link rel="stylesheet">` element next to ours. Backends without a
+    /// document ignore it. Every url is linked at most once.
+    fn link_external(&mut self, url: &str, position: LinkPosition) -> Result<(), Error> {
+        let _ = (url, position);
+        Ok(())
+    }
+
+    /// Sets an attribute of the `style` element holding the styles, see
+    /// [StyleProviderBuilder::style_attribute](crate::StyleProviderBuilder::style_attribute).
+    /// Backends without a document ignore it.
+    fn set_attribute(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        let _ = (name, value);
+        Ok(())
+    }
+
+    /// Removes an external stylesheet added by [Backend::link_external]
+    fn unlink_external(&mut self, url: &str) {
+        let _ = url;
+    }
 }
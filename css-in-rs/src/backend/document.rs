@@ -0,0 +1,154 @@
+use std::{
+    rc::Rc,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use dioxus::document::Document;
+
+use crate::{extract::push_json_string, Error, GenerateContext, Theme};
+
+use super::{Backend, CssGeneratorFn, LinkPosition};
+
+/// A backend which mounts styles through the document API of the active Dioxus
+/// renderer, i.e. by evaluating JavaScript. In contrast to the web_sys based
+/// backend, it does not need direct access to the DOM from Rust, so it works
+/// on desktop, mobile and LiveView as well as on the web.
+///
+/// Styles are put into a `style` element which is created in the `head` of
+/// the document on first use.
+pub struct DocumentBackend {
+    document: Rc<dyn Document>,
+    id: String,
+    css: String,
+    /// The external stylesheets with the number in the id of their `link`
+    /// element, in the order they have been linked
+    links: Vec<(String, LinkPosition, u64)>,
+    next_link: u64,
+}
+
+impl DocumentBackend {
+    /// Uses the document of the active Dioxus renderer. Has to be called from
+    /// within the Dioxus runtime, i.e. from a component or a hook.
+    pub fn current() -> Self {
+        Self::with_document(dioxus::document::document())
+    }
+
+    pub fn with_document(document: Rc<dyn Document>) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        let id = format!("css-in-rs-{}", NEXT_ID.fetch_add(1, Ordering::Relaxed));
+
+        Self {
+            document,
+            id,
+            css: Default::default(),
+            links: Default::default(),
+            next_link: 0,
+        }
+    }
+
+    /// A script declaring `style`, our `style` element
+    fn style_script(&self) -> String {
+        format!(
+            "let style = document.getElementById(\"{id}\");\n\
+             if (!style) {{\n  \
+               style = document.createElement(\"style\");\n  \
+               style.id = \"{id}\";\n  \
+               document.head.appendChild(style);\n\
+             }}\n",
+            id = self.id,
+        )
+    }
+
+    /// Sets (or with `append`, extends) the content of our `style` element
+    fn send(&self, css: &str, append: bool) {
+        let operator = if append { "+=" } else { "=" };
+        let mut script = self.style_script();
+        script.push_str(&format!("style.textContent {operator} "));
+        push_json_string(&mut script, css);
+        script.push(';');
+
+        // Fire and forget: We are not interested in the result
+        let _ = self.document.eval(script);
+    }
+}
+
+impl<T: Theme> Backend<T> for DocumentBackend {
+    fn replace_all(&mut self, css: String) {
+        self.css = css;
+        self.send(&self.css, false);
+    }
+
+    fn append(&mut self, css: &str) {
+        self.css.push_str(css);
+        self.send(css, true);
+    }
+
+    fn current_css(&self) -> &str {
+        &self.css
+    }
+
+    fn run_css_generator(
+        &mut self,
+        generator: CssGeneratorFn<T>,
+        theme: &T,
+        ctx: &mut GenerateContext<T>,
+    ) {
+        let mut css = String::new();
+        (generator)(theme, &mut css, ctx);
+        Backend::<T>::append(self, &css);
+    }
+
+    fn new_scope(&self) -> Box<dyn Backend<T>> {
+        Box::new(DocumentBackend::with_document(self.document.clone()))
+    }
+
+    fn link_external(&mut self, url: &str, position: LinkPosition) -> Result<(), Error> {
+        if self.links.iter().any(|(linked, ..)| linked == url) {
+            return Ok(());
+        }
+
+        // Links after the styles are inserted after the last one of them
+        let anchor = match position {
+            LinkPosition::Before => "style.before(link);".to_owned(),
+            LinkPosition::After => match self.links.iter().rfind(|(_, p, _)| *p == position) {
+                Some((.., last)) => {
+                    format!(
+                        "document.getElementById(\"{}-link-{last}\").after(link);",
+                        self.id
+                    )
+                }
+                None => "style.after(link);".to_owned(),
+            },
+        };
+        let mut script = self.style_script();
+        script.push_str(&format!(
+            "let link = document.createElement(\"link\");\n\
+             link.id = \"{id}-link-{number}\";\n\
+             link.rel = \"stylesheet\";\n\
+             link.href = ",
+            id = self.id,
+            number = self.next_link,
+        ));
+        push_json_string(&mut script, url);
+        script.push_str(";\n");
+        script.push_str(&anchor);
+
+        let _ = self.document.eval(script);
+        self.links.push((url.to_owned(), position, self.next_link));
+        self.next_link += 1;
+        Ok(())
+    }
+
+    fn unlink_external(&mut self, url: &str) {
+        let Some(idx) = self.links.iter().position(|(linked, ..)| linked == url) else {
+            return;
+        };
+
+        let (.., number) = self.links.remove(idx);
+        let script = format!(
+            "document.getElementById(\"{id}-link-{number}\")?.remove();",
+            id = self.id
+        );
+        let _ = self.document.eval(script);
+    }
+}
@@ -0,0 +1,48 @@
+use crate::{GenerateContext, Theme};
+
+use super::{Backend, CssGeneratorFn};
+
+/// A backend which keeps all styles in memory. It does not need a DOM and
+/// works on every target, for example in unit tests running natively.
+#[derive(Default)]
+pub struct MemoryBackend {
+    css: String,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The styles managed by this backend
+    pub fn css(&self) -> &str {
+        &self.css
+    }
+}
+
+impl<T: Theme> Backend<T> for MemoryBackend {
+    fn replace_all(&mut self, css: String) {
+        self.css = css;
+    }
+
+    fn append(&mut self, css: &str) {
+        self.css.push_str(css);
+    }
+
+    fn current_css(&self) -> &str {
+        &self.css
+    }
+
+    fn run_css_generator(
+        &mut self,
+        generator: CssGeneratorFn<T>,
+        theme: &T,
+        ctx: &mut GenerateContext<T>,
+    ) {
+        (generator)(theme, &mut self.css, ctx);
+    }
+
+    fn new_scope(&self) -> Box<dyn Backend<T>> {
+        Box::new(MemoryBackend::new())
+    }
+}
@@ -1,46 +1,653 @@
-use wasm_bindgen::JsCast;
+use wasm_bindgen::{closure::Closure, JsCast};
 
-use crate::Theme;
+use crate::{
+    extract::{hydration_mismatch, CRITICAL_ATTRIBUTE, HYDRATED_ATTRIBUTE},
+    Error, GenerateContext, Theme,
+};
 
-use super::{Backend, CssGeneratorFn};
+use super::{memory::MemoryBackend, Backend, CssGeneratorFn, LinkPosition, StyleChunk};
 
+/// Mounts the styles in a `style` element. Changes are written once per
+/// animation frame, see [Backend::needs_flush].
 pub struct WebSysBackend {
     current_style: String,
     styles: web_sys::Element,
+    /// Streamed responses may contain multiple elements, see
+    /// [StyleStream](crate::extract::StyleStream)
+    critical: Vec<CriticalStyles>,
+    /// Whether `current_style` has changed since it has been written
+    dirty: bool,
+    /// The `link` elements of external stylesheets, in document order within
+    /// each position
+    links: Vec<ExternalLink>,
+    /// The chunks to write with the next flush, see [Backend::replace_chunks]
+    pending_chunks: Option<Vec<StyleChunk>>,
+    /// The `style` elements of the chunks following `styles`, in document order
+    chunks: Vec<ChunkElement>,
+    /// Attributes set by [Backend::set_attribute], which are given to the
+    /// elements of chunks and scopes as well
+    attributes: Vec<(String, String)>,
+}
+
+/// A `style` element holding a chunk, see [Backend::replace_chunks]
+struct ChunkElement {
+    name: Option<&'static str>,
+    /// Distinguishes chunks of the same name, i.e. of multiple raw stylesheets
+    occurrence: usize,
+    element: web_sys::Element,
+    css: String,
+}
+
+struct ExternalLink {
+    url: String,
+    position: LinkPosition,
+    element: web_sys::Element,
+}
+
+/// A `style` element with critical CSS which has been inlined into the HTML
+/// document, together with the type names of the classes it still contains
+struct CriticalStyles {
+    element: web_sys::Element,
+    pending: Vec<String>,
+}
+
+/// What to do if there is no document to mount styles in, i.e. in a web
+/// worker, during prerendering or when running natively
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoDocument {
+    /// Panic, just like [WebSysBackend::quickstart]
+    Panic,
+    /// Keep the styles in memory, see [MemoryBackend]. The classnames still
+    /// work, so shared code paths need no special handling.
+    Memory,
+    /// Keep the styles in memory and mount them as soon as a document is
+    /// available. Availability is checked whenever styles are added or updated.
+    Defer,
+}
+
+/// Returns the current window, or `None` if there is none
+pub(crate) fn active_window() -> Option<web_sys::Window> {
+    // Imported functions of wasm-bindgen panic on other targets
+    if !cfg!(target_arch = "wasm32") {
+        return None;
+    }
+
+    web_sys::window()
+}
+
+/// Returns the document of the current window
+fn active_document() -> Result<web_sys::Document, Error> {
+    active_window()
+        .ok_or(Error::NoWindow)?
+        .document()
+        .ok_or(Error::NoDocument)
+}
+
+/// Returns the `sessionStorage` of the current window, or `None` if there is none
+pub(crate) fn session_storage() -> Option<web_sys::Storage> {
+    active_window()?.session_storage().ok()?
+}
+
+/// Calls `callback` before the next repaint. Returns `false` if there is no window.
+pub(crate) fn request_animation_frame(callback: impl FnOnce() + 'static) -> bool {
+    let Some(window) = active_window() else {
+        return false;
+    };
+
+    let callback = Closure::once_into_js(callback);
+    window
+        .request_animation_frame(callback.unchecked_ref())
+        .is_ok()
+}
+
+/// Calls `callback` once `timeout` has passed. Returns `false` if there is no window.
+pub(crate) fn set_timeout(
+    timeout: core::time::Duration,
+    callback: impl FnOnce() + 'static,
+) -> bool {
+    let Some(window) = active_window() else {
+        return false;
+    };
+
+    let callback = Closure::once_into_js(callback);
+    let millis = i32::try_from(timeout.as_millis()).unwrap_or(i32::MAX);
+    window
+        .set_timeout_with_callback_and_timeout_and_arguments_0(callback.unchecked_ref(), millis)
+        .is_ok()
+}
+
+fn dom_error(err: wasm_bindgen::JsValue) -> Error {
+    Error::Dom(format!("{err:?}"))
+}
+
+/// Appends a new `style` element to the head of the document
+fn create_style_element(doc: &web_sys::Document) -> Result<web_sys::Element, Error> {
+    let head = doc.head().ok_or(Error::NoHead)?;
+    let styles = doc.create_element("style").map_err(dom_error)?;
+    head.append_child(&styles).map_err(dom_error)?;
+    Ok(styles)
 }
 
 impl WebSysBackend {
     pub fn quickstart() -> Self {
-        let document = web_sys::window().unwrap().document().unwrap();
-        Self::new_and_mount_in_root(&document)
+        Self::try_quickstart_web().unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [WebSysBackend::quickstart], but returns `None` if there is no document
+    pub fn try_quickstart() -> Option<Self> {
+        Self::try_quickstart_web().ok()
+    }
+
+    /// Like [WebSysBackend::quickstart], but returns an error instead of panicking
+    pub fn try_quickstart_web() -> Result<Self, Error> {
+        let document = active_document()?;
+        Self::try_new_and_mount(&document)
     }
 
     pub fn new_and_mount_in_root(root: &web_sys::Node) -> Self {
-        let styles = if let Some(doc) = root.dyn_ref::<web_sys::Document>() {
-            let head = doc.head().unwrap();
-            let styles = doc.create_element("style").unwrap();
-            head.append_child(&styles).unwrap();
-            styles
-        } else {
-            panic!("This is most likely a shadow root. Not supported yet");
-        };
+        Self::try_new_and_mount(root).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [WebSysBackend::new_and_mount_in_root], but returns an error
+    /// instead of panicking, i.e. if `root` is neither a document nor a
+    /// shadow root
+    pub fn try_new_and_mount(root: &web_sys::Node) -> Result<Self, Error> {
+        if let Some(shadow_root) = root.dyn_ref::<web_sys::ShadowRoot>() {
+            return Self::try_new_in_shadow_root(shadow_root);
+        }
+
+        let doc = root
+            .dyn_ref::<web_sys::Document>()
+            .ok_or(Error::UnsupportedRoot)?;
+        let styles = create_style_element(doc)?;
+        Ok(Self::attach_to_style_element(styles))
+    }
+
+    /// Mounts the styles in a new `style` element inside of the given shadow
+    /// root, i.e. of a custom element, see [crate::web_components]. Styles
+    /// in the shadow root only apply to its content.
+    pub fn try_new_in_shadow_root(root: &web_sys::ShadowRoot) -> Result<Self, Error> {
+        let doc = root.owner_document().ok_or(Error::NoDocument)?;
+        let styles = doc.create_element("style").map_err(dom_error)?;
+        root.append_child(&styles).map_err(dom_error)?;
+
+        // Critical styles of the document are not taken over, since they do
+        // not apply in the shadow root
+        Ok(Self {
+            styles,
+            current_style: Default::default(),
+            critical: Vec::new(),
+            dirty: false,
+            links: Default::default(),
+            pending_chunks: None,
+            chunks: Default::default(),
+            attributes: Default::default(),
+        })
+    }
+
+    /// Mounts the styles in an existing `style` element, i.e. one which the
+    /// host page has created with its own attributes (like a `nonce`) at a
+    /// position of its choice. Its content is replaced by the styles. The
+    /// elements of scoped providers are inserted right after it and get the
+    /// same `nonce`.
+    pub fn attach_to_style_element(styles: web_sys::Element) -> Self {
+        let selector = format!("style[{CRITICAL_ATTRIBUTE}]");
+        let elements = styles
+            .owner_document()
+            .and_then(|doc| doc.query_selector_all(&selector).ok());
+        let count = elements.as_ref().map_or(0, |elements| elements.length());
+        let critical = (0..count)
+            .filter_map(|idx| elements.as_ref()?.get(idx)?.dyn_into::<web_sys::Element>().ok())
+            .map(|element| {
+                let pending = element
+                    .get_attribute(CRITICAL_ATTRIBUTE)
+                    .unwrap_or_default();
+                let pending = pending.split_whitespace().map(str::to_owned).collect();
+                CriticalStyles { element, pending }
+            })
+            .collect();
 
         Self {
             styles,
             current_style: Default::default(),
+            critical,
+            dirty: false,
+            links: Default::default(),
+            pending_chunks: None,
+            chunks: Default::default(),
+            attributes: Default::default(),
         }
     }
 }
 
+impl WebSysBackend {
+    /// Gives the `nonce` and the attributes set by [Backend::set_attribute]
+    /// to another element holding our styles. The `id` is not copied, since
+    /// it has to be unique.
+    fn copy_attributes(&self, element: &web_sys::Element) -> Result<(), Error> {
+        let nonce = self.styles.get_attribute("nonce");
+        let nonce = nonce.as_deref().map(|nonce| ("nonce", nonce));
+        let attributes = self
+            .attributes
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()));
+        for (name, value) in nonce.into_iter().chain(attributes) {
+            if name != "id" {
+                element.set_attribute(name, value).map_err(dom_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The last element holding our styles
+    fn last_element(&self) -> &web_sys::Element {
+        self.chunks
+            .last()
+            .map_or(&self.styles, |chunk| &chunk.element)
+    }
+
+    /// Writes the chunks into `style` elements following ours. The first
+    /// chunk stays in our element if it holds shared styles. Elements are
+    /// reused by name, so only the ones whose styles have changed are written.
+    fn write_chunks(&mut self, chunks: Vec<StyleChunk>) -> Result<(), Error> {
+        let document = self.styles.owner_document().ok_or(Error::NoDocument)?;
+        let parent = self.styles.parent_node().ok_or(Error::Detached)?;
+
+        let mut chunks = chunks.into_iter().peekable();
+        let shared = chunks.next_if(|chunk| chunk.name.is_none());
+        let shared = shared.map(|chunk| chunk.css).unwrap_or_default();
+        if self.styles.text_content().as_deref() != Some(&shared) {
+            self.styles.set_text_content(Some(&shared));
+        }
+
+        let mut previous = self.styles.clone();
+        let mut remaining = core::mem::take(&mut self.chunks);
+        for chunk in chunks {
+            let occurrence = self
+                .chunks
+                .iter()
+                .filter(|other| other.name == chunk.name)
+                .count();
+            let reused = remaining
+                .iter()
+                .position(|other| other.name == chunk.name && other.occurrence == occurrence);
+            let mut element = match reused {
+                Some(idx) => remaining.remove(idx),
+                None => {
+                    let element = document.create_element("style").map_err(dom_error)?;
+                    if let Some(name) = chunk.name {
+                        element
+                            .set_attribute("data-classes", name)
+                            .map_err(dom_error)?;
+                    }
+                    self.copy_attributes(&element)?;
+                    ChunkElement {
+                        name: chunk.name,
+                        occurrence,
+                        element,
+                        css: String::new(),
+                    }
+                }
+            };
+
+            if element.css != chunk.css || reused.is_none() {
+                element.element.set_text_content(Some(&chunk.css));
+                element.css = chunk.css;
+            }
+            let next = previous.next_sibling();
+            if !element.element.is_same_node(next.as_ref()) {
+                parent
+                    .insert_before(&element.element, next.as_ref())
+                    .map_err(dom_error)?;
+            }
+            previous = element.element.clone();
+            self.chunks.push(element);
+        }
+
+        for chunk in remaining {
+            chunk.element.remove();
+        }
+        Ok(())
+    }
+
+    /// Creates a `link` element for the stylesheet and inserts it before or
+    /// after our `style` element
+    fn create_link(&self, url: &str, position: LinkPosition) -> Result<web_sys::Element, Error> {
+        let document = self.styles.owner_document().ok_or(Error::NoDocument)?;
+        let parent = self.styles.parent_node().ok_or(Error::Detached)?;
+        let link = document.create_element("link").map_err(dom_error)?;
+        link.set_attribute("rel", "stylesheet").map_err(dom_error)?;
+        link.set_attribute("href", url).map_err(dom_error)?;
+        if let Some(nonce) = self.styles.get_attribute("nonce") {
+            link.set_attribute("nonce", &nonce).map_err(dom_error)?;
+        }
+
+        let next = match position {
+            LinkPosition::Before => Some(self.styles.clone().into()),
+            LinkPosition::After => {
+                let last = self
+                    .links
+                    .iter()
+                    .rfind(|link| link.position == LinkPosition::After)
+                    .map_or(self.last_element(), |link| &link.element);
+                last.next_sibling()
+            }
+        };
+        parent
+            .insert_before(&link, next.as_ref())
+            .map_err(dom_error)?;
+        Ok(link)
+    }
+}
+
 impl<T: Theme> Backend<T> for WebSysBackend {
     fn replace_all(&mut self, css: String) {
         self.current_style = css;
-        self.styles.set_text_content(Some(&self.current_style));
+        self.dirty = true;
+    }
+
+    fn append(&mut self, css: &str) {
+        self.current_style.push_str(css);
+        self.dirty = true;
+    }
+
+    fn replace_chunks(&mut self, chunks: Vec<StyleChunk>) {
+        self.current_style = chunks.iter().map(|chunk| chunk.css.as_str()).collect();
+        self.pending_chunks = Some(chunks);
+        self.dirty = true;
+    }
+
+    fn current_css(&self) -> &str {
+        &self.current_style
     }
 
-    fn run_css_generator(&mut self, generator: CssGeneratorFn<T>, theme: &T, counter: &mut u64) {
+    fn run_css_generator(
+        &mut self,
+        generator: CssGeneratorFn<T>,
+        theme: &T,
+        ctx: &mut GenerateContext<T>,
+    ) {
         // TODO: There is probably a much faster way than to append this style this way
-        (generator)(theme, &mut self.current_style, counter);
-        self.styles.set_text_content(Some(&self.current_style));
+        (generator)(theme, &mut self.current_style, ctx);
+        self.dirty = true;
+    }
+
+    fn new_scope(&self) -> Box<dyn Backend<T>> {
+        let document = self.styles.owner_document().unwrap();
+        let styles = match self.styles.parent_node() {
+            Some(parent) => {
+                let styles = document.create_element("style").unwrap();
+                let next = self.last_element().next_sibling();
+                parent.insert_before(&styles, next.as_ref()).unwrap();
+                styles
+            }
+            None => create_style_element(&document).unwrap_or_else(|err| panic!("{err}")),
+        };
+        self.copy_attributes(&styles)
+            .unwrap_or_else(|err| panic!("{err}"));
+
+        // Critical styles are only taken over by the root provider
+        Box::new(Self {
+            styles,
+            current_style: Default::default(),
+            critical: Vec::new(),
+            dirty: false,
+            links: Default::default(),
+            pending_chunks: None,
+            chunks: Default::default(),
+            attributes: self.attributes.clone(),
+        })
+    }
+
+    fn mounted(&mut self, name: &'static str) {
+        for critical in &mut self.critical {
+            critical.pending.retain(|pending| pending != name);
+        }
+        if !self.critical.iter().any(|critical| critical.pending.is_empty()) {
+            return;
+        }
+
+        // All styles of an element have been mounted again. They have to be
+        // written before the inlined ones are removed.
+        Backend::<T>::flush(self);
+        self.critical.retain(|critical| {
+            let done = critical.pending.is_empty();
+            if done && cfg!(debug_assertions) {
+                let server = critical.element.text_content().unwrap_or_default();
+                if let Some(diff) = hydration_mismatch(&server, &self.current_style) {
+                    web_sys::console::warn_1(&diff.into());
+                }
+            }
+            if done && critical.element.has_attribute(HYDRATED_ATTRIBUTE) {
+                critical.element.set_text_content(None);
+            } else if done {
+                critical.element.remove();
+            }
+            !done
+        });
+    }
+
+    fn needs_flush(&self) -> bool {
+        self.dirty
+    }
+
+    fn flush(&mut self) {
+        if !self.dirty {
+            return;
+        }
+
+        self.dirty = false;
+        let written = match self.pending_chunks.take() {
+            Some(chunks) => self.write_chunks(chunks).is_ok(),
+            None => false,
+        };
+        // A detached element gets all styles, so they are not lost
+        if !written {
+            self.styles.set_text_content(Some(&self.current_style));
+        }
+    }
+
+    fn link_external(&mut self, url: &str, position: LinkPosition) -> Result<(), Error> {
+        if self.links.iter().any(|link| link.url == url) {
+            return Ok(());
+        }
+
+        let element = self.create_link(url, position)?;
+        self.links.push(ExternalLink {
+            url: url.to_owned(),
+            position,
+            element,
+        });
+        Ok(())
+    }
+
+    fn set_attribute(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        self.styles.set_attribute(name, value).map_err(dom_error)?;
+        if name != "id" {
+            for chunk in &self.chunks {
+                chunk.element.set_attribute(name, value).map_err(dom_error)?;
+            }
+        }
+
+        self.attributes.retain(|(other, _)| other != name);
+        self.attributes.push((name.to_owned(), value.to_owned()));
+        Ok(())
+    }
+
+    fn unlink_external(&mut self, url: &str) {
+        self.links.retain(|link| {
+            let keep = link.url != url;
+            if !keep {
+                link.element.remove();
+            }
+            keep
+        });
+    }
+}
+
+/// Keeps styles in memory until a document is available, then moves them into
+/// a [WebSysBackend]. See [NoDocument::Defer].
+pub struct DeferredBackend {
+    state: DeferredState,
+}
+
+enum DeferredState {
+    Pending {
+        memory: MemoryBackend,
+        /// Type names passed to [Backend::mounted], replayed once mounted
+        mounted: Vec<&'static str>,
+        /// External stylesheets, linked once mounted
+        links: Vec<(String, LinkPosition)>,
+        /// Attributes of the `style` element, set once mounted
+        attributes: Vec<(String, String)>,
+    },
+    Mounted(WebSysBackend),
+}
+
+impl DeferredBackend {
+    pub fn new() -> Self {
+        let state = DeferredState::Pending {
+            memory: MemoryBackend::new(),
+            mounted: Default::default(),
+            links: Default::default(),
+            attributes: Default::default(),
+        };
+        Self { state }
+    }
+
+    /// Whether the styles have been moved into a document
+    pub fn is_mounted(&self) -> bool {
+        matches!(self.state, DeferredState::Mounted(_))
+    }
+
+    fn try_mount<T: Theme>(&mut self) {
+        let DeferredState::Pending {
+            memory,
+            mounted,
+            links,
+            attributes,
+        } = &mut self.state
+        else {
+            return;
+        };
+        let Some(mut backend) = WebSysBackend::try_quickstart() else {
+            return;
+        };
+
+        Backend::<T>::replace_all(&mut backend, memory.css().to_owned());
+        for name in mounted.drain(..) {
+            Backend::<T>::mounted(&mut backend, name);
+        }
+        for (url, position) in links.drain(..) {
+            // There is no caller left to report errors to
+            let _ = Backend::<T>::link_external(&mut backend, &url, position);
+        }
+        for (name, value) in attributes.drain(..) {
+            let _ = Backend::<T>::set_attribute(&mut backend, &name, &value);
+        }
+        self.state = DeferredState::Mounted(backend);
+    }
+
+    fn backend<T: Theme>(&mut self) -> &mut dyn Backend<T> {
+        self.try_mount::<T>();
+        match &mut self.state {
+            DeferredState::Pending { memory, .. } => memory,
+            DeferredState::Mounted(backend) => backend,
+        }
+    }
+}
+
+impl Default for DeferredBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Theme> Backend<T> for DeferredBackend {
+    fn replace_all(&mut self, css: String) {
+        self.backend::<T>().replace_all(css);
+    }
+
+    fn append(&mut self, css: &str) {
+        self.backend::<T>().append(css);
+    }
+
+    fn replace_chunks(&mut self, chunks: Vec<StyleChunk>) {
+        self.backend::<T>().replace_chunks(chunks);
+    }
+
+    fn current_css(&self) -> &str {
+        match &self.state {
+            DeferredState::Pending { memory, .. } => memory.css(),
+            DeferredState::Mounted(backend) => Backend::<T>::current_css(backend),
+        }
+    }
+
+    fn run_css_generator(
+        &mut self,
+        generator: CssGeneratorFn<T>,
+        theme: &T,
+        ctx: &mut GenerateContext<T>,
+    ) {
+        self.backend::<T>().run_css_generator(generator, theme, ctx);
+    }
+
+    fn new_scope(&self) -> Box<dyn Backend<T>> {
+        match &self.state {
+            DeferredState::Pending { .. } => Box::new(DeferredBackend::new()),
+            DeferredState::Mounted(backend) => Backend::<T>::new_scope(backend),
+        }
+    }
+
+    fn mounted(&mut self, name: &'static str) {
+        match &mut self.state {
+            DeferredState::Pending { mounted, .. } => mounted.push(name),
+            DeferredState::Mounted(backend) => Backend::<T>::mounted(backend, name),
+        }
+    }
+
+    fn needs_flush(&self) -> bool {
+        match &self.state {
+            DeferredState::Pending { .. } => false,
+            DeferredState::Mounted(backend) => Backend::<T>::needs_flush(backend),
+        }
+    }
+
+    fn flush(&mut self) {
+        if let DeferredState::Mounted(backend) = &mut self.state {
+            Backend::<T>::flush(backend);
+        }
+    }
+
+    fn link_external(&mut self, url: &str, position: LinkPosition) -> Result<(), Error> {
+        self.try_mount::<T>();
+        match &mut self.state {
+            DeferredState::Pending { links, .. } => {
+                if !links.iter().any(|(linked, _)| linked == url) {
+                    links.push((url.to_owned(), position));
+                }
+                Ok(())
+            }
+            DeferredState::Mounted(backend) => Backend::<T>::link_external(backend, url, position),
+        }
+    }
+
+    fn unlink_external(&mut self, url: &str) {
+        match &mut self.state {
+            DeferredState::Pending { links, .. } => links.retain(|(linked, _)| linked != url),
+            DeferredState::Mounted(backend) => Backend::<T>::unlink_external(backend, url),
+        }
+    }
+
+    fn set_attribute(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        self.try_mount::<T>();
+        match &mut self.state {
+            DeferredState::Pending { attributes, .. } => {
+                attributes.retain(|(other, _)| other != name);
+                attributes.push((name.to_owned(), value.to_owned()));
+                Ok(())
+            }
+            DeferredState::Mounted(backend) => Backend::<T>::set_attribute(backend, name, value),
+        }
     }
 }
@@ -0,0 +1,154 @@
+//! Named breakpoints for responsive styles. Store [Breakpoints] in your theme
+//! and use its media queries with `@media(...)` in
+//! [make_styles!](crate::make_styles), so responsive rules do not repeat
+//! hard-coded widths.
+//!
+//! # Example
+//! ```
+//! # use css_in_rs::{make_styles, testing::render_classes_to_string, Theme};
+//! use css_in_rs::breakpoints::{Breakpoint::*, Breakpoints};
+//!
+//! #[derive(Clone, PartialEq)]
+//! struct MyTheme {
+//!     breakpoints: Breakpoints,
+//! }
+//!
+//! impl Theme for MyTheme {
+//!     fn fast_cmp(&self, other: &Self) -> bool {
+//!         self == other
+//!     }
+//! }
+//!
+//! make_styles! {
+//!     (theme: MyTheme) -> LayoutClasses {
+//!         sidebar {
+//!             display: "none",
+//!         },
+//!         @media(theme.breakpoints.up(Md)) {
+//!             sidebar {
+//!                 display: "block",
+//!             },
+//!         },
+//!     }
+//! }
+//!
+//! let theme = MyTheme { breakpoints: Breakpoints::default() };
+//! let css = render_classes_to_string::<LayoutClasses>(&theme);
+//! assert!(css.contains("@media (min-width: 900px) {"));
+//!
+//! assert_eq!(
+//!     theme.breakpoints.only(Sm).to_string(),
+//!     "(min-width: 600px) and (max-width: 899.95px)"
+//! );
+//! ```
+
+use core::fmt::{Display, Formatter, Result};
+
+/// A named breakpoint. Each breakpoint covers the widths from its own value up
+/// to the value of the next one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Breakpoint {
+    /// Extra small, i.e. phones
+    Xs,
+    /// Small, i.e. tablets in portrait mode
+    Sm,
+    /// Medium, i.e. tablets in landscape mode
+    Md,
+    /// Large, i.e. laptops
+    Lg,
+    /// Extra large, i.e. desktops
+    Xl,
+}
+
+impl Breakpoint {
+    const ALL: [Breakpoint; 5] = [Self::Xs, Self::Sm, Self::Md, Self::Lg, Self::Xl];
+
+    fn next(self) -> Option<Self> {
+        Self::ALL.get(self as usize + 1).copied()
+    }
+}
+
+/// The minimum widths of all [Breakpoint]s in pixels. The defaults match
+/// the breakpoints of Material UI.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Breakpoints {
+    widths: [f64; 5],
+}
+
+impl Default for Breakpoints {
+    fn default() -> Self {
+        Self::new(600.0, 900.0, 1200.0, 1536.0)
+    }
+}
+
+impl Breakpoints {
+    /// Uses the given minimum widths; [Breakpoint::Xs] always starts at `0px`
+    ///
+    /// # Panics
+    /// Panics if the widths are not increasing
+    pub fn new(sm: f64, md: f64, lg: f64, xl: f64) -> Self {
+        let widths = [0.0, sm, md, lg, xl];
+        assert!(
+            widths.windows(2).all(|pair| pair[0] < pair[1]),
+            "Breakpoints must be increasing, got {widths:?}"
+        );
+
+        Self { widths }
+    }
+
+    /// The minimum width of the given breakpoint in pixels
+    pub fn width(&self, breakpoint: Breakpoint) -> f64 {
+        self.widths[breakpoint as usize]
+    }
+
+    /// Matches `breakpoint` and all larger ones
+    pub fn up(&self, breakpoint: Breakpoint) -> MediaQuery {
+        MediaQuery::new(Some(self.width(breakpoint)), None)
+    }
+
+    /// Matches all breakpoints smaller than `breakpoint`
+    pub fn down(&self, breakpoint: Breakpoint) -> MediaQuery {
+        MediaQuery::new(None, Some(self.width(breakpoint)))
+    }
+
+    /// Matches `start` and all larger breakpoints smaller than `end`
+    pub fn between(&self, start: Breakpoint, end: Breakpoint) -> MediaQuery {
+        MediaQuery::new(Some(self.width(start)), Some(self.width(end)))
+    }
+
+    /// Matches exactly the given breakpoint
+    pub fn only(&self, breakpoint: Breakpoint) -> MediaQuery {
+        let end = breakpoint.next().map(|next| self.width(next));
+        MediaQuery::new(Some(self.width(breakpoint)), end)
+    }
+}
+
+/// A media query for a range of widths, created by [Breakpoints]. It can be
+/// used with `@media(...)` or in any other position where a string is expected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MediaQuery {
+    min: Option<f64>,
+    /// Exclusive upper bound
+    end: Option<f64>,
+}
+
+impl MediaQuery {
+    fn new(min: Option<f64>, end: Option<f64>) -> Self {
+        Self { min, end }
+    }
+}
+
+impl Display for MediaQuery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        // `max-width` is inclusive. Subtract a little so the ranges of
+        // neighboring breakpoints do not overlap
+        match (self.min, self.end) {
+            (Some(min), Some(end)) => {
+                write!(f, "(min-width: {min}px) and (max-width: {}px)", end - 0.05)
+            }
+            (Some(min), None) => write!(f, "(min-width: {min}px)"),
+            (None, Some(end)) => write!(f, "(max-width: {}px)", end - 0.05),
+            (None, None) => write!(f, "all"),
+        }
+    }
+}
@@ -0,0 +1,388 @@
+use core::{
+    borrow::Borrow,
+    fmt::{self, Debug, Display, Formatter},
+    hash::{Hash, Hasher},
+    ops::Deref,
+};
+use std::borrow::Cow;
+
+/// Joins classnames into a single [ClassString](crate::class_list::ClassString),
+/// separated by spaces, for use in `class` attributes. Entries are either
+/// classnames (see [ClassNames](crate::class_list::ClassNames)),
+/// like the fields of a [Classes](crate::Classes) struct or the variants
+/// selected by the generated `<rule>_variants` methods, or conditional entries
+/// `condition => classname`, which are only included if the condition is
+/// `true`. Empty classnames are skipped.
+///
+/// The result only allocates if it is longer than
+/// [INLINE_CAPACITY](crate::class_list::INLINE_CAPACITY) bytes, so it is cheap to build on hot render paths.
+///
+/// # Example
+/// ```
+/// # use css_in_rs::{classes, make_styles, EmptyTheme, StyleProvider};
+/// make_styles! {
+///     (_theme: EmptyTheme) -> MyClasses {
+///         button {
+///             padding: "5px",
+///         },
+///         primary {
+///             color: "blue",
+///         },
+///         disabled {
+///             opacity: "0.5",
+///         },
+///     }
+/// }
+///
+/// let style_provider = StyleProvider::new_headless(EmptyTheme);
+/// let cls = style_provider.add_classes::<MyClasses>();
+///
+/// let active = true;
+/// let class = classes![cls.button, active => cls.primary, !active => cls.disabled];
+/// assert_eq!(class, "css-0 css-2");
+/// ```
+///
+/// Variants (see `variants { ... }` in [make_styles!](crate::make_styles))
+/// are selected using their enums:
+/// ```
+/// # use css_in_rs::{classes, make_styles, EmptyTheme, StyleProvider};
+/// make_styles! {
+///     (_theme: EmptyTheme) -> ButtonClasses {
+///         button {
+///             variants {
+///                 size: {
+///                     small { padding: "2px" },
+///                     large { padding: "8px" },
+///                 },
+///             },
+///         },
+///         active {
+///             color: "blue",
+///         },
+///     }
+/// }
+///
+/// let style_provider = StyleProvider::new_headless(EmptyTheme);
+/// let cls = style_provider.add_classes::<ButtonClasses>();
+///
+/// let class = classes![cls.button_variants(Size::Large), true => cls.active];
+/// assert_eq!(class, "css-1 css-2 css-0");
+/// ```
+#[macro_export]
+macro_rules! classes {
+    (@parts [$($parts:expr,)*]) => {
+        $crate::class_list::join(&[$($parts,)*])
+    };
+    (@parts [$($parts:expr,)*] $cond:expr => $class:expr $(, $($rest:tt)*)?) => {
+        $crate::classes!(@parts [
+            $($parts,)*
+            if $cond { ::core::option::Option::Some(&$class as &dyn $crate::class_list::ClassNames) } else { ::core::option::Option::None },
+        ] $($($rest)*)?)
+    };
+    (@parts [$($parts:expr,)*] $class:expr $(, $($rest:tt)*)?) => {
+        $crate::classes!(@parts [
+            $($parts,)*
+            ::core::option::Option::Some(&$class as &dyn $crate::class_list::ClassNames),
+        ] $($($rest)*)?)
+    };
+    ($($entries:tt)*) => {
+        $crate::classes!(@parts [] $($entries)*)
+    };
+}
+
+#[doc(hidden)]
+pub fn join(parts: &[Option<&dyn ClassNames>]) -> ClassString {
+    let mut result = ClassString::new();
+    for part in parts.iter().flatten() {
+        part.push_to(&mut result);
+    }
+    result
+}
+
+/// Values which can be entries of [classes!]
+pub trait ClassNames {
+    /// Appends the classnames using [ClassString::push_class]
+    fn push_to(&self, classes: &mut ClassString);
+}
+
+impl ClassNames for str {
+    fn push_to(&self, classes: &mut ClassString) {
+        classes.push_class(self);
+    }
+}
+
+impl ClassNames for String {
+    fn push_to(&self, classes: &mut ClassString) {
+        classes.push_class(self);
+    }
+}
+
+impl ClassNames for Cow<'_, str> {
+    fn push_to(&self, classes: &mut ClassString) {
+        classes.push_class(self);
+    }
+}
+
+impl ClassNames for ClassString {
+    fn push_to(&self, classes: &mut ClassString) {
+        classes.push_class(self);
+    }
+}
+
+impl<T: ClassNames + ?Sized> ClassNames for &T {
+    fn push_to(&self, classes: &mut ClassString) {
+        (**self).push_to(classes);
+    }
+}
+
+impl<T: ClassNames> ClassNames for Option<T> {
+    fn push_to(&self, classes: &mut ClassString) {
+        if let Some(class) = self {
+            class.push_to(classes);
+        }
+    }
+}
+
+/// The classes of a rule together with the classes of the selected variants,
+/// returned by the `<rule>_variants` methods generated for rules with
+/// `variants { ... }`. Unlike the `<rule>` methods, which return a `String`,
+/// it only borrows the classnames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VariantClasses<'a, const N: usize>([Option<&'a str>; N]);
+
+impl<'a, const N: usize> VariantClasses<'a, N> {
+    #[doc(hidden)]
+    pub const fn new(classes: [Option<&'a str>; N]) -> Self {
+        Self(classes)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.0.iter().flatten().copied()
+    }
+}
+
+impl<const N: usize> ClassNames for VariantClasses<'_, N> {
+    fn push_to(&self, classes: &mut ClassString) {
+        for class in self.iter() {
+            classes.push_class(class);
+        }
+    }
+}
+
+impl<const N: usize> Display for VariantClasses<'_, N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (idx, class) in self.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(" ")?;
+            }
+            f.write_str(class)?;
+        }
+        Ok(())
+    }
+}
+
+/// The number of bytes a [ClassString] holds without allocating
+pub const INLINE_CAPACITY: usize = 62;
+
+/// A string of classnames returned by [classes!]. Short strings are stored
+/// inline, so they do not allocate. It dereferences to `str` and can be
+/// converted into a `String`.
+#[derive(Clone)]
+pub struct ClassString(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Inline {
+        len: u8,
+        bytes: [u8; INLINE_CAPACITY],
+    },
+    Heap(String),
+}
+
+impl ClassString {
+    pub const fn new() -> Self {
+        Self(Repr::Inline {
+            len: 0,
+            bytes: [0; INLINE_CAPACITY],
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            // SAFETY: Only complete `str`s are copied into the buffer
+            Repr::Inline { len, bytes } => unsafe {
+                core::str::from_utf8_unchecked(&bytes[..*len as usize])
+            },
+            Repr::Heap(s) => s,
+        }
+    }
+
+    /// Appends a classname, separated by a space. Empty classnames are skipped.
+    pub fn push_class(&mut self, class: &str) {
+        if class.is_empty() {
+            return;
+        }
+        if !self.is_empty() {
+            self.push_str(" ");
+        }
+        self.push_str(class);
+    }
+
+    fn push_str(&mut self, s: &str) {
+        let heap = match &mut self.0 {
+            Repr::Inline { len, bytes } => {
+                let start = *len as usize;
+                let end = start + s.len();
+                if end <= INLINE_CAPACITY {
+                    bytes[start..end].copy_from_slice(s.as_bytes());
+                    *len = end as u8;
+                    return;
+                }
+
+                let mut heap = String::with_capacity(2 * end);
+                heap.push_str(self.as_str());
+                heap.push_str(s);
+                heap
+            }
+            Repr::Heap(heap) => return heap.push_str(s),
+        };
+        self.0 = Repr::Heap(heap);
+    }
+
+    pub fn into_string(self) -> String {
+        match self.0 {
+            Repr::Inline { .. } => self.as_str().to_owned(),
+            Repr::Heap(s) => s,
+        }
+    }
+}
+
+impl Default for ClassString {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for ClassString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for ClassString {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for ClassString {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Display for ClassString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Debug for ClassString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl PartialEq for ClassString {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for ClassString {}
+
+impl Hash for ClassString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl PartialEq<str> for ClassString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for ClassString {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for ClassString {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl From<ClassString> for String {
+    fn from(classes: ClassString) -> Self {
+        classes.into_string()
+    }
+}
+
+#[cfg(feature = "dioxus")]
+impl dioxus::prelude::IntoAttributeValue for ClassString {
+    fn into_value(self) -> dioxus::dioxus_core::AttributeValue {
+        dioxus::dioxus_core::AttributeValue::Text(self.into_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn join() {
+        let button = String::from("css-0");
+        let empty = String::new();
+        assert_eq!(crate::classes![], "");
+        assert_eq!(crate::classes![button, "css-1",], "css-0 css-1");
+        assert_eq!(crate::classes![empty, false => button, "css-1"], "css-1");
+        assert_eq!(
+            crate::classes!(button, 1 + 1 == 2 => "css-1"),
+            "css-0 css-1"
+        );
+    }
+
+    #[test]
+    fn class_string() {
+        use super::{ClassString, INLINE_CAPACITY};
+
+        let mut classes = ClassString::new();
+        classes.push_class("css-0");
+        classes.push_class("");
+        classes.push_class("css-1");
+        assert_eq!(classes, "css-0 css-1");
+
+        // Switches to the heap once the inline buffer is full
+        let long = "x".repeat(INLINE_CAPACITY);
+        classes.push_class(&long);
+        assert_eq!(classes, format!("css-0 css-1 {long}"));
+        classes.push_class("css-2");
+        assert_eq!(classes.into_string(), format!("css-0 css-1 {long} css-2"));
+    }
+
+    #[test]
+    fn variants() {
+        use super::VariantClasses;
+
+        let variants = VariantClasses::new([Some("css-0"), None, Some("css-2")]);
+        assert_eq!(variants.to_string(), "css-0 css-2");
+        let selected: Option<&str> = None;
+        assert_eq!(
+            crate::classes![variants, selected, true => Some("css-3")],
+            "css-0 css-2 css-3"
+        );
+    }
+}
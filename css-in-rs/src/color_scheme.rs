@@ -0,0 +1,74 @@
+//! Switching between a light and a dark theme, following the color scheme
+//! preferred by the operating system (`prefers-color-scheme`).
+
+use wasm_bindgen::{closure::Closure, JsCast};
+
+use crate::{backend::web::active_window, StyleProvider, Theme};
+
+const DARK_QUERY: &str = "(prefers-color-scheme: dark)";
+
+/// Applies `light` or `dark` to a [StyleProvider], depending on the preferred
+/// color scheme. Whenever the preference changes (i.e. the operating system
+/// switches to dark mode in the evening), the theme is updated accordingly.
+/// The watcher stops when it is dropped.
+///
+/// # Example
+/// ```no_run
+/// # use css_in_rs::{color_scheme::ColorSchemeWatcher, StyleProvider, Theme};
+/// #[derive(Clone, PartialEq)]
+/// struct MyTheme {
+///     background: &'static str,
+/// }
+///
+/// impl Theme for MyTheme {
+///     fn fast_cmp(&self, other: &Self) -> bool {
+///         self == other
+///     }
+/// }
+///
+/// let light = MyTheme { background: "white" };
+/// let dark = MyTheme { background: "black" };
+///
+/// let style_provider = StyleProvider::quickstart_web(light.clone());
+/// let watcher = ColorSchemeWatcher::new(&style_provider, light, dark);
+/// ```
+pub struct ColorSchemeWatcher {
+    query: web_sys::MediaQueryList,
+    listener: Closure<dyn FnMut(web_sys::MediaQueryListEvent)>,
+}
+
+impl ColorSchemeWatcher {
+    /// Applies the theme for the current preference and starts watching it.
+    /// Returns `None` (and leaves the theme untouched) if there is no window
+    /// supporting `matchMedia`, i.e. in a web worker or when running natively.
+    pub fn new<T: Theme>(provider: &StyleProvider<T>, light: T, dark: T) -> Option<Self> {
+        let query = active_window()?.match_media(DARK_QUERY).ok()??;
+
+        let theme = if query.matches() { &dark } else { &light };
+        provider.update_theme(theme.clone());
+
+        let provider = provider.clone();
+        let listener = Closure::<dyn FnMut(_)>::new(move |event: web_sys::MediaQueryListEvent| {
+            let theme = if event.matches() { &dark } else { &light };
+            provider.update_theme(theme.clone());
+        });
+        query
+            .add_event_listener_with_callback("change", listener.as_ref().unchecked_ref())
+            .ok()?;
+
+        Some(Self { query, listener })
+    }
+
+    /// Whether the dark color scheme is currently preferred
+    pub fn is_dark(&self) -> bool {
+        self.query.matches()
+    }
+}
+
+impl Drop for ColorSchemeWatcher {
+    fn drop(&mut self) {
+        let _ = self
+            .query
+            .remove_event_listener_with_callback("change", self.listener.as_ref().unchecked_ref());
+    }
+}
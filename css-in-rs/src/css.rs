@@ -0,0 +1,566 @@
+//! A tiny and forgiving CSS parser. It only understands as much structure as
+//! needed to post-process the generated styles: blocks, declarations and
+//! statements. Anything it does not understand is passed through verbatim.
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Node<'a> {
+    /// A rule or an at-rule with a block, e.g. `div.css-1 { ... }` or
+    /// `@media print { ... }`
+    Block {
+        prelude: &'a str,
+        children: Vec<Node<'a>>,
+    },
+    /// A single declaration, e.g. `color: red`
+    Declaration { property: &'a str, value: &'a str },
+    /// Something terminated by `;` which is not a declaration, e.g. `@import "foo.css"`
+    Statement(&'a str),
+    /// A comment between other nodes, including the `/*` and `*/` markers
+    Comment(&'a str),
+}
+
+pub fn parse(src: &str) -> Vec<Node<'_>> {
+    let mut parser = Parser { src, pos: 0 };
+    parser.parse_nodes(false)
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn parse_nodes(&mut self, nested: bool) -> Vec<Node<'a>> {
+        let mut nodes = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+
+            if rest.is_empty() {
+                break;
+            }
+
+            if rest.starts_with("/*") {
+                let len = rest.find("*/").map(|idx| idx + 2).unwrap_or(rest.len());
+                nodes.push(Node::Comment(&rest[..len]));
+                self.pos += len;
+                continue;
+            }
+
+            if rest.starts_with('}') {
+                self.pos += 1;
+                if nested {
+                    break;
+                }
+
+                // Stray closing brace on the top level: ignore it
+                continue;
+            }
+
+            let start = self.pos;
+            let end = start + find_terminator(rest);
+            let text = self.src[start..end].trim();
+            self.pos = end;
+
+            match self.rest().chars().next() {
+                Some('{') => {
+                    self.pos += 1;
+                    let children = self.parse_nodes(true);
+                    nodes.push(Node::Block {
+                        prelude: text,
+                        children,
+                    });
+                }
+                Some(';') => {
+                    self.pos += 1;
+                    nodes.extend(make_leaf(text));
+                }
+                _ => {
+                    // Either '}' or the end of the input: The last declaration
+                    // of a block does not need a trailing semicolon
+                    nodes.extend(make_leaf(text));
+                }
+            }
+        }
+
+        nodes
+    }
+}
+
+/// Returns the byte offset of the next `{`, `;` or `}` which is neither
+/// part of a string, a comment, nor enclosed by parentheses
+fn find_terminator(src: &str) -> usize {
+    let mut depth = 0usize;
+    let mut chars = src.char_indices().peekable();
+
+    while let Some((idx, ch)) = chars.next() {
+        match ch {
+            '"' | '\'' => skip_string(&mut chars, ch),
+            '\\' => {
+                chars.next();
+            }
+            '/' if matches!(chars.peek(), Some((_, '*'))) => {
+                chars.next();
+                let mut prev = ' ';
+                for (_, ch) in chars.by_ref() {
+                    if prev == '*' && ch == '/' {
+                        break;
+                    }
+                    prev = ch;
+                }
+            }
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            '{' | ';' | '}' if depth == 0 => return idx,
+            _ => {}
+        }
+    }
+
+    src.len()
+}
+
+fn skip_string(chars: &mut impl Iterator<Item = (usize, char)>, quote: char) {
+    while let Some((_, ch)) = chars.next() {
+        match ch {
+            '\\' => {
+                chars.next();
+            }
+            ch if ch == quote => break,
+            _ => {}
+        }
+    }
+}
+
+fn make_leaf(text: &str) -> Option<Node<'_>> {
+    if text.is_empty() {
+        return None;
+    }
+
+    if !text.starts_with('@') {
+        if let Some((property, value)) = text.split_once(':') {
+            let node = Node::Declaration {
+                property: property.trim(),
+                value: value.trim(),
+            };
+            return Some(node);
+        }
+    }
+
+    Some(Node::Statement(text))
+}
+
+/// Controls how generated CSS is formatted before it is handed to the backend
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CssFormat {
+    /// Use the CSS exactly as emitted by the generators
+    #[default]
+    Generated,
+    /// Strip whitespace and comments, merge identical declarations and
+    /// shorten colors. Useful for production builds.
+    Minified,
+    /// Indent nested blocks and put every declaration on its own line.
+    /// Useful when reading the stylesheet in the browser's devtools.
+    Pretty,
+}
+
+impl CssFormat {
+    pub(crate) fn apply(self, css: String) -> String {
+        match self {
+            CssFormat::Generated => css,
+            CssFormat::Minified => minify(&css),
+            CssFormat::Pretty => prettify(&css),
+        }
+    }
+}
+
+/// Minifies the given stylesheet: Whitespace and comments are stripped,
+/// identical declarations within a block are merged, empty blocks are
+/// dropped and hex colors are shortened where possible.
+pub fn minify(src: &str) -> String {
+    let mut result = String::with_capacity(src.len());
+    write_minified(&parse(src), &mut result);
+    result
+}
+
+/// Minifies the given stylesheet like [minify], but returns every top-level
+/// rule on its own, i.e. to compare two stylesheets rule by rule
+#[cfg_attr(not(feature = "web-sys"), allow(dead_code))]
+pub(crate) fn minified_rules(src: &str) -> Vec<String> {
+    let nodes = parse(src);
+    let rules = nodes.iter().map(|node| {
+        let mut rule = String::new();
+        write_minified(core::slice::from_ref(node), &mut rule);
+        rule
+    });
+    rules.filter(|rule| !rule.is_empty()).collect()
+}
+
+fn write_minified(nodes: &[Node], result: &mut String) {
+    let mut needs_semicolon = false;
+
+    for (idx, node) in nodes.iter().enumerate() {
+        match node {
+            Node::Block { prelude, children } => {
+                if children.iter().all(|child| matches!(child, Node::Comment(_))) {
+                    continue;
+                }
+
+                if needs_semicolon {
+                    result.push(';');
+                }
+                compress(prelude, Context::Prelude, result);
+                result.push('{');
+                write_minified(children, result);
+                result.push('}');
+                needs_semicolon = false;
+            }
+            Node::Declaration { property, value } => {
+                // Identical declarations later in the same block make this one redundant
+                let is_duplicate = nodes[idx + 1..].iter().any(|other| other == node);
+                if is_duplicate {
+                    continue;
+                }
+
+                if needs_semicolon {
+                    result.push(';');
+                }
+                result.push_str(property);
+                result.push(':');
+                compress(value, Context::Value, result);
+                needs_semicolon = true;
+            }
+            Node::Statement(statement) => {
+                if needs_semicolon {
+                    result.push(';');
+                }
+                compress(statement, Context::Prelude, result);
+                result.push(';');
+                needs_semicolon = false;
+            }
+            Node::Comment(_) => {}
+        }
+    }
+}
+
+/// Formats the given stylesheet with one declaration per line. Nested blocks
+/// are indented by two spaces per level.
+pub fn prettify(src: &str) -> String {
+    let mut result = String::with_capacity(src.len() * 2);
+    write_pretty(&parse(src), 0, &mut result);
+    result
+}
+
+fn write_pretty(nodes: &[Node], depth: usize, result: &mut String) {
+    for node in nodes {
+        for _ in 0..depth {
+            result.push_str("  ");
+        }
+
+        match node {
+            Node::Block { prelude, children } => {
+                result.push_str(prelude);
+                result.push_str(" {\n");
+                write_pretty(children, depth + 1, result);
+                for _ in 0..depth {
+                    result.push_str("  ");
+                }
+                result.push_str("}\n");
+            }
+            Node::Declaration { property, value } => {
+                result.push_str(property);
+                result.push_str(": ");
+                result.push_str(value);
+                result.push_str(";\n");
+            }
+            Node::Statement(statement) => {
+                result.push_str(statement);
+                result.push_str(";\n");
+            }
+            Node::Comment(comment) => {
+                result.push_str(comment);
+                result.push('\n');
+            }
+        }
+    }
+}
+
+/// Extracts the rules which only apply to print media, formatted like
+/// [prettify]. Rules under `@media print` are unwrapped, so the result can be
+/// used as a dedicated print stylesheet. Queries with further conditions, like
+/// `@media print and (orientation: landscape)`, are kept as they are.
+pub fn extract_print(src: &str) -> String {
+    let mut result = String::new();
+    write_pretty(&print_nodes(parse(src)), 0, &mut result);
+    result
+}
+
+fn print_nodes(nodes: Vec<Node<'_>>) -> Vec<Node<'_>> {
+    let mut result = Vec::new();
+
+    for node in nodes {
+        let Node::Block { prelude, children } = node else {
+            continue;
+        };
+
+        if let Some(query) = prelude.strip_prefix("@media") {
+            match print_query(query) {
+                Some(PrintQuery::Unconditional) => result.extend(children),
+                Some(PrintQuery::Conditional) => result.push(Node::Block { prelude, children }),
+                None => {}
+            }
+        } else if prelude.starts_with('@') {
+            // Other at-rules like `@supports` may contain print rules as well
+            let children = print_nodes(children);
+            if !children.is_empty() {
+                result.push(Node::Block { prelude, children });
+            }
+        }
+    }
+
+    result
+}
+
+enum PrintQuery {
+    /// `print` or `only print`
+    Unconditional,
+    /// Applies to print media, but only under further conditions
+    Conditional,
+}
+
+fn print_query(query: &str) -> Option<PrintQuery> {
+    let mut result = None;
+
+    for query in query.split(',') {
+        let query = query.trim().to_ascii_lowercase();
+        let query = query.strip_prefix("only ").unwrap_or(&query).trim_start();
+        if query == "print" {
+            return Some(PrintQuery::Unconditional);
+        }
+        if query.starts_with("print ") {
+            result = Some(PrintQuery::Conditional);
+        }
+    }
+
+    result
+}
+
+/// Removes all comments (and the line breaks following them). Everything
+/// else is kept verbatim.
+pub fn strip_comments(src: &str) -> String {
+    let mut result = String::with_capacity(src.len());
+    let mut chars = src.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' | '\'' => {
+                let quote = ch;
+                result.push(quote);
+                while let Some(ch) = chars.next() {
+                    result.push(ch);
+                    if ch == '\\' {
+                        result.extend(chars.next());
+                    } else if ch == quote {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for ch in chars.by_ref() {
+                    if prev == '*' && ch == '/' {
+                        break;
+                    }
+                    prev = ch;
+                }
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+            }
+            _ => result.push(ch),
+        }
+    }
+
+    result
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Context {
+    Prelude,
+    Value,
+}
+
+/// Collapses whitespace and, in values, shortens hex colors. Strings are
+/// copied verbatim. Within `url(...)`, a `#` starts a fragment like
+/// `url(#gradient)` instead of a color, so it is kept as it is.
+fn compress(src: &str, context: Context, result: &mut String) {
+    let is_selector = context == Context::Prelude && !src.starts_with('@');
+    let is_separator = |ch: char| match ch {
+        ',' | '(' | ')' => true,
+        '>' | '~' | '+' => is_selector,
+        _ => false,
+    };
+
+    let mut pending_space = false;
+    let mut in_url = false;
+    let mut chars = src.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch.is_whitespace() {
+            pending_space = true;
+            continue;
+        }
+
+        if pending_space {
+            let after_separator = result.ends_with(|prev: char| is_separator(prev) && prev != ')');
+            let before_separator = is_separator(ch) && ch != '(';
+            if !after_separator && !before_separator {
+                result.push(' ');
+            }
+            pending_space = false;
+        }
+
+        match ch {
+            '"' | '\'' => {
+                let quote = ch;
+                result.push(quote);
+                while let Some(ch) = chars.next() {
+                    result.push(ch);
+                    if ch == '\\' {
+                        result.extend(chars.next());
+                    } else if ch == quote {
+                        break;
+                    }
+                }
+            }
+            '(' => {
+                in_url = result.len() >= 3
+                    && result.is_char_boundary(result.len() - 3)
+                    && result[result.len() - 3..].eq_ignore_ascii_case("url");
+                result.push(ch);
+            }
+            ')' => {
+                in_url = false;
+                result.push(ch);
+            }
+            '#' if context == Context::Value && !in_url => {
+                let mut color = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if !ch.is_ascii_alphanumeric() && ch != '-' && ch != '_' {
+                        break;
+                    }
+                    color.push(ch);
+                    chars.next();
+                }
+
+                result.push('#');
+                result.push_str(&shorten_hex_color(&color));
+            }
+            _ => result.push(ch),
+        }
+    }
+}
+
+fn shorten_hex_color(color: &str) -> String {
+    let is_hex = color.chars().all(|ch| ch.is_ascii_hexdigit());
+    if !is_hex || (color.len() != 6 && color.len() != 8) {
+        return color.to_owned();
+    }
+
+    let lowercase = color.to_ascii_lowercase();
+    let bytes = lowercase.as_bytes();
+    if bytes.chunks(2).all(|pair| pair[0] == pair[1]) {
+        bytes.chunks(2).map(|pair| pair[0] as char).collect()
+    } else {
+        color.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract_print, minify, parse, prettify, strip_comments, Node};
+
+    #[test]
+    fn parse_nested() {
+        let nodes = parse("/* hi */\n@media print {\n.css-1 {\n  color: red;\n}\n}\n@import \"a;b.css\";\n");
+
+        let expected = vec![
+            Node::Comment("/* hi */"),
+            Node::Block {
+                prelude: "@media print",
+                children: vec![Node::Block {
+                    prelude: ".css-1",
+                    children: vec![Node::Declaration {
+                        property: "color",
+                        value: "red",
+                    }],
+                }],
+            },
+            Node::Statement("@import \"a;b.css\""),
+        ];
+        assert_eq!(nodes, expected);
+    }
+
+    #[test]
+    fn minify_simple() {
+        let css = "div.css-0 > span,  a {\n  color: #FFCC00;\n  margin: 0  auto;\n  color: #FFCC00;\n}\n\
+                   .css-1 {\n}\n\
+                   @media (hover: none) and (max-width: 10px) {\n  .css-2 {\n    content: \"a  b\";\n    background: url( x.png ) #123456;\n  }\n}\n";
+
+        assert_eq!(
+            minify(css),
+            "div.css-0>span,a{margin:0 auto;color:#fc0}\
+             @media (hover: none) and (max-width: 10px){.css-2{content:\"a  b\";background:url(x.png) #123456}}"
+        );
+    }
+
+    #[test]
+    fn minify_url_fragments() {
+        let css = ".css-0 {\n  fill: url(#aabbcc) #AABBCC;\n  stroke: URL( #AbCdEf );\n  color: #AbCdEf;\n}\n";
+
+        assert_eq!(
+            minify(css),
+            ".css-0{fill:url(#aabbcc) #abc;stroke:URL(#AbCdEf);color:#AbCdEf}"
+        );
+    }
+
+    #[test]
+    fn prettify_nested() {
+        let css = "@media print {\n.css-1 {\n  color: red;\n}\n}\n/* c */ a{b:c;d:e}";
+
+        assert_eq!(
+            prettify(css),
+            "@media print {\n  .css-1 {\n    color: red;\n  }\n}\n/* c */\na {\n  b: c;\n  d: e;\n}\n"
+        );
+    }
+
+    #[test]
+    fn strip() {
+        let css = "/* a */\n.css-0 {\n  content: \"/* b */\";\n}\n/* c */";
+        assert_eq!(strip_comments(css), ".css-0 {\n  content: \"/* b */\";\n}\n");
+    }
+
+    #[test]
+    fn extract_print_rules() {
+        let css = ".css-0 {\n  color: red;\n}\n\
+                   @media print {\n.css-0 {\n  color: black;\n}\n}\n\
+                   @media screen {\n.css-1 {\n  color: blue;\n}\n}\n\
+                   @media not print {\n.css-2 {\n  display: none;\n}\n}\n\
+                   @supports (display: grid) {\n@media PRINT and (orientation: landscape) {\n.css-3 {\n  display: grid;\n}\n}\n}\n";
+
+        assert_eq!(
+            extract_print(css),
+            ".css-0 {\n  color: black;\n}\n\
+             @supports (display: grid) {\n  @media PRINT and (orientation: landscape) {\n    .css-3 {\n      display: grid;\n    }\n  }\n}\n"
+        );
+    }
+}
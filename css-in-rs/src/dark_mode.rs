@@ -0,0 +1,157 @@
+//! Dark overrides within the rules. In [make_styles!](crate::make_styles),
+//! `@dark { ... }` is a shorthand for
+//! `"@media (prefers-color-scheme: dark)" { ... }`, so the rules within follow
+//! the color scheme of the operating system. If the app has a dark mode switch
+//! of its own, the [StyleProvider] can turn these blocks into rules below an
+//! ancestor selector instead, see [DarkMode::Selector].
+//!
+//! # Example
+//! ```
+//! # use css_in_rs::{make_styles, dark_mode::DarkMode, CssFormat, EmptyTheme, StyleProvider};
+//! make_styles! {
+//!     (_theme: EmptyTheme) -> CardClasses {
+//!         card {
+//!             background_color: "white",
+//!         },
+//!         @dark {
+//!             card {
+//!                 background_color: "black",
+//!             },
+//!         },
+//!     }
+//! }
+//!
+//! let style_provider = StyleProvider::new_headless(EmptyTheme);
+//! style_provider.set_format(CssFormat::Minified);
+//! style_provider.add_classes::<CardClasses>();
+//! assert_eq!(
+//!     style_provider.current_css(),
+//!     ".css-0{background-color:white}\
+//!      @media (prefers-color-scheme: dark){.css-0{background-color:black}}"
+//! );
+//!
+//! let style_provider = StyleProvider::builder()
+//!     .format(CssFormat::Minified)
+//!     .dark_mode(DarkMode::Selector(".dark".to_owned()))
+//!     .build_headless(EmptyTheme);
+//! style_provider.add_classes::<CardClasses>();
+//! assert_eq!(
+//!     style_provider.current_css(),
+//!     ".css-0{background-color:white}.dark .css-0{background-color:black}"
+//! );
+//! ```
+
+use crate::css::{parse, Node};
+#[cfg(doc)]
+use crate::StyleProvider;
+
+/// The media query of `@dark`, without whitespace
+const DARK_QUERY: &str = "@media(prefers-color-scheme:dark)";
+
+/// How the dark overrides are applied, see the [module documentation](self)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum DarkMode {
+    /// The rules apply if the operating system prefers a dark color scheme
+    #[default]
+    Media,
+    /// The rules apply to the descendants of the elements matching the
+    /// selector, i.e. `.dark` or `[data-theme=dark]`, which is usually set on
+    /// the `body`. Every `@media (prefers-color-scheme: dark)` block is
+    /// replaced, including the ones of raw stylesheets.
+    Selector(String),
+}
+
+impl DarkMode {
+    pub(crate) fn apply(&self, css: String) -> String {
+        match self {
+            DarkMode::Media => css,
+            DarkMode::Selector(ancestor) => {
+                let mut result = String::with_capacity(css.len());
+                write_nodes(&parse(&css), ancestor, false, &mut result);
+                result
+            }
+        }
+    }
+}
+
+fn is_dark_query(prelude: &str) -> bool {
+    let query = prelude.chars().filter(|ch| !ch.is_whitespace());
+    query.eq(DARK_QUERY.chars())
+}
+
+fn write_nodes(nodes: &[Node], ancestor: &str, dark: bool, result: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Block { prelude, children } if is_dark_query(prelude) => {
+                write_nodes(children, ancestor, true, result);
+            }
+            Node::Block { prelude, children } => {
+                match dark && !prelude.starts_with('@') {
+                    true => write_prefixed(prelude, ancestor, result),
+                    false => result.push_str(prelude),
+                }
+                result.push_str(" {\n");
+                write_nodes(children, ancestor, dark, result);
+                result.push_str("}\n");
+            }
+            Node::Declaration { property, value } => {
+                result.push_str(&format!("  {property}: {value};\n"));
+            }
+            Node::Statement(statement) => {
+                result.push_str(statement);
+                result.push_str(";\n");
+            }
+            Node::Comment(_) => {}
+        }
+    }
+}
+
+/// Writes the selector list with the ancestor in front of every selector,
+/// i.e. `.dark a, .dark b` for `a, b`
+fn write_prefixed(selectors: &str, ancestor: &str, result: &mut String) {
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut list = Vec::new();
+    for (idx, ch) in selectors.char_indices() {
+        match ch {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                list.push(&selectors[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    list.push(&selectors[start..]);
+
+    let prefixed = list
+        .iter()
+        .map(|selector| format!("{ancestor} {}", selector.trim()))
+        .collect::<Vec<_>>();
+    result.push_str(&prefixed.join(", "));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DarkMode;
+
+    #[test]
+    fn selector() {
+        let src = ".a { color: black; }\n\
+                   @media (prefers-color-scheme: dark) {\n\
+                   .a, :is(.b, .c) { color: white; }\n\
+                   @media print { .a { color: gray; } }\n\
+                   }\n\
+                   @media (prefers-color-scheme: light) { .a {} }";
+        let mode = DarkMode::Selector("[data-theme=dark]".to_owned());
+        assert_eq!(
+            mode.apply(src.to_owned()),
+            ".a {\n  color: black;\n}\n\
+             [data-theme=dark] .a, [data-theme=dark] :is(.b, .c) {\n  color: white;\n}\n\
+             @media print {\n[data-theme=dark] .a {\n  color: gray;\n}\n}\n\
+             @media (prefers-color-scheme: light) {\n.a {\n}\n}\n"
+        );
+        assert_eq!(DarkMode::Media.apply(src.to_owned()), src);
+    }
+}
@@ -0,0 +1,30 @@
+//! Derives for the structs generated by [make_styles!](crate::make_styles)
+//! which depend on the features of this crate. The proc macro cannot see them,
+//! so it wraps every struct in [classes_struct!](crate::classes_struct).
+
+#[cfg(feature = "serde")]
+pub use serde;
+
+/// Adds `Serialize` and `Deserialize` with the `serde` feature
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! classes_struct {
+    ($($item:tt)*) => {
+        #[derive(
+            $crate::derive::serde::Serialize,
+            $crate::derive::serde::Deserialize,
+        )]
+        #[serde(crate = "::css_in_rs::derive::serde")]
+        $($item)*
+    };
+}
+
+#[cfg(not(feature = "serde"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! classes_struct {
+    ($($item:tt)*) => {
+        $($item)*
+    };
+}
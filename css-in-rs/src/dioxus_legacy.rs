@@ -0,0 +1,82 @@
+//! Hooks for the `Scope` API of Dioxus 0.4, enabled by the `dioxus04`
+//! feature. They take the scope as their first argument and return
+//! references which live as long as the component, like the hooks of css-in-rs
+//! 0.2. The `dioxus` feature provides the hooks for current Dioxus versions
+//! instead; both features can be enabled at the same time.
+//!
+//! Hot reloading, scoped providers and the other backends are only supported
+//! by the hooks of the `dioxus` feature.
+//!
+//! # Example
+//! ```no_run
+//! #![allow(non_snake_case)]
+//! # extern crate dioxus04 as dioxus;
+//!
+//! use css_in_rs::{
+//!     dioxus_legacy::{use_style, use_style_provider_quickstart},
+//!     make_styles, EmptyTheme,
+//! };
+//! use dioxus::prelude::*;
+//!
+//! make_styles! {
+//!     (_theme: EmptyTheme) -> MyClasses {
+//!         red_text {
+//!             color: "red",
+//!         },
+//!     }
+//! }
+//!
+//! fn Demo(cx: Scope) -> Element {
+//!     let classes: &MyClasses = use_style(cx);
+//!
+//!     cx.render(rsx! {
+//!         div { class: "{classes.red_text}", "This text is supposed to be red." }
+//!     })
+//! }
+//!
+//! fn App(cx: Scope) -> Element {
+//!     use_style_provider_quickstart(cx, || EmptyTheme);
+//!
+//!     cx.render(rsx! {
+//!         Demo {}
+//!     })
+//! }
+//! ```
+
+use dioxus04::core::ScopeState;
+
+use crate::{Classes, StyleProvider, Theme};
+
+/// Quickly sets up a StyleProvider in the global document and provides it to
+/// the subtree. Styles will be attached to `window.document.head`
+pub fn use_style_provider_quickstart<T: Theme>(
+    cx: &ScopeState,
+    make_theme: impl FnOnce() -> T,
+) -> &StyleProvider<T> {
+    cx.use_hook(|| cx.provide_context(StyleProvider::quickstart_web(make_theme())))
+}
+
+/// Returns the surrounding StyleProvider
+///
+/// # Panics
+/// Panics if there is none
+pub fn use_style_provider<T: Theme>(cx: &ScopeState) -> &StyleProvider<T> {
+    cx.use_hook(|| {
+        cx.consume_context().unwrap_or_else(|| {
+            panic!(
+                "There is no StyleProvider<{}>. Set one up using use_style_provider_quickstart",
+                core::any::type_name::<T>()
+            )
+        })
+    })
+}
+
+/// Mounts the styles of `C` in the surrounding StyleProvider and returns the
+/// classnames, see [StyleProvider::add_classes]
+///
+/// # Panics
+/// Panics if there is no StyleProvider
+pub fn use_style<C: Classes + 'static>(cx: &ScopeState) -> &C {
+    let provider = use_style_provider::<C::Theme>(cx);
+    cx.use_hook(|| provider.add_classes())
+}
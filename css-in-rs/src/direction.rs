@@ -0,0 +1,384 @@
+//! Support for right-to-left locales. With [Direction::Rtl], the
+//! [StyleProvider] flips physical properties and values while generating the
+//! styles, i.e. `margin-left` becomes `margin-right` and `float: left` becomes
+//! `float: right`. This way, the same [make_styles!](crate::make_styles)
+//! source serves both directions. See [StyleProvider::set_direction].
+//!
+//! Alternatively, the physical properties can be replaced by logical ones
+//! like `margin-inline-start`, which the browser resolves according to the
+//! `direction` and `writing-mode` of each element. See [to_logical].
+//!
+//! # Example
+//! ```
+//! # use css_in_rs::{make_styles, direction::Direction, CssFormat, EmptyTheme, StyleProvider};
+//! make_styles! {
+//!     (_theme: EmptyTheme) -> MyClasses {
+//!         icon {
+//!             float: "left",
+//!             margin_left: "4px",
+//!             padding: "1px 2px 3px 4px",
+//!         },
+//!     }
+//! }
+//!
+//! let style_provider = StyleProvider::builder()
+//!     .format(CssFormat::Minified)
+//!     .direction(Direction::Rtl)
+//!     .build_headless(EmptyTheme);
+//! style_provider.add_classes::<MyClasses>();
+//! assert_eq!(
+//!     style_provider.current_css(),
+//!     ".css-0{float:right;margin-right:4px;padding:1px 4px 3px 2px}"
+//! );
+//! ```
+
+use std::borrow::Cow;
+
+use crate::css::{parse, Node};
+#[cfg(doc)]
+use crate::{StyleProvider, StyleProviderBuilder};
+
+/// The direction of the text of the page
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Direction {
+    /// Left to right, the styles are used as they are
+    #[default]
+    Ltr,
+    /// Right to left, physical properties and values are flipped
+    Rtl,
+}
+
+impl Direction {
+    pub(crate) fn apply(self, css: String) -> String {
+        match self {
+            Direction::Ltr => css,
+            Direction::Rtl => flip(&css),
+        }
+    }
+}
+
+/// Swaps left and right in the properties and values of the given
+/// stylesheet, see the [module documentation](self)
+pub fn flip(src: &str) -> String {
+    rewrite(src, |property, value, result| {
+        write_declaration(
+            result,
+            &flip_property(property),
+            &flip_value(property, value),
+        );
+    })
+}
+
+/// Replaces the physical properties and values of the given stylesheet by
+/// logical ones, i.e. `margin-left` by `margin-inline-start` and
+/// `text-align: right` by `text-align: end`. This way, the styles follow the
+/// `direction` and the `writing-mode` of the elements. See
+/// [StyleProviderBuilder::logical_properties].
+///
+/// Shorthands with multiple values like `padding: 1px 2px 3px 4px` are split
+/// into `padding-block: 1px 3px` and `padding-inline: 4px 2px`. Sizes like
+/// `width` are kept.
+pub fn to_logical(src: &str) -> String {
+    rewrite(src, |property, value, result| {
+        let name = property.to_ascii_lowercase();
+        let (words, important) = split_important(value);
+        match split_words(words).as_slice() {
+            [top, right, rest @ ..] if LOGICAL_SHORTHANDS.contains(&name.as_str()) => {
+                let (bottom, left) = match rest {
+                    [] => (top, right),
+                    [bottom] => (bottom, right),
+                    [bottom, left, ..] => (bottom, left),
+                };
+                let (block, inline) = match name.strip_prefix("border-") {
+                    Some(kind) => (
+                        format!("border-block-{kind}"),
+                        format!("border-inline-{kind}"),
+                    ),
+                    None => (format!("{name}-block"), format!("{name}-inline")),
+                };
+                write_declaration(result, &block, &join_pair(top, bottom, important));
+                write_declaration(result, &inline, &join_pair(left, right, important));
+            }
+            _ => write_declaration(
+                result,
+                &logical_property(&name).map_or(Cow::Borrowed(property), Cow::Owned),
+                &logical_value(&name, value),
+            ),
+        }
+    })
+}
+
+fn rewrite(src: &str, declaration: impl Fn(&str, &str, &mut String)) -> String {
+    let mut result = String::with_capacity(src.len());
+    write_rewritten(&parse(src), &declaration, &mut result);
+    result
+}
+
+fn write_rewritten(
+    nodes: &[Node],
+    declaration: &impl Fn(&str, &str, &mut String),
+    result: &mut String,
+) {
+    for node in nodes {
+        match node {
+            Node::Block { prelude, children } => {
+                result.push_str(prelude);
+                result.push_str(" {\n");
+                write_rewritten(children, declaration, result);
+                result.push_str("}\n");
+            }
+            Node::Declaration { property, value } => declaration(property, value, result),
+            Node::Statement(statement) => {
+                result.push_str(statement);
+                result.push_str(";\n");
+            }
+            Node::Comment(_) => {}
+        }
+    }
+}
+
+fn write_declaration(result: &mut String, property: &str, value: &str) {
+    result.push_str(&format!("  {property}: {value};\n"));
+}
+
+/// Splits off a trailing `!important`, which is returned with a leading space
+fn split_important(value: &str) -> (&str, &'static str) {
+    match value.strip_suffix("!important") {
+        Some(value) => (value.trim_end(), " !important"),
+        None => (value, ""),
+    }
+}
+
+/// `margin-left` becomes `margin-right`, `border-top-left-radius` becomes
+/// `border-top-right-radius` and so on. Custom properties are kept.
+fn flip_property(property: &str) -> Cow<'_, str> {
+    if property.starts_with("--") || !(property.contains("left") || property.contains("right")) {
+        return Cow::Borrowed(property);
+    }
+
+    let segments = property.split('-').map(|segment| match segment {
+        "left" => "right",
+        "right" => "left",
+        segment => segment,
+    });
+    Cow::Owned(segments.collect::<Vec<_>>().join("-"))
+}
+
+fn flip_value<'a>(property: &str, value: &'a str) -> Cow<'a, str> {
+    let (value, important) = split_important(value);
+
+    let flipped = match property.to_ascii_lowercase().as_str() {
+        "float" | "clear" | "text-align" | "justify-self" | "justify-items" => {
+            map_words(value, |word| match word {
+                "left" => "right",
+                "right" => "left",
+                word => word,
+            })
+        }
+        "cursor" => map_words(value, flip_cursor),
+        "margin" | "padding" | "border-width" | "border-style" | "border-color" | "inset"
+        | "scroll-margin" | "scroll-padding" => {
+            let mut words = split_words(value);
+            if words.len() != 4 {
+                return Cow::Borrowed(value);
+            }
+            words.swap(1, 3);
+            words.join(" ")
+        }
+        "border-radius" => value
+            .split('/')
+            .map(|radii| flip_radii(radii.trim()))
+            .collect::<Vec<_>>()
+            .join(" / "),
+        _ => return Cow::Borrowed(value),
+    };
+
+    Cow::Owned(format!("{flipped}{important}"))
+}
+
+/// Flips the corners of a `border-radius`: top-left, top-right, bottom-right
+/// and bottom-left
+fn flip_radii(radii: &str) -> String {
+    match split_words(radii).as_slice() {
+        [a, b] => format!("{b} {a}"),
+        [a, b, c] => format!("{b} {a} {b} {c}"),
+        [a, b, c, d] => format!("{b} {a} {d} {c}"),
+        _ => radii.to_owned(),
+    }
+}
+
+fn flip_cursor(cursor: &str) -> &str {
+    match cursor {
+        "e-resize" => "w-resize",
+        "w-resize" => "e-resize",
+        "ne-resize" => "nw-resize",
+        "nw-resize" => "ne-resize",
+        "se-resize" => "sw-resize",
+        "sw-resize" => "se-resize",
+        "nesw-resize" => "nwse-resize",
+        "nwse-resize" => "nesw-resize",
+        cursor => cursor,
+    }
+}
+
+/// The shorthands for the four sides which are split into a block and an
+/// inline shorthand by [to_logical]
+const LOGICAL_SHORTHANDS: &[&str] = &[
+    "margin",
+    "padding",
+    "inset",
+    "scroll-margin",
+    "scroll-padding",
+    "border-width",
+    "border-style",
+    "border-color",
+];
+
+/// `margin-left` becomes `margin-inline-start`, `border-top-width` becomes
+/// `border-block-start-width`, `border-top-left-radius` becomes
+/// `border-start-start-radius` and so on. `None` for other properties.
+fn logical_property(property: &str) -> Option<String> {
+    fn side(side: &str) -> Option<&'static str> {
+        Some(match side {
+            "top" => "block-start",
+            "bottom" => "block-end",
+            "left" => "inline-start",
+            "right" => "inline-end",
+            _ => return None,
+        })
+    }
+
+    let segments = property.split('-').collect::<Vec<_>>();
+    match segments.as_slice() {
+        [side_] => side(side_).map(|side| format!("inset-{side}")),
+        ["margin" | "padding" | "border", side_] => {
+            side(side_).map(|side| format!("{}-{side}", segments[0]))
+        }
+        ["scroll", kind @ ("margin" | "padding"), side_] => {
+            side(side_).map(|side| format!("scroll-{kind}-{side}"))
+        }
+        ["border", side_, kind @ ("width" | "style" | "color")] => {
+            side(side_).map(|side| format!("border-{side}-{kind}"))
+        }
+        ["border", vertical, horizontal, "radius"] => {
+            let block = match *vertical {
+                "top" => "start",
+                "bottom" => "end",
+                _ => return None,
+            };
+            let inline = match *horizontal {
+                "left" => "start",
+                "right" => "end",
+                _ => return None,
+            };
+            Some(format!("border-{block}-{inline}-radius"))
+        }
+        _ => None,
+    }
+}
+
+fn logical_value<'a>(property: &str, value: &'a str) -> Cow<'a, str> {
+    let (words, important) = split_important(value);
+    let logical = match property {
+        "float" | "clear" => map_words(words, |word| match word {
+            "left" => "inline-start",
+            "right" => "inline-end",
+            word => word,
+        }),
+        "text-align" | "text-align-last" => map_words(words, |word| match word {
+            "left" => "start",
+            "right" => "end",
+            word => word,
+        }),
+        _ => return Cow::Borrowed(value),
+    };
+
+    Cow::Owned(format!("{logical}{important}"))
+}
+
+/// The value of a block or inline shorthand, which can be shortened if both
+/// sides are equal
+fn join_pair(start: &str, end: &str, important: &str) -> String {
+    match start == end {
+        true => format!("{start}{important}"),
+        false => format!("{start} {end}{important}"),
+    }
+}
+
+fn map_words<'a>(value: &'a str, f: impl Fn(&'a str) -> &'a str) -> String {
+    split_words(value)
+        .into_iter()
+        .map(f)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Splits a value at the whitespace which is not enclosed by parentheses or
+/// quotes, i.e. `calc(1px + 2px) 3px` into `calc(1px + 2px)` and `3px`
+fn split_words(value: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut start = None;
+
+    for (idx, ch) in value.char_indices() {
+        match (ch, quote) {
+            ('"' | '\'', None) => quote = Some(ch),
+            (ch, Some(q)) if ch == q => quote = None,
+            ('(', None) => depth += 1,
+            (')', None) => depth = depth.saturating_sub(1),
+            (ch, None) if ch.is_whitespace() && depth == 0 => {
+                if let Some(start) = start.take() {
+                    words.push(&value[start..idx]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(idx);
+    }
+    if let Some(start) = start {
+        words.push(&value[start..]);
+    }
+
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{flip, to_logical};
+
+    #[test]
+    fn flips() {
+        let src = ".a { margin-left: 1px; border-top-left-radius: 2px; --left: 0; }\n\
+                   .b { text-align: left !important; cursor: ne-resize; }\n\
+                   @media print { .c { margin: 0 calc(1px + 2px) 0 auto; right: 0; } }\n\
+                   .d { border-radius: 1px 2px 3px / 4px 5px; padding: 1px 2px; }";
+        assert_eq!(
+            flip(src),
+            ".a {\n  margin-right: 1px;\n  border-top-right-radius: 2px;\n  --left: 0;\n}\n\
+             .b {\n  text-align: right !important;\n  cursor: nw-resize;\n}\n\
+             @media print {\n.c {\n  margin: 0 auto 0 calc(1px + 2px);\n  left: 0;\n}\n}\n\
+             .d {\n  border-radius: 2px 1px 2px 3px / 5px 4px;\n  padding: 1px 2px;\n}\n"
+        );
+    }
+
+    #[test]
+    fn logical() {
+        let src = ".a { margin-left: 1px; border-top-width: 2px; border-bottom-left-radius: 0; }\n\
+                   .b { float: right !important; text-align: left; top: 0; width: 1px; }\n\
+                   .c { padding: 1px 2px 3px 4px; margin: 1px auto; border-color: red; }\n\
+                   .d { inset: 0 1px 2px !important; --top: 0; }";
+        assert_eq!(
+            to_logical(src),
+            ".a {\n  margin-inline-start: 1px;\n  border-block-start-width: 2px;\n  \
+             border-end-start-radius: 0;\n}\n\
+             .b {\n  float: inline-end !important;\n  text-align: start;\n  \
+             inset-block-start: 0;\n  width: 1px;\n}\n\
+             .c {\n  padding-block: 1px 3px;\n  padding-inline: 4px 2px;\n  \
+             margin-block: 1px;\n  margin-inline: auto;\n  border-color: red;\n}\n\
+             .d {\n  inset-block: 0 2px !important;\n  inset-inline: 1px !important;\n  \
+             --top: 0;\n}\n"
+        );
+    }
+}
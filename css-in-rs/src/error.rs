@@ -0,0 +1,45 @@
+use core::fmt::{Display, Formatter, Result};
+
+/// Errors of the fallible constructors, i.e. [StyleProvider::try_quickstart_web](crate::StyleProvider::try_quickstart_web),
+/// of [StyleProvider::link_external](crate::StyleProvider::link_external) and
+/// of the checks of [SanitizePolicy::Reject](crate::sanitize::SanitizePolicy::Reject)
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// There is no window, i.e. in a web worker or when running natively
+    NoWindow,
+    /// The window has no document
+    NoDocument,
+    /// The document has no `head` to put the `style` element in
+    NoHead,
+    /// Styles can only be mounted in a document or a shadow root, not in
+    /// another node
+    UnsupportedRoot,
+    /// The `style` element of the styles has been removed from the document
+    Detached,
+    /// A DOM operation failed, with the JavaScript error formatted as a string
+    Dom(String),
+    /// An interpolated value would break out of its declaration, see
+    /// [sanitize](crate::sanitize). The styles contain the escaped value.
+    UnsafeValue(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Error::NoWindow => f.write_str("There is no window"),
+            Error::NoDocument => f.write_str("There is no document to mount the styles in"),
+            Error::NoHead => f.write_str("The document has no head"),
+            Error::UnsupportedRoot => {
+                f.write_str("Styles can only be mounted in a document or a shadow root")
+            }
+            Error::Detached => f.write_str("The style element is not part of a document"),
+            Error::Dom(err) => write!(f, "A DOM operation failed: {err}"),
+            Error::UnsafeValue(value) => {
+                write!(f, "Unsafe value interpolated into CSS: {value:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,163 @@
+//! A bounded log of recent operations of a [StyleProvider], which can be
+//! attached to crash or error reports. See [StyleProvider::set_event_log].
+//!
+//! # Example
+//! ```
+//! # use css_in_rs::{make_styles, EmptyTheme, StyleProvider};
+//! use css_in_rs::events::StyleEventKind;
+//!
+//! make_styles! {
+//!     (_theme: EmptyTheme) -> MyClasses {
+//!         active {
+//!             color: "red",
+//!         },
+//!     }
+//! }
+//!
+//! let style_provider = StyleProvider::new_headless(EmptyTheme);
+//! style_provider.set_event_log(16);
+//! style_provider.add_classes::<MyClasses>();
+//!
+//! let events = style_provider.recent_events();
+//! assert_eq!(events.len(), 1);
+//! assert!(matches!(events[0].kind, StyleEventKind::Mounted { .. }));
+//! assert!(events[0].to_string().starts_with("#0 mounted "));
+//! ```
+
+use core::fmt::{Display, Formatter, Result};
+use std::collections::VecDeque;
+
+#[cfg(doc)]
+use crate::StyleProvider;
+use crate::{direction::Direction, CssFormat};
+
+/// An entry of the event log
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StyleEvent {
+    /// Number of events recorded before this one. Gaps at the start of
+    /// [StyleProvider::recent_events] show how many events have been dropped.
+    pub seq: u64,
+    pub kind: StyleEventKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StyleEventKind {
+    /// [Classes](crate::Classes) have been mounted for the first time
+    Mounted {
+        /// The type name of the [Classes](crate::Classes)
+        name: &'static str,
+        /// The first number of the reserved classnames
+        start: u64,
+    },
+    /// Keyframes have been mounted, see [StyleProvider::add_keyframes]
+    KeyframesMounted {
+        name: &'static str,
+    },
+    /// A stylesheet has been mounted by [StyleProvider::add_raw_css]
+    RawCssMounted {
+        /// The first number of the reserved classnames
+        start: u64,
+    },
+    /// The theme has changed and all styles have been regenerated
+    ThemeUpdated {
+        /// Counts the theme changes of the provider
+        generation: u64,
+    },
+    /// Saving a theme failed, so the previous one has been restored, see
+    /// [StyleProvider::persist_theme]
+    ThemeRolledBack,
+    /// Loading a persisted theme failed, see [StyleProvider::load_theme]
+    ThemeLoadFailed,
+    FormatChanged(CssFormat),
+    /// See [StyleProvider::set_direction]
+    DirectionChanged(Direction),
+    /// A scoped provider has been created, see [StyleProvider::scoped]
+    ScopeCreated {
+        prefix: String,
+    },
+}
+
+impl Display for StyleEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "#{} ", self.seq)?;
+        match &self.kind {
+            StyleEventKind::Mounted { name, start } => write!(f, "mounted {name} at {start}"),
+            StyleEventKind::KeyframesMounted { name } => write!(f, "mounted keyframes {name}"),
+            StyleEventKind::RawCssMounted { start } => write!(f, "mounted raw css at {start}"),
+            StyleEventKind::ThemeUpdated { generation } => {
+                write!(f, "updated theme (generation {generation})")
+            }
+            StyleEventKind::ThemeRolledBack => write!(f, "saving the theme failed, rolled back"),
+            StyleEventKind::ThemeLoadFailed => write!(f, "loading the theme failed"),
+            StyleEventKind::FormatChanged(format) => write!(f, "changed format to {format:?}"),
+            StyleEventKind::DirectionChanged(direction) => {
+                write!(f, "changed direction to {direction:?}")
+            }
+            StyleEventKind::ScopeCreated { prefix } => write!(f, "created scope {prefix}"),
+        }
+    }
+}
+
+/// Keeps the most recent events, up to a fixed capacity. A capacity of zero
+/// disables the log.
+#[derive(Default)]
+pub(crate) struct EventLog {
+    capacity: usize,
+    next_seq: u64,
+    events: VecDeque<StyleEvent>,
+}
+
+impl EventLog {
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.events.len() > capacity {
+            self.events.pop_front();
+        }
+    }
+
+    pub fn record(&mut self, kind: impl FnOnce() -> StyleEventKind) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(StyleEvent {
+            seq: self.next_seq,
+            kind: kind(),
+        });
+        self.next_seq += 1;
+    }
+
+    pub fn recent(&self) -> Vec<StyleEvent> {
+        self.events.iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EventLog, StyleEventKind};
+
+    #[test]
+    fn bounded() {
+        let mut log = EventLog::default();
+        log.record(|| StyleEventKind::ThemeLoadFailed);
+        assert!(log.recent().is_empty());
+
+        log.set_capacity(2);
+        for generation in 0..3 {
+            log.record(|| StyleEventKind::ThemeUpdated { generation });
+        }
+        let seqs = log
+            .recent()
+            .iter()
+            .map(|event| event.seq)
+            .collect::<Vec<_>>();
+        assert_eq!(seqs, [1, 2]);
+
+        log.set_capacity(1);
+        assert_eq!(log.recent()[0].seq, 2);
+    }
+}
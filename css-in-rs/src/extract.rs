@@ -0,0 +1,540 @@
+//! Extraction of styles ahead of time, i.e. on a server or in a build script.
+//!
+//! Classnames are assigned in the order in which styles are mounted. The
+//! extracted styles therefore only match if the client mounts the same
+//! [Classes] in the same order.
+
+use core::cell::RefCell;
+use std::{collections::BTreeSet, rc::Rc};
+
+use crate::{css, Classes, CssFormat, StyleProvider, Theme};
+
+/// Attribute marking a `style` element containing critical CSS. Its value
+/// lists the type names of the contained [Classes], separated by spaces.
+pub(crate) const CRITICAL_ATTRIBUTE: &str = "data-css-in-rs-critical";
+
+/// Attribute marking a critical `style` element which is part of a hydrated
+/// DOM tree. It is emptied instead of removed, since the framework still
+/// refers to it.
+#[cfg(feature = "web-sys")]
+pub(crate) const HYDRATED_ATTRIBUTE: &str = "data-css-in-rs-hydrated";
+
+/// Collects the styles needed for the first paint, so they can be inlined
+/// into the static HTML shell before any wasm is loaded.
+///
+/// The generated `style` tag is recognized by the web backend once the
+/// [StyleProvider] boots: It is removed as soon as all contained [Classes]
+/// have been mounted again by the client. Until then, both the inlined and the
+/// mounted rules are present, so there is no flash of unstyled content. The
+/// client has to mount the [Classes] in the same order in which they have
+/// been added here, so the generated classnames match.
+///
+/// In debug builds, the web backend compares the inlined styles with its own
+/// ones before removing them, and logs the differing rules as a warning to
+/// the console. So a different theme or mount order on the client does not
+/// go unnoticed.
+///
+/// # Example
+/// ```
+/// # use css_in_rs::{make_styles, EmptyTheme};
+/// use css_in_rs::extract::CriticalCss;
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> AppClasses {
+///         root {
+///             margin: "0px",
+///         },
+///     }
+/// }
+///
+/// let critical = CriticalCss::new(EmptyTheme).add::<AppClasses>();
+/// assert_eq!(critical.css(), ".css-0{margin:0px}");
+///
+/// let html = format!("<head>{}</head>", critical.to_style_tag());
+/// ```
+pub struct CriticalCss<T> {
+    provider: StyleProvider<T>,
+    classes: Vec<&'static str>,
+}
+
+impl<T: Theme> CriticalCss<T> {
+    pub fn new(theme: T) -> Self {
+        let provider = StyleProvider::new_headless(theme);
+        provider.set_format(CssFormat::Minified);
+
+        Self {
+            provider,
+            classes: Default::default(),
+        }
+    }
+
+    pub fn add<C>(mut self) -> Self
+    where
+        C: Classes<Theme = T>,
+    {
+        // Includes the dependencies of `C` which have not been added before
+        let count = self.provider.mounted_count();
+        self.provider.add_classes::<C>();
+        let mounted = self.provider.mounted_after(count);
+        self.classes.extend(mounted.into_iter().map(|(name, _)| name));
+
+        self
+    }
+
+    /// The minified styles of all added [Classes]
+    pub fn css(&self) -> String {
+        self.provider.current_css()
+    }
+
+    /// Returns a `style` tag containing [CriticalCss::css], which can be
+    /// inlined into the `head` of an HTML document
+    pub fn to_style_tag(&self) -> String {
+        style_tag(&self.classes, &self.css())
+    }
+}
+
+/// Compares the inlined styles of the server with the ones the client has
+/// generated, rule by rule. Returns a description of the rules of the server
+/// which the client does not have, or `None` if there are none.
+#[cfg_attr(not(feature = "web-sys"), allow(dead_code))]
+pub(crate) fn hydration_mismatch(server: &str, client: &str) -> Option<String> {
+    use core::fmt::Write;
+
+    let client = css::minified_rules(client);
+    let selector = |rule: &str| rule.split('{').next().unwrap_or_default().to_owned();
+
+    let mut diff = String::new();
+    for rule in css::minified_rules(server) {
+        if client.contains(&rule) {
+            continue;
+        }
+
+        let _ = writeln!(diff, "- {rule}");
+        let prelude = selector(&rule);
+        match client.iter().find(|other| selector(other) == prelude) {
+            Some(other) => {
+                let _ = writeln!(diff, "+ {other}");
+            }
+            None => diff.push_str("+ (missing)\n"),
+        }
+    }
+
+    if diff.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "css-in-rs: The styles inlined by the server differ from the ones of the \
+         client, i.e. because of a different theme or mount order:\n{diff}"
+    ))
+}
+
+/// Returns a `style` tag with critical CSS, which is recognized by the web
+/// backend, see [CRITICAL_ATTRIBUTE]
+fn style_tag(classes: &[&str], css: &str) -> String {
+    let classes = classes
+        .join(" ")
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;");
+
+    let css = escape_style_content(css);
+    format!("<style {CRITICAL_ATTRIBUTE}=\"{classes}\">{css}</style>")
+}
+
+/// Escapes CSS to be written into a `style` element as is
+pub(crate) fn escape_style_content(css: &str) -> String {
+    // `</style` would terminate the element early. `\/` is a valid escape
+    // sequence for `/` in CSS
+    css.replace("</", "<\\/")
+}
+
+/// Records which [Classes] are requested from a [StyleProvider] during a
+/// render pass on the server, i.e. by `use_styles` of the rendered
+/// components, so only their styles are inlined into the HTML. Unlike
+/// [CriticalCss], the [Classes] do not have to be listed up front.
+///
+/// [Classes] which have been mounted before the render pass are recorded as
+/// well if they are requested again. Styles shared by all [Classes], like
+/// keyframes, font faces and raw stylesheets, are always included. As with
+/// [CriticalCss], the client has to mount the [Classes] in the same order as
+/// the provider on the server, so the generated classnames match.
+///
+/// # Example
+/// ```
+/// # use css_in_rs::{make_styles, CssFormat, EmptyTheme, StyleProvider};
+/// use css_in_rs::extract::RenderCollector;
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> HeaderClasses {
+///         header {
+///             height: "64px",
+///         },
+///     }
+/// }
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> DialogClasses {
+///         dialog {
+///             padding: "16px",
+///         },
+///     }
+/// }
+///
+/// let style_provider = StyleProvider::new_headless(EmptyTheme);
+/// style_provider.set_format(CssFormat::Minified);
+/// style_provider.add_classes::<DialogClasses>();
+///
+/// let collector = RenderCollector::start(&style_provider);
+/// // Rendering the page, which only shows the header
+/// style_provider.add_classes::<HeaderClasses>();
+/// let critical = collector.finish();
+///
+/// assert_eq!(critical.classes(), [std::any::type_name::<HeaderClasses>()]);
+/// assert_eq!(critical.css(), ".css-1{height:64px}");
+/// let html = format!("<head>{}</head>", critical.to_style_tag());
+/// ```
+pub struct RenderCollector<T> {
+    provider: StyleProvider<T>,
+    /// The indices of the used generators, see [StyleProvider::start_collecting]
+    used: Rc<RefCell<Vec<usize>>>,
+}
+
+impl<T: Theme> RenderCollector<T> {
+    /// Starts recording the [Classes] requested from the provider. Multiple
+    /// collectors may record at the same time; recording stops once the
+    /// collector is finished or dropped.
+    pub fn start(provider: &StyleProvider<T>) -> Self {
+        Self {
+            provider: provider.clone(),
+            used: provider.start_collecting(),
+        }
+    }
+
+    /// Stops recording and returns the styles of the recorded [Classes]
+    pub fn finish(self) -> CollectedCss {
+        let (classes, css) = self.provider.collected(&self.used.borrow());
+        CollectedCss { classes, css }
+    }
+}
+
+/// The styles recorded by a [RenderCollector]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CollectedCss {
+    classes: Vec<&'static str>,
+    css: String,
+}
+
+impl CollectedCss {
+    /// The type names of the recorded [Classes], in the order of the stylesheet
+    pub fn classes(&self) -> &[&'static str] {
+        &self.classes
+    }
+
+    /// The styles of the recorded [Classes], formatted like the styles of the
+    /// provider
+    pub fn css(&self) -> &str {
+        &self.css
+    }
+
+    /// Returns a `style` tag containing [CollectedCss::css], see
+    /// [CriticalCss::to_style_tag]
+    pub fn to_style_tag(&self) -> String {
+        style_tag(&self.classes, &self.css)
+    }
+}
+
+/// Emits the styles of a streamed server-side render in chunks, so styles of
+/// components which are streamed late arrive right before their markup.
+///
+/// Every chunk is a `style` tag with all styles mounted since the previous
+/// one. Each [Classes] is therefore emitted only once, no matter how often it
+/// is requested while rendering. Styles shared by all [Classes], like
+/// keyframes, font faces and raw stylesheets, are emitted with the next chunk
+/// as well. The web backend recognizes every chunk like the tag of
+/// [CriticalCss]: It is removed once its [Classes] are mounted by the client.
+///
+/// Within a chunk, styles are ordered like in the stylesheet. The order across
+/// chunks is the order of mounting, so a [Classes] with a higher priority
+/// which is streamed later still comes after earlier chunks. Styles which have
+/// been emitted are not updated if the theme changes during the render.
+///
+/// # Example
+/// ```
+/// # use css_in_rs::{make_styles, CssFormat, EmptyTheme, StyleProvider};
+/// use css_in_rs::extract::StyleStream;
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> ShellClasses {
+///         shell {
+///             display: "flex",
+///         },
+///     }
+/// }
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> CommentsClasses {
+///         comments {
+///             margin: "8px",
+///         },
+///     }
+/// }
+///
+/// let style_provider = StyleProvider::new_headless(EmptyTheme);
+/// style_provider.set_format(CssFormat::Minified);
+/// let mut stream = StyleStream::new(&style_provider);
+///
+/// style_provider.add_classes::<ShellClasses>();
+/// let shell = stream.next_chunk().unwrap();
+/// assert!(shell.ends_with(">.css-0{display:flex}</style>"));
+///
+/// // Nothing new has been mounted
+/// style_provider.add_classes::<ShellClasses>();
+/// assert_eq!(stream.next_chunk(), None);
+///
+/// // The comments are loaded asynchronously and streamed afterwards
+/// style_provider.add_classes::<CommentsClasses>();
+/// let comments = stream.next_chunk().unwrap();
+/// assert!(comments.ends_with(">.css-1{margin:8px}</style>"));
+/// ```
+pub struct StyleStream<T> {
+    provider: StyleProvider<T>,
+    position: StreamPosition,
+}
+
+/// What a [StyleStream] has emitted so far
+#[derive(Default)]
+pub(crate) struct StreamPosition {
+    /// Whether the first chunk has been emitted, which declares the layers
+    pub started: bool,
+    pub keyframes: usize,
+    pub font_faces: usize,
+    pub raw_css: usize,
+    pub generators: usize,
+    pub atoms: BTreeSet<&'static str>,
+}
+
+impl<T: Theme> StyleStream<T> {
+    /// Starts streaming the styles of the provider. The first chunk includes
+    /// all styles which have been mounted before.
+    pub fn new(provider: &StyleProvider<T>) -> Self {
+        Self {
+            provider: provider.clone(),
+            position: Default::default(),
+        }
+    }
+
+    /// Returns a `style` tag with the styles mounted since the previous
+    /// chunk, which should be written before the markup rendered since then.
+    /// Returns `None` if nothing new has been mounted.
+    pub fn next_chunk(&mut self) -> Option<String> {
+        let (classes, css) = self.next_styles()?;
+        Some(style_tag(&classes, &css))
+    }
+
+    /// Returns the type names and styles of [StyleStream::next_chunk]
+    pub(crate) fn next_styles(&mut self) -> Option<(Vec<&'static str>, String)> {
+        let (classes, css) = self.provider.streamed(&mut self.position);
+        if classes.is_empty() && css.is_empty() {
+            return None;
+        }
+        Some((classes, css))
+    }
+}
+
+/// Maps the chunks of a code-split application (i.e. one chunk per route) to
+/// the [Classes] they mount and the CSS generated for them. Servers can use
+/// it to preload the right styles alongside each chunk.
+///
+/// Styles are only included in the first chunk which mounts them, just like
+/// a [StyleProvider] mounts every style only once.
+///
+/// # Example
+/// ```
+/// # use css_in_rs::{make_styles, EmptyTheme};
+/// use css_in_rs::extract::StyleManifest;
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> ButtonClasses {
+///         button {
+///             padding: "5px",
+///         },
+///     }
+/// }
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> SettingsClasses {
+///         panel {
+///             margin: "10px",
+///         },
+///     }
+/// }
+///
+/// let mut manifest = StyleManifest::new(EmptyTheme);
+/// manifest.chunk("home").add::<ButtonClasses>();
+/// manifest
+///     .chunk("settings")
+///     .add::<ButtonClasses>() // already part of `home`
+///     .add::<SettingsClasses>();
+///
+/// let chunks = manifest.chunks();
+/// assert_eq!(chunks[0].classes(), [std::any::type_name::<ButtonClasses>()]);
+/// assert_eq!(chunks[1].classes(), [std::any::type_name::<SettingsClasses>()]);
+/// assert!(chunks[1].css().contains(".css-1"));
+///
+/// let json = manifest.to_json();
+/// assert!(json.starts_with(r#"{"home":{"classes":["#));
+/// ```
+pub struct StyleManifest<T> {
+    provider: StyleProvider<T>,
+    chunks: Vec<Chunk>,
+}
+
+/// A single entry of a [StyleManifest]
+pub struct Chunk {
+    name: String,
+    classes: Vec<&'static str>,
+    css: String,
+}
+
+impl Chunk {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The type names of the [Classes] which have been mounted first by this chunk
+    pub fn classes(&self) -> &[&'static str] {
+        &self.classes
+    }
+
+    /// The styles generated for [Chunk::classes]
+    pub fn css(&self) -> &str {
+        &self.css
+    }
+}
+
+impl<T: Theme> StyleManifest<T> {
+    pub fn new(theme: T) -> Self {
+        Self {
+            provider: StyleProvider::new_headless(theme),
+            chunks: Default::default(),
+        }
+    }
+
+    /// Returns a builder to add [Classes] to the chunk with the given name.
+    /// The chunk is created if it does not exist yet.
+    pub fn chunk(&mut self, name: impl Into<String>) -> ChunkBuilder<'_, T> {
+        let name = name.into();
+        let idx = match self.chunks.iter().position(|chunk| chunk.name == name) {
+            Some(idx) => idx,
+            None => {
+                self.chunks.push(Chunk {
+                    name,
+                    classes: Default::default(),
+                    css: Default::default(),
+                });
+                self.chunks.len() - 1
+            }
+        };
+
+        ChunkBuilder {
+            manifest: self,
+            idx,
+        }
+    }
+
+    pub fn chunks(&self) -> &[Chunk] {
+        &self.chunks
+    }
+
+    /// Serializes the manifest as a JSON object, mapping chunk names to
+    /// objects with the fields `classes` (array of type names) and `css`
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{");
+        for (idx, chunk) in self.chunks.iter().enumerate() {
+            if idx > 0 {
+                json.push(',');
+            }
+
+            push_json_string(&mut json, &chunk.name);
+            json.push_str(":{\"classes\":[");
+            for (idx, classes) in chunk.classes.iter().enumerate() {
+                if idx > 0 {
+                    json.push(',');
+                }
+                push_json_string(&mut json, classes);
+            }
+            json.push_str("],\"css\":");
+            push_json_string(&mut json, &chunk.css);
+            json.push('}');
+        }
+        json.push('}');
+
+        json
+    }
+}
+
+/// Adds [Classes] to a chunk of a [StyleManifest]
+pub struct ChunkBuilder<'a, T> {
+    manifest: &'a mut StyleManifest<T>,
+    idx: usize,
+}
+
+impl<'a, T: Theme> ChunkBuilder<'a, T> {
+    pub fn add<C>(self) -> Self
+    where
+        C: Classes<Theme = T>,
+    {
+        // Includes the dependencies of `C` which have not been added before
+        let provider = &self.manifest.provider;
+        let count = provider.mounted_count();
+        provider.add_classes::<C>();
+
+        let chunk = &mut self.manifest.chunks[self.idx];
+        for (name, css) in provider.mounted_after(count) {
+            chunk.classes.push(name);
+            chunk.css.push_str(&css);
+        }
+
+        self
+    }
+}
+
+pub(crate) fn push_json_string(json: &mut String, s: &str) {
+    use core::fmt::Write;
+
+    json.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            ch if ch.is_control() => {
+                let _ = write!(json, "\\u{:04x}", ch as u32);
+            }
+            ch => json.push(ch),
+        }
+    }
+    json.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::hydration_mismatch;
+
+    #[test]
+    fn hydration_mismatches() {
+        let server = ".css-0{color:red}.css-1{margin:0px}";
+        let client = ".css-0 {\n  color: red;\n}\n.css-1 { margin: 0px; }\n.css-2 {}";
+        assert_eq!(hydration_mismatch(server, client), None);
+
+        let client = ".css-0{color:blue}";
+        let diff = hydration_mismatch(server, client).unwrap();
+        let expected = "- .css-0{color:red}\n\
+                        + .css-0{color:blue}\n\
+                        - .css-1{margin:0px}\n\
+                        + (missing)\n";
+        assert!(diff.ends_with(expected), "{diff}");
+    }
+}
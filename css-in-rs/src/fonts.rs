@@ -0,0 +1,222 @@
+//! Web fonts. A [FontFace] declares a font using `@font-face`, see
+//! [StyleProvider::add_font_face]. Browsers usually render text with a
+//! fallback font until the web font has been loaded, so the text jumps once it
+//! arrives. With the `web-sys` feature, components can wait for the fonts
+//! instead, using [fonts_ready] or [on_fonts_ready], or style the document
+//! differently until they are loaded using [add_class_when_fonts_ready].
+//!
+//! # Example
+//! ```
+//! # use css_in_rs::{fonts::FontFace, url, CssFormat, EmptyTheme, StyleProvider};
+//! let inter = FontFace::new("Inter")
+//!     .src(url!("/fonts/inter.woff2"), "woff2")
+//!     .weight("100 900")
+//!     .display("swap");
+//!
+//! let style_provider = StyleProvider::new_headless(EmptyTheme);
+//! style_provider.set_format(CssFormat::Minified);
+//! style_provider.add_font_face(&inter);
+//! assert_eq!(
+//!     style_provider.current_css(),
+//!     "@font-face{font-family:\"Inter\";\
+//!      src:url(\"/fonts/inter.woff2\") format(\"woff2\");\
+//!      font-weight:100 900;font-display:swap}"
+//! );
+//!
+//! // In value position, the font displays as its quoted family name
+//! assert_eq!(format!("{inter}, sans-serif"), "\"Inter\", sans-serif");
+//! ```
+
+use core::fmt::{Display, Formatter, Result};
+
+use crate::values::{quote, Url};
+#[cfg(doc)]
+use crate::StyleProvider;
+
+/// A font declared using `@font-face`, see the [module documentation](self).
+/// It displays as its quoted family name, so it can be used in value position
+/// of [make_styles!](crate::make_styles).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FontFace {
+    family: String,
+    sources: Vec<String>,
+    descriptors: Vec<(String, String)>,
+}
+
+impl FontFace {
+    pub fn new(family: impl Into<String>) -> Self {
+        Self {
+            family: family.into(),
+            sources: Default::default(),
+            descriptors: Default::default(),
+        }
+    }
+
+    /// Adds a file to load the font from. The `format` is i.e. `woff2`. The
+    /// browser uses the first source it supports.
+    pub fn src(mut self, url: Url, format: &str) -> Self {
+        self.sources
+            .push(format!("{url} format({})", quote(format)));
+        self
+    }
+
+    /// Adds a font installed on the system of the user as a source
+    pub fn local(mut self, name: &str) -> Self {
+        self.sources.push(format!("local({})", quote(name)));
+        self
+    }
+
+    /// The weight or range of weights the font covers, i.e. `700` or
+    /// `100 900` for a variable font
+    pub fn weight(self, weight: impl Display) -> Self {
+        self.descriptor("font-weight", weight)
+    }
+
+    /// The style the font covers, i.e. `italic`
+    pub fn style(self, style: impl Display) -> Self {
+        self.descriptor("font-style", style)
+    }
+
+    /// How the text is rendered while the font is loading, i.e. `swap` or
+    /// `optional`
+    pub fn display(self, display: impl Display) -> Self {
+        self.descriptor("font-display", display)
+    }
+
+    /// The characters the font covers, i.e. `U+0000-00FF`
+    pub fn unicode_range(self, range: impl Display) -> Self {
+        self.descriptor("unicode-range", range)
+    }
+
+    /// Adds any other descriptor, i.e. `size-adjust`
+    pub fn descriptor(mut self, name: &str, value: impl Display) -> Self {
+        self.descriptors.push((name.to_owned(), value.to_string()));
+        self
+    }
+
+    /// The family name of the font
+    pub fn family(&self) -> &str {
+        &self.family
+    }
+
+    /// The `@font-face` rule
+    pub fn css(&self) -> String {
+        let mut css = format!("@font-face {{\n  font-family: {};\n", quote(&self.family));
+        if !self.sources.is_empty() {
+            css.push_str(&format!("  src: {};\n", self.sources.join(", ")));
+        }
+        for (name, value) in &self.descriptors {
+            css.push_str(&format!("  {name}: {value};\n"));
+        }
+        css.push_str("}\n");
+        css
+    }
+}
+
+impl Display for FontFace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        f.write_str(&quote(&self.family))
+    }
+}
+
+#[cfg(feature = "web-sys")]
+pub use self::web::{add_class_when_fonts_ready, fonts_ready, on_fonts_ready, FontsReady};
+
+#[cfg(feature = "web-sys")]
+mod web {
+    use core::{
+        cell::RefCell,
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll, Waker},
+    };
+    use std::rc::Rc;
+
+    use wasm_bindgen::{closure::Closure, JsValue};
+
+    use crate::backend::web::active_window;
+
+    /// Calls `callback` once the fonts used by the document have been loaded,
+    /// using `document.fonts.ready`. Returns `false` if there is no document
+    /// supporting it, i.e. in a web worker or when running natively; the
+    /// callback is not called then.
+    pub fn on_fonts_ready(callback: impl FnOnce() + 'static) -> bool {
+        let fonts = active_window()
+            .and_then(|window| window.document())
+            .map(|document| document.fonts());
+        let Some(ready) = fonts.and_then(|fonts| fonts.ready().ok()) else {
+            return false;
+        };
+
+        let closure: Closure<dyn FnMut(JsValue)> = Closure::once(move |_: JsValue| callback());
+        let _ = ready.then(&closure);
+        // The promise settles only once, so the closure is not needed afterwards
+        closure.forget();
+        true
+    }
+
+    /// Resolves once the fonts used by the document have been loaded, see
+    /// [on_fonts_ready]. Resolves immediately if there is no document
+    /// supporting it.
+    pub fn fonts_ready() -> FontsReady {
+        let state = Rc::new(RefCell::new(ReadyState::default()));
+        let shared = state.clone();
+        let supported = on_fonts_ready(move || {
+            let waker = {
+                let mut state = shared.borrow_mut();
+                state.done = true;
+                state.waker.take()
+            };
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        });
+        state.borrow_mut().done |= !supported;
+        FontsReady { state }
+    }
+
+    /// Adds a class to the root element of the document (`<html>`) once the
+    /// fonts have been loaded, i.e. to hide text until then using
+    /// `html:not(.fonts-loaded)`. Without support for `document.fonts`, the
+    /// class is added right away.
+    pub fn add_class_when_fonts_ready(class_name: impl Into<String>) {
+        let class_name = class_name.into();
+        let add_class = move || {
+            let root = active_window()
+                .and_then(|window| window.document())
+                .and_then(|document| document.document_element());
+            if let Some(root) = root {
+                let _ = root.class_list().add_1(&class_name);
+            }
+        };
+
+        if !on_fonts_ready(add_class.clone()) {
+            add_class();
+        }
+    }
+
+    /// The future returned by [fonts_ready]
+    pub struct FontsReady {
+        state: Rc<RefCell<ReadyState>>,
+    }
+
+    #[derive(Default)]
+    struct ReadyState {
+        done: bool,
+        waker: Option<Waker>,
+    }
+
+    impl Future for FontsReady {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            let mut state = self.state.borrow_mut();
+            if state.done {
+                return Poll::Ready(());
+            }
+
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
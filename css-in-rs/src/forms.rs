@@ -0,0 +1,113 @@
+//! Prebuilt styles for form controls. Form styling is the same in most
+//! applications, so [FormClasses] covers the common parts: inputs, checkboxes,
+//! labels and helper texts, including their focused, disabled and invalid
+//! states.
+//!
+//! The colors are taken from the theme, see [FormTheme].
+//!
+//! # Example
+//! ```
+//! # use css_in_rs::{EmptyTheme, StyleProvider};
+//! use css_in_rs::forms::FormClasses;
+//!
+//! let provider = StyleProvider::new_headless(EmptyTheme);
+//! let classes = provider.add_classes::<FormClasses<EmptyTheme>>();
+//! assert_eq!(classes.input, "css-0");
+//! assert!(provider.current_css().contains(".css-0:user-invalid"));
+//! ```
+
+use core::{fmt::Write, marker::PhantomData};
+
+use crate::{Classes, EmptyTheme, GenerateContext, Theme};
+
+/// The colors used by [FormClasses]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormPalette {
+    /// Used for focus outlines and checked checkboxes
+    pub accent: String,
+    pub border: String,
+    /// Used for invalid inputs and their helper texts
+    pub error: String,
+    /// Used for placeholders and helper texts
+    pub muted: String,
+}
+
+impl Default for FormPalette {
+    fn default() -> Self {
+        Self {
+            accent: "#1976d2".to_owned(),
+            border: "#c4c4c4".to_owned(),
+            error: "#d32f2f".to_owned(),
+            muted: "#6b6b6b".to_owned(),
+        }
+    }
+}
+
+/// Themes which can be used with [FormClasses]. Implement
+/// [FormTheme::form_palette] to use the colors of your theme.
+pub trait FormTheme: Theme {
+    fn form_palette(&self) -> FormPalette {
+        FormPalette::default()
+    }
+}
+
+impl FormTheme for EmptyTheme {}
+
+/// Classes for form controls, see the [module documentation](self)
+pub struct FormClasses<T> {
+    /// For text inputs, selects and textareas
+    pub input: String,
+    pub checkbox: String,
+    pub label: String,
+    /// For hints below an input. They turn red if the input is invalid.
+    pub helper_text: String,
+    pub root_all: String,
+    _theme: PhantomData<T>,
+}
+
+impl<T: FormTheme> Classes for FormClasses<T> {
+    type Theme = T;
+
+    fn generate(theme: &Self::Theme, css: &mut String, ctx: &mut GenerateContext<Self::Theme>) {
+        let start = ctx.reserve(4);
+        let classes = Self::new(ctx.prefix(), start);
+        let (input, checkbox, label, helper_text) = (
+            &classes.input,
+            &classes.checkbox,
+            &classes.label,
+            &classes.helper_text,
+        );
+        let FormPalette {
+            accent,
+            border,
+            error,
+            muted,
+        } = theme.form_palette();
+
+        let _ = write!(
+            css,
+            ".{input} {{\n  border: 1px solid {border};\n  border-radius: 4px;\n  \
+             padding: 6px 8px;\n  font: inherit;\n}}\n\
+             .{input}:focus-visible {{\n  outline: 2px solid {accent};\n  outline-offset: -1px;\n}}\n\
+             .{input}::placeholder {{\n  color: {muted};\n}}\n\
+             .{input}:user-invalid {{\n  border-color: {error};\n}}\n\
+             .{input}:disabled, .{checkbox}:disabled {{\n  opacity: 0.5;\n  cursor: not-allowed;\n}}\n\
+             .{checkbox} {{\n  accent-color: {accent};\n  margin: 0 8px 0 0;\n}}\n\
+             .{label} {{\n  display: block;\n  margin-bottom: 4px;\n}}\n\
+             .{helper_text} {{\n  margin-top: 4px;\n  font-size: 0.875em;\n  color: {muted};\n}}\n\
+             .{input}:user-invalid ~ .{helper_text} {{\n  color: {error};\n}}\n"
+        );
+    }
+
+    fn new(prefix: &str, start: u64) -> Self {
+        let class = |idx: u64| format!("{prefix}-{}", start + idx);
+        Self {
+            input: class(0),
+            checkbox: class(1),
+            label: class(2),
+            helper_text: class(3),
+            root_all: (0..4).map(class).collect::<Vec<_>>().join(" "),
+            _theme: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Integration with [Dioxus fullstack](https://dioxuslabs.com/learn/0.6/guides/fullstack/),
+//! so server-rendered pages contain their styles and the client hydrates
+//! them with matching classnames.
+//!
+//! On the server, [use_style_provider_fullstack] sets up a headless provider
+//! which records the styles mounted while rendering. [FullstackStyles] renders
+//! them into the page as critical CSS, see [CriticalCss](crate::extract::CriticalCss),
+//! and passes them to the client through the hydration data of Dioxus. On the
+//! client, the same hook mounts the styles in the document. The inlined
+//! styles are emptied once the client has mounted all of them again, so there
+//! is no flash of unstyled content.
+//!
+//! Classnames match as long as the client renders the same components in the
+//! same order as the server, as required by hydration anyway.
+//!
+//! # Example
+//! ```no_run
+//! #![allow(non_snake_case)]
+//!
+//! use css_in_rs::{
+//!     fullstack::{use_style_provider_fullstack, FullstackStyles},
+//!     make_styles, Classes, EmptyTheme,
+//! };
+//! use dioxus::prelude::*;
+//!
+//! make_styles! {
+//!     (_theme: EmptyTheme) -> PageClasses {
+//!         page {
+//!             margin: "0px auto",
+//!             max_width: "960px",
+//!         },
+//!     }
+//! }
+//!
+//! fn Page() -> Element {
+//!     let classes = PageClasses::use_style();
+//!
+//!     rsx! {
+//!         main { class: "{classes.page}", "Hello" }
+//!     }
+//! }
+//!
+//! fn App() -> Element {
+//!     use_style_provider_fullstack(|| EmptyTheme);
+//!
+//!     rsx! {
+//!         Page {}
+//!         // Has to come after all components whose styles it should contain
+//!         FullstackStyles {}
+//!     }
+//! }
+//!
+//! fn main() {
+//!     // Built with `dioxus/server` for the server and `dioxus/web` for the client
+//!     dioxus::launch(App);
+//! }
+//! ```
+
+use core::cell::RefCell;
+use std::rc::Rc;
+
+use dioxus::{fullstack::prelude::use_server_cached, prelude::*};
+
+use crate::{
+    backend::web::WebSysBackend,
+    extract::{escape_style_content, StyleStream},
+    CssFormat, StyleProvider, Theme,
+};
+
+/// Emits the styles mounted on the server since the previous call, see
+/// [StyleStream]. It is type-erased, so [FullstackStyles] does not depend on
+/// the theme.
+#[derive(Clone)]
+struct ServerStyles(Rc<RefCell<NextStyles>>);
+
+type NextStyles = dyn FnMut() -> Option<(Vec<&'static str>, String)>;
+
+/// Sets up the StyleProvider of a fullstack application. On the client, the
+/// styles are mounted in the document, like [use_style_provider_quickstart](crate::use_style_provider_quickstart).
+/// Without a document, i.e. while rendering on the server, the styles are
+/// collected for [FullstackStyles] instead.
+pub fn use_style_provider_fullstack<T: Theme>(make_theme: impl FnOnce() -> T) -> StyleProvider<T> {
+    let provider = crate::use_provider(|builder| match WebSysBackend::try_quickstart() {
+        Some(backend) => Some(builder.build_with_backend(backend, make_theme())),
+        None => {
+            let provider = builder
+                .format(CssFormat::Minified)
+                .build_headless(make_theme());
+            let mut stream = StyleStream::new(&provider);
+            let next = move || stream.next_styles();
+            provide_context(ServerStyles(Rc::new(RefCell::new(next))));
+            Some(provider)
+        }
+    });
+    provider.unwrap()
+}
+
+/// Renders the styles which have been mounted on the server since the
+/// previous [FullstackStyles] into a `style` element. Components are rendered
+/// in order, so it has to be placed after the components whose styles it
+/// should contain, i.e. at the end of the root component. Components
+/// streamed later, like the ones within a suspense boundary, need a
+/// [FullstackStyles] of their own.
+///
+/// On the client, the same element is rendered from the hydration data,
+/// so it matches the markup of the server. Its content is removed once the
+/// client has mounted the contained styles.
+#[component]
+pub fn FullstackStyles() -> Element {
+    let (classes, css) = use_server_cached(|| {
+        let Some(styles) = try_consume_context::<ServerStyles>() else {
+            return Default::default();
+        };
+        let next = &mut *styles.0.borrow_mut();
+        let (classes, css) = next().unwrap_or_default();
+        (classes.join(" "), escape_style_content(&css))
+    });
+
+    if classes.is_empty() && css.is_empty() {
+        return rsx! {};
+    }
+    // The attributes are `CRITICAL_ATTRIBUTE` and `HYDRATED_ATTRIBUTE`
+    rsx! {
+        style {
+            "data-css-in-rs-critical": classes,
+            "data-css-in-rs-hydrated": true,
+            dangerous_inner_html: css,
+        }
+    }
+}
@@ -0,0 +1,322 @@
+//! The generation of the styles, independent of where they are mounted:
+//! [Theme], [Classes] and the [GenerateContext] which hands out their
+//! classnames, as well as the assembly of the generated CSS. Nothing in here
+//! depends on a DOM; the backends mount the result, see [backend].
+//!
+//! The items are re-exported at the root of the crate.
+
+use std::collections::BTreeMap;
+
+use doc_cfg::doc_cfg;
+
+#[cfg(doc)]
+use crate::{backend, make_styles, StyleProviderBuilder};
+use crate::{
+    backend::CssGeneratorFn, graph, lookup::SourceLocation, sanitize::SanitizePolicy, StyleProvider,
+};
+
+/// A trait for themes: Themes contain shared data which can be
+/// used in your styles.
+///
+/// For example, you can define color, paddings, and so on in a datatype.
+/// Alternatively, you can use css variables which is probably even better.
+/// However, themes are an easy way to precompute some things, for example
+/// complex box-shadows. They can also be changed at runtime which will
+/// update all styles which depend on this theme.
+///
+/// If you do not need theme support, you can use [EmptyTheme]  
+///
+/// Types implementing [PartialEq] can use `#[derive(Theme)]`.
+pub trait Theme: Clone + 'static {
+    /// A fingerprint of the fields of the theme. During hot reloading, the
+    /// hooks of this crate rebuild their StyleProvider when it changes, since
+    /// existing themes cannot be used as values of the new type anymore.
+    /// `#[derive(Theme)]` hashes the names and types of the fields; manual
+    /// implementations should change it together with the fields.
+    const SCHEMA: u64 = 0;
+
+    fn fast_cmp(&self, other: &Self) -> bool;
+}
+
+/// An empty theme. Use if no theme support is needed.
+///
+/// If you do need themes (i.e. certain data which is shared
+/// between all your styles), use a custom type and implement
+/// the [Theme] trait.
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EmptyTheme;
+
+impl Theme for EmptyTheme {
+    fn fast_cmp(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+/// This trait will be implemented by the classnames-struct generated
+/// by the [make_styles!] macro. You probably won't implement it yourself
+/// unless you need something very specific which the macro cannot handle.
+///
+/// Example
+/// ```
+/// # use css_in_rs::{Classes, EmptyTheme, GenerateContext};
+/// struct MyClasses {
+///     active: String,
+///     disabled: String,
+/// }
+///
+/// impl Classes for MyClasses {
+///     type Theme = EmptyTheme;
+///
+///     fn generate(_: &Self::Theme, css: &mut String, ctx: &mut GenerateContext<Self::Theme>) {
+///         use core::fmt::Write;
+///         let start = ctx.reserve(2);
+///         let prefix = ctx.prefix();
+///         writeln!(css, ".{prefix}-{} {{ background-color: transparent; }}", start).unwrap();
+///         writeln!(css, ".{prefix}-{} {{ background-color: #f0f0f0; }}", start + 1).unwrap();
+///     }
+///
+///     fn new(prefix: &str, start: u64) -> Self {
+///         MyClasses {
+///             active: format!("{prefix}-{}", start),
+///             disabled: format!("{prefix}-{}", start + 1),
+///         }
+///     }
+/// }
+/// ```
+pub trait Classes: Sized {
+    /// The [Theme] which this style depend on
+    type Theme: Theme;
+
+    /// Rules of atomic classes which are referenced by the classnames, see
+    /// `#[atomic]` in [make_styles!]. Every rule is only mounted once per
+    /// [StyleProvider], no matter how many [Classes] share it.
+    /// Their selectors lack the prefix of the classnames, i.e.
+    /// `.a-3f2c9e01b7d4 { ... }`, which the [StyleProvider] inserts, see
+    /// [StyleProviderBuilder::prefix].
+    const ATOMS: &'static [&'static str] = &[];
+
+    /// Expected size of the CSS emitted by [Classes::generate] in bytes, which
+    /// the [StyleProvider] uses as the initial capacity of the stylesheet.
+    /// It is only a hint, not a bound: Generated by [make_styles!], literal
+    /// parts are counted exactly, but the sizes of classnames and values which
+    /// are only known at runtime are guessed. Long values simply make the
+    /// stylesheet grow as usual.
+    const CSS_CAPACITY_HINT: usize = 0;
+
+    /// The names of the classes as written in [make_styles!], in the order of
+    /// their numbers, see [StyleProvider::hot_replace]
+    const CLASS_NAMES: &'static [&'static str] = &[];
+
+    /// Where the classes are defined, in the order of their numbers, see
+    /// [StyleProvider::lookup]
+    const CLASS_LOCATIONS: &'static [SourceLocation] = &[];
+
+    /// Styles with a lower priority are emitted before the ones with a higher
+    /// priority, no matter in which order they are mounted. Styles with the
+    /// same priority keep their mount order. Set with `#[priority(...)]` in
+    /// [make_styles!].
+    const PRIORITY: i32 = 0;
+
+    /// Whether the styles depend on the theme. Styles which do not are kept
+    /// when the theme changes instead of being generated again.
+    /// [make_styles!] sets it to `false` if the theme is never read.
+    const READS_THEME: bool = true;
+
+    /// Static information about this style, see [graph::StyleGraph]
+    const INFO: &'static graph::ClassesInfo = &graph::ClassesInfo::EMPTY;
+
+    /// Mounts the other [Classes] which are referenced by this style, see
+    /// [GenerateContext::classes_of]. It is called before this style is mounted.
+    fn add_dependencies(provider: &StyleProvider<Self::Theme>) {
+        let _ = provider;
+    }
+
+    fn generate(theme: &Self::Theme, css: &mut String, ctx: &mut GenerateContext<Self::Theme>);
+    /// Creates the classnames, given the prefix and the first number reserved
+    /// in [Classes::generate]
+    fn new(prefix: &str, start: u64) -> Self;
+
+    /// The names of the classes as written in [make_styles!] together with
+    /// their generated classnames, in the order of their numbers. Atomic
+    /// classes (see `#[atomic]`) are not included.
+    ///
+    /// # Example
+    /// ```
+    /// # use css_in_rs::{make_styles, Classes, EmptyTheme, StyleProvider};
+    /// make_styles! {
+    ///     (_theme: EmptyTheme) -> MyClasses {
+    ///         button {
+    ///             color: "red",
+    ///         },
+    ///         icon {
+    ///             width: "16px",
+    ///         },
+    ///     }
+    /// }
+    ///
+    /// let style_provider = StyleProvider::new_headless(EmptyTheme);
+    /// let classes = style_provider.add_classes::<MyClasses>();
+    /// let all = classes.iter().collect::<Vec<_>>();
+    /// assert_eq!(all, [("button", "css-0"), ("icon", "css-1")]);
+    /// ```
+    fn iter(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        core::iter::empty()
+    }
+
+    #[doc_cfg(feature = "dioxus")]
+    fn use_style() -> Self {
+        match crate::try_use_style_provider() {
+            Some(provider) => provider.use_styles(),
+            // Rendered again once the provider has been rebuilt. No provider
+            // uses an empty prefix, so these classnames do not match any styles.
+            None => Self::new("", 0),
+        }
+    }
+}
+
+/// Passed to [Classes::generate]. It hands out the numbers of new classnames
+/// and gives access to the classnames of other mounted [Classes].
+pub struct GenerateContext<'a, T> {
+    pub(crate) counter: u64,
+    pub(crate) prefix: &'a str,
+    pub(crate) mounted: &'a Mounted<T>,
+    pub(crate) sanitize: SanitizePolicy,
+}
+
+impl<'a, T: Theme> GenerateContext<'a, T> {
+    /// Reserves `count` consecutive classnames and returns the number of the first
+    pub fn reserve(&mut self, count: u64) -> u64 {
+        let start = self.counter;
+        self.counter += count;
+        start
+    }
+
+    /// The prefix of all classnames, see [StyleProviderBuilder::prefix]
+    pub fn prefix(&self) -> &'a str {
+        self.prefix
+    }
+
+    /// How interpolated values are checked, see [StyleProviderBuilder::sanitize]
+    pub fn sanitize_policy(&self) -> SanitizePolicy {
+        self.sanitize
+    }
+
+    /// Returns the classnames of other [Classes], i.e. to reference them in a
+    /// selector. They are mounted by [Classes::add_dependencies].
+    ///
+    /// # Panics
+    /// Panics if `C` has not been mounted yet
+    pub fn classes_of<C>(&self) -> C
+    where
+        C: Classes<Theme = T>,
+    {
+        match self.mounted.start_of(C::generate) {
+            Some(start) => C::new(self.prefix, start),
+            None => panic!(
+                "{} has not been mounted. Mount it in `Classes::add_dependencies`",
+                std::any::type_name::<C>()
+            ),
+        }
+    }
+}
+
+pub(crate) struct CssGenerator<T> {
+    pub(crate) generator: CssGeneratorFn<T>,
+    /// The type name of the [Classes]
+    pub(crate) name: &'static str,
+    /// The atomic rules which have been mounted first by this generator
+    pub(crate) atoms: Vec<&'static str>,
+    /// All atomic rules used by this generator, see [Classes::ATOMS]
+    pub(crate) all_atoms: &'static [&'static str],
+    pub(crate) start: u64,
+    pub(crate) stop: u64,
+    /// See [Classes::CSS_CAPACITY_HINT]
+    pub(crate) estimate: usize,
+    /// See [Classes::CLASS_NAMES]
+    pub(crate) class_names: &'static [&'static str],
+    /// See [Classes::CLASS_LOCATIONS]
+    pub(crate) class_locations: &'static [SourceLocation],
+    /// The CSS emitted instead of the generated one, see [StyleProvider::hot_replace]
+    pub(crate) replacement: Option<String>,
+    /// See [Classes::PRIORITY]
+    pub(crate) priority: i32,
+    /// See [Classes::READS_THEME]
+    pub(crate) reads_theme: bool,
+    /// The CSS generated by the last update, kept for styles which do not
+    /// read the theme
+    pub(crate) cached: Option<String>,
+    /// See [StyleProvider::set_enabled]
+    pub(crate) enabled: bool,
+    #[cfg(feature = "graph")]
+    pub(crate) info: &'static graph::ClassesInfo,
+}
+
+impl<T: Theme> CssGenerator<T> {
+    pub(crate) fn generate(
+        &self,
+        theme: &T,
+        css: &mut String,
+        prefix: &str,
+        mounted: &Mounted<T>,
+        sanitize: SanitizePolicy,
+    ) {
+        push_atoms(css, prefix, &self.atoms);
+        self.generate_rules(theme, css, prefix, mounted, sanitize);
+    }
+
+    /// Like [CssGenerator::generate], but without the atomic rules
+    pub(crate) fn generate_rules(
+        &self,
+        theme: &T,
+        css: &mut String,
+        prefix: &str,
+        mounted: &Mounted<T>,
+        sanitize: SanitizePolicy,
+    ) {
+        if let Some(replacement) = &self.replacement {
+            css.push_str(replacement);
+            return;
+        }
+
+        let mut ctx = GenerateContext {
+            counter: self.start,
+            prefix,
+            mounted,
+            sanitize,
+        };
+        (self.generator)(theme, css, &mut ctx);
+        assert_eq!(ctx.counter, self.stop);
+    }
+}
+
+/// Appends atomic rules (see [Classes::ATOMS]) with the prefix of the provider,
+/// so the atomic classes of providers with different prefixes do not collide
+pub(crate) fn push_atoms<'a>(
+    css: &mut String,
+    prefix: &str,
+    atoms: impl IntoIterator<Item = &'a &'static str>,
+) {
+    for atom in atoms {
+        // Every rule starts with `.` followed by the rest of the classname
+        css.push('.');
+        css.push_str(prefix);
+        css.push('-');
+        css.push_str(&atom[1..]);
+    }
+}
+
+/// All generators of a [StyleProvider], in the order in which they have been mounted
+pub(crate) struct Mounted<T> {
+    pub(crate) generators: Vec<CssGenerator<T>>,
+    pub(crate) generator_to_idx: BTreeMap<CssGeneratorFn<T>, usize>,
+}
+
+impl<T> Mounted<T> {
+    pub(crate) fn start_of(&self, generator: CssGeneratorFn<T>) -> Option<u64> {
+        let idx = *self.generator_to_idx.get(&generator)?;
+
+        // The generator currently being mounted is not pushed yet
+        self.generators.get(idx).map(|generator| generator.start)
+    }
+}
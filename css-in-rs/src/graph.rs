@@ -0,0 +1,196 @@
+//! A graph of the mounted [Classes](crate::Classes): which theme fields they
+//! read, which at-rules they use and which other classes they reference. It
+//! helps to audit the coupling between styles and the theme in large code
+//! bases, i.e. before renaming or removing a theme field.
+//!
+//! The graph is available with the `graph` feature, see
+//! [StyleProvider::style_graph](crate::StyleProvider::style_graph). It can be
+//! exported as JSON or in the DOT format of Graphviz.
+
+use doc_cfg::doc_cfg;
+
+/// Static information about a [Classes](crate::Classes) type, emitted by
+/// [make_styles!](crate::make_styles)
+#[derive(Debug)]
+pub struct ClassesInfo {
+    /// Fields of the theme which are read, i.e. `palette.primary`, and called
+    /// methods of the theme, i.e. `spacing()`
+    pub theme_fields: &'static [&'static str],
+    /// Kinds of used at-rules, i.e. `@media`
+    pub at_rules: &'static [&'static str],
+    /// Type names of the referenced classes
+    pub dependencies: &'static [fn() -> &'static str],
+}
+
+impl ClassesInfo {
+    pub const EMPTY: ClassesInfo = ClassesInfo {
+        theme_fields: &[],
+        at_rules: &[],
+        dependencies: &[],
+    };
+}
+
+/// The mounted [Classes](crate::Classes) of a
+/// [StyleProvider](crate::StyleProvider), in the order in which they have
+/// been mounted
+///
+/// # Example
+/// ```
+/// # use css_in_rs::{make_styles, StyleProvider, Theme};
+/// #[derive(Clone, PartialEq)]
+/// struct MyTheme {
+///     primary: String,
+/// }
+///
+/// impl Theme for MyTheme {
+///     fn fast_cmp(&self, other: &Self) -> bool {
+///         self == other
+///     }
+/// }
+///
+/// make_styles! {
+///     (theme: MyTheme) -> ButtonClasses {
+///         button {
+///             color: theme.primary,
+///         },
+///         "@media print" {
+///             button {
+///                 color: "black",
+///             },
+///         },
+///     }
+/// }
+///
+/// let provider = StyleProvider::new_headless(MyTheme { primary: "red".to_owned() });
+/// provider.add_classes::<ButtonClasses>();
+///
+/// let graph = provider.style_graph();
+/// let node = &graph.nodes()[0];
+/// assert_eq!(node.theme_fields(), ["primary"]);
+/// assert_eq!(node.at_rules(), ["@media"]);
+/// assert!(graph.to_dot().contains("\"theme.primary\""));
+/// ```
+#[doc_cfg(feature = "graph")]
+pub struct StyleGraph {
+    nodes: Vec<StyleNode>,
+}
+
+/// A single [Classes](crate::Classes) type of a [StyleGraph]
+#[doc_cfg(feature = "graph")]
+pub struct StyleNode {
+    name: &'static str,
+    info: &'static ClassesInfo,
+}
+
+#[doc_cfg(feature = "graph")]
+impl StyleNode {
+    /// The type name of the classes
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn theme_fields(&self) -> &'static [&'static str] {
+        self.info.theme_fields
+    }
+
+    pub fn at_rules(&self) -> &'static [&'static str] {
+        self.info.at_rules
+    }
+
+    /// The type names of the referenced classes
+    pub fn dependencies(&self) -> Vec<&'static str> {
+        self.info.dependencies.iter().map(|name| name()).collect()
+    }
+}
+
+#[doc_cfg(feature = "graph")]
+impl StyleGraph {
+    pub(crate) fn new(
+        nodes: impl IntoIterator<Item = (&'static str, &'static ClassesInfo)>,
+    ) -> Self {
+        let nodes = nodes
+            .into_iter()
+            .map(|(name, info)| StyleNode { name, info })
+            .collect();
+        Self { nodes }
+    }
+
+    pub fn nodes(&self) -> &[StyleNode] {
+        &self.nodes
+    }
+
+    /// Serializes the graph as a JSON array of objects with the fields `name`,
+    /// `themeFields`, `atRules` and `dependencies`
+    pub fn to_json(&self) -> String {
+        use crate::extract::push_json_string;
+
+        let push_array = |json: &mut String, items: &[&str]| {
+            json.push('[');
+            for (idx, item) in items.iter().enumerate() {
+                if idx > 0 {
+                    json.push(',');
+                }
+                push_json_string(json, item);
+            }
+            json.push(']');
+        };
+
+        let mut json = String::from("[");
+        for (idx, node) in self.nodes.iter().enumerate() {
+            if idx > 0 {
+                json.push(',');
+            }
+            json.push_str("{\"name\":");
+            push_json_string(&mut json, node.name);
+            json.push_str(",\"themeFields\":");
+            push_array(&mut json, node.theme_fields());
+            json.push_str(",\"atRules\":");
+            push_array(&mut json, node.at_rules());
+            json.push_str(",\"dependencies\":");
+            push_array(&mut json, &node.dependencies());
+            json.push('}');
+        }
+        json.push(']');
+
+        json
+    }
+
+    /// Exports the graph in the DOT format of Graphviz. Classes are drawn as
+    /// ellipses, the theme fields they read as boxes. References between
+    /// classes are solid edges, reads of theme fields dashed ones.
+    pub fn to_dot(&self) -> String {
+        use core::fmt::Write;
+
+        let mut dot = String::from("digraph styles {\n");
+        for node in &self.nodes {
+            let mut label = node.name.to_owned();
+            if !node.at_rules().is_empty() {
+                label.push('\n');
+                label.push_str(&node.at_rules().join(" "));
+            }
+            let _ = writeln!(dot, "  {} [label={}];", quote(node.name), quote(&label));
+
+            for dependency in node.dependencies() {
+                let _ = writeln!(dot, "  {} -> {};", quote(node.name), quote(dependency));
+            }
+            for field in node.theme_fields() {
+                let field = quote(&format!("theme.{field}"));
+                let _ = writeln!(dot, "  {field} [shape=box];");
+                let _ = writeln!(dot, "  {} -> {field} [style=dashed];", quote(node.name));
+            }
+        }
+        dot.push_str("}\n");
+
+        dot
+    }
+}
+
+/// Quotes an identifier of the DOT format
+#[doc_cfg(feature = "graph")]
+fn quote(s: &str) -> String {
+    let s = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{s}\"")
+}
@@ -0,0 +1,37 @@
+use core::fmt::{Display, Formatter, Result};
+
+/// Keyframes defined by [keyframes!](crate::keyframes), which can be mounted
+/// independently of any [Classes](crate::Classes), see
+/// [StyleProvider::add_keyframes](crate::StyleProvider::add_keyframes). It
+/// displays as its name, so it can be used in value position of
+/// [make_styles!](crate::make_styles).
+#[derive(Clone, Copy)]
+pub struct Keyframes {
+    name: &'static str,
+    generate: fn(&mut String),
+}
+
+impl Keyframes {
+    #[doc(hidden)]
+    pub const fn new(name: &'static str, generate: fn(&mut String)) -> Self {
+        Self { name, generate }
+    }
+
+    /// The name of the keyframes, prefixed by the name of the crate
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The `@keyframes` rule
+    pub fn css(&self) -> String {
+        let mut css = String::new();
+        (self.generate)(&mut css);
+        css
+    }
+}
+
+impl Display for Keyframes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        f.write_str(self.name)
+    }
+}
@@ -64,132 +64,305 @@
 //!     }
 //! }
 //!
-//! fn Demo(cx: Scope) -> Element {
-//!     let classes: &MyClasses = MyClasses::use_style(cx);
+//! fn Demo() -> Element {
+//!     let classes: MyClasses = MyClasses::use_style();
 //!
-//!     render! {
+//!     rsx! {
 //!         div {
-//!             class: &classes.red_text as &str,
+//!             class: "{classes.red_text}",
 //!             "This text is supposed to be red.",
 //!         }
 //!         button {
-//!             class: &classes.primary as &str,
+//!             class: "{classes.primary}",
 //!             "Click me",
 //!         }
 //!     }
 //! }
 //!
-//! fn App(cx: Scope) -> Element {
-//!     use_style_provider_quickstart(cx, || EmptyTheme);
+//! fn App() -> Element {
+//!     use_style_provider_quickstart(|| EmptyTheme);
 //!
-//!     cx.render(rsx! {
+//!     rsx! {
 //!         Demo {}
-//!     })
+//!     }
 //! }
 //!
 //! fn main() {
 //!     // launch the web app
-//!     dioxus_web::launch(App);
+//!     dioxus::launch(App);
 //! }
 //! # }
 //! ```
+//!
+//! ## Without a browser
+//! Generating the styles does not need a DOM. Without the `web-sys` feature,
+//! which is disabled by default, the crate has no wasm-only dependencies, so
+//! servers, CLIs and tests can use it natively: [StyleProvider::new_headless]
+//! keeps the styles in memory, [SyncStyleProvider] shares them between
+//! threads and [extract] inlines them into HTML. The `web-sys` feature adds
+//! the backend which mounts the styles in a document, as well as the modules
+//! which watch the browser, like `color_scheme` and `motion`. The generation
+//! of the styles itself lives in [generation], which never depends on it.
 #![cfg_attr(feature = "unstable-doc-cfg", feature(doc_cfg))]
 
 #[doc_cfg(feature = "dioxus")]
 use dioxus::prelude::*;
 
+// The code generated by `make_styles!` refers to `::css_in_rs`, so the unit
+// tests can use it
+#[cfg(test)]
+extern crate self as css_in_rs;
+
+mod css;
+mod error;
+mod keyframes;
+mod profiling;
+mod raw_css;
+mod shared_names;
 mod style_provider;
+mod subscription;
+mod sync;
 
 pub mod backend;
+pub mod breakpoints;
+#[cfg(feature = "web-sys")]
+pub mod color_scheme;
+#[doc(hidden)]
+pub mod class_list;
+pub mod dark_mode;
+#[doc(hidden)]
+pub mod derive;
+pub mod direction;
+#[cfg(feature = "dioxus04")]
+pub mod dioxus_legacy;
+pub mod events;
+pub mod extract;
+pub mod fonts;
+pub mod forms;
+#[cfg(feature = "dioxus-fullstack")]
+pub mod fullstack;
+pub mod generation;
+pub mod graph;
+pub mod lookup;
+#[cfg(feature = "web-sys")]
+pub mod motion;
+pub mod overrides;
+pub mod prelude;
+pub mod sanitize;
+pub mod stats;
+pub mod stylesheet;
+#[doc(hidden)]
+pub mod template;
+pub mod testing;
+#[cfg(feature = "web-sys")]
+pub mod transition;
+pub mod values;
+#[cfg(feature = "web-sys")]
+pub mod web_components;
 
-pub use css_in_rs_macro::make_styles;
+pub use css_in_rs_macro::{define_styles_fragment, keyframes, make_styles, Theme};
+pub use css::CssFormat;
+pub use error::Error;
 use doc_cfg::doc_cfg;
-pub use style_provider::StyleProvider;
+pub use generation::{Classes, EmptyTheme, GenerateContext, Theme};
+pub use keyframes::Keyframes;
+pub use raw_css::ScopedClasses;
+pub use style_provider::{StyleProvider, StyleProviderBuilder};
+pub use subscription::Subscription;
+pub use sync::SyncStyleProvider;
 
-/// A trait for themes: Themes contain shared data which can be
-/// used in your styles.
-///
-/// For example, you can define color, paddings, and so on in a datatype.
-/// Alternatively, you can use css variables which is probably even better.
-/// However, themes are an easy way to precompute some things, for example
-/// complex box-shadows. They can also be changed at runtime which will
-/// update all styles which depend on this theme.
-///
-/// If you do not need theme support, you can use [EmptyTheme]  
-pub trait Theme: Clone + 'static {
-    fn fast_cmp(&self, other: &Self) -> bool;
-}
+/// The StyleProvider created by a hook. It is stored type-erased, so it
+/// survives changes of the theme type during hot reloading, see
+/// [Theme::SCHEMA].
+#[doc_cfg(feature = "dioxus")]
+type ProviderSlot = std::rc::Rc<core::cell::RefCell<Option<style_provider::ErasedProvider>>>;
 
-/// An empty theme. Use if no theme support is needed.
-///
-/// If you do need themes (i.e. certain data which is shared
-/// between all your styles), use a custom type and implement
-/// the [Theme] trait.
-#[derive(Clone, Copy)]
-pub struct EmptyTheme;
-
-impl Theme for EmptyTheme {
-    fn fast_cmp(&self, _: &Self) -> bool {
-        true
-    }
+/// Shares the [ProviderSlot] of a hook with the subtree. A rebuilt provider
+/// replaces the stale one in the slot, so the subtree finds it in the same
+/// context.
+#[doc_cfg(feature = "dioxus")]
+#[derive(Clone)]
+struct ProviderContext<T> {
+    slot: ProviderSlot,
+    /// The component which created the provider
+    scope: ScopeId,
+    _theme: core::marker::PhantomData<fn() -> T>,
 }
 
-/// This trait will be implemented by the classnames-struct generated
-/// by the [make_styles!] macro. You probably won't implement it yourself
-/// unless you need something very specific which the macro cannot handle.
-///
-/// Example
-/// ```
-/// # use css_in_rs::{Classes, EmptyTheme};
-/// struct MyClasses {
-///     active: String,
-///     disabled: String,
-/// }
-///
-/// impl Classes for MyClasses {
-///     type Theme = EmptyTheme;
-///
-///     fn generate(_: &Self::Theme, css: &mut String, counter: &mut u64) {
-///         use core::fmt::Write;
-///         writeln!(css, "css-{counter} {{ background-color: transparent; }}").unwrap();
-///         *counter += 1;
-///         writeln!(css, "css-{counter} {{ background-color: #f0f0f0; }}").unwrap();
-///         *counter += 1;
-///     }
-///
-///     fn new(start: u64) -> Self {
-///         MyClasses {
-///             active: format!("css-{}", start),
-///             disabled: format!("css-{}", start + 1),
-///         }
-///     }
-/// }
-/// ```
-pub trait Classes: Sized {
-    /// The [Theme] which this style depend on
-    type Theme: Theme;
-
-    fn generate(theme: &Self::Theme, css: &mut String, counter: &mut u64);
-    fn new(start: u64) -> Self;
-
-    #[doc_cfg(feature = "dioxus")]
-    fn use_style() -> Self {
-        let provider = use_style_provider();
-        provider.use_styles()
+/// Returns the StyleProvider of the current component and provides it to the
+/// subtree. It is built on the first render, and again if the theme type has
+/// changed since. A rebuilt provider gets a classname prefix of its own, since
+/// the styles mounted by the stale one stay in the document. Returns `None`
+/// if `build` does.
+#[doc_cfg(feature = "dioxus")]
+fn use_provider<T: Theme>(
+    build: impl FnOnce(StyleProviderBuilder<T>) -> Option<StyleProvider<T>>,
+) -> Option<StyleProvider<T>> {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    static REBUILDS: AtomicUsize = AtomicUsize::new(0);
+
+    let slot = use_hook(ProviderSlot::default);
+    let previous = slot.borrow().as_ref().map(|erased| erased.get::<T>());
+
+    let mut builder = StyleProvider::builder();
+    match previous {
+        Some(Some(provider)) => return Some(provider),
+        Some(None) => {
+            let rebuild = REBUILDS.fetch_add(1, Ordering::Relaxed);
+            builder = builder.prefix(format!("{}-r{rebuild}", style_provider::DEFAULT_PREFIX));
+        }
+        None => {}
     }
+
+    let provider = build(builder)?;
+    // Drops the stale provider, if any
+    *slot.borrow_mut() = Some(style_provider::ErasedProvider::new(provider.clone()));
+    provide_context(ProviderContext::<T> {
+        slot,
+        scope: current_scope_id().expect("Hooks are called while rendering"),
+        _theme: core::marker::PhantomData,
+    });
+    Some(provider)
 }
 
 /// Quickly sets up a StyleProvider in the global document. Styles will be attached
 /// to `window.document.head`
 #[doc_cfg(feature = "dioxus")]
-pub fn use_style_provider_quickstart<'a, T: Theme>(
-    make_theme: impl FnOnce() -> T,
+pub fn use_style_provider_quickstart<T: Theme>(make_theme: impl FnOnce() -> T) -> StyleProvider<T> {
+    use_provider(|builder| Some(builder.build_web(make_theme()))).unwrap()
+}
+
+/// Sets up a StyleProvider which mounts its styles through the document API of
+/// the active Dioxus renderer, see [DocumentBackend](backend::document::DocumentBackend).
+/// In contrast to [use_style_provider_quickstart], this also works on desktop,
+/// mobile and LiveView.
+#[doc_cfg(feature = "dioxus")]
+pub fn use_style_provider_document<T: Theme>(make_theme: impl FnOnce() -> T) -> StyleProvider<T> {
+    use_provider(|builder| {
+        let backend = backend::document::DocumentBackend::current();
+        Some(builder.build_with_backend(backend, make_theme()))
+    })
+    .unwrap()
+}
+
+/// Sets up a StyleProvider for the subtree of the current component, with a
+/// theme derived from the surrounding one, like a nested `ThemeProvider` in
+/// MUI. Components in the subtree get classnames of their own, so their styles
+/// only apply within the subtree. See [StyleProvider::scoped].
+///
+/// # Panics
+/// Panics if there is no surrounding provider, or while it is rebuilt, see
+/// [try_use_style_provider_scoped]
+#[doc_cfg(feature = "dioxus")]
+pub fn use_style_provider_scoped<T: Theme>(
+    derive_theme: impl Fn(&T) -> T + 'static,
 ) -> StyleProvider<T> {
-    let provider = use_hook(|| StyleProvider::quickstart_web(make_theme()));
-    use_context_provider(|| provider.clone())
+    try_use_style_provider_scoped(derive_theme).unwrap_or_else(|| rebuilding_panic::<T>())
 }
 
+/// Like [use_style_provider_scoped], but returns `None` while the surrounding
+/// provider is rebuilt, see [try_use_style_provider]
+#[doc_cfg(feature = "dioxus")]
+pub fn try_use_style_provider_scoped<T: Theme>(
+    derive_theme: impl Fn(&T) -> T + 'static,
+) -> Option<StyleProvider<T>> {
+    let parent = try_use_style_provider::<T>();
+    // The prefix is derived from the parent, which has been rebuilt as well
+    use_provider(|_| parent.map(|parent| parent.scoped(derive_theme)))
+}
+
+/// Switches the theme of the surrounding StyleProvider between `light` and
+/// `dark`, following the color scheme preferred by the operating system. See
+/// [ColorSchemeWatcher](color_scheme::ColorSchemeWatcher). Returns whether the
+/// dark theme was active while rendering; without a window, the theme is left
+/// untouched and `false` is returned. The same goes for the renders while the
+/// provider is rebuilt, see [try_use_style_provider].
+#[doc_cfg(feature = "dioxus")]
+pub fn use_system_theme<T: Theme>(light: impl FnOnce() -> T, dark: impl FnOnce() -> T) -> bool {
+    type WatcherSlot =
+        std::rc::Rc<core::cell::RefCell<Option<(usize, Option<color_scheme::ColorSchemeWatcher>)>>>;
+
+    let provider = try_use_style_provider::<T>();
+    let slot = use_hook(WatcherSlot::default);
+    let Some(provider) = provider else {
+        return false;
+    };
+    let mut slot = slot.borrow_mut();
+
+    // Watch again if the provider has been rebuilt, see [use_provider]
+    if slot.as_ref().is_none_or(|(id, _)| *id != provider.id()) {
+        let watcher = color_scheme::ColorSchemeWatcher::new(&provider, light(), dark());
+        *slot = Some((provider.id(), watcher));
+    }
+
+    let (_, watcher) = slot.as_ref().unwrap();
+    watcher.as_ref().is_some_and(|watcher| watcher.is_dark())
+}
+
+/// Returns whether the user prefers reduced motion, see [motion]. The
+/// component is rendered again whenever the preference changes. Without a
+/// window, `false` is returned.
+#[doc_cfg(feature = "dioxus")]
+pub fn use_reduced_motion() -> bool {
+    let mut reduce = use_signal(motion::prefers_reduced_motion);
+    use_hook(|| {
+        let watcher = motion::ReducedMotionWatcher::new(move |value| reduce.set(value));
+        std::rc::Rc::new(watcher)
+    });
+    reduce()
+}
+
+/// Returns whether the fonts used by the document have been loaded, see
+/// [fonts::on_fonts_ready]. The component is rendered again once they are.
+/// Without support for `document.fonts`, `true` is returned.
+#[doc_cfg(feature = "dioxus")]
+pub fn use_fonts_ready() -> bool {
+    let mut ready = use_signal(|| false);
+    let supported = use_hook(|| fonts::on_fonts_ready(move || ready.set(true)));
+    ready() || !supported
+}
+
+/// Returns the surrounding StyleProvider
+///
+/// # Panics
+/// Panics if there is none, or while it is rebuilt, see [try_use_style_provider]
 #[doc_cfg(feature = "dioxus")]
 pub fn use_style_provider<T: Theme>() -> StyleProvider<T> {
-    use_context()
+    try_use_style_provider().unwrap_or_else(|| rebuilding_panic::<T>())
+}
+
+#[cfg(feature = "dioxus")]
+fn rebuilding_panic<T>() -> ! {
+    panic!(
+        "The StyleProvider<{}> is rebuilt after hot reloading. Use try_use_style_provider instead",
+        core::any::type_name::<T>()
+    )
+}
+
+/// Like [use_style_provider], but returns `None` while the provider is
+/// rebuilt: If the theme type has changed during hot reloading, the component
+/// which created the provider replaces it on its next render. Both that
+/// component and the current one are rendered again. [Classes::use_style] and
+/// [use_system_theme] use it, so they keep working during hot reloading.
+///
+/// # Panics
+/// Panics if there is none
+#[doc_cfg(feature = "dioxus")]
+pub fn try_use_style_provider<T: Theme>() -> Option<StyleProvider<T>> {
+    // Looked up again on each render instead of caching it with `use_hook`,
+    // which would panic as soon as the theme type changes during hot reloading
+    let Some(context) = try_consume_context::<ProviderContext<T>>() else {
+        panic!(
+            "There is no StyleProvider<{}>. Set one up using use_style_provider_quickstart",
+            core::any::type_name::<T>()
+        );
+    };
+
+    let provider = context.slot.borrow().as_ref().and_then(|erased| erased.get());
+    if provider.is_none() {
+        schedule_update_any()(context.scope);
+        schedule_update()();
+    }
+    provider
 }
@@ -0,0 +1,68 @@
+//! Maps generated classnames back to their definition, i.e. for a debugging
+//! overlay or a console helper answering "where did `css-42` come from?".
+//! See [StyleProvider::lookup].
+//!
+//! # Example
+//! ```
+//! # use css_in_rs::{make_styles, EmptyTheme, StyleProvider};
+//! make_styles! {
+//!     (_theme: EmptyTheme) -> MyClasses {
+//!         button {
+//!             color: "red",
+//!         },
+//!         icon {
+//!             width: "16px",
+//!         },
+//!     }
+//! }
+//!
+//! let style_provider = StyleProvider::new_headless(EmptyTheme);
+//! let classes = style_provider.add_classes::<MyClasses>();
+//!
+//! let info = style_provider.lookup(&classes.icon).unwrap();
+//! assert!(info.classes.ends_with("MyClasses"));
+//! assert_eq!(info.field, "icon");
+//! assert!(info.location.unwrap().file.ends_with(".rs"));
+//! assert_eq!(style_provider.lookup("css-99"), None);
+//! ```
+
+use core::fmt::{Display, Formatter, Result};
+
+#[cfg(doc)]
+use crate::StyleProvider;
+
+/// A position in the source code, as given by `file!()`, `line!()` and `column!()`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SourceLocation {
+    pub file: &'static str,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+/// Where a generated classname comes from, see [StyleProvider::lookup]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RuleInfo {
+    /// The type name of the [Classes](crate::Classes)
+    pub classes: &'static str,
+    /// The name of the class as written in [make_styles!](crate::make_styles)
+    pub field: &'static str,
+    /// Where the class is defined. `None` for [Classes](crate::Classes) which
+    /// have not been created by [make_styles!](crate::make_styles).
+    pub location: Option<SourceLocation>,
+}
+
+impl Display for RuleInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}::{}", self.classes, self.field)?;
+        if let Some(location) = &self.location {
+            write!(f, " @ {location}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+//! Following the preference of the user for reduced motion
+//! (`prefers-reduced-motion`). In [make_styles!](crate::make_styles),
+//! animations are usually restricted using `"@motion-safe" { ... }` or
+//! disabled using `"@motion-reduce" { ... }`. If the preference is needed at
+//! runtime as well, i.e. to skip a scroll animation, use a
+//! [ReducedMotionWatcher].
+
+use wasm_bindgen::{closure::Closure, JsCast};
+
+use crate::backend::web::active_window;
+
+const REDUCE_QUERY: &str = "(prefers-reduced-motion: reduce)";
+
+/// Whether the user currently prefers reduced motion. `false` if there is no
+/// window supporting `matchMedia`, i.e. in a web worker or when running natively.
+pub fn prefers_reduced_motion() -> bool {
+    active_window()
+        .and_then(|window| window.match_media(REDUCE_QUERY).ok().flatten())
+        .is_some_and(|query| query.matches())
+}
+
+/// Calls a function whenever the preference for reduced motion changes. The
+/// watcher stops when it is dropped.
+///
+/// # Example
+/// ```no_run
+/// # use css_in_rs::motion::ReducedMotionWatcher;
+/// let watcher = ReducedMotionWatcher::new(|reduce| {
+///     println!("Reduced motion is now {}", if reduce { "on" } else { "off" });
+/// })
+/// .unwrap();
+/// let animate = !watcher.prefers_reduced_motion();
+/// ```
+pub struct ReducedMotionWatcher {
+    query: web_sys::MediaQueryList,
+    listener: Closure<dyn FnMut(web_sys::MediaQueryListEvent)>,
+}
+
+impl ReducedMotionWatcher {
+    /// Starts watching the preference; `on_change` is not called for the
+    /// current one. Returns `None` if there is no window supporting
+    /// `matchMedia`, i.e. in a web worker or when running natively.
+    pub fn new(mut on_change: impl FnMut(bool) + 'static) -> Option<Self> {
+        let query = active_window()?.match_media(REDUCE_QUERY).ok()??;
+
+        let listener = Closure::<dyn FnMut(_)>::new(move |event: web_sys::MediaQueryListEvent| {
+            on_change(event.matches());
+        });
+        query
+            .add_event_listener_with_callback("change", listener.as_ref().unchecked_ref())
+            .ok()?;
+
+        Some(Self { query, listener })
+    }
+
+    /// Whether reduced motion is currently preferred
+    pub fn prefers_reduced_motion(&self) -> bool {
+        self.query.matches()
+    }
+}
+
+impl Drop for ReducedMotionWatcher {
+    fn drop(&mut self) {
+        let _ = self
+            .query
+            .remove_event_listener_with_callback("change", self.listener.as_ref().unchecked_ref());
+    }
+}
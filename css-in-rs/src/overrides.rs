@@ -0,0 +1,220 @@
+//! Overrides of the styles of library components by the theme, like the
+//! `components` key of MUI themes. Libraries opt in using the
+//! `#[overrides("Key")]` option of [make_styles!](crate::make_styles). The
+//! overriding declarations are appended to every rule selecting exactly one
+//! class (like `root { ... }`), so they win over the declarations of the
+//! library. Apps can adjust library components this way without forking
+//! their styles.
+//!
+//! # Example
+//! ```
+//! # use css_in_rs::{make_styles, testing::render_classes_to_string, Theme};
+//! use css_in_rs::overrides::{ComponentOverrides, OverridableTheme};
+//!
+//! #[derive(Clone, PartialEq)]
+//! struct MyTheme {
+//!     components: ComponentOverrides,
+//! }
+//!
+//! impl Theme for MyTheme {
+//!     fn fast_cmp(&self, other: &Self) -> bool {
+//!         self == other
+//!     }
+//! }
+//!
+//! impl OverridableTheme for MyTheme {
+//!     fn components(&self) -> &ComponentOverrides {
+//!         &self.components
+//!     }
+//! }
+//!
+//! // Part of a component library
+//! make_styles! {
+//!     #[overrides("Button")]
+//!     (_theme: MyTheme) -> ButtonClasses {
+//!         root {
+//!             padding: "4px",
+//!             border_radius: "4px",
+//!         },
+//!     }
+//! }
+//!
+//! // Part of the app
+//! let theme = MyTheme {
+//!     components: ComponentOverrides::new().set("Button", "root", "border_radius", "0"),
+//! };
+//! let css = render_classes_to_string::<ButtonClasses>(&theme);
+//! assert_eq!(
+//!     css,
+//!     ".css-0 {\n  padding: 4px;\n  border-radius: 4px;\n  border-radius: 0;\n}\n"
+//! );
+//! ```
+
+use core::fmt::{Display, Formatter, Result};
+use std::collections::BTreeMap;
+
+use crate::{sanitize::SanitizePolicy, Theme};
+
+/// Themes which can override the styles of components, see the
+/// [module documentation](self)
+pub trait OverridableTheme: Theme {
+    fn components(&self) -> &ComponentOverrides;
+}
+
+/// Declarations overriding the classes of components, by the key of the
+/// component (given in `#[overrides(...)]`) and the name of the class
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ComponentOverrides {
+    components: BTreeMap<String, BTreeMap<String, Vec<(String, String)>>>,
+}
+
+impl ComponentOverrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a declaration to the class `class` of the component `component`.
+    /// Like in [make_styles!](crate::make_styles), underscores in the
+    /// property become dashes, except for custom properties. The value is
+    /// checked like other interpolated values, see [sanitize](crate::sanitize).
+    ///
+    /// # Panics
+    /// Panics if the property is not a valid property name, i.e. if it is
+    /// empty or contains anything but ASCII letters, digits, `-` and `_`
+    pub fn set(
+        mut self,
+        component: impl Into<String>,
+        class: impl Into<String>,
+        property: &str,
+        value: impl Display,
+    ) -> Self {
+        let is_name = |ch: char| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_';
+        assert!(
+            !property.is_empty() && property.chars().all(is_name),
+            "Invalid property name: {property:?}"
+        );
+        let property = match property.starts_with("--") {
+            true => property.to_owned(),
+            false => property.replace('_', "-"),
+        };
+
+        self.components
+            .entry(component.into())
+            .or_default()
+            .entry(class.into())
+            .or_default()
+            .push((property, value.to_string()));
+        self
+    }
+
+    /// The overriding declarations of the given class, formatted like the
+    /// declarations generated by [make_styles!](crate::make_styles). Unsafe
+    /// values are escaped unless another policy is set using
+    /// [Declarations::sanitize].
+    pub fn declarations(&self, component: &str, class: &str) -> Declarations<'_> {
+        let declarations = self
+            .components
+            .get(component)
+            .and_then(|classes| classes.get(class))
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        Declarations {
+            declarations,
+            policy: SanitizePolicy::default(),
+        }
+    }
+}
+
+/// See [ComponentOverrides::declarations]
+pub struct Declarations<'a> {
+    declarations: &'a [(String, String)],
+    policy: SanitizePolicy,
+}
+
+impl Declarations<'_> {
+    /// Sets how the values are checked, usually the policy of the provider
+    pub fn sanitize(self, policy: SanitizePolicy) -> Self {
+        Self { policy, ..self }
+    }
+}
+
+impl Display for Declarations<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        for (property, value) in self.declarations {
+            let value = crate::sanitize::interpolate(value, self.policy);
+            writeln!(f, "  {property}: {value};")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ComponentOverrides, OverridableTheme};
+    use crate::{make_styles, sanitize::SanitizePolicy, CssFormat, StyleProvider, Theme};
+
+    #[derive(Clone, PartialEq)]
+    struct MyTheme {
+        color: String,
+        components: ComponentOverrides,
+    }
+
+    impl Theme for MyTheme {
+        fn fast_cmp(&self, other: &Self) -> bool {
+            self == other
+        }
+    }
+
+    impl OverridableTheme for MyTheme {
+        fn components(&self) -> &ComponentOverrides {
+            &self.components
+        }
+    }
+
+    make_styles! {
+        #[overrides("Button")]
+        (theme: MyTheme) -> ButtonClasses {
+            root {
+                background: theme.color,
+            },
+        }
+    }
+
+    #[test]
+    fn sanitized_values() {
+        let theme = MyTheme {
+            color: "blue; } html { x: y".to_owned(),
+            components: ComponentOverrides::new().set(
+                "Button",
+                "root",
+                "color",
+                "red; } body { display: none",
+            ),
+        };
+        let provider = StyleProvider::builder()
+            .format(CssFormat::Minified)
+            .build_headless(theme.clone());
+        provider.add_classes::<ButtonClasses>();
+        assert_eq!(
+            provider.current_css(),
+            r".css-0{background:blue\; \} html \{ x: y;color:red\; \} body \{ display: none}"
+        );
+
+        let provider = StyleProvider::builder()
+            .format(CssFormat::Minified)
+            .sanitize(SanitizePolicy::Trust)
+            .build_headless(theme);
+        provider.add_classes::<ButtonClasses>();
+        // The injected rules are trusted
+        assert_eq!(
+            provider.current_css(),
+            ".css-0{background:blue}html{x:y;color:red}body{display:none}"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid property name")]
+    fn invalid_property() {
+        let _ = ComponentOverrides::new().set("Button", "root", "color: red; x", "y");
+    }
+}
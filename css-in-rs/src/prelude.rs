@@ -0,0 +1,36 @@
+//! The commonly used items, so components need a single import line. The
+//! hooks are included if the `dioxus` feature is enabled.
+//!
+//! # Example
+//! ```
+//! use css_in_rs::prelude::*;
+//!
+//! make_styles! {
+//!     (_theme: EmptyTheme) -> MyClasses {
+//!         button {
+//!             padding: "5px",
+//!         },
+//!         active {
+//!             color: "blue",
+//!         },
+//!     }
+//! }
+//!
+//! let style_provider = StyleProvider::new_headless(EmptyTheme);
+//! let cls = style_provider.add_classes::<MyClasses>();
+//! assert_eq!(classes![cls.button, true => cls.active], "css-1 css-0");
+//! ```
+
+pub use crate::{
+    classes, keyframes, make_styles, shared_names, Classes, CssFormat, EmptyTheme, Keyframes,
+    StyleProvider, Theme,
+};
+
+#[cfg(feature = "web-sys")]
+pub use crate::backend::web::NoDocument;
+#[cfg(feature = "dioxus")]
+pub use crate::{
+    try_use_style_provider, try_use_style_provider_scoped, use_style_provider,
+    use_style_provider_document, use_style_provider_quickstart, use_style_provider_scoped,
+    use_system_theme,
+};
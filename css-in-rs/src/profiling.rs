@@ -0,0 +1,97 @@
+//! Integration with the browser's Performance timeline. Measurements show up
+//! in the Performance panel of the devtools when profiling an app.
+
+use core::time::Duration;
+
+/// Records a `performance.measure` entry with the given name, spanning from
+/// the creation of this guard until it is dropped. Without the `web-sys`
+/// feature or without access to `window.performance`, this is a no-op.
+pub struct Measurement {
+    #[cfg(feature = "web-sys")]
+    inner: Option<(web_sys::Performance, String)>,
+}
+
+impl Measurement {
+    pub fn start(name: impl Into<String>) -> Self {
+        #[cfg(feature = "web-sys")]
+        {
+            let name = name.into();
+            let performance = web_sys::window().and_then(|window| window.performance());
+            let inner = performance.map(|performance| {
+                let _ = performance.mark(&format!("{name} (start)"));
+                (performance, name)
+            });
+
+            Self { inner }
+        }
+
+        #[cfg(not(feature = "web-sys"))]
+        {
+            let _ = name;
+            Self {}
+        }
+    }
+}
+
+#[cfg(feature = "web-sys")]
+impl Drop for Measurement {
+    fn drop(&mut self) {
+        if let Some((performance, name)) = &self.inner {
+            let start = format!("{name} (start)");
+            let end = format!("{name} (end)");
+            let _ = performance.mark(&end);
+            let _ = performance.measure_with_start_mark_and_end_mark(name, &start, &end);
+            performance.clear_marks_with_mark_name(&start);
+            performance.clear_marks_with_mark_name(&end);
+        }
+    }
+}
+
+/// Measures the time elapsed since its creation. Uses `performance.now()`
+/// in the browser, since [std::time::Instant] is not available there.
+pub(crate) struct Stopwatch {
+    #[cfg(not(target_arch = "wasm32"))]
+    start: std::time::Instant,
+    #[cfg(target_arch = "wasm32")]
+    start: Option<f64>,
+}
+
+impl Stopwatch {
+    pub fn start() -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            start: std::time::Instant::now(),
+            #[cfg(target_arch = "wasm32")]
+            start: now(),
+        }
+    }
+
+    /// The elapsed time, or `None` if there is no clock
+    pub fn elapsed(&self) -> Option<Duration> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            Some(self.start.elapsed())
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let millis = now()? - self.start?;
+            Some(Duration::from_secs_f64(millis.max(0.0) / 1000.0))
+        }
+    }
+}
+
+/// `performance.now()` in milliseconds
+#[cfg(target_arch = "wasm32")]
+fn now() -> Option<f64> {
+    #[cfg(feature = "web-sys")]
+    {
+        let performance = web_sys::window()?.performance()?;
+        Some(performance.now())
+    }
+
+    #[cfg(not(feature = "web-sys"))]
+    {
+        None
+    }
+}
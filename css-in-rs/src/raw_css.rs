@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+
+use crate::css::{parse, Node};
+#[cfg(doc)]
+use crate::StyleProvider;
+
+/// The classnames of a stylesheet mounted by [StyleProvider::add_raw_css],
+/// by the classname used in the stylesheet
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScopedClasses {
+    classes: BTreeMap<String, String>,
+}
+
+impl ScopedClasses {
+    /// Returns the generated classname for a class of the stylesheet, i.e.
+    /// `css-3` for `card`
+    pub fn get(&self, class: &str) -> Option<&str> {
+        self.classes.get(class).map(String::as_str)
+    }
+
+    /// Iterates the classes of the stylesheet and their generated classnames
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.classes
+            .iter()
+            .map(|(class, classname)| (class.as_str(), classname.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.classes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+    }
+}
+
+/// Replaces the classes in the selectors of `src` by generated classnames,
+/// numbered from `start`. The preludes of at-rules and the declarations are
+/// kept as they are.
+pub(crate) fn scope(src: &str, prefix: &str, start: u64) -> (String, ScopedClasses) {
+    let mut classes = BTreeMap::<String, String>::new();
+    let css = scope_with(src, |class| {
+        let next = start + classes.len() as u64;
+        let classname = classes
+            .entry(class.to_owned())
+            .or_insert_with(|| format!("{prefix}-{next}"));
+        Some(classname.clone())
+    });
+    (css, ScopedClasses { classes })
+}
+
+/// Like [scope], but the classnames are given by `classname`. Classes for
+/// which it returns `None` are kept as they are.
+pub(crate) fn scope_with(src: &str, mut classname: impl FnMut(&str) -> Option<String>) -> String {
+    let mut scoper = Scoper {
+        classname: &mut classname,
+        css: String::with_capacity(src.len()),
+    };
+    scoper.write_nodes(&parse(src));
+    scoper.css
+}
+
+struct Scoper<'a> {
+    classname: &'a mut dyn FnMut(&str) -> Option<String>,
+    css: String,
+}
+
+impl Scoper<'_> {
+    fn write_nodes(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            match node {
+                Node::Block { prelude, children } => {
+                    match prelude.starts_with('@') {
+                        true => self.css.push_str(prelude),
+                        false => self.write_selector(prelude),
+                    }
+                    self.css.push_str(" {\n");
+                    self.write_nodes(children);
+                    self.css.push_str("}\n");
+                }
+                Node::Declaration { property, value } => {
+                    self.css.push_str(&format!("  {property}: {value};\n"));
+                }
+                Node::Statement(statement) => {
+                    self.css.push_str(&format!("{statement};\n"));
+                }
+                Node::Comment(_) => {}
+            }
+        }
+    }
+
+    fn write_selector(&mut self, mut rest: &str) {
+        while let Some(ch) = rest.chars().next() {
+            let len = match ch {
+                '"' | '\'' => string_len(rest, ch),
+                '[' => rest.find(']').map_or(rest.len(), |idx| idx + 1),
+                '.' => {
+                    let class = &rest[1..];
+                    let class =
+                        &class[..class.find(|ch| !is_ident_char(ch)).unwrap_or(class.len())];
+                    if !class.is_empty() && !class.starts_with(|ch: char| ch.is_ascii_digit()) {
+                        let classname = (self.classname)(class);
+                        self.css.push('.');
+                        self.css.push_str(classname.as_deref().unwrap_or(class));
+                        rest = &rest[1 + class.len()..];
+                        continue;
+                    }
+                    1
+                }
+                _ => ch.len_utf8(),
+            };
+            self.css.push_str(&rest[..len]);
+            rest = &rest[len..];
+        }
+    }
+}
+
+fn is_ident_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' || !ch.is_ascii()
+}
+
+/// The length of the string at the start of `src`, including the quotes
+fn string_len(src: &str, quote: char) -> usize {
+    let mut chars = src.char_indices().skip(1);
+    while let Some((idx, ch)) = chars.next() {
+        match ch {
+            '\\' => {
+                chars.next();
+            }
+            ch if ch == quote => return idx + 1,
+            _ => {}
+        }
+    }
+    src.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::scope;
+
+    #[test]
+    fn scope_selectors() {
+        let src = "/* cms */ .card, .card > a[href$=\".pdf\"] { color: red; margin: .5em }\n\
+                   @media (min-width: 10.5em) { .card.active:hover { opacity: 0.5 } }";
+        let (css, classes) = scope(src, "css", 3);
+        assert_eq!(
+            css,
+            ".css-3, .css-3 > a[href$=\".pdf\"] {\n  color: red;\n  margin: .5em;\n}\n\
+             @media (min-width: 10.5em) {\n.css-3.css-4:hover {\n  opacity: 0.5;\n}\n}\n"
+        );
+        assert_eq!(classes.get("card"), Some("css-3"));
+        assert_eq!(classes.get("active"), Some("css-4"));
+        assert_eq!(classes.len(), 2);
+    }
+}
@@ -0,0 +1,215 @@
+//! Checks of values which are interpolated into the generated CSS. A value
+//! like `red } body { display: none` (i.e. from a theme which the user can
+//! edit) would otherwise end the rule early and inject its own rules. See
+//! [StyleProviderBuilder::sanitize].
+//!
+//! A value is unsafe if it contains `{`, `}`, `;` or `/*` outside of quotes,
+//! an unterminated string, or `</` which would end a `<style>` element.
+//! Literal values of [make_styles!](crate::make_styles) are not checked, but
+//! the ones of [overrides](crate::overrides) are.
+//!
+//! # Example
+//! ```
+//! # use css_in_rs::{make_styles, CssFormat, StyleProvider, Theme};
+//! #[derive(Clone, PartialEq)]
+//! struct MyTheme {
+//!     accent: String,
+//! }
+//!
+//! impl Theme for MyTheme {
+//!     fn fast_cmp(&self, other: &Self) -> bool {
+//!         self == other
+//!     }
+//! }
+//!
+//! make_styles! {
+//!     (theme: MyTheme) -> MyClasses {
+//!         link {
+//!             color: theme.accent,
+//!             content: "\"}\"",
+//!         },
+//!     }
+//! }
+//!
+//! let theme = MyTheme {
+//!     accent: "red } body { display: none".to_owned(),
+//! };
+//! let style_provider = StyleProvider::builder()
+//!     .format(CssFormat::Minified)
+//!     .build_headless(theme);
+//! style_provider.add_classes::<MyClasses>();
+//! assert_eq!(
+//!     style_provider.current_css(),
+//!     r#".css-0{color:red \} body \{ display: none;content:"}"}"#
+//! );
+//! ```
+
+use core::{
+    cell::RefCell,
+    fmt::{Display, Formatter, Result},
+};
+
+#[cfg(doc)]
+use crate::{Error, StyleProvider, StyleProviderBuilder};
+
+/// What to do with unsafe interpolated values, see the [module documentation](self)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SanitizePolicy {
+    /// Escapes the offending characters, so the value cannot break out of its
+    /// declaration or prelude. The declaration is usually invalid afterwards
+    /// and ignored by the browser.
+    #[default]
+    Sanitize,
+    /// Escapes unsafe values like [SanitizePolicy::Sanitize], and reports them
+    /// as [Error::UnsafeValue] from [StyleProvider::try_update_theme] and
+    /// [StyleProvider::try_add_classes]. Useful in tests and if the values are
+    /// trusted, so an unsafe value is a bug.
+    Reject,
+    /// Uses the values as they are, i.e. to intentionally interpolate several
+    /// declarations at once
+    Trust,
+}
+
+#[doc(hidden)]
+pub fn interpolate(value: &dyn Display, policy: SanitizePolicy) -> Interpolated<'_> {
+    Interpolated { value, policy }
+}
+
+/// An interpolated value, which is checked when it is displayed
+#[doc(hidden)]
+pub struct Interpolated<'a> {
+    value: &'a dyn Display,
+    policy: SanitizePolicy,
+}
+
+impl Display for Interpolated<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.policy == SanitizePolicy::Trust {
+            return self.value.fmt(f);
+        }
+
+        let value = self.value.to_string();
+        let sanitized = sanitize(&value);
+        if self.policy == SanitizePolicy::Reject && sanitized != value {
+            REJECTED.with_borrow_mut(|rejected| {
+                rejected.get_or_insert(value);
+            });
+        }
+        f.write_str(&sanitized)
+    }
+}
+
+thread_local! {
+    /// The first value rejected by [SanitizePolicy::Reject] since the last
+    /// call of [take_rejected]
+    static REJECTED: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Returns the first value rejected since the last call, and forgets it
+pub(crate) fn take_rejected() -> Option<String> {
+    REJECTED.take()
+}
+
+/// Escapes everything which could end the surrounding declaration or prelude
+pub fn sanitize(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut quote = None;
+    let mut chars = value.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match (ch, quote) {
+            ('\\', _) => {
+                // A trailing backslash would escape the following character
+                if let Some(next) = chars.next() {
+                    result.push(ch);
+                    result.push(next);
+                }
+            }
+            ('<', _) if chars.peek() == Some(&'/') => result.push_str("\\3c "),
+            ('"' | '\'', None) => {
+                quote = Some(ch);
+                result.push(ch);
+            }
+            (ch, Some(q)) if ch == q => {
+                quote = None;
+                result.push(ch);
+            }
+            ('\n', Some(_)) => result.push_str("\\a "),
+            ('{' | '}' | ';', None) => {
+                result.push('\\');
+                result.push(ch);
+            }
+            ('/', None) if chars.peek() == Some(&'*') => result.push_str("\\/"),
+            _ => result.push(ch),
+        }
+    }
+
+    // Terminates an unterminated string
+    result.extend(quote);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sanitize, SanitizePolicy};
+    use crate::{CssFormat, Error, StyleProvider};
+
+    #[derive(Clone, PartialEq, crate::Theme)]
+    struct Accent(&'static str);
+
+    crate::make_styles! {
+        (theme: Accent) -> LinkClasses {
+            link {
+                color: theme.0,
+            },
+        }
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(sanitize("1px solid red"), "1px solid red");
+        assert_eq!(sanitize("\"a;b}\""), "\"a;b}\"");
+        assert_eq!(sanitize("red;} a{"), "red\\;\\} a\\{");
+        assert_eq!(sanitize("\"open"), "\"open\"");
+        assert_eq!(sanitize("url(a) /* x"), "url(a) \\/* x");
+        assert_eq!(sanitize("\"</style>\""), "\"\\3c /style>\"");
+        assert_eq!(sanitize("red\\"), "red");
+    }
+
+    #[test]
+    fn rejected() {
+        let unsafe_value = || Err(Error::UnsafeValue("red;".to_owned()));
+        let provider = StyleProvider::builder()
+            .format(CssFormat::Minified)
+            .sanitize(SanitizePolicy::Reject)
+            .build_headless(Accent("red;"));
+
+        // Mounted anyway, but escaped
+        assert_eq!(
+            provider.try_add_classes::<LinkClasses>().map(|_| ()),
+            unsafe_value()
+        );
+        assert_eq!(provider.current_css(), ".css-0{color:red\\;}");
+        assert!(provider.try_add_classes::<LinkClasses>().is_ok());
+
+        assert_eq!(provider.try_update_theme(Accent("blue")), Ok(()));
+        assert_eq!(provider.current_css(), ".css-0{color:blue}");
+
+        // The theme of a scoped provider is checked as well
+        let scoped = provider.scoped(|theme| {
+            if theme.0 == "green" {
+                Accent("red;")
+            } else {
+                theme.clone()
+            }
+        });
+        scoped.add_classes::<LinkClasses>();
+        assert_eq!(provider.try_update_theme(Accent("green")), unsafe_value());
+        assert_eq!(provider.current_css(), ".css-0{color:blue}");
+        assert_eq!(scoped.current_css(), ".css-s0-0{color:blue}");
+
+        // Without `try_`, the value is escaped
+        provider.update_theme(Accent("green"));
+        assert_eq!(scoped.current_css(), ".css-s0-0{color:red\\;}");
+    }
+}
@@ -0,0 +1,53 @@
+/// Declares identifiers which are shared by multiple [make_styles!](crate::make_styles)
+/// invocations, like the names of keyframes or counters. Every name becomes a
+/// `&'static str` constant, prefixed by the name of the crate, so it will not
+/// clash with names declared by other crates. Declare each name only once per
+/// crate, i.e. in a single module of identifiers.
+///
+/// Keyframes are defined using `@keyframes(NAME) { ... }`. The name can be
+/// referenced in any value.
+///
+/// # Example
+/// ```
+/// # use css_in_rs::{make_styles, shared_names, testing::render_classes_to_string, EmptyTheme};
+/// mod names {
+///     css_in_rs::shared_names! {
+///         /// Used by all loading indicators
+///         pub PULSE = "pulse";
+///     }
+/// }
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> SpinnerClasses {
+///         @keyframes(names::PULSE) {
+///             "from" {
+///                 opacity: "0",
+///             },
+///         },
+///         spinner {
+///             animation_name: names::PULSE,
+///         },
+///     }
+/// }
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> ButtonClasses {
+///         loading {
+///             animation_name: names::PULSE,
+///         },
+///     }
+/// }
+///
+/// assert_eq!(names::PULSE, "css-in-rs-pulse");
+/// let css = render_classes_to_string::<SpinnerClasses>(&EmptyTheme);
+/// assert!(css.starts_with("@keyframes css-in-rs-pulse {\nfrom {\n"));
+/// ```
+#[macro_export]
+macro_rules! shared_names {
+    ($($(#[$meta:meta])* $vis:vis $name:ident = $value:literal;)*) => {
+        $(
+            $(#[$meta])*
+            $vis const $name: &str = concat!(env!("CARGO_PKG_NAME"), "-", $value);
+        )*
+    };
+}
@@ -0,0 +1,96 @@
+//! Metrics of the stylesheet of a [StyleProvider], i.e. to show CSS bloat in
+//! a performance dashboard or to assert a size budget in tests. See
+//! [StyleProvider::stats].
+//!
+//! # Example
+//! ```
+//! # use css_in_rs::{make_styles, CssFormat, EmptyTheme, StyleProvider};
+//! make_styles! {
+//!     (_theme: EmptyTheme) -> MyClasses {
+//!         button {
+//!             color: "red",
+//!         },
+//!         "@media print" {
+//!             button {
+//!                 color: "black",
+//!             },
+//!         },
+//!     }
+//! }
+//!
+//! let style_provider = StyleProvider::new_headless(EmptyTheme);
+//! style_provider.set_format(CssFormat::Minified);
+//! style_provider.add_classes::<MyClasses>();
+//!
+//! let stats = style_provider.stats();
+//! assert_eq!(stats.rules, 2);
+//! assert_eq!(stats.bytes, style_provider.current_css().len());
+//! assert_eq!(stats.classes, 1);
+//! assert!(stats.bytes < 1024, "The styles exceed their budget");
+//! ```
+
+use core::time::Duration;
+
+use crate::css::{self, Node};
+#[cfg(doc)]
+use crate::StyleProvider;
+
+/// Returned by [StyleProvider::stats]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StyleStats {
+    /// The number of style rules, including the ones nested in at-rules like
+    /// `@media`. At-rules themselves are not counted.
+    pub rules: usize,
+    /// The size of the stylesheet in bytes
+    pub bytes: usize,
+    /// The number of mounted [Classes](crate::Classes)
+    pub classes: usize,
+    /// How long it took to regenerate all styles the last time, i.e. after
+    /// the theme has changed. `None` if they have not been regenerated yet or
+    /// if there is no clock.
+    pub last_update: Option<Duration>,
+}
+
+impl StyleStats {
+    pub(crate) fn new(css: &str, classes: usize, last_update: Option<Duration>) -> Self {
+        Self {
+            rules: count_rules(&css::parse(css)),
+            bytes: css.len(),
+            classes,
+            last_update,
+        }
+    }
+}
+
+fn count_rules(nodes: &[Node]) -> usize {
+    nodes
+        .iter()
+        .map(|node| match node {
+            Node::Block { prelude, children } if prelude.starts_with('@') => {
+                // The blocks of keyframes are no style rules
+                match prelude.contains("keyframes") {
+                    true => 0,
+                    false => count_rules(children),
+                }
+            }
+            Node::Block { .. } => 1,
+            _ => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::css;
+
+    #[test]
+    fn count_rules() {
+        let src = "@layer a, b;\n\
+                   .a, .b { color: red; }\n\
+                   @media print { .a { color: black; } @supports (gap: 0) { .b {} } }\n\
+                   @keyframes fade { from { opacity: 0; } to { opacity: 1; } }\n\
+                   /* .c {} */";
+        assert_eq!(super::count_rules(&css::parse(src)), 3);
+    }
+}
@@ -1,14 +1,30 @@
-use core::cell::RefCell;
-use std::{collections::btree_map::Entry, rc::Rc};
-
-#[doc_cfg(feature = "dioxus")]
-use dioxus::prelude::*;
+use core::{cell::RefCell, future::Future, marker::PhantomData};
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    rc::{Rc, Weak},
+};
 
 use doc_cfg::doc_cfg;
 
+#[cfg(doc)]
+use crate::extract::{RenderCollector, StyleStream};
+#[cfg(feature = "web-sys")]
+use crate::{backend::web::NoDocument, transition::ThemeTransition};
 use crate::{
-    backend::{Backend, CssGeneratorFn},
-    Classes, Theme,
+    backend::{memory::MemoryBackend, Backend, CssGeneratorFn, LinkPosition, StyleChunk},
+    dark_mode::DarkMode,
+    direction::Direction,
+    events::{EventLog, StyleEvent, StyleEventKind},
+    extract::StreamPosition,
+    fonts::FontFace,
+    generation::{push_atoms, CssGenerator, GenerateContext, Mounted},
+    lookup::RuleInfo,
+    profiling::{Measurement, Stopwatch},
+    sanitize::SanitizePolicy,
+    stats::StyleStats,
+    subscription::{Subscription, ThemeListeners},
+    sync::SyncStyleProvider,
+    Classes, CssFormat, Error, Keyframes, ScopedClasses, Theme,
 };
 
 /// Manages dynamically inserted styles. You should usually have exactly one.
@@ -46,19 +62,231 @@ pub struct StyleProvider<T> {
     inner: Rc<RefCell<Inner<T>>>,
 }
 
+/// Fingerprint of the layout of a theme type. When hot patching changes the
+/// fields of a theme, the old values are still around (i.e. in the dioxus
+/// context) and must not be used as values of the new type.
+#[cfg(any(feature = "dioxus", test))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ThemeSchema(u64);
+
+#[cfg(any(feature = "dioxus", test))]
+impl ThemeSchema {
+    pub fn of<T: Theme>() -> Self {
+        use std::hash::{DefaultHasher, Hash, Hasher};
+
+        let mut hasher = DefaultHasher::new();
+        core::any::TypeId::of::<T>().hash(&mut hasher);
+        core::any::type_name::<T>().hash(&mut hasher);
+        core::mem::size_of::<T>().hash(&mut hasher);
+        core::mem::align_of::<T>().hash(&mut hasher);
+        T::SCHEMA.hash(&mut hasher);
+        Self(hasher.finish())
+    }
+}
+
+/// A StyleProvider stored where it may outlive its theme type, i.e. in a
+/// hook. The provider is only handed out again if the schema it has been
+/// created with still matches; values of a changed theme type are never
+/// looked at as `StyleProvider<T>`.
+#[cfg(any(feature = "dioxus", test))]
+pub(crate) struct ErasedProvider {
+    schema: ThemeSchema,
+    provider: Box<dyn core::any::Any>,
+}
+
+#[cfg(any(feature = "dioxus", test))]
+impl ErasedProvider {
+    pub fn new<T: Theme>(provider: StyleProvider<T>) -> Self {
+        Self {
+            schema: ThemeSchema::of::<T>(),
+            provider: Box::new(provider),
+        }
+    }
+
+    /// Returns the provider, or `None` if the theme type has changed since.
+    /// A stale provider is dropped through the type information it has been
+    /// stored with, so its state is released as the old type
+    pub fn get<T: Theme>(&self) -> Option<StyleProvider<T>> {
+        if self.schema != ThemeSchema::of::<T>() {
+            return None;
+        }
+        self.provider.downcast_ref::<StyleProvider<T>>().cloned()
+    }
+}
+
 impl<T: Theme> StyleProvider<T> {
+    fn from_inner(mut inner: Inner<T>) -> Self {
+        let inner = Rc::new_cyclic(|this| {
+            inner.this = this.clone();
+            RefCell::new(inner)
+        });
+
+        Self { inner }
+    }
+
+    /// Identifies the shared state, so clones of a provider have the same id
+    #[cfg(feature = "dioxus")]
+    pub(crate) fn id(&self) -> usize {
+        Rc::as_ptr(&self.inner).cast::<()>() as usize
+    }
+
     /// Quickly sets up a [StyleProvider] for the given theme at the active document.
     /// It will create a new `style` tag to the head and use it to mount new styles.
     #[cfg(feature = "web-sys")]
     pub fn quickstart_web(theme: T) -> Self {
         let inner = Inner::quickstart_web(theme);
-        let inner = Rc::new(RefCell::new(inner));
+        Self::from_inner(inner)
+    }
+
+    /// Like [StyleProvider::quickstart_web], but returns an error instead of
+    /// panicking, i.e. if there is no document
+    ///
+    /// # Example
+    /// ```
+    /// # use css_in_rs::{EmptyTheme, Error, StyleProvider};
+    /// // Running natively, so there is no window
+    /// let result = StyleProvider::try_quickstart_web(EmptyTheme);
+    /// assert_eq!(result.err(), Some(Error::NoWindow));
+    /// ```
+    #[cfg(feature = "web-sys")]
+    pub fn try_quickstart_web(theme: T) -> Result<Self, crate::Error> {
+        let backend = crate::backend::web::WebSysBackend::try_quickstart_web()?;
+        Ok(Self::new_with_backend(backend, theme))
+    }
+
+    /// Like [StyleProvider::quickstart_web], but mounts the styles in the head
+    /// of the given document instead of the active one, i.e. of an iframe, a
+    /// popup window opened for printing, or a document created by a
+    /// `DOMParser`. Panics if the document has no head.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # #[cfg(feature = "web-sys")] {
+    /// # use css_in_rs::{EmptyTheme, StyleProvider};
+    /// // A popup window showing a printable version of the page
+    /// let popup = web_sys::window().unwrap().open().unwrap().unwrap();
+    /// let document = popup.document().unwrap();
+    /// let style_provider = StyleProvider::new_in_document(&document, EmptyTheme);
+    /// # }
+    /// ```
+    #[cfg(feature = "web-sys")]
+    pub fn new_in_document(document: &web_sys::Document, theme: T) -> Self {
+        let backend = crate::backend::web::WebSysBackend::new_and_mount_in_root(document);
+        Self::new_with_backend(backend, theme)
+    }
+
+    /// Sets up a [StyleProvider] which mounts its styles in the given `style`
+    /// element instead of a new one in the `head`, see
+    /// [WebSysBackend::attach_to_style_element](crate::backend::web::WebSysBackend::attach_to_style_element)
+    ///
+    /// # Example
+    /// ```no_run
+    /// # #[cfg(feature = "web-sys")] {
+    /// # use css_in_rs::{EmptyTheme, StyleProvider};
+    /// let document = web_sys::window().unwrap().document().unwrap();
+    /// let element = document.get_element_by_id("app-styles").unwrap();
+    /// let style_provider = StyleProvider::attach_to_style_element(element, EmptyTheme);
+    /// # }
+    /// ```
+    #[cfg(feature = "web-sys")]
+    pub fn attach_to_style_element(element: web_sys::Element, theme: T) -> Self {
+        let backend = crate::backend::web::WebSysBackend::attach_to_style_element(element);
+        Self::new_with_backend(backend, theme)
+    }
+
+    /// Like [StyleProvider::quickstart_web], but uses the given fallback if
+    /// there is no document, i.e. in a web worker or during prerendering. This
+    /// way, code shared between a worker and the main thread does not need to
+    /// distinguish between them.
+    ///
+    /// # Example
+    /// ```
+    /// # use css_in_rs::{backend::web::NoDocument, EmptyTheme, StyleProvider};
+    /// // Running natively, so there is no document
+    /// let style_provider = StyleProvider::quickstart_web_or(EmptyTheme, NoDocument::Memory);
+    /// ```
+    #[cfg(feature = "web-sys")]
+    pub fn quickstart_web_or(theme: T, fallback: NoDocument) -> Self {
+        use crate::backend::web::{DeferredBackend, WebSysBackend};
+
+        if let Some(backend) = WebSysBackend::try_quickstart() {
+            return Self::new_with_backend(backend, theme);
+        }
+
+        match fallback {
+            NoDocument::Panic => panic!("There is no document to mount the styles in"),
+            NoDocument::Memory => Self::new_headless(theme),
+            NoDocument::Defer => Self::new_with_backend(DeferredBackend::new(), theme),
+        }
+    }
+
+    /// Returns a builder to configure a new [StyleProvider], i.e. to use a
+    /// custom classname prefix.
+    ///
+    /// # Example
+    /// ```
+    /// # use css_in_rs::{make_styles, EmptyTheme, StyleProvider};
+    /// make_styles! {
+    ///     (_theme: EmptyTheme) -> MyClasses {
+    ///         active {
+    ///             color: "red",
+    ///         },
+    ///     }
+    /// }
+    ///
+    /// let style_provider = StyleProvider::builder()
+    ///     .prefix("myapp")
+    ///     .build_headless(EmptyTheme);
+    /// let cls = style_provider.add_classes::<MyClasses>();
+    /// assert_eq!(cls.active, "myapp-0");
+    /// ```
+    pub fn builder() -> StyleProviderBuilder<T> {
+        StyleProviderBuilder {
+            prefix: DEFAULT_PREFIX.to_owned(),
+            format: CssFormat::default(),
+            direction: Direction::default(),
+            logical_properties: false,
+            dark_mode: DarkMode::default(),
+            layers: Vec::new(),
+            sanitize: SanitizePolicy::default(),
+            chunked: false,
+            style_attributes: Vec::new(),
+            #[cfg(feature = "web-sys")]
+            persist_key: None,
+            #[cfg(feature = "web-sys")]
+            theme_transition: None,
+            _theme: PhantomData,
+        }
+    }
 
-        StyleProvider { inner }
+    /// Sets up a [StyleProvider] for the given theme which mounts its styles
+    /// using a custom [Backend].
+    pub fn new_with_backend<B: Backend<T>>(backend: B, theme: T) -> Self {
+        let inner = Inner::new_with_backend(backend, theme);
+        Self::from_inner(inner)
     }
 
-    fn add_css_generator(&self, generator: CssGeneratorFn<T>) -> u64 {
-        self.inner.borrow_mut().add_css_generator(generator)
+    /// Sets up a [StyleProvider] for the given theme which keeps all styles in
+    /// memory (see [MemoryBackend]). It does not need a DOM, so it can be used
+    /// in unit tests running natively under `cargo test`.
+    ///
+    /// # Example
+    /// ```
+    /// # use css_in_rs::{make_styles, EmptyTheme, StyleProvider};
+    /// make_styles! {
+    ///     (_theme: EmptyTheme) -> MyClasses {
+    ///         active {
+    ///             color: "red",
+    ///         },
+    ///     }
+    /// }
+    ///
+    /// let style_provider = StyleProvider::new_headless(EmptyTheme);
+    /// let cls = style_provider.add_classes::<MyClasses>();
+    /// assert_eq!(cls.active, "css-0");
+    /// ```
+    pub fn new_headless(theme: T) -> Self {
+        Self::new_with_backend(MemoryBackend::new(), theme)
     }
 
     /// Mount new styles and returns the dynamically generated classnames.
@@ -68,14 +296,669 @@ impl<T: Theme> StyleProvider<T> {
     where
         C: Classes<Theme = T>,
     {
-        let start = self.add_css_generator(C::generate);
-        C::new(start)
+        C::add_dependencies(self);
+        let start = self.inner.borrow_mut().add_classes::<C>();
+        C::new(&self.inner.borrow().prefix, start)
+    }
+
+    /// Like [StyleProvider::add_classes], but fails if the styles contain a
+    /// value rejected by [SanitizePolicy::Reject]. The styles are mounted
+    /// anyway, with the value escaped.
+    pub fn try_add_classes<C>(&self) -> Result<C, Error>
+    where
+        C: Classes<Theme = T>,
+    {
+        crate::sanitize::take_rejected();
+        let classes = self.add_classes();
+        match crate::sanitize::take_rejected() {
+            Some(value) => Err(Error::UnsafeValue(value)),
+            None => Ok(classes),
+        }
+    }
+
+    /// Mounts keyframes defined by [keyframes!](crate::keyframes) and returns
+    /// their name. Keyframes with the same name are only mounted once.
+    pub fn add_keyframes(&self, keyframes: &Keyframes) -> &'static str {
+        self.inner.borrow_mut().add_keyframes(*keyframes);
+        keyframes.name()
+    }
+
+    /// Mounts the `@font-face` rule of a web font, see [crate::fonts]. The
+    /// same font face is only mounted once.
+    pub fn add_font_face(&self, font: &FontFace) {
+        self.inner.borrow_mut().add_font_face(font.css());
+    }
+
+    /// Mounts a stylesheet which is only known at runtime, i.e. loaded from
+    /// a CMS or the user's configuration. Its classes are replaced by
+    /// generated classnames, like the classes of [make_styles!](crate::make_styles).
+    /// Other selectors, at-rules and declarations are kept as they are.
+    /// The same stylesheet is only mounted once.
+    ///
+    /// # Example
+    /// ```
+    /// # use css_in_rs::{EmptyTheme, StyleProvider};
+    /// let style_provider = StyleProvider::new_headless(EmptyTheme);
+    /// style_provider.set_format(css_in_rs::CssFormat::Minified);
+    /// let classes = style_provider.add_raw_css(".banner { color: red } .banner a { color: blue }");
+    /// assert_eq!(classes.get("banner"), Some("css-0"));
+    /// assert_eq!(
+    ///     style_provider.current_css(),
+    ///     ".css-0{color:red}.css-0 a{color:blue}"
+    /// );
+    /// ```
+    pub fn add_raw_css(&self, css: &str) -> ScopedClasses {
+        self.inner.borrow_mut().add_raw_css(css)
+    }
+
+    /// Mounts a [Stylesheet](crate::stylesheet::Stylesheet) built at runtime,
+    /// see [StyleProvider::add_raw_css]
+    pub fn add_stylesheet(&self, stylesheet: &crate::stylesheet::Stylesheet) -> ScopedClasses {
+        self.add_raw_css(&stylesheet.to_string())
+    }
+
+    /// Links an external stylesheet, i.e. of a component library or a web
+    /// font, before the generated styles. Generated rules therefore override
+    /// external ones with the same specificity. The stylesheets are loaded by
+    /// the backend, i.e. using a `<link rel="stylesheet">` element; a headless
+    /// provider only keeps track of them. Linking the same url again does
+    /// nothing.
+    ///
+    /// # Example
+    /// ```
+    /// # use css_in_rs::{backend::LinkPosition, EmptyTheme, StyleProvider};
+    /// let style_provider = StyleProvider::new_headless(EmptyTheme);
+    /// style_provider.link_external("/vendor/reset.css").unwrap();
+    /// style_provider
+    ///     .link_external_at("/vendor/overrides.css", LinkPosition::After)
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     style_provider.external_links(),
+    ///     [
+    ///         ("/vendor/reset.css".to_owned(), LinkPosition::Before),
+    ///         ("/vendor/overrides.css".to_owned(), LinkPosition::After),
+    ///     ]
+    /// );
+    /// assert!(style_provider.unlink_external("/vendor/reset.css"));
+    /// ```
+    pub fn link_external(&self, url: impl Into<String>) -> Result<(), Error> {
+        self.link_external_at(url, LinkPosition::Before)
+    }
+
+    /// Like [StyleProvider::link_external], but with the given position
+    /// relative to the generated styles. Stylesheets with the same position
+    /// are loaded in the order they have been linked.
+    pub fn link_external_at(
+        &self,
+        url: impl Into<String>,
+        position: LinkPosition,
+    ) -> Result<(), Error> {
+        self.inner.borrow_mut().link_external(url.into(), position)
+    }
+
+    /// Removes a stylesheet linked by [StyleProvider::link_external]. Returns
+    /// whether it has been linked.
+    pub fn unlink_external(&self, url: &str) -> bool {
+        self.inner.borrow_mut().unlink_external(url)
+    }
+
+    /// The linked external stylesheets, in the order they have been linked
+    pub fn external_links(&self) -> Vec<(String, LinkPosition)> {
+        self.inner.borrow().external.clone()
+    }
+
+    /// Development only: Replaces the styles of mounted [Classes] by the given
+    /// CSS, so a dev server can hot-swap edited styles without reloading the
+    /// application. The [Classes] are given by their type name or the name of
+    /// the struct, i.e. `ButtonClasses`. Their classes are written as in
+    /// [make_styles!](crate::make_styles), i.e. `.primary`, and keep their
+    /// generated classnames. The replacement lasts until the next one.
+    ///
+    /// Returns whether the styles have been replaced, which is not the case if
+    /// no such [Classes] are mounted or in release builds.
+    ///
+    /// # Example
+    /// ```
+    /// # use css_in_rs::{make_styles, CssFormat, EmptyTheme, StyleProvider};
+    /// make_styles! {
+    ///     (_theme: EmptyTheme) -> ButtonClasses {
+    ///         primary {
+    ///             color: "red",
+    ///         },
+    ///     }
+    /// }
+    ///
+    /// let style_provider = StyleProvider::new_headless(EmptyTheme);
+    /// style_provider.set_format(CssFormat::Minified);
+    /// style_provider.add_classes::<ButtonClasses>();
+    /// let replaced = style_provider.hot_replace("ButtonClasses", ".primary { color: blue }");
+    /// // Release builds of the crate keep the generated styles
+    /// let color = if replaced { "blue" } else { "red" };
+    /// assert_eq!(style_provider.current_css(), format!(".css-0{{color:{color}}}"));
+    /// ```
+    pub fn hot_replace(&self, name: &str, css: &str) -> bool {
+        cfg!(debug_assertions) && self.inner.borrow_mut().hot_replace(name, css)
+    }
+
+    /// Returns where a generated classname like `css-42` comes from, see
+    /// [crate::lookup]. Atomic classes following the classname are ignored.
+    /// Returns `None` if the classname has not been generated by this provider
+    /// or by [Classes], i.e. for [StyleProvider::add_raw_css].
+    pub fn lookup(&self, class_name: &str) -> Option<RuleInfo> {
+        let class_name = class_name.split_whitespace().next()?;
+        self.inner.borrow().lookup(class_name)
+    }
+
+    /// Returns the complete stylesheet which is currently mounted
+    ///
+    /// # Example
+    /// ```
+    /// # use css_in_rs::{make_styles, EmptyTheme, StyleProvider};
+    /// make_styles! {
+    ///     (_theme: EmptyTheme) -> MyClasses {
+    ///         active {
+    ///             color: "red",
+    ///         },
+    ///     }
+    /// }
+    ///
+    /// let style_provider = StyleProvider::new_headless(EmptyTheme);
+    /// style_provider.set_format(css_in_rs::CssFormat::Minified);
+    /// style_provider.add_classes::<MyClasses>();
+    /// assert_eq!(style_provider.current_css(), ".css-0{color:red}");
+    /// ```
+    pub fn current_css(&self) -> String {
+        self.inner.borrow().backend.current_css().to_owned()
+    }
+
+    /// Metrics of the current stylesheet, see [crate::stats]
+    pub fn stats(&self) -> StyleStats {
+        let inner = self.inner.borrow();
+        StyleStats::new(
+            inner.backend.current_css(),
+            inner.mounted.generators.len(),
+            inner.last_update,
+        )
+    }
+
+    /// Writes pending changes to the DOM right away. Changes are usually
+    /// batched and written once per animation frame, so mounting many
+    /// [Classes] at once causes only one style recalculation. Tests reading
+    /// the computed styles right after mounting need to flush first.
+    /// Scoped providers (see [StyleProvider::scoped]) are flushed as well.
+    pub fn flush(&self) {
+        self.inner.borrow_mut().flush();
+    }
+
+    /// Returns the rules of the mounted stylesheet which only apply to print
+    /// media. Rules under `@media print` are unwrapped, so the result can be
+    /// put into a separate `<style media="print">` element or window, i.e. for
+    /// a "print this view" feature with a minimal stylesheet.
+    ///
+    /// # Example
+    /// ```
+    /// # use css_in_rs::{make_styles, EmptyTheme, StyleProvider};
+    /// make_styles! {
+    ///     (_theme: EmptyTheme) -> MyClasses {
+    ///         toolbar {
+    ///             display: "flex",
+    ///         },
+    ///         "@media print" {
+    ///             toolbar {
+    ///                 display: "none",
+    ///             },
+    ///         },
+    ///     }
+    /// }
+    ///
+    /// let style_provider = StyleProvider::new_headless(EmptyTheme);
+    /// style_provider.set_format(css_in_rs::CssFormat::Minified);
+    /// style_provider.add_classes::<MyClasses>();
+    /// assert_eq!(style_provider.print_css(), ".css-0{display:none}");
+    /// ```
+    pub fn print_css(&self) -> String {
+        let inner = self.inner.borrow();
+        let css = crate::css::extract_print(inner.backend.current_css());
+        inner.format.apply(css)
+    }
+
+    /// Returns the part of the stylesheet which has been generated for the given
+    /// [Classes] type, or `None` if these classes have not been mounted
+    pub fn css_of<C>(&self) -> Option<String>
+    where
+        C: Classes<Theme = T>,
+    {
+        self.inner.borrow().css_of(C::generate)
+    }
+
+    /// Temporarily removes the rules of the given [Classes] from the
+    /// stylesheet, or restores them, i.e. for A/B tests of styles or to
+    /// toggle a high contrast mode. The classnames stay the same. Their atomic
+    /// rules (see `#[atomic]` in [make_styles!](crate::make_styles)) are kept,
+    /// since they are shared with other [Classes].
+    ///
+    /// Returns whether the classes are mounted; otherwise, nothing changes.
+    ///
+    /// # Example
+    /// ```
+    /// # use css_in_rs::{make_styles, CssFormat, EmptyTheme, StyleProvider};
+    /// make_styles! {
+    ///     (_theme: EmptyTheme) -> ContrastClasses {
+    ///         text {
+    ///             color: "black",
+    ///         },
+    ///     }
+    /// }
+    ///
+    /// let style_provider = StyleProvider::new_headless(EmptyTheme);
+    /// style_provider.set_format(CssFormat::Minified);
+    /// let classes = style_provider.add_classes::<ContrastClasses>();
+    ///
+    /// assert!(style_provider.set_enabled::<ContrastClasses>(false));
+    /// assert_eq!(style_provider.current_css(), "");
+    /// assert!(style_provider.set_enabled::<ContrastClasses>(true));
+    /// assert_eq!(style_provider.current_css(), ".css-0{color:black}");
+    /// assert_eq!(classes.text, "css-0");
+    /// ```
+    pub fn set_enabled<C>(&self, enabled: bool) -> bool
+    where
+        C: Classes<Theme = T>,
+    {
+        self.inner.borrow_mut().set_enabled(C::generate, enabled)
+    }
+
+    /// Returns a graph of all mounted [Classes], see [StyleGraph](crate::graph::StyleGraph)
+    #[doc_cfg(feature = "graph")]
+    pub fn style_graph(&self) -> crate::graph::StyleGraph {
+        let inner = self.inner.borrow();
+        let generators = inner.mounted.generators.iter();
+        crate::graph::StyleGraph::new(generators.map(|generator| (generator.name, generator.info)))
+    }
+
+    /// Returns the number of mounted [Classes]
+    pub(crate) fn mounted_count(&self) -> usize {
+        self.inner.borrow().mounted_count()
+    }
+
+    /// Returns the type names and styles of all [Classes] which have been
+    /// mounted after the first `count` ones, see [StyleProvider::mounted_count]
+    pub(crate) fn mounted_after(&self, count: usize) -> Vec<(&'static str, String)> {
+        self.inner.borrow().mounted_after(count)
+    }
+
+    /// Starts recording which [Classes] are used, see [RenderCollector]
+    pub(crate) fn start_collecting(&self) -> Rc<RefCell<Vec<usize>>> {
+        let used = Rc::default();
+        self.inner
+            .borrow_mut()
+            .collectors
+            .push(Rc::downgrade(&used));
+        used
+    }
+
+    /// Returns the type names and styles of the given generators, see
+    /// [RenderCollector::finish]
+    pub(crate) fn collected(&self, used: &[usize]) -> (Vec<&'static str>, String) {
+        self.inner.borrow().collected(used)
+    }
+
+    /// Returns the type names and styles mounted since the given position,
+    /// see [StyleStream::next_chunk]
+    pub(crate) fn streamed(&self, position: &mut StreamPosition) -> (Vec<&'static str>, String) {
+        self.inner.borrow().streamed(position)
+    }
+
+    /// Creates a [StyleProvider] for a subtree of the application, i.e. a
+    /// dialog which uses a dark theme in an otherwise light application. Its
+    /// theme is derived from ours using `derive_theme`, which is called again
+    /// whenever our theme changes.
+    ///
+    /// The scoped provider mounts its styles separately and its classnames use
+    /// a prefix of their own (like `css-s0-3`), so [Classes] mounted by both
+    /// providers get different classnames and do not affect each other.
+    ///
+    /// # Example
+    /// ```
+    /// # use css_in_rs::{make_styles, StyleProvider, Theme};
+    /// #[derive(Clone, PartialEq)]
+    /// struct MyTheme {
+    ///     background: String,
+    /// }
+    ///
+    /// impl Theme for MyTheme {
+    ///     fn fast_cmp(&self, other: &Self) -> bool {
+    ///         self == other
+    ///     }
+    /// }
+    ///
+    /// make_styles! {
+    ///     (theme: MyTheme) -> PaperClasses {
+    ///         paper {
+    ///             background: theme.background,
+    ///         },
+    ///     }
+    /// }
+    ///
+    /// let light = MyTheme { background: "white".to_owned() };
+    /// let style_provider = StyleProvider::new_headless(light);
+    /// let dialog_provider = style_provider.scoped(|_theme| MyTheme {
+    ///     background: "black".to_owned(),
+    /// });
+    ///
+    /// assert_eq!(style_provider.add_classes::<PaperClasses>().paper, "css-0");
+    /// assert_eq!(dialog_provider.add_classes::<PaperClasses>().paper, "css-s0-0");
+    /// assert!(dialog_provider.current_css().contains("black"));
+    /// ```
+    pub fn scoped(&self, derive_theme: impl Fn(&T) -> T + 'static) -> StyleProvider<T> {
+        let mut inner = self.inner.borrow_mut();
+
+        let prefix = format!("{}-s{}", inner.prefix, inner.scope_counter);
+        inner.scope_counter += 1;
+
+        let theme = derive_theme(&inner.current_theme);
+        inner.events.record(|| StyleEventKind::ScopeCreated {
+            prefix: prefix.clone(),
+        });
+        let mut scoped = Inner::new_with_prefix(inner.backend.new_scope(), theme, prefix);
+        scoped.format = inner.format;
+        scoped.direction = inner.direction;
+        scoped.logical_properties = inner.logical_properties;
+        scoped.dark_mode = inner.dark_mode.clone();
+        scoped.sanitize = inner.sanitize;
+        scoped.profiling = inner.profiling;
+        scoped.chunked = inner.chunked;
+        #[cfg(feature = "web-sys")]
+        {
+            scoped.theme_transition = inner.theme_transition.clone();
+        }
+        if !inner.layers.is_empty() {
+            scoped.layers = inner.layers.clone();
+            scoped.update();
+        }
+        let scoped = StyleProvider::from_inner(scoped);
+
+        inner.scopes.push(Scope {
+            inner: Rc::downgrade(&scoped.inner),
+            derive_theme: Box::new(derive_theme),
+        });
+
+        scoped
     }
 
-    /// Change the theme. All styles will be recomputed, but the classnames will
-    /// not change.
+    /// Change the theme. All styles reading the theme will be recomputed (see
+    /// [Classes::READS_THEME]), but the classnames will not change. The themes
+    /// of scoped providers (see [StyleProvider::scoped]) are derived again.
     pub fn update_theme(&self, theme: T) {
         self.inner.borrow_mut().update_theme(theme);
+        self.notify_theme_change();
+    }
+
+    /// Like [StyleProvider::update_theme], but keeps the current theme if the
+    /// styles of the new one contain a value rejected by
+    /// [SanitizePolicy::Reject], including the styles of scoped providers.
+    ///
+    /// # Example
+    /// ```
+    /// # use css_in_rs::{make_styles, sanitize::SanitizePolicy, CssFormat, Error, StyleProvider, Theme};
+    /// #[derive(Clone, PartialEq, Theme)]
+    /// struct MyTheme {
+    ///     accent: String,
+    /// }
+    ///
+    /// make_styles! {
+    ///     (theme: MyTheme) -> MyClasses {
+    ///         link {
+    ///             color: theme.accent,
+    ///         },
+    ///     }
+    /// }
+    ///
+    /// let theme = |accent: &str| MyTheme { accent: accent.to_owned() };
+    /// let style_provider = StyleProvider::builder()
+    ///     .format(CssFormat::Minified)
+    ///     .sanitize(SanitizePolicy::Reject)
+    ///     .build_headless(theme("red"));
+    /// style_provider.add_classes::<MyClasses>();
+    ///
+    /// let result = style_provider.try_update_theme(theme("red } body { display: none"));
+    /// assert_eq!(result, Err(Error::UnsafeValue("red } body { display: none".to_owned())));
+    /// assert_eq!(style_provider.current_css(), ".css-0{color:red}");
+    /// ```
+    pub fn try_update_theme(&self, theme: T) -> Result<(), Error> {
+        if let Some(value) = self.inner.borrow().rejected_value(&theme) {
+            return Err(Error::UnsafeValue(value));
+        }
+        self.update_theme(theme);
+        Ok(())
+    }
+
+    /// Calls `callback` with the new theme whenever the theme changes, i.e.
+    /// to redraw a canvas or to pass colors to a JavaScript widget. The
+    /// callback is called after all styles have been updated, and it is
+    /// removed once the returned [Subscription] is dropped. The callbacks of
+    /// scoped providers (see [StyleProvider::scoped]) are called with their
+    /// derived theme.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::{cell::RefCell, rc::Rc};
+    /// # use css_in_rs::{StyleProvider, Theme};
+    /// #[derive(Clone, PartialEq)]
+    /// struct MyTheme {
+    ///     accent: &'static str,
+    /// }
+    ///
+    /// impl Theme for MyTheme {
+    ///     fn fast_cmp(&self, other: &Self) -> bool {
+    ///         self == other
+    ///     }
+    /// }
+    ///
+    /// let style_provider = StyleProvider::new_headless(MyTheme { accent: "blue" });
+    /// let chart_color = Rc::new(RefCell::new("blue"));
+    /// let subscription = style_provider.on_theme_change({
+    ///     let chart_color = chart_color.clone();
+    ///     move |theme| *chart_color.borrow_mut() = theme.accent
+    /// });
+    ///
+    /// style_provider.update_theme(MyTheme { accent: "orange" });
+    /// assert_eq!(*chart_color.borrow(), "orange");
+    ///
+    /// drop(subscription);
+    /// style_provider.update_theme(MyTheme { accent: "green" });
+    /// assert_eq!(*chart_color.borrow(), "orange");
+    /// ```
+    pub fn on_theme_change(&self, callback: impl Fn(&T) + 'static) -> Subscription {
+        self.inner.borrow().theme_listeners.subscribe(callback)
+    }
+
+    /// Calls the callbacks of [StyleProvider::on_theme_change] for the theme
+    /// changes so far. They are called once the provider is no longer
+    /// borrowed, so they can use it.
+    fn notify_theme_change(&self) {
+        let pending = core::mem::take(&mut self.inner.borrow_mut().pending_notifications);
+        for notify in pending {
+            notify();
+        }
+    }
+
+    /// Changes the theme to the one given as JSON, i.e. loaded from a config
+    /// file fetched at runtime, so white-label deployments can change their
+    /// brand colors without recompiling. Returns an error and keeps the
+    /// current theme if the JSON does not describe a theme.
+    ///
+    /// # Example
+    /// ```
+    /// # use css_in_rs::{make_styles, CssFormat, StyleProvider, Theme};
+    /// #[derive(Clone, PartialEq, serde::Deserialize)]
+    /// struct BrandTheme {
+    ///     primary: String,
+    /// }
+    ///
+    /// impl Theme for BrandTheme {
+    ///     fn fast_cmp(&self, other: &Self) -> bool {
+    ///         self == other
+    ///     }
+    /// }
+    ///
+    /// make_styles! {
+    ///     (theme: BrandTheme) -> ButtonClasses {
+    ///         button {
+    ///             color: theme.primary,
+    ///         },
+    ///     }
+    /// }
+    ///
+    /// let theme = BrandTheme { primary: "blue".to_owned() };
+    /// let style_provider = StyleProvider::new_headless(theme);
+    /// style_provider.set_format(CssFormat::Minified);
+    /// style_provider.add_classes::<ButtonClasses>();
+    ///
+    /// style_provider.update_theme_from_json(r#"{ "primary": "teal" }"#).unwrap();
+    /// assert_eq!(style_provider.current_css(), ".css-0{color:teal}");
+    /// assert!(style_provider.update_theme_from_json(r#"{ "secondary": "red" }"#).is_err());
+    /// ```
+    #[doc_cfg(feature = "serde")]
+    pub fn update_theme_from_json(&self, json: &str) -> Result<(), serde_json::Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let theme = serde_json::from_str(json)?;
+        self.update_theme(theme);
+        Ok(())
+    }
+
+    /// Applies the theme immediately and persists it using `save`, typically
+    /// a dioxus fullstack server function. If saving fails, the previous theme
+    /// is restored, unless the theme has been changed again in the meantime.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use css_in_rs::{StyleProvider, Theme};
+    /// #[derive(Clone, PartialEq)]
+    /// struct MyTheme {
+    ///     dark: bool,
+    /// }
+    ///
+    /// impl Theme for MyTheme {
+    ///     fn fast_cmp(&self, other: &Self) -> bool {
+    ///         self == other
+    ///     }
+    /// }
+    ///
+    /// // Usually a `#[server]` function storing the theme in the user's profile
+    /// async fn save_theme(theme: MyTheme) -> Result<(), String> {
+    ///     Ok(())
+    /// }
+    ///
+    /// // Usually a `#[server]` function, returning `None` if nothing is stored yet
+    /// async fn load_theme() -> Result<Option<MyTheme>, String> {
+    ///     Ok(Some(MyTheme { dark: true }))
+    /// }
+    ///
+    /// async fn setup(provider: StyleProvider<MyTheme>) -> Result<(), String> {
+    ///     provider.load_theme(load_theme()).await?;
+    ///     provider.persist_theme(MyTheme { dark: false }, save_theme).await
+    /// }
+    /// ```
+    pub async fn persist_theme<E, F>(&self, theme: T, save: impl FnOnce(T) -> F) -> Result<(), E>
+    where
+        F: Future<Output = Result<(), E>>,
+    {
+        let (previous, generation) = {
+            let mut inner = self.inner.borrow_mut();
+            let previous = inner.current_theme.clone();
+            inner.update_theme(theme.clone());
+            (previous, inner.theme_generation)
+        };
+        self.notify_theme_change();
+
+        let result = save(theme).await;
+        if result.is_err() {
+            let mut inner = self.inner.borrow_mut();
+            if inner.theme_generation == generation {
+                inner.update_theme(previous);
+                inner.events.record(|| StyleEventKind::ThemeRolledBack);
+            }
+            drop(inner);
+            self.notify_theme_change();
+        }
+
+        result
+    }
+
+    /// Loads a persisted theme using `load` and applies it. If `load` returns
+    /// `None`, the current theme is kept. See [StyleProvider::persist_theme].
+    pub async fn load_theme<E>(
+        &self,
+        load: impl Future<Output = Result<Option<T>, E>>,
+    ) -> Result<(), E> {
+        let theme = load.await.inspect_err(|_| {
+            let mut inner = self.inner.borrow_mut();
+            inner.events.record(|| StyleEventKind::ThemeLoadFailed);
+        })?;
+        if let Some(theme) = theme {
+            self.update_theme(theme);
+        }
+
+        Ok(())
+    }
+
+    /// Changes how the generated CSS is formatted. All styles will be recomputed
+    /// if the format changes; the ones of scoped providers as well. See
+    /// [CssFormat] for the available formats.
+    ///
+    /// By default, the CSS is used as generated. A typical choice is to
+    /// pretty-print it in debug builds and to minify it for releases:
+    /// ```no_run
+    /// # use css_in_rs::{CssFormat, EmptyTheme, StyleProvider};
+    /// let style_provider = StyleProvider::quickstart_web(EmptyTheme);
+    /// style_provider.set_format(if cfg!(debug_assertions) {
+    ///     CssFormat::Pretty
+    /// } else {
+    ///     CssFormat::Minified
+    /// });
+    /// ```
+    pub fn set_format(&self, format: CssFormat) {
+        self.inner.borrow_mut().set_format(format);
+    }
+
+    /// Changes the direction of the text, i.e. when the user switches to a
+    /// right-to-left locale. All styles will be recomputed if the direction
+    /// changes; the ones of scoped providers as well. See [crate::direction].
+    pub fn set_direction(&self, direction: Direction) {
+        self.inner.borrow_mut().set_direction(direction);
+    }
+
+    /// Enables or disables the animation of theme switches, i.e. when the
+    /// user starts to prefer reduced motion (see [crate::motion]). See
+    /// [StyleProviderBuilder::theme_transition]. Scoped providers follow.
+    #[doc_cfg(feature = "web-sys")]
+    pub fn set_theme_transition(&self, transition: Option<ThemeTransition>) {
+        self.inner.borrow_mut().set_theme_transition(transition);
+    }
+
+    /// Enables or disables profiling. If enabled, mounting new styles and updating
+    /// the theme will be recorded using `performance.mark` and `performance.measure`.
+    /// The entries are named after the mounted [Classes] type, so style costs
+    /// show up directly in the Performance panel of the browser's devtools.
+    /// Scoped providers follow.
+    #[doc_cfg(feature = "web-sys")]
+    pub fn set_profiling(&self, enabled: bool) {
+        self.inner.borrow_mut().set_profiling(enabled);
+    }
+
+    /// Keeps a log of the last `capacity` operations of this provider, like
+    /// mounting styles or updating the theme, see [StyleProvider::recent_events].
+    /// A capacity of zero (the default) disables the log.
+    pub fn set_event_log(&self, capacity: usize) {
+        self.inner.borrow_mut().events.set_capacity(capacity);
+    }
+
+    /// The most recent events, oldest first. Useful to attach styling context
+    /// to crash or error reports. See [crate::events].
+    pub fn recent_events(&self) -> Vec<StyleEvent> {
+        self.inner.borrow().events.recent()
     }
 
     /// A convenience hook to mount styles and cache the classnames.
@@ -92,26 +975,419 @@ impl<T: Theme> StyleProvider<T> {
     }
 }
 
-struct CssGenerator<T> {
-    generator: CssGeneratorFn<T>,
-    start: u64,
-    stop: u64,
+pub(crate) const DEFAULT_PREFIX: &str = "css";
+
+/// Configures a new [StyleProvider], see [StyleProvider::builder]
+pub struct StyleProviderBuilder<T> {
+    prefix: String,
+    format: CssFormat,
+    direction: Direction,
+    logical_properties: bool,
+    dark_mode: DarkMode,
+    layers: Vec<String>,
+    sanitize: SanitizePolicy,
+    chunked: bool,
+    style_attributes: Vec<(String, String)>,
+    #[cfg(feature = "web-sys")]
+    persist_key: Option<String>,
+    #[cfg(feature = "web-sys")]
+    theme_transition: Option<ThemeTransition>,
+    _theme: PhantomData<T>,
+}
+
+impl<T: Theme> StyleProviderBuilder<T> {
+    /// Sets the prefix of all generated classnames, which is `css` by default.
+    /// Multiple [StyleProvider]s on the same page (i.e. of independent apps or
+    /// micro-frontends) need different prefixes, so their classnames do not
+    /// collide.
+    ///
+    /// # Panics
+    /// Panics if the prefix is not a valid CSS identifier, i.e. if it is empty,
+    /// starts with a digit or contains anything but ASCII letters, digits, `-`
+    /// and `_`
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        assert!(is_ident(&prefix), "Invalid classname prefix: {prefix:?}");
+
+        self.prefix = prefix;
+        self
+    }
+
+    /// Declares the order of cascade layers, from the lowest to the highest
+    /// priority. The [StyleProvider] emits an `@layer` statement before all
+    /// other styles, so rules in later layers win, no matter in which order
+    /// the [Classes] are mounted. Rules are assigned to layers using
+    /// `@layer name { ... }` in [make_styles!](crate::make_styles).
+    ///
+    /// # Example
+    /// ```
+    /// # use css_in_rs::{make_styles, CssFormat, EmptyTheme, StyleProvider};
+    /// make_styles! {
+    ///     (_theme: EmptyTheme) -> ButtonClasses {
+    ///         "@layer library" {
+    ///             button {
+    ///                 color: "gray",
+    ///             },
+    ///         },
+    ///     }
+    /// }
+    ///
+    /// let style_provider = StyleProvider::builder()
+    ///     .layers(["reset", "library", "app"])
+    ///     .format(CssFormat::Minified)
+    ///     .build_headless(EmptyTheme);
+    /// style_provider.add_classes::<ButtonClasses>();
+    /// assert_eq!(
+    ///     style_provider.current_css(),
+    ///     "@layer reset,library,app;@layer library{.css-0{color:gray}}"
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if a name is not a valid layer name, i.e. not an identifier
+    /// (see [StyleProviderBuilder::prefix]) or a dotted sequence of them
+    /// like `framework.base`
+    pub fn layers<S: Into<String>>(mut self, layers: impl IntoIterator<Item = S>) -> Self {
+        self.layers = layers.into_iter().map(Into::into).collect();
+        for layer in &self.layers {
+            assert!(
+                layer.split('.').all(is_ident),
+                "Invalid layer name: {layer:?}"
+            );
+        }
+
+        self
+    }
+
+    /// Sets the initial format, see [StyleProvider::set_format]
+    pub fn format(mut self, format: CssFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets the initial direction, see [StyleProvider::set_direction]
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Replaces physical properties like `margin-left` by logical ones like
+    /// `margin-inline-start` in all generated styles, so they adapt to the
+    /// `direction` and `writing-mode` of the page. Disabled by default, see
+    /// [to_logical](crate::direction::to_logical).
+    ///
+    /// # Example
+    /// ```
+    /// # use css_in_rs::{make_styles, CssFormat, EmptyTheme, StyleProvider};
+    /// make_styles! {
+    ///     (_theme: EmptyTheme) -> MyClasses {
+    ///         icon {
+    ///             margin_left: "4px",
+    ///             text_align: "right",
+    ///         },
+    ///     }
+    /// }
+    ///
+    /// let style_provider = StyleProvider::builder()
+    ///     .format(CssFormat::Minified)
+    ///     .logical_properties(true)
+    ///     .build_headless(EmptyTheme);
+    /// style_provider.add_classes::<MyClasses>();
+    /// assert_eq!(
+    ///     style_provider.current_css(),
+    ///     ".css-0{margin-inline-start:4px;text-align:end}"
+    /// );
+    /// ```
+    pub fn logical_properties(mut self, enabled: bool) -> Self {
+        self.logical_properties = enabled;
+        self
+    }
+
+    /// Sets how `@dark` blocks are applied, see [dark_mode](crate::dark_mode).
+    /// By default, they follow the color scheme of the operating system.
+    pub fn dark_mode(mut self, dark_mode: DarkMode) -> Self {
+        self.dark_mode = dark_mode;
+        self
+    }
+
+    /// Sets how values interpolated into the styles are checked, see
+    /// [sanitize](crate::sanitize). Unsafe values are escaped by default; use
+    /// [SanitizePolicy::Trust] for values which interpolate several
+    /// declarations on purpose.
+    pub fn sanitize(mut self, policy: SanitizePolicy) -> Self {
+        self.sanitize = policy;
+        self
+    }
+
+    /// Mounts the styles of every [Classes] type in a `style` element of its
+    /// own, tagged with the type name like
+    /// `<style data-classes="my_app::ButtonClasses">`, instead of a single
+    /// element for all styles. This way, the styles of a component can be
+    /// inspected in the devtools, and updates only touch the elements whose
+    /// styles have changed. Disabled by default, since mounting new styles
+    /// generates the chunks of all mounted ones again.
+    ///
+    /// Only the web backend creates separate elements, see
+    /// [Backend::replace_chunks]. Styles shared by all [Classes], like
+    /// keyframes and font faces, stay in the original element.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use css_in_rs::{EmptyTheme, StyleProvider};
+    /// let style_provider = StyleProvider::builder()
+    ///     .chunked(cfg!(debug_assertions))
+    ///     .build_web(EmptyTheme);
+    /// ```
+    pub fn chunked(mut self, enabled: bool) -> Self {
+        self.chunked = enabled;
+        self
+    }
+
+    /// Sets an attribute of the injected `style` element, i.e. an `id` or a
+    /// `data-*` attribute to identify it in external tooling. The elements of
+    /// scoped providers and chunks (see [StyleProviderBuilder::chunked]) get
+    /// the same attributes, except for the `id`. Backends without a document
+    /// ignore them.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use css_in_rs::{EmptyTheme, StyleProvider};
+    /// let style_provider = StyleProvider::builder()
+    ///     .style_attribute("id", "app-styles")
+    ///     .style_attribute("data-source", "css-in-rs")
+    ///     .media("screen")
+    ///     .build_web(EmptyTheme);
+    /// ```
+    ///
+    /// # Panics
+    /// Panics while building if the backend cannot set the attribute, i.e. if
+    /// the name is not a valid attribute name
+    pub fn style_attribute(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.style_attributes.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the `media` attribute of the injected `style` element, so the
+    /// styles only apply to the given media query, i.e. `screen` or `print`.
+    /// See [StyleProviderBuilder::style_attribute].
+    pub fn media(self, query: impl Into<String>) -> Self {
+        self.style_attribute("media", query)
+    }
+
+    /// Development only: Saves which classnames have been assigned to which
+    /// [Classes] in the `sessionStorage` under the given key, and restores
+    /// them after a reload. This way, classnames stay the same across full
+    /// page reloads, even if the [Classes] are mounted in a different order,
+    /// so element state and CSS breakpoints in the devtools survive them.
+    ///
+    /// [Classes] whose number of classes has changed get new classnames. This
+    /// option is ignored in release builds and if there is no `sessionStorage`.
+    #[doc_cfg(feature = "web-sys")]
+    pub fn persist_classnames(mut self, key: impl Into<String>) -> Self {
+        self.persist_key = Some(key.into());
+        self
+    }
+
+    /// Animates theme switches, see [transition](crate::transition). Disabled
+    /// by default.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use core::time::Duration;
+    /// # use css_in_rs::{transition::ThemeTransition, EmptyTheme, StyleProvider};
+    /// let style_provider = StyleProvider::builder()
+    ///     .theme_transition(ThemeTransition::new(Duration::from_millis(200)))
+    ///     .build_web(EmptyTheme);
+    /// ```
+    #[doc_cfg(feature = "web-sys")]
+    pub fn theme_transition(mut self, transition: ThemeTransition) -> Self {
+        self.theme_transition = Some(transition);
+        self
+    }
+
+    /// Builds a [StyleProvider] which mounts its styles using a custom [Backend]
+    pub fn build_with_backend<B: Backend<T>>(self, backend: B, theme: T) -> StyleProvider<T> {
+        let mut inner = Inner::new_with_prefix(Box::new(backend), theme, self.prefix);
+        inner.format = self.format;
+        inner.direction = self.direction;
+        inner.logical_properties = self.logical_properties;
+        inner.dark_mode = self.dark_mode;
+        inner.sanitize = self.sanitize;
+        inner.chunked = self.chunked;
+        for (name, value) in &self.style_attributes {
+            inner
+                .backend
+                .set_attribute(name, value)
+                .unwrap_or_else(|err| panic!("{err}"));
+        }
+        #[cfg(feature = "web-sys")]
+        {
+            inner.theme_transition = self.theme_transition;
+        }
+        #[cfg(feature = "web-sys")]
+        if let Some(key) = self.persist_key.filter(|_| cfg!(debug_assertions)) {
+            if let Some(storage) = crate::backend::web::session_storage() {
+                let saved = storage.get_item(&key).ok().flatten().unwrap_or_default();
+                let save = move |assignments: &str| {
+                    let _ = storage.set_item(&key, assignments);
+                };
+                inner.pin_classnames(&saved, Box::new(save));
+            }
+        }
+        if !self.layers.is_empty() {
+            inner.layers = self.layers;
+            inner.update();
+        }
+        StyleProvider::from_inner(inner)
+    }
+
+    /// Builds a [StyleProvider] which keeps all styles in memory, see
+    /// [StyleProvider::new_headless]
+    pub fn build_headless(self, theme: T) -> StyleProvider<T> {
+        self.build_with_backend(MemoryBackend::new(), theme)
+    }
+
+    /// Builds a [SyncStyleProvider], which keeps its styles in memory like
+    /// [StyleProviderBuilder::build_headless]. Settings which need a browser,
+    /// like a theme transition, are ignored.
+    #[cfg_attr(not(feature = "web-sys"), allow(unused_mut))]
+    pub fn build_sync(mut self, theme: T) -> SyncStyleProvider<T>
+    where
+        T: Send,
+    {
+        #[cfg(feature = "web-sys")]
+        {
+            self.persist_key = None;
+            self.theme_transition = None;
+        }
+        SyncStyleProvider::from_headless(self.build_headless(theme))
+    }
+
+    /// Builds a [StyleProvider] which mounts its styles in the active document,
+    /// see [StyleProvider::quickstart_web]
+    #[cfg(feature = "web-sys")]
+    pub fn build_web(self, theme: T) -> StyleProvider<T> {
+        self.build_with_backend(crate::backend::web::WebSysBackend::quickstart(), theme)
+    }
+}
+
+/// Whether `s` can be used as a CSS identifier without escaping
+fn is_ident(s: &str) -> bool {
+    s.chars()
+        .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+        && s.starts_with(|ch: char| !ch.is_ascii_digit())
+}
+
+/// An entry of the stylesheet, see [Inner::order]
+enum Item {
+    RawCss(usize),
+    Generator(usize),
+}
+
+/// The classnames assigned in previous sessions, see
+/// [StyleProviderBuilder::persist_classnames]
+struct PinnedClassnames {
+    /// The reserved range of classnames, by the type name of the [Classes]
+    ranges: BTreeMap<String, (u64, u64)>,
+    save: Box<dyn Fn(&str)>,
 }
 
-impl<T: Theme> CssGenerator<T> {
-    fn generate(&self, theme: &T, css: &mut String) {
-        let mut counter = self.start;
-        (self.generator)(theme, css, &mut counter);
-        assert_eq!(counter, self.stop);
+impl PinnedClassnames {
+    /// Parses lines like `3 5 app::ButtonClasses`. Invalid lines are skipped.
+    fn parse(saved: &str) -> BTreeMap<String, (u64, u64)> {
+        let parse_line = |line: &str| {
+            let (start, rest) = line.split_once(' ')?;
+            let (stop, name) = rest.split_once(' ')?;
+            let range = (start.parse().ok()?, stop.parse().ok()?);
+            Some((name.to_owned(), range))
+        };
+        saved.lines().filter_map(parse_line).collect()
+    }
+
+    fn save(&self) {
+        let lines = self
+            .ranges
+            .iter()
+            .map(|(name, (start, stop))| format!("{start} {stop} {name}\n"));
+        (self.save)(&lines.collect::<String>());
     }
 }
 
+/// A stylesheet mounted by [StyleProvider::add_raw_css]
+struct RawCss {
+    source: String,
+    classes: ScopedClasses,
+    /// The scoped stylesheet
+    css: String,
+    /// The number of generators mounted before, to keep the order of the
+    /// stylesheet when it's regenerated
+    position: usize,
+}
+
+/// A provider created by [StyleProvider::scoped]
+struct Scope<T> {
+    inner: Weak<RefCell<Inner<T>>>,
+    derive_theme: Box<dyn Fn(&T) -> T>,
+}
+
 struct Inner<T> {
     backend: Box<dyn Backend<T>>,
     current_theme: T,
-    generators: Vec<CssGenerator<T>>,
-    generator_to_idx: std::collections::BTreeMap<CssGeneratorFn<T>, usize>,
+    /// Incremented whenever the theme changes
+    theme_generation: u64,
+    /// See [StyleProvider::on_theme_change]
+    theme_listeners: Rc<ThemeListeners<T>>,
+    /// Calls of the theme listeners of this provider and its scopes, which
+    /// are made once the provider is no longer borrowed
+    pending_notifications: Vec<Box<dyn FnOnce()>>,
+    mounted: Mounted<T>,
+    /// The prefix of all classnames, i.e. `css` for `css-0`
+    prefix: String,
     counter: u64,
+    mounted_atoms: std::collections::BTreeSet<&'static str>,
+    format: CssFormat,
+    direction: Direction,
+    /// See [StyleProviderBuilder::logical_properties]
+    logical_properties: bool,
+    /// See [StyleProviderBuilder::dark_mode]
+    dark_mode: DarkMode,
+    sanitize: SanitizePolicy,
+    profiling: bool,
+    /// See [StyleProviderBuilder::chunked]
+    chunked: bool,
+    /// The generators used while a [RenderCollector] is alive, by collector
+    collectors: Vec<Weak<RefCell<Vec<usize>>>>,
+    scopes: Vec<Scope<T>>,
+    /// Number of scoped providers created so far, used for their prefixes
+    scope_counter: u64,
+    /// The order of cascade layers, see [StyleProviderBuilder::layers]
+    layers: Vec<String>,
+    /// Keyframes mounted by [StyleProvider::add_keyframes]
+    keyframes: Vec<Keyframes>,
+    /// The rules of the fonts mounted by [StyleProvider::add_font_face]
+    font_faces: Vec<String>,
+    /// Stylesheets mounted by [StyleProvider::add_raw_css]
+    raw_css: Vec<RawCss>,
+    /// Stylesheets linked by [StyleProvider::link_external]
+    external: Vec<(String, LinkPosition)>,
+    pinned: Option<PinnedClassnames>,
+    /// The provider owning this state, used to flush the backend later
+    #[cfg_attr(not(feature = "web-sys"), allow(dead_code))]
+    this: Weak<RefCell<Inner<T>>>,
+    /// Whether the backend will be flushed in the next animation frame
+    flush_scheduled: bool,
+    /// See [StyleProviderBuilder::theme_transition]
+    #[cfg(feature = "web-sys")]
+    theme_transition: Option<ThemeTransition>,
+    /// Whether the rule of the theme transition is emitted
+    #[cfg(feature = "web-sys")]
+    transitioning: bool,
+    /// The duration of the last call of `update`, see [StyleStats::last_update]
+    last_update: Option<core::time::Duration>,
+    events: EventLog,
+    /// Sum of the estimates of all generators and the size of their atoms,
+    /// see [Classes::CSS_CAPACITY_HINT]
+    css_estimate: usize,
 }
 
 impl<T: Theme> Inner<T> {
@@ -122,56 +1398,1503 @@ impl<T: Theme> Inner<T> {
     }
 
     pub fn new_with_backend<B: Backend<T>>(backend: B, theme: T) -> Self {
-        let backend = Box::new(backend);
+        Self::new_with_prefix(Box::new(backend), theme, DEFAULT_PREFIX.to_owned())
+    }
+
+    pub fn new_with_prefix(backend: Box<dyn Backend<T>>, theme: T, prefix: String) -> Self {
         Self {
             backend,
             current_theme: theme,
-            generators: Default::default(),
-            generator_to_idx: Default::default(),
+            theme_generation: 0,
+            theme_listeners: ThemeListeners::new(),
+            pending_notifications: Vec::new(),
+            mounted: Mounted {
+                generators: Default::default(),
+                generator_to_idx: Default::default(),
+            },
+            prefix,
             counter: 0,
+            mounted_atoms: Default::default(),
+            format: CssFormat::default(),
+            direction: Direction::default(),
+            logical_properties: false,
+            dark_mode: DarkMode::default(),
+            sanitize: SanitizePolicy::default(),
+            profiling: false,
+            chunked: false,
+            collectors: Default::default(),
+            scopes: Default::default(),
+            scope_counter: 0,
+            layers: Default::default(),
+            keyframes: Default::default(),
+            font_faces: Default::default(),
+            raw_css: Default::default(),
+            external: Default::default(),
+            pinned: None,
+            this: Weak::new(),
+            flush_scheduled: false,
+            #[cfg(feature = "web-sys")]
+            theme_transition: None,
+            #[cfg(feature = "web-sys")]
+            transitioning: false,
+            last_update: None,
+            events: Default::default(),
+            css_estimate: 0,
         }
     }
 
-    pub fn add_css_generator(&mut self, generator: CssGeneratorFn<T>) -> u64 {
-        debug_assert_eq!(self.generator_to_idx.len(), self.generators.len());
+    /// Restores the classnames saved by `save`, see
+    /// [StyleProviderBuilder::persist_classnames]. New [Classes] get
+    /// classnames after all restored ones, so they cannot collide.
+    #[cfg_attr(not(feature = "web-sys"), allow(dead_code))]
+    fn pin_classnames(&mut self, saved: &str, save: Box<dyn Fn(&str)>) {
+        let ranges = PinnedClassnames::parse(saved);
+        let stop = ranges.values().map(|(_, stop)| *stop).max();
+        self.counter = self.counter.max(stop.unwrap_or_default());
+        self.pinned = Some(PinnedClassnames { ranges, save });
+    }
+
+    /// Returns the restored start of the classnames of the given generator, if
+    /// it still reserves the same number of classnames
+    fn pinned_start(&self, generator: CssGeneratorFn<T>, name: &str) -> Option<u64> {
+        let (start, stop) = *self.pinned.as_ref()?.ranges.get(name)?;
+        let mut ctx = GenerateContext {
+            counter: start,
+            prefix: &self.prefix,
+            mounted: &self.mounted,
+            sanitize: self.sanitize,
+        };
+        (generator)(&self.current_theme, &mut String::new(), &mut ctx);
+        (ctx.counter == stop).then_some(start)
+    }
+
+    fn measure(&self, name: impl FnOnce() -> String) -> Option<Measurement> {
+        self.profiling.then(|| Measurement::start(name()))
+    }
+
+    /// Mounts the given [Classes] unless they are mounted already, and returns
+    /// the first number of their classnames
+    pub fn add_classes<C>(&mut self) -> u64
+    where
+        C: Classes<Theme = T>,
+    {
+        let generator: CssGeneratorFn<T> = C::generate;
+        let name = std::any::type_name::<C>();
+        let estimate = C::CSS_CAPACITY_HINT;
+        let priority = C::PRIORITY;
+        let mounted = &mut self.mounted;
+        debug_assert_eq!(mounted.generator_to_idx.len(), mounted.generators.len());
 
-        match self.generator_to_idx.entry(generator) {
+        match mounted.generator_to_idx.entry(generator) {
             Entry::Vacant(vac) => {
-                vac.insert(self.generators.len());
+                vac.insert(mounted.generators.len());
             }
             Entry::Occupied(occ) => {
                 let idx = *occ.get();
-                return self.generators[idx].start;
+                self.record_use(idx);
+                return self.mounted.generators[idx].start;
             }
         }
+        self.record_use(self.mounted.generators.len());
+
+        let _measurement = self.measure(|| format!("css-in-rs: add {name}"));
+
+        // Appending is only possible if nothing mounted so far is emitted after these styles
+        let reorder = self.chunked
+            || self
+                .mounted
+                .generators
+                .iter()
+                .any(|generator| generator.priority > priority)
+            || (priority < 0 && !self.raw_css.is_empty());
+
+        // Atomic rules are shared between all classes, so each of them is only mounted once
+        let atoms = C::ATOMS
+            .iter()
+            .copied()
+            .filter(|atom| self.mounted_atoms.insert(atom))
+            .collect::<Vec<_>>();
+        if !atoms.is_empty() && !reorder {
+            let mut css = String::new();
+            push_atoms(&mut css, &self.prefix, &atoms);
+            self.backend.append(&self.postprocess(css));
+        }
+        // `.{prefix}-` replaces the leading `.` of every atom, see `push_atoms`
+        let atoms_len = atoms.iter().map(|atom| self.prefix.len() + 1 + atom.len());
+        self.css_estimate += estimate + atoms_len.sum::<usize>();
+
+        let start = self.pinned_start(generator, name).unwrap_or(self.counter);
+        let mut ctx = GenerateContext {
+            counter: start,
+            prefix: &self.prefix,
+            mounted: &self.mounted,
+            sanitize: self.sanitize,
+        };
+        if reorder {
+            // Only reserves the classnames, the styles are emitted by `update`
+            (generator)(&self.current_theme, &mut String::new(), &mut ctx);
+        } else if self.format == CssFormat::Generated && !self.has_postprocessing() {
+            self.backend
+                .run_css_generator(generator, &self.current_theme, &mut ctx);
+        } else {
+            let mut css = String::with_capacity(estimate);
+            (generator)(&self.current_theme, &mut css, &mut ctx);
+            self.backend.append(&self.postprocess(css));
+        }
+        let stop = ctx.counter;
+        self.counter = self.counter.max(stop);
+        if let Some(pinned) = &mut self.pinned {
+            pinned.ranges.insert(name.to_owned(), (start, stop));
+            pinned.save();
+        }
 
-        let start = self.counter;
-        self.backend
-            .run_css_generator(generator, &self.current_theme, &mut self.counter);
-        let stop = self.counter;
         let generator = CssGenerator {
             generator,
+            name,
+            atoms,
+            all_atoms: C::ATOMS,
             start,
             stop,
+            estimate,
+            class_names: C::CLASS_NAMES,
+            class_locations: C::CLASS_LOCATIONS,
+            replacement: None,
+            priority,
+            reads_theme: C::READS_THEME,
+            cached: None,
+            enabled: true,
+            #[cfg(feature = "graph")]
+            info: C::INFO,
         };
 
-        self.generators.push(generator);
+        self.mounted.generators.push(generator);
+        if reorder {
+            self.update();
+        }
+        self.backend.mounted(name);
+        self.events
+            .record(|| StyleEventKind::Mounted { name, start });
+        self.schedule_flush();
         start
     }
 
-    fn update(&mut self) {
-        let mut css = String::default();
-        for generator in &self.generators {
-            generator.generate(&self.current_theme, &mut css);
+    /// Appends styles shared by all [Classes], or generates all chunks again,
+    /// see [StyleProviderBuilder::chunked]
+    fn append(&mut self, css: String) {
+        match self.chunked {
+            true => self.update(),
+            false => self.backend.append(&self.postprocess(css)),
         }
+    }
 
-        self.backend.replace_all(css);
+    /// Tells the [RenderCollector]s that the generator has been used
+    fn record_use(&mut self, idx: usize) {
+        self.collectors.retain(|collector| {
+            let Some(collector) = collector.upgrade() else {
+                return false;
+            };
+            let mut used = collector.borrow_mut();
+            if !used.contains(&idx) {
+                used.push(idx);
+            }
+            true
+        });
     }
 
-    pub fn update_theme(&mut self, theme: T) {
-        if !self.current_theme.fast_cmp(&theme) {
-            self.current_theme = theme;
-            self.update();
+    /// Returns the type names and the styles of the given generators, in the
+    /// order of the stylesheet, together with all styles which are shared by
+    /// all [Classes], like keyframes or raw stylesheets
+    pub fn collected(&self, used: &[usize]) -> (Vec<&'static str>, String) {
+        use core::fmt::Write;
+
+        let mut css = String::new();
+        if !self.layers.is_empty() {
+            let _ = writeln!(css, "@layer {};", self.layers.join(", "));
         }
+        for keyframes in &self.keyframes {
+            css.push_str(&keyframes.css());
+        }
+        for font_face in &self.font_faces {
+            css.push_str(font_face);
+        }
+
+        let mut names = Vec::with_capacity(used.len());
+        // Atomic rules may have been mounted first by generators which have
+        // not been used
+        let mut atoms = std::collections::BTreeSet::new();
+        for item in self.order() {
+            match item {
+                Item::Generator(idx) if used.contains(&idx) => {
+                    let generator = &self.mounted.generators[idx];
+                    names.push(generator.name);
+                    if !generator.enabled {
+                        continue;
+                    }
+
+                    let new_atoms = generator
+                        .all_atoms
+                        .iter()
+                        .filter(|atom| atoms.insert(**atom));
+                    push_atoms(&mut css, &self.prefix, new_atoms);
+                    generator.generate_rules(
+                        &self.current_theme,
+                        &mut css,
+                        &self.prefix,
+                        &self.mounted,
+                        self.sanitize,
+                    );
+                }
+                Item::Generator(_) => {}
+                Item::RawCss(idx) => css.push_str(&self.raw_css[idx].css),
+            }
+        }
+
+        (names, self.postprocess(css))
+    }
+
+    /// Returns the type names and the styles of everything mounted since the
+    /// given position, which is advanced, see [StyleStream]
+    pub fn streamed(&self, position: &mut StreamPosition) -> (Vec<&'static str>, String) {
+        use core::fmt::Write;
+
+        let mut css = String::new();
+        if !position.started && !self.layers.is_empty() {
+            let _ = writeln!(css, "@layer {};", self.layers.join(", "));
+        }
+        position.started = true;
+        for keyframes in &self.keyframes[position.keyframes..] {
+            css.push_str(&keyframes.css());
+        }
+        for font_face in &self.font_faces[position.font_faces..] {
+            css.push_str(font_face);
+        }
+
+        let mut names = Vec::new();
+        for item in self.order() {
+            match item {
+                Item::Generator(idx) if idx >= position.generators => {
+                    let generator = &self.mounted.generators[idx];
+                    names.push(generator.name);
+                    if !generator.enabled {
+                        continue;
+                    }
+
+                    // Atomic rules of earlier chunks are still part of the document
+                    let new_atoms = generator
+                        .all_atoms
+                        .iter()
+                        .filter(|atom| position.atoms.insert(**atom));
+                    push_atoms(&mut css, &self.prefix, new_atoms);
+                    generator.generate_rules(
+                        &self.current_theme,
+                        &mut css,
+                        &self.prefix,
+                        &self.mounted,
+                        self.sanitize,
+                    );
+                }
+                Item::RawCss(idx) if idx >= position.raw_css => {
+                    css.push_str(&self.raw_css[idx].css)
+                }
+                _ => {}
+            }
+        }
+
+        position.keyframes = self.keyframes.len();
+        position.font_faces = self.font_faces.len();
+        position.raw_css = self.raw_css.len();
+        position.generators = self.mounted.generators.len();
+        if css.is_empty() {
+            return (names, css);
+        }
+        (names, self.postprocess(css))
+    }
+
+    pub fn add_keyframes(&mut self, keyframes: Keyframes) {
+        if self.keyframes.iter().any(|k| k.name() == keyframes.name()) {
+            return;
+        }
+
+        self.keyframes.push(keyframes);
+        self.append(keyframes.css());
+        self.events.record(|| StyleEventKind::KeyframesMounted {
+            name: keyframes.name(),
+        });
+        self.schedule_flush();
+    }
+
+    pub fn add_font_face(&mut self, css: String) {
+        if self.font_faces.contains(&css) {
+            return;
+        }
+
+        self.font_faces.push(css.clone());
+        self.append(css);
+        self.schedule_flush();
+    }
+
+    pub fn add_raw_css(&mut self, src: &str) -> ScopedClasses {
+        if let Some(raw_css) = self.raw_css.iter().find(|raw_css| raw_css.source == src) {
+            return raw_css.classes.clone();
+        }
+
+        let start = self.counter;
+        let (css, classes) = crate::raw_css::scope(src, &self.prefix, start);
+        self.counter += classes.len() as u64;
+        self.css_estimate += css.len();
+        self.events
+            .record(|| StyleEventKind::RawCssMounted { start });
+
+        // Raw stylesheets have the default priority
+        let reorder = self.chunked
+            || self
+                .mounted
+                .generators
+                .iter()
+                .any(|generator| generator.priority > 0);
+        if !reorder {
+            self.backend.append(&self.postprocess(css.clone()));
+        }
+        self.raw_css.push(RawCss {
+            source: src.to_owned(),
+            classes: classes.clone(),
+            css,
+            position: self.mounted.generators.len(),
+        });
+        if reorder {
+            self.update();
+        }
+        self.schedule_flush();
+        classes
+    }
+
+    pub fn link_external(&mut self, url: String, position: LinkPosition) -> Result<(), Error> {
+        if self.external.iter().any(|(linked, _)| *linked == url) {
+            return Ok(());
+        }
+
+        self.backend.link_external(&url, position)?;
+        self.external.push((url, position));
+        Ok(())
+    }
+
+    pub fn unlink_external(&mut self, url: &str) -> bool {
+        let Some(idx) = self.external.iter().position(|(linked, _)| linked == url) else {
+            return false;
+        };
+
+        self.backend.unlink_external(url);
+        self.external.remove(idx);
+        true
+    }
+
+    pub fn hot_replace(&mut self, name: &str, css: &str) -> bool {
+        let is_named = |generator: &&mut CssGenerator<T>| {
+            let struct_name = generator.name.rsplit("::").next();
+            generator.name == name || struct_name == Some(name)
+        };
+        let Some(generator) = self.mounted.generators.iter_mut().find(is_named) else {
+            return false;
+        };
+
+        let prefix = &self.prefix;
+        let classname = |class: &str| {
+            let idx = generator
+                .class_names
+                .iter()
+                .position(|name| *name == class)?;
+            Some(format!("{prefix}-{}", generator.start + idx as u64))
+        };
+        generator.replacement = Some(crate::raw_css::scope_with(css, classname));
+        self.update();
+        true
+    }
+
+    pub fn lookup(&self, class_name: &str) -> Option<RuleInfo> {
+        let number = class_name.strip_prefix(&self.prefix)?.strip_prefix('-')?;
+        let number = number.parse::<u64>().ok()?;
+        let generator = self
+            .mounted
+            .generators
+            .iter()
+            .find(|generator| (generator.start..generator.stop).contains(&number))?;
+
+        let idx = (number - generator.start) as usize;
+        Some(RuleInfo {
+            classes: generator.name,
+            field: generator.class_names.get(idx)?,
+            location: generator.class_locations.get(idx).copied(),
+        })
+    }
+
+    fn update(&mut self) {
+        self.regenerate(true);
+    }
+
+    /// The raw stylesheets and generators in the order of the stylesheet:
+    /// Sorted by priority, then by mount order. A raw stylesheet comes before
+    /// the generator mounted right after it.
+    fn order(&self) -> Vec<Item> {
+        let mut order = Vec::with_capacity(self.raw_css.len() + self.mounted.generators.len());
+        for (idx, raw_css) in self.raw_css.iter().enumerate() {
+            order.push(((0, 2 * raw_css.position), Item::RawCss(idx)));
+        }
+        for (idx, generator) in self.mounted.generators.iter().enumerate() {
+            order.push(((generator.priority, 2 * idx + 1), Item::Generator(idx)));
+        }
+        order.sort_unstable_by_key(|(key, _)| *key);
+        order.into_iter().map(|(_, item)| item).collect()
+    }
+
+    /// Generates all styles again, except the ones which do not read the
+    /// theme unless `all` is set, see [Classes::READS_THEME]
+    fn regenerate(&mut self, all: bool) {
+        use core::fmt::Write;
+
+        let _measurement = self.measure(|| "css-in-rs: update".to_owned());
+        let stopwatch = Stopwatch::start();
+
+        let mut css = String::with_capacity(self.css_estimate);
+        if !self.layers.is_empty() {
+            let _ = writeln!(css, "@layer {};", self.layers.join(", "));
+        }
+        #[cfg(feature = "web-sys")]
+        if let Some(transition) = self
+            .theme_transition
+            .as_ref()
+            .filter(|_| self.transitioning)
+        {
+            css.push_str(&transition.css());
+        }
+        for keyframes in &self.keyframes {
+            css.push_str(&keyframes.css());
+        }
+        for font_face in &self.font_faces {
+            css.push_str(font_face);
+        }
+
+        // Only split up if chunked, see `StyleProviderBuilder::chunked`
+        let mut chunks = Vec::new();
+        let mut cached = Vec::new();
+        for item in self.order() {
+            match item {
+                Item::Generator(idx) => {
+                    let generator = &self.mounted.generators[idx];
+                    if self.chunked && !css.is_empty() {
+                        let css = core::mem::take(&mut css);
+                        chunks.push(StyleChunk { name: None, css });
+                    }
+
+                    let keep = !all && !generator.reads_theme;
+                    if !generator.enabled {
+                        // Atomic rules are shared with other classes
+                        push_atoms(&mut css, &self.prefix, &generator.atoms);
+                    } else if let Some(cache) = generator.cached.as_ref().filter(|_| keep) {
+                        css.push_str(cache);
+                    } else {
+                        let start = css.len();
+                        generator.generate(
+                            &self.current_theme,
+                            &mut css,
+                            &self.prefix,
+                            &self.mounted,
+                            self.sanitize,
+                        );
+                        if !generator.reads_theme {
+                            cached.push((idx, css[start..].to_owned()));
+                        }
+                    }
+
+                    if self.chunked && !css.is_empty() {
+                        let css = core::mem::take(&mut css);
+                        chunks.push(StyleChunk {
+                            name: Some(generator.name),
+                            css,
+                        });
+                    }
+                }
+                Item::RawCss(idx) => css.push_str(&self.raw_css[idx].css),
+            }
+        }
+        for (idx, css) in cached {
+            self.mounted.generators[idx].cached = Some(css);
+        }
+
+        if self.chunked {
+            if !css.is_empty() {
+                chunks.push(StyleChunk { name: None, css });
+            }
+            let chunks = chunks
+                .into_iter()
+                .map(|chunk| StyleChunk {
+                    css: self.postprocess(chunk.css),
+                    ..chunk
+                })
+                .collect();
+            self.backend.replace_chunks(chunks);
+        } else {
+            self.backend.replace_all(self.postprocess(css));
+        }
+        self.last_update = stopwatch.elapsed();
+        self.schedule_flush();
+    }
+
+    /// Makes sure that the changes deferred by the backend are written before
+    /// the next repaint, see [Backend::needs_flush]
+    fn schedule_flush(&mut self) {
+        if self.flush_scheduled || !self.backend.needs_flush() {
+            return;
+        }
+
+        #[cfg(feature = "web-sys")]
+        {
+            let this = self.this.clone();
+            self.flush_scheduled = crate::backend::web::request_animation_frame(move || {
+                if let Some(inner) = this.upgrade() {
+                    inner.borrow_mut().flush();
+                }
+            });
+        }
+        if !self.flush_scheduled {
+            self.backend.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        self.flush_scheduled = false;
+        self.backend.flush();
+        self.for_each_scope(Inner::flush);
+    }
+
+    pub fn set_enabled(&mut self, generator: CssGeneratorFn<T>, enabled: bool) -> bool {
+        let Some(&idx) = self.mounted.generator_to_idx.get(&generator) else {
+            return false;
+        };
+
+        let generator = &mut self.mounted.generators[idx];
+        if generator.enabled != enabled {
+            generator.enabled = enabled;
+            // The cache is not updated while disabled
+            generator.cached = None;
+            self.regenerate(false);
+        }
+        true
+    }
+
+    pub fn css_of(&self, generator: CssGeneratorFn<T>) -> Option<String> {
+        let idx = *self.mounted.generator_to_idx.get(&generator)?;
+        Some(self.css_at(idx))
+    }
+
+    /// Whether generated CSS is changed by [Inner::postprocess], apart from
+    /// formatting
+    fn has_postprocessing(&self) -> bool {
+        self.logical_properties
+            || self.direction != Direction::Ltr
+            || self.dark_mode != DarkMode::Media
+    }
+
+    /// Applies the logical properties, the direction, the dark mode and the
+    /// format to newly generated CSS
+    fn postprocess(&self, css: String) -> String {
+        let css = match self.logical_properties {
+            true => crate::direction::to_logical(&css),
+            false => css,
+        };
+        let css = self.dark_mode.apply(self.direction.apply(css));
+        self.format.apply(css)
+    }
+
+    fn css_at(&self, idx: usize) -> String {
+        let mut css = String::with_capacity(self.mounted.generators[idx].estimate);
+        self.mounted.generators[idx].generate(
+            &self.current_theme,
+            &mut css,
+            &self.prefix,
+            &self.mounted,
+            self.sanitize,
+        );
+        self.postprocess(css)
+    }
+
+    /// Returns the type names and styles of all [Classes] which have been
+    /// mounted after the first `count` ones
+    pub fn mounted_after(&self, count: usize) -> Vec<(&'static str, String)> {
+        (count..self.mounted.generators.len())
+            .map(|idx| (self.mounted.generators[idx].name, self.css_at(idx)))
+            .collect()
+    }
+
+    pub fn mounted_count(&self) -> usize {
+        self.mounted.generators.len()
+    }
+
+    pub fn set_format(&mut self, format: CssFormat) {
+        if self.format != format {
+            self.format = format;
+            self.update();
+            self.events.record(|| StyleEventKind::FormatChanged(format));
+            self.for_each_scope(|inner| inner.set_format(format));
+        }
+    }
+
+    pub fn set_direction(&mut self, direction: Direction) {
+        if self.direction != direction {
+            self.direction = direction;
+            self.update();
+            self.events
+                .record(|| StyleEventKind::DirectionChanged(direction));
+            self.for_each_scope(|inner| inner.set_direction(direction));
+        }
+    }
+
+    #[cfg(feature = "web-sys")]
+    pub fn set_theme_transition(&mut self, transition: Option<ThemeTransition>) {
+        self.for_each_scope(|inner| inner.set_theme_transition(transition.clone()));
+        self.theme_transition = transition;
+    }
+
+    #[cfg(feature = "web-sys")]
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profiling = enabled;
+        self.for_each_scope(|inner| inner.set_profiling(enabled));
+    }
+
+    /// Calls `f` with the state of every scoped provider which is still alive
+    fn for_each_scope(&self, mut f: impl FnMut(&mut Inner<T>)) {
+        for scope in &self.scopes {
+            if let Some(inner) = scope.inner.upgrade() {
+                f(&mut inner.borrow_mut());
+            }
+        }
+    }
+
+    pub fn update_theme(&mut self, theme: T) {
+        if !self.current_theme.fast_cmp(&theme) {
+            self.current_theme = theme;
+            self.theme_generation += 1;
+            #[cfg(feature = "web-sys")]
+            self.start_theme_transition();
+            self.regenerate(false);
+            let generation = self.theme_generation;
+            self.events
+                .record(|| StyleEventKind::ThemeUpdated { generation });
+            if !self.theme_listeners.is_empty() {
+                let listeners = self.theme_listeners.clone();
+                let theme = self.current_theme.clone();
+                self.pending_notifications
+                    .push(Box::new(move || listeners.notify(&theme)));
+            }
+            self.update_scopes();
+        }
+    }
+
+    /// Emits the rule of the theme transition with the next update, and
+    /// removes it once the transition is over
+    #[cfg(feature = "web-sys")]
+    fn start_theme_transition(&mut self) {
+        let Some(transition) = &self.theme_transition else {
+            return;
+        };
+
+        let this = self.this.clone();
+        let generation = self.theme_generation;
+        self.transitioning = crate::backend::web::set_timeout(transition.duration(), move || {
+            if let Some(inner) = this.upgrade() {
+                let mut inner = inner.borrow_mut();
+                // Another transition has started in the meantime
+                if inner.theme_generation == generation && inner.transitioning {
+                    inner.transitioning = false;
+                    inner.regenerate(false);
+                }
+            }
+        });
+    }
+
+    /// Generates the styles for `theme` without mounting them, and returns the
+    /// first value rejected by [SanitizePolicy::Reject]
+    fn rejected_value(&self, theme: &T) -> Option<String> {
+        crate::sanitize::take_rejected();
+        let mut css = String::new();
+        if self.sanitize == SanitizePolicy::Reject {
+            for generator in &self.mounted.generators {
+                generator.generate_rules(
+                    theme,
+                    &mut css,
+                    &self.prefix,
+                    &self.mounted,
+                    self.sanitize,
+                );
+                css.clear();
+            }
+        }
+        if let Some(value) = crate::sanitize::take_rejected() {
+            return Some(value);
+        }
+
+        self.scopes.iter().find_map(|scope| {
+            let inner = scope.inner.upgrade()?;
+            let theme = (scope.derive_theme)(theme);
+            let value = inner.borrow().rejected_value(&theme);
+            value
+        })
+    }
+
+    fn update_scopes(&mut self) {
+        // Scoped providers which have been dropped are forgotten
+        self.scopes.retain(|scope| scope.inner.strong_count() > 0);
+        for scope in &self.scopes {
+            if let Some(inner) = scope.inner.upgrade() {
+                let theme = (scope.derive_theme)(&self.current_theme);
+                let mut inner = inner.borrow_mut();
+                inner.update_theme(theme);
+                let pending = core::mem::take(&mut inner.pending_notifications);
+                self.pending_notifications.extend(pending);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::StyleProvider;
+    use crate::Theme;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Dark(bool);
+
+    impl Theme for Dark {
+        fn fast_cmp(&self, other: &Self) -> bool {
+            self == other
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    fn current(provider: &StyleProvider<Dark>) -> Dark {
+        provider.inner.borrow().current_theme
+    }
+
+    #[test]
+    fn persist_theme_rollback() {
+        let provider = StyleProvider::new_headless(Dark(false));
+
+        let result = block_on(provider.persist_theme(Dark(true), |_| async { Ok::<_, ()>(()) }));
+        assert_eq!(result, Ok(()));
+        assert_eq!(current(&provider), Dark(true));
+
+        let result = block_on(provider.persist_theme(Dark(false), |_| async { Err(()) }));
+        assert_eq!(result, Err(()));
+        assert_eq!(current(&provider), Dark(true));
+
+        // A newer theme must not be overwritten by the rollback
+        let result = block_on(provider.persist_theme(Dark(false), |_| {
+            provider.update_theme(Dark(true));
+            provider.update_theme(Dark(false));
+            async { Err(()) }
+        }));
+        assert_eq!(result, Err(()));
+        assert_eq!(current(&provider), Dark(false));
+
+        block_on(provider.load_theme(async { Ok::<_, ()>(Some(Dark(true))) })).unwrap();
+        assert_eq!(current(&provider), Dark(true));
+    }
+
+    #[test]
+    fn scoped_theme_follows_parent() {
+        let provider = StyleProvider::new_headless(Dark(false));
+        let inverted = provider.scoped(|theme| Dark(!theme.0));
+        assert_eq!(current(&inverted), Dark(true));
+
+        provider.update_theme(Dark(true));
+        assert_eq!(current(&inverted), Dark(false));
+
+        // Dropped scopes are no longer updated
+        drop(inverted);
+        provider.update_theme(Dark(false));
+        assert!(provider.inner.borrow().scopes.is_empty());
+    }
+
+    #[test]
+    fn theme_change_listeners() {
+        use std::{cell::RefCell, rc::Rc};
+
+        let provider = StyleProvider::new_headless(Dark(false));
+        let inverted = provider.scoped(|theme| Dark(!theme.0));
+        let calls = Rc::new(RefCell::new(Vec::new()));
+
+        let subscription = provider.on_theme_change({
+            let (provider, calls) = (provider.clone(), calls.clone());
+            // The provider can be used by the callback
+            move |theme| calls.borrow_mut().push((*theme, current(&provider)))
+        });
+        inverted
+            .on_theme_change({
+                let calls = calls.clone();
+                move |theme| calls.borrow_mut().push((*theme, *theme))
+            })
+            .detach();
+
+        provider.update_theme(Dark(true));
+        provider.update_theme(Dark(true));
+        assert_eq!(
+            *calls.borrow(),
+            [(Dark(true), Dark(true)), (Dark(false), Dark(false))]
+        );
+
+        drop(subscription);
+        calls.borrow_mut().clear();
+        provider.update_theme(Dark(false));
+        assert_eq!(*calls.borrow(), [(Dark(true), Dark(true))]);
+    }
+
+    #[test]
+    fn theme_schema() {
+        use super::ThemeSchema;
+
+        assert_eq!(ThemeSchema::of::<Dark>(), ThemeSchema::of::<Dark>());
+        assert_ne!(
+            ThemeSchema::of::<Dark>(),
+            ThemeSchema::of::<crate::EmptyTheme>()
+        );
+
+        // Stands in for a theme before and after hot patching renamed a field
+        #[derive(Clone, PartialEq, crate::Theme)]
+        struct Before {
+            primary: String,
+        }
+        #[derive(Clone, PartialEq, crate::Theme)]
+        struct Same {
+            primary: String,
+        }
+        #[derive(Clone, PartialEq, crate::Theme)]
+        struct After {
+            secondary: String,
+        }
+        assert_eq!(Before::SCHEMA, Same::SCHEMA);
+        assert_ne!(Before::SCHEMA, After::SCHEMA);
+        assert_ne!(Before::SCHEMA, Dark::SCHEMA);
+    }
+
+    #[test]
+    fn erased_provider() {
+        use super::{ErasedProvider, ThemeSchema};
+
+        let provider = StyleProvider::new_headless(Dark(false));
+        let mut erased = ErasedProvider::new(provider.clone());
+        let same = erased.get::<Dark>().unwrap();
+        assert!(std::rc::Rc::ptr_eq(&same.inner, &provider.inner));
+        assert!(erased.get::<crate::EmptyTheme>().is_none());
+        drop(same);
+
+        // As if hot patching had changed the fields of `Dark`
+        erased.schema = ThemeSchema(0);
+        assert!(erased.get::<Dark>().is_none());
+
+        // The stale provider is released
+        drop(erased);
+        assert_eq!(std::rc::Rc::strong_count(&provider.inner), 1);
+    }
+
+    #[cfg(feature = "dioxus")]
+    #[test]
+    fn rebuilt_by_hooks() {
+        use std::cell::RefCell;
+
+        use dioxus::prelude::*;
+
+        use super::ThemeSchema;
+
+        thread_local! {
+            static CONTEXT: RefCell<Option<crate::ProviderContext<Dark>>> = const { RefCell::new(None) };
+            static CHILD: RefCell<Option<ScopeId>> = const { RefCell::new(None) };
+            static SEEN: RefCell<Vec<Option<usize>>> = const { RefCell::new(Vec::new()) };
+        }
+
+        fn app() -> Element {
+            crate::use_provider(|builder| Some(builder.build_headless(Dark(false))));
+            let context = try_consume_context::<crate::ProviderContext<Dark>>();
+            CONTEXT.with_borrow_mut(|slot| *slot = context);
+            rsx! { Child {} }
+        }
+
+        #[component]
+        fn Child() -> Element {
+            let provider = crate::try_use_style_provider::<Dark>();
+            let classes = <PanelClasses as crate::Classes>::use_style();
+            // Classnames of the stale provider must not be handed out
+            assert_eq!(classes.panel.starts_with("css"), provider.is_some());
+            CHILD.with_borrow_mut(|child| *child = current_scope_id().ok());
+            SEEN.with_borrow_mut(|seen| seen.push(provider.map(|provider| provider.id())));
+            rsx! {}
+        }
+
+        // As if hot patching had changed the fields of `Dark`
+        let make_stale = || {
+            let context = CONTEXT.with_borrow(|context| context.clone().unwrap());
+            context.slot.borrow_mut().as_mut().unwrap().schema = ThemeSchema(0);
+        };
+
+        let mut dom = VirtualDom::new(app);
+        dom.rebuild_in_place();
+        let first = SEEN.with_borrow(|seen| seen[0].unwrap());
+
+        // Both are rendered again, parents first
+        make_stale();
+        dom.mark_dirty(ScopeId::APP);
+        dom.mark_dirty(CHILD.with_borrow(|child| child.unwrap()));
+        dom.render_immediate(&mut dioxus::dioxus_core::NoOpMutations);
+        let second = SEEN.with_borrow(|seen| seen[1].unwrap());
+        assert_ne!(first, second);
+
+        // Only the child is rendered again; it waits for the provider
+        make_stale();
+        dom.mark_dirty(CHILD.with_borrow(|child| child.unwrap()));
+        dom.render_immediate(&mut dioxus::dioxus_core::NoOpMutations);
+        assert_eq!(SEEN.with_borrow(|seen| seen[2]), None);
+
+        dom.process_events();
+        dom.render_immediate(&mut dioxus::dioxus_core::NoOpMutations);
+        let third = SEEN.with_borrow(|seen| seen[3].unwrap());
+        assert_ne!(second, third);
+    }
+
+    /// Reserves `N` classnames without emitting any CSS
+    struct Reserve<const N: u64>;
+
+    impl<const N: u64> crate::Classes for Reserve<N> {
+        type Theme = Dark;
+
+        fn generate(_: &Dark, _: &mut String, ctx: &mut crate::GenerateContext<Dark>) {
+            ctx.reserve(N);
+        }
+
+        fn new(_: &str, _: u64) -> Self {
+            Reserve
+        }
+    }
+
+    #[test]
+    fn pinned_classnames() {
+        use std::{cell::RefCell, rc::Rc};
+
+        let start_of = |provider: &StyleProvider<Dark>, name: &str| {
+            let inner = provider.inner.borrow();
+            inner.pinned.as_ref().unwrap().ranges[name].0
+        };
+        let one = std::any::type_name::<Reserve<1>>();
+        let two = std::any::type_name::<Reserve<2>>();
+        let three = std::any::type_name::<Reserve<3>>();
+
+        let saved = Rc::new(RefCell::new(String::new()));
+        let provider = StyleProvider::new_headless(Dark(false));
+        let save = saved.clone();
+        let save = Box::new(move |assignments: &str| *save.borrow_mut() = assignments.to_owned());
+        provider.inner.borrow_mut().pin_classnames("", save);
+        provider.add_classes::<Reserve<1>>();
+        provider.add_classes::<Reserve<2>>();
+        assert_eq!(start_of(&provider, two), 1);
+
+        // `Reserve<2>` keeps its classnames, although it is mounted first. So
+        // does `Reserve<1>`, while the new `Reserve<3>` gets new ones.
+        let saved = format!("{}garbage\n", saved.borrow());
+        let provider = StyleProvider::new_headless(Dark(false));
+        provider
+            .inner
+            .borrow_mut()
+            .pin_classnames(&saved, Box::new(|_| {}));
+        provider.add_classes::<Reserve<2>>();
+        provider.add_classes::<Reserve<3>>();
+        provider.add_classes::<Reserve<1>>();
+        assert_eq!(start_of(&provider, two), 1);
+        assert_eq!(start_of(&provider, three), 3);
+        assert_eq!(start_of(&provider, one), 0);
+    }
+
+    /// Emits `i{order:P}` with the priority `P`
+    struct Prioritized<const P: i32>;
+
+    impl<const P: i32> crate::Classes for Prioritized<P> {
+        type Theme = Dark;
+        const PRIORITY: i32 = P;
+
+        fn generate(_: &Dark, css: &mut String, _: &mut crate::GenerateContext<Dark>) {
+            css.push_str(&format!("i{{order:{P}}}"));
+        }
+
+        fn new(_: &str, _: u64) -> Self {
+            Prioritized
+        }
+    }
+
+    #[test]
+    fn priority_order() {
+        let provider = StyleProvider::new_headless(Dark(false));
+        provider.set_format(crate::CssFormat::Minified);
+        provider.add_classes::<Prioritized<1>>();
+        provider.add_raw_css("b{order:0}");
+        provider.add_classes::<Prioritized<-1>>();
+        provider.add_classes::<Prioritized<0>>();
+        assert_eq!(
+            provider.current_css(),
+            "i{order:-1}b{order:0}i{order:0}i{order:1}"
+        );
+
+        // Regenerating keeps the order
+        provider.update_theme(Dark(true));
+        assert_eq!(
+            provider.current_css(),
+            "i{order:-1}b{order:0}i{order:0}i{order:1}"
+        );
+    }
+
+    /// Keeps the names of the last chunks
+    struct ChunkBackend {
+        css: String,
+        names: std::rc::Rc<core::cell::RefCell<Vec<Option<&'static str>>>>,
+    }
+
+    impl crate::backend::Backend<Dark> for ChunkBackend {
+        fn replace_all(&mut self, css: String) {
+            self.css = css;
+        }
+
+        fn append(&mut self, _: &str) {
+            unreachable!("Nothing is appended in chunked mode");
+        }
+
+        fn replace_chunks(&mut self, chunks: Vec<crate::backend::StyleChunk>) {
+            *self.names.borrow_mut() = chunks.iter().map(|chunk| chunk.name).collect();
+            self.css = chunks.into_iter().map(|chunk| chunk.css).collect();
+        }
+
+        fn current_css(&self) -> &str {
+            &self.css
+        }
+
+        fn run_css_generator(
+            &mut self,
+            _: crate::backend::CssGeneratorFn<Dark>,
+            _: &Dark,
+            _: &mut crate::GenerateContext<Dark>,
+        ) {
+            unreachable!("Nothing is appended in chunked mode");
+        }
+
+        fn new_scope(&self) -> Box<dyn crate::backend::Backend<Dark>> {
+            Box::new(ChunkBackend {
+                css: String::new(),
+                names: std::rc::Rc::default(),
+            })
+        }
+    }
+
+    #[test]
+    fn chunks() {
+        let names = std::rc::Rc::default();
+        let backend = ChunkBackend {
+            css: String::new(),
+            names: std::rc::Rc::clone(&names),
+        };
+        let provider = StyleProvider::builder()
+            .format(crate::CssFormat::Minified)
+            .chunked(true)
+            .build_with_backend(backend, Dark(false));
+        provider.add_classes::<Prioritized<1>>();
+        provider.add_raw_css("b{order:0}");
+        provider.add_classes::<Prioritized<0>>();
+
+        assert_eq!(provider.current_css(), "b{order:0}i{order:0}i{order:1}");
+        let expected = [
+            None,
+            Some(std::any::type_name::<Prioritized<0>>()),
+            Some(std::any::type_name::<Prioritized<1>>()),
+        ];
+        assert_eq!(*names.borrow(), expected);
+    }
+
+    #[test]
+    fn chunked_scopes() {
+        let names = std::rc::Rc::default();
+        let backend = ChunkBackend {
+            css: String::new(),
+            names: std::rc::Rc::clone(&names),
+        };
+        let provider = StyleProvider::builder()
+            .format(crate::CssFormat::Minified)
+            .chunked(true)
+            .build_with_backend(backend, Dark(false));
+        provider.add_classes::<Prioritized<0>>();
+
+        let scoped = provider.scoped(|_| Dark(true));
+        scoped.add_classes::<Prioritized<2>>();
+        scoped.add_classes::<Prioritized<1>>();
+
+        assert_eq!(provider.current_css(), "i{order:0}");
+        assert_eq!(scoped.current_css(), "i{order:1}i{order:2}");
+        let expected = [Some(std::any::type_name::<Prioritized<0>>())];
+        assert_eq!(*names.borrow(), expected);
+    }
+
+    /// Defers appended styles until they are flushed, like the web backend,
+    /// and counts the writes of all its scopes
+    struct FlushBackend {
+        css: String,
+        dirty: bool,
+        writes: std::rc::Rc<core::cell::Cell<usize>>,
+    }
+
+    impl FlushBackend {
+        fn new(writes: &std::rc::Rc<core::cell::Cell<usize>>) -> Self {
+            Self {
+                css: String::new(),
+                dirty: false,
+                writes: std::rc::Rc::clone(writes),
+            }
+        }
+    }
+
+    impl crate::backend::Backend<Dark> for FlushBackend {
+        fn replace_all(&mut self, css: String) {
+            self.css = css;
+            self.dirty = true;
+        }
+
+        fn append(&mut self, css: &str) {
+            self.css.push_str(css);
+            self.dirty = true;
+        }
+
+        fn current_css(&self) -> &str {
+            &self.css
+        }
+
+        fn run_css_generator(
+            &mut self,
+            generator: crate::backend::CssGeneratorFn<Dark>,
+            theme: &Dark,
+            ctx: &mut crate::GenerateContext<Dark>,
+        ) {
+            (generator)(theme, &mut self.css, ctx);
+            self.dirty = true;
+        }
+
+        fn new_scope(&self) -> Box<dyn crate::backend::Backend<Dark>> {
+            Box::new(FlushBackend::new(&self.writes))
+        }
+
+        fn needs_flush(&self) -> bool {
+            self.dirty
+        }
+
+        fn flush(&mut self) {
+            if self.dirty {
+                self.dirty = false;
+                self.writes.set(self.writes.get() + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn batched_flushes() {
+        let writes = std::rc::Rc::default();
+        let provider = StyleProvider::builder()
+            .format(crate::CssFormat::Minified)
+            .build_with_backend(FlushBackend::new(&writes), Dark(false));
+
+        // Pretend an animation frame has been requested already
+        provider.inner.borrow_mut().flush_scheduled = true;
+        provider.add_classes::<Prioritized<0>>();
+        provider.add_classes::<Prioritized<1>>();
+        provider.add_classes::<Prioritized<2>>();
+        assert_eq!(writes.get(), 0);
+
+        // The frame callback writes all of them at once
+        provider.inner.borrow_mut().flush();
+        assert_eq!(writes.get(), 1);
+        assert!(!provider.inner.borrow().flush_scheduled);
+        assert_eq!(provider.current_css(), "i{order:0}i{order:1}i{order:2}");
+
+        // Without a window, changes are written right away
+        provider.add_classes::<Prioritized<3>>();
+        assert_eq!(writes.get(), 2);
+        provider.add_classes::<Prioritized<3>>();
+        assert_eq!(writes.get(), 2);
+    }
+
+    #[test]
+    fn synchronous_flush() {
+        let writes = std::rc::Rc::default();
+        let provider = StyleProvider::builder()
+            .format(crate::CssFormat::Minified)
+            .build_with_backend(FlushBackend::new(&writes), Dark(false));
+        let scoped = provider.scoped(|_| Dark(true));
+
+        provider.inner.borrow_mut().flush_scheduled = true;
+        scoped.inner.borrow_mut().flush_scheduled = true;
+        provider.add_classes::<Prioritized<0>>();
+        scoped.add_classes::<Prioritized<1>>();
+        assert_eq!(writes.get(), 0);
+
+        // Flushing the parent writes the scope as well
+        provider.flush();
+        assert_eq!(writes.get(), 2);
+        assert!(!scoped.inner.borrow().flush_scheduled);
+
+        // Nothing is written twice
+        provider.flush();
+        scoped.flush();
+        assert_eq!(writes.get(), 2);
+    }
+
+    thread_local! {
+        static GENERATED: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+    }
+
+    /// Emits `i{order:0}` and counts how often it has been generated
+    struct Static;
+
+    impl crate::Classes for Static {
+        type Theme = Dark;
+        const READS_THEME: bool = false;
+
+        fn generate(_: &Dark, css: &mut String, _: &mut crate::GenerateContext<Dark>) {
+            GENERATED.with(|generated| generated.set(generated.get() + 1));
+            css.push_str("i{order:0}");
+        }
+
+        fn new(_: &str, _: u64) -> Self {
+            Static
+        }
+    }
+
+    #[test]
+    fn keeps_static_styles() {
+        let provider = StyleProvider::new_headless(Dark(false));
+        provider.set_format(crate::CssFormat::Minified);
+        provider.add_classes::<Static>();
+        provider.update_theme(Dark(true));
+        provider.update_theme(Dark(false));
+        assert_eq!(GENERATED.with(|generated| generated.get()), 2);
+        assert_eq!(provider.current_css(), "i{order:0}");
+
+        // Other changes generate all styles again
+        provider.set_format(crate::CssFormat::Pretty);
+        assert_eq!(GENERATED.with(|generated| generated.get()), 3);
+    }
+
+    crate::make_styles! {
+        #[atomic]
+        (_theme: Dark) -> IconClasses {
+            icon {
+                margin_left: "4px",
+            },
+        }
+    }
+
+    #[test]
+    fn prefixed_atoms() {
+        use crate::direction::Direction;
+
+        let provider = |prefix, direction| {
+            let provider = StyleProvider::builder()
+                .prefix(prefix)
+                .direction(direction)
+                .format(crate::CssFormat::Minified)
+                .build_headless(Dark(false));
+            let classes = provider.add_classes::<IconClasses>();
+            (classes.icon, provider.current_css())
+        };
+        let (ltr, ltr_css) = provider("app1", Direction::Ltr);
+        let (rtl, rtl_css) = provider("app2", Direction::Rtl);
+
+        // Both stylesheets are mounted in the same document
+        let ltr_atom = ltr.split(' ').nth(1).unwrap();
+        let rtl_atom = rtl.split(' ').nth(1).unwrap();
+        assert!(ltr_atom.starts_with("app1-a-"));
+        assert_eq!(rtl_atom, ltr_atom.replacen("app1", "app2", 1));
+        assert_eq!(ltr_css, format!(".{ltr_atom}{{margin-left:4px}}"));
+        assert_eq!(rtl_css, format!(".{rtl_atom}{{margin-right:4px}}"));
+    }
+
+    #[test]
+    fn disabled_classes() {
+        let provider = StyleProvider::new_headless(Dark(false));
+        provider.set_format(crate::CssFormat::Minified);
+        assert!(!provider.set_enabled::<Prioritized<0>>(false));
+        provider.add_classes::<Prioritized<0>>();
+        provider.add_classes::<Prioritized<1>>();
+
+        assert!(provider.set_enabled::<Prioritized<0>>(false));
+        provider.update_theme(Dark(true));
+        assert_eq!(provider.current_css(), "i{order:1}");
+        assert!(provider.set_enabled::<Prioritized<0>>(true));
+        assert_eq!(provider.current_css(), "i{order:0}i{order:1}");
+    }
+
+    crate::make_styles! {
+        (theme: Dark) -> EstimatedClasses {
+            ".card > .title" {
+                width: format!("{}px", 100 + theme.0 as u8),
+                color: "red",
+            },
+            title {
+                margin: "0 auto",
+            },
+        }
+    }
+
+    crate::make_styles! {
+        #[atomic]
+        (theme: Dark) -> EstimatedAtomicClasses {
+            badge {
+                padding: "2px",
+                margin_left: "4px",
+                width: format!("{}px", 100 + theme.0 as u8),
+            },
+        }
+    }
+
+    crate::make_styles! {
+        #[compact]
+        (theme: Dark) -> EstimatedCompactClasses {
+            ".row > .cell" {
+                width: format!("{}px", 100 + theme.0 as u8),
+                color: "red",
+            },
+            cell {
+                margin: "0 auto",
+            },
+        }
+    }
+
+    crate::make_styles! {
+        (theme: Dark) -> LongValueClasses {
+            grid {
+                grid_template_columns: "[a] 1fr ".repeat(20 + theme.0 as usize),
+            },
+        }
+    }
+
+    #[test]
+    fn css_capacity_hint() {
+        // Suffices for values of the usual length
+        fn check<C: crate::Classes<Theme = Dark>>() {
+            let css = crate::testing::render_classes_to_string::<C>(&Dark(false));
+            assert!(C::CSS_CAPACITY_HINT >= css.len(), "{css}");
+
+            // The preallocation includes the atomic rules
+            let provider = StyleProvider::new_headless(Dark(false));
+            provider.add_classes::<C>();
+            let css = provider.current_css();
+            assert!(provider.inner.borrow().css_estimate >= css.len(), "{css}");
+        }
+
+        check::<EstimatedClasses>();
+        check::<EstimatedAtomicClasses>();
+        check::<EstimatedCompactClasses>();
+
+        // Longer values exceed it, which only costs a reallocation
+        let provider = StyleProvider::new_headless(Dark(false));
+        provider.add_classes::<LongValueClasses>();
+        let css = provider.current_css();
+        assert!(<LongValueClasses as crate::Classes>::CSS_CAPACITY_HINT < css.len());
+        assert!(css.contains(&"[a] 1fr ".repeat(20)), "{css}");
+    }
+
+    crate::make_styles! {
+        (_theme: Dark) -> PanelClasses {
+            panel {
+                padding_left: "4px",
+            },
+        }
+    }
+
+    #[test]
+    fn scoped_settings_follow_parent() {
+        use crate::{direction::Direction, CssFormat};
+
+        let provider = StyleProvider::new_headless(Dark(false));
+        let scoped = provider.scoped(|theme| *theme);
+        scoped.add_classes::<PanelClasses>();
+
+        provider.set_format(CssFormat::Minified);
+        assert_eq!(scoped.current_css(), ".css-s0-0{padding-left:4px}");
+        provider.set_direction(Direction::Rtl);
+        assert_eq!(scoped.current_css(), ".css-s0-0{padding-right:4px}");
+    }
+
+    #[test]
+    fn hot_replace_in_debug_builds() {
+        let provider = StyleProvider::new_headless(Dark(false));
+        provider.set_format(crate::CssFormat::Minified);
+        provider.add_classes::<PanelClasses>();
+
+        let replaced = provider.hot_replace("PanelClasses", ".panel { padding: 0 }");
+        assert_eq!(replaced, cfg!(debug_assertions));
+        let expected = match replaced {
+            true => ".css-0{padding:0}",
+            false => ".css-0{padding-left:4px}",
+        };
+        assert_eq!(provider.current_css(), expected);
+        assert!(!provider.hot_replace("MissingClasses", ".panel { padding: 0 }"));
+    }
+
+    #[test]
+    fn source_comments() {
+        let provider = StyleProvider::new_headless(Dark(false));
+        provider.add_classes::<PanelClasses>();
+        let comment = format!("/* PanelClasses::panel @ {}:", file!());
+        let css = provider.current_css();
+        assert_eq!(css.starts_with(&comment), cfg!(debug_assertions), "{css}");
+        assert!(css.ends_with(".css-0 {\n  padding-left: 4px;\n}\n"), "{css}");
+
+        provider.set_format(crate::CssFormat::Minified);
+        assert_eq!(provider.current_css(), ".css-0{padding-left:4px}");
+    }
+
+    #[cfg(feature = "dioxus04")]
+    #[test]
+    fn legacy_hooks() {
+        use core::cell::RefCell;
+        use dioxus04::prelude::*;
+
+        thread_local! {
+            static SEEN: RefCell<Vec<(String, String)>> = const { RefCell::new(Vec::new()) };
+        }
+
+        fn app(cx: Scope) -> Element {
+            cx.use_hook(|| cx.provide_context(StyleProvider::new_headless(Dark(false))));
+            let classes = crate::dioxus_legacy::use_style::<PanelClasses>(cx);
+            let provider = crate::dioxus_legacy::use_style_provider::<Dark>(cx);
+            let seen = (classes.panel.clone(), provider.current_css());
+            SEEN.with_borrow_mut(|all| all.push(seen));
+            None
+        }
+
+        let mut dom = VirtualDom::new(app);
+        let _ = dom.rebuild();
+        dom.mark_dirty(ScopeId(0));
+        let _ = dom.render_immediate();
+
+        let seen = SEEN.take();
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[0], seen[1]);
+        assert_eq!(seen[0].0, "css-0");
+        assert!(seen[0].1.contains(".css-0 {\n  padding-left: 4px;\n}"));
     }
 }
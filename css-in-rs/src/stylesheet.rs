@@ -0,0 +1,104 @@
+//! Stylesheets built at runtime, for code which generates styles
+//! algorithmically (i.e. charts or editors) instead of writing them with
+//! [make_styles!](crate::make_styles). They are mounted using
+//! [StyleProvider::add_stylesheet], which scopes their classes like
+//! [StyleProvider::add_raw_css].
+//!
+//! # Example
+//! ```
+//! # use css_in_rs::{EmptyTheme, StyleProvider};
+//! use css_in_rs::stylesheet::{Rule, Stylesheet};
+//!
+//! let mut stylesheet = Stylesheet::new();
+//! for (idx, color) in ["red", "green"].into_iter().enumerate() {
+//!     stylesheet.push(Rule::new(format!(".series{idx}")).prop("fill", color));
+//! }
+//! stylesheet.push(
+//!     Rule::new("@media print").rule(Rule::new(".series0").prop("fill", "black")),
+//! );
+//!
+//! let style_provider = StyleProvider::new_headless(EmptyTheme);
+//! style_provider.set_format(css_in_rs::CssFormat::Minified);
+//! let classes = style_provider.add_stylesheet(&stylesheet);
+//! assert_eq!(classes.get("series1"), Some("css-1"));
+//! assert_eq!(
+//!     style_provider.current_css(),
+//!     ".css-0{fill:red}.css-1{fill:green}@media print{.css-0{fill:black}}"
+//! );
+//! ```
+
+use core::fmt::{Display, Formatter, Result};
+
+#[cfg(doc)]
+use crate::StyleProvider;
+
+/// A rule with a selector or an at-rule with a prelude, containing
+/// declarations and nested rules
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    prelude: String,
+    declarations: Vec<(String, String)>,
+    rules: Vec<Rule>,
+}
+
+impl Rule {
+    /// Creates an empty rule, i.e. for `.card > a` or `@media print`
+    pub fn new(prelude: impl Into<String>) -> Self {
+        Self {
+            prelude: prelude.into(),
+            declarations: Default::default(),
+            rules: Default::default(),
+        }
+    }
+
+    /// Adds a declaration like `color: red`
+    pub fn prop(mut self, property: impl Into<String>, value: impl Display) -> Self {
+        self.declarations.push((property.into(), value.to_string()));
+        self
+    }
+
+    /// Adds a nested rule, i.e. a rule inside of `@media print`
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "{} {{", self.prelude)?;
+        for (property, value) in &self.declarations {
+            writeln!(f, "  {property}: {value};")?;
+        }
+        for rule in &self.rules {
+            rule.fmt(f)?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+/// A list of rules, displayed as CSS
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stylesheet {
+    rules: Vec<Rule>,
+}
+
+impl Stylesheet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, rule: Rule) {
+        self.rules.push(rule);
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+}
+
+impl Display for Stylesheet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        self.rules.iter().try_for_each(|rule| rule.fmt(f))
+    }
+}
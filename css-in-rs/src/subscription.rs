@@ -0,0 +1,81 @@
+use core::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+
+#[cfg(doc)]
+use crate::StyleProvider;
+
+/// Keeps a callback registered by [StyleProvider::on_theme_change]. The
+/// callback is removed once the subscription is dropped, unless it has been
+/// detached.
+#[must_use = "The callback is removed once the subscription is dropped"]
+pub struct Subscription {
+    unsubscribe: Option<Box<dyn FnOnce()>>,
+}
+
+impl Subscription {
+    /// Keeps the callback registered for as long as the provider lives
+    pub fn detach(mut self) {
+        self.unsubscribe = None;
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(unsubscribe) = self.unsubscribe.take() {
+            unsubscribe();
+        }
+    }
+}
+
+type Listener<T> = Rc<dyn Fn(&T)>;
+
+/// The callbacks of [StyleProvider::on_theme_change], shared with the
+/// subscriptions to remove them
+pub(crate) struct ThemeListeners<T> {
+    next_id: Cell<u64>,
+    listeners: RefCell<Vec<(u64, Listener<T>)>>,
+}
+
+impl<T: 'static> ThemeListeners<T> {
+    pub fn new() -> Rc<Self> {
+        Rc::new(Self {
+            next_id: Cell::new(0),
+            listeners: Default::default(),
+        })
+    }
+
+    pub fn subscribe(self: &Rc<Self>, callback: impl Fn(&T) + 'static) -> Subscription {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        self.listeners.borrow_mut().push((id, Rc::new(callback)));
+
+        let this = Rc::downgrade(self);
+        Subscription {
+            unsubscribe: Some(Box::new(move || {
+                if let Some(this) = Weak::upgrade(&this) {
+                    this.listeners
+                        .borrow_mut()
+                        .retain(|(other, _)| *other != id);
+                }
+            })),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.listeners.borrow().is_empty()
+    }
+
+    /// Calls all callbacks registered so far. Callbacks may subscribe or
+    /// unsubscribe while being called; this takes effect with the next change.
+    pub fn notify(&self, theme: &T) {
+        let listeners = self
+            .listeners
+            .borrow()
+            .iter()
+            .map(|(_, callback)| callback.clone())
+            .collect::<Vec<_>>();
+        for callback in listeners {
+            callback(theme);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! A [StyleProvider] which can be shared between threads, i.e. by the request
+//! handlers of a multi-threaded server rendering pages ahead of time.
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+#[cfg(doc)]
+use crate::StyleProviderBuilder;
+use crate::{
+    fonts::FontFace, stats::StyleStats, Classes, CssFormat, Keyframes, ScopedClasses,
+    StyleProvider, Theme,
+};
+
+/// A headless [StyleProvider] which is `Send` and `Sync`, so it can be shared
+/// between the handlers of a multi-threaded server, like ones of Axum or
+/// Actix. Since all handlers share the provider, [Classes] mounted by one of
+/// them get the same classnames in all others, and styles are generated only
+/// once.
+///
+/// Calls lock the provider, so they are serialized. Only the methods needed
+/// on a server are available, as the provider itself must not leave the
+/// lock.
+///
+/// # Example
+/// ```
+/// # use css_in_rs::{make_styles, CssFormat, EmptyTheme, StyleProvider, SyncStyleProvider};
+/// make_styles! {
+///     (_theme: EmptyTheme) -> PageClasses {
+///         page {
+///             margin: "0px",
+///         },
+///     }
+/// }
+///
+/// let style_provider = StyleProvider::builder()
+///     .format(CssFormat::Minified)
+///     .build_sync(EmptyTheme);
+///
+/// let handlers = (0..4).map(|_| {
+///     let style_provider = style_provider.clone();
+///     std::thread::spawn(move || style_provider.add_classes::<PageClasses>().page)
+/// });
+/// for handler in handlers.collect::<Vec<_>>() {
+///     assert_eq!(handler.join().unwrap(), "css-0");
+/// }
+/// assert_eq!(style_provider.current_css(), ".css-0{margin:0px}");
+/// ```
+pub struct SyncStyleProvider<T> {
+    inner: Arc<Mutex<Unshared<T>>>,
+}
+
+/// A [StyleProvider] which is only ever accessed by one thread at a time
+struct Unshared<T>(StyleProvider<T>);
+
+// SAFETY: The provider is headless and has neither a theme transition nor
+// pinned classnames, so no callback of the browser refers to it. It is only
+// accessible through the mutex of [SyncStyleProvider]. Neither the provider
+// nor any other value holding its `Rc`s is handed out, so they are never used
+// by two threads at once. The theme is the only state which is moved between
+// threads, hence `T: Send`.
+unsafe impl<T: Send> Send for Unshared<T> {}
+
+impl<T> Clone for SyncStyleProvider<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: Theme + Send> SyncStyleProvider<T> {
+    pub fn new(theme: T) -> Self {
+        StyleProvider::builder().build_sync(theme)
+    }
+
+    /// Takes over a provider which has been built by
+    /// [StyleProviderBuilder::build_sync], so it does not refer to a browser
+    pub(crate) fn from_headless(provider: StyleProvider<T>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Unshared(provider))),
+        }
+    }
+
+    fn provider(&self) -> MutexGuard<'_, Unshared<T>> {
+        // A panicking generator leaves the provider borrowed by no one, so it
+        // can still be used
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// See [StyleProvider::add_classes]
+    pub fn add_classes<C>(&self) -> C
+    where
+        C: Classes<Theme = T>,
+    {
+        self.provider().0.add_classes::<C>()
+    }
+
+    /// See [StyleProvider::add_keyframes]
+    pub fn add_keyframes(&self, keyframes: &Keyframes) -> &'static str {
+        self.provider().0.add_keyframes(keyframes)
+    }
+
+    /// See [StyleProvider::add_font_face]
+    pub fn add_font_face(&self, font: &FontFace) {
+        self.provider().0.add_font_face(font);
+    }
+
+    /// See [StyleProvider::add_raw_css]
+    pub fn add_raw_css(&self, css: &str) -> ScopedClasses {
+        self.provider().0.add_raw_css(css)
+    }
+
+    /// See [StyleProvider::update_theme]
+    pub fn update_theme(&self, theme: T) {
+        self.provider().0.update_theme(theme);
+    }
+
+    /// See [StyleProvider::set_format]
+    pub fn set_format(&self, format: CssFormat) {
+        self.provider().0.set_format(format);
+    }
+
+    /// Returns all styles, i.e. to inline them into a page, see
+    /// [StyleProvider::current_css]
+    pub fn current_css(&self) -> String {
+        self.provider().0.current_css()
+    }
+
+    /// See [StyleProvider::css_of]
+    pub fn css_of<C>(&self) -> Option<String>
+    where
+        C: Classes<Theme = T>,
+    {
+        self.provider().0.css_of::<C>()
+    }
+
+    /// See [StyleProvider::stats]
+    pub fn stats(&self) -> StyleStats {
+        self.provider().0.stats()
+    }
+}
@@ -0,0 +1,31 @@
+//! Rendering of the static templates emitted by
+//! [make_styles!](crate::make_styles) in `#[compact]` mode. A single
+//! instance of [render] is shared by all structs, so the generated code only
+//! consists of the template and the values which are computed at runtime.
+
+use core::fmt::{Display, Write};
+
+/// A part of a template
+pub enum Piece {
+    Str(&'static str),
+    /// The class with the given id, relative to the first reserved classname
+    Class(u64),
+    /// The value with the given index
+    Value(usize),
+}
+
+pub fn render(
+    css: &mut String,
+    pieces: &[Piece],
+    prefix: &str,
+    start: u64,
+    values: &[&dyn Display],
+) {
+    for piece in pieces {
+        let _ = match piece {
+            Piece::Str(s) => css.write_str(s),
+            Piece::Class(id) => write!(css, "{prefix}-{}", start + id),
+            Piece::Value(idx) => write!(css, "{}", values[*idx]),
+        };
+    }
+}
@@ -0,0 +1,38 @@
+//! Helpers to test styles without a DOM. They work in plain `cargo test`
+//! and do not require a browser or `wasm-bindgen-test`. To test styles in a
+//! browser, see `testing::browser` (requires the `web-sys` feature).
+
+use crate::{Classes, StyleProvider};
+
+#[cfg(feature = "web-sys")]
+pub mod browser;
+
+/// Renders the styles of the given [Classes] type into a string. Nothing is
+/// mounted anywhere. The first classname is always `css-0` (unless there are
+/// [dependencies](Classes::add_dependencies), which come first), so the output is
+/// deterministic and well suited for snapshot tests (for example using `insta`).
+///
+/// The comments pointing to the source of each rule (emitted by
+/// [make_styles!](crate::make_styles) in debug builds) are stripped, so
+/// snapshots do not change when the rules move around in the source file.
+///
+/// # Example
+/// ```
+/// # use css_in_rs::{make_styles, testing::render_classes_to_string, EmptyTheme};
+/// make_styles! {
+///     (_theme: EmptyTheme) -> MyClasses {
+///         title {
+///             font_weight: "bold",
+///         },
+///     }
+/// }
+///
+/// let css = render_classes_to_string::<MyClasses>(&EmptyTheme);
+/// assert_eq!(css, ".css-0 {\n  font-weight: bold;\n}\n");
+/// ```
+pub fn render_classes_to_string<C: Classes>(theme: &C::Theme) -> String {
+    let provider = StyleProvider::new_headless(theme.clone());
+    provider.add_classes::<C>();
+
+    crate::css::strip_comments(&provider.css_of::<C>().unwrap_or_default())
+}
@@ -0,0 +1,128 @@
+//! Fixtures for integration tests running in a browser, i.e. using
+//! `wasm-bindgen-test` with `wasm_bindgen_test_configure!(run_in_browser)`.
+//! In contrast to [render_classes_to_string](super::render_classes_to_string),
+//! they test the styles as applied by the browser.
+//!
+//! # Example
+//! ```no_run
+//! # use css_in_rs::{make_styles, testing::browser::StyleFixture, EmptyTheme};
+//! make_styles! {
+//!     (_theme: EmptyTheme) -> ButtonClasses {
+//!         button {
+//!             color: "red",
+//!             padding: "4px",
+//!         },
+//!     }
+//! }
+//!
+//! // #[wasm_bindgen_test]
+//! fn button_is_red() {
+//!     let fixture = StyleFixture::new(EmptyTheme);
+//!     let classes = fixture.add_classes::<ButtonClasses>();
+//!
+//!     let button = fixture.element("button", &classes.button);
+//!     fixture.assert_style(&button, "color", "rgb(255, 0, 0)");
+//!     fixture.assert_style(&button, "padding-left", "4px");
+//! }
+//! ```
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{Classes, StyleProvider, Theme};
+
+/// A [StyleProvider] mounted in the document of the test, together with a
+/// container for sample elements. The container is removed when the fixture
+/// is dropped.
+///
+/// Every fixture uses a classname prefix of its own, so styles mounted by
+/// other tests running in the same document do not interfere.
+pub struct StyleFixture<T> {
+    provider: StyleProvider<T>,
+    root: web_sys::Element,
+}
+
+impl<T: Theme> StyleFixture<T> {
+    /// # Panics
+    /// Panics if there is no document, i.e. if the test does not run in a browser
+    pub fn new(theme: T) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+
+        let provider = StyleProvider::builder()
+            .prefix(format!("test{id}"))
+            .build_web(theme);
+
+        let document = document();
+        let root = document.create_element("div").unwrap();
+        document.body().unwrap().append_child(&root).unwrap();
+
+        Self { provider, root }
+    }
+
+    pub fn provider(&self) -> &StyleProvider<T> {
+        &self.provider
+    }
+
+    /// Mounts the given [Classes], see [StyleProvider::add_classes]
+    pub fn add_classes<C>(&self) -> C
+    where
+        C: Classes<Theme = T>,
+    {
+        self.provider.add_classes()
+    }
+
+    /// Creates an element with the given tag and classes (separated by
+    /// spaces) inside of the container
+    pub fn element(&self, tag: &str, class: &str) -> web_sys::Element {
+        self.element_in(&self.root, tag, class)
+    }
+
+    /// Like [StyleFixture::element], but creates the element inside of
+    /// `parent`, i.e. to test selectors like `.list > .item`
+    pub fn element_in(
+        &self,
+        parent: &web_sys::Element,
+        tag: &str,
+        class: &str,
+    ) -> web_sys::Element {
+        let element = document().create_element(tag).unwrap();
+        element.set_class_name(class);
+        parent.append_child(&element).unwrap();
+        element
+    }
+
+    /// Returns the computed value of a CSS property, i.e. `rgb(255, 0, 0)`
+    /// for `color: red`. Shorthands like `padding` are usually not
+    /// available; use the longhands like `padding-left` instead. Pending
+    /// styles are written first, see [StyleProvider::flush].
+    pub fn computed_style(&self, element: &web_sys::Element, property: &str) -> String {
+        self.provider.flush();
+        let window = web_sys::window().unwrap();
+        let style = window.get_computed_style(element).unwrap().unwrap();
+        style.get_property_value(property).unwrap()
+    }
+
+    /// Asserts the computed value of a CSS property, see [StyleFixture::computed_style]
+    #[track_caller]
+    pub fn assert_style(&self, element: &web_sys::Element, property: &str, expected: &str) {
+        let actual = self.computed_style(element, property);
+        assert_eq!(
+            actual.trim(),
+            expected,
+            "Unexpected `{property}` of <{} class=\"{}\">",
+            element.tag_name().to_lowercase(),
+            element.class_name(),
+        );
+    }
+}
+
+impl<T> Drop for StyleFixture<T> {
+    fn drop(&mut self) {
+        self.root.remove();
+    }
+}
+
+fn document() -> web_sys::Document {
+    let window = web_sys::window().expect("Browser fixtures need a window");
+    window.document().expect("Browser fixtures need a document")
+}
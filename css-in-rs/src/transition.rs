@@ -0,0 +1,74 @@
+//! Smooth theme switches. Usually, the colors jump as soon as
+//! [StyleProvider::update_theme] swaps the theme. With a [ThemeTransition],
+//! the [StyleProvider] emits a temporary rule like
+//! `* { transition: background-color 200ms, color 200ms }` along with the new
+//! styles, so the colors fade instead. The rule is removed once the
+//! transition is over. See [StyleProviderBuilder::theme_transition].
+
+use core::{fmt::Write, time::Duration};
+
+#[cfg(doc)]
+use crate::{StyleProvider, StyleProviderBuilder};
+
+/// The properties which are animated by default
+const DEFAULT_PROPERTIES: &[&str] = &["background-color", "border-color", "color"];
+
+/// How theme switches are animated, see the [module documentation](self)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ThemeTransition {
+    duration: Duration,
+    properties: Vec<String>,
+}
+
+impl ThemeTransition {
+    /// Animates `background-color`, `border-color` and `color` for the given
+    /// duration
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            properties: DEFAULT_PROPERTIES.iter().map(|&p| p.to_owned()).collect(),
+        }
+    }
+
+    /// Replaces the animated properties, i.e. to add `fill` for icons
+    pub fn properties<S: Into<String>>(mut self, properties: impl IntoIterator<Item = S>) -> Self {
+        self.properties = properties.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// The temporary rule emitted while the transition is running
+    pub(crate) fn css(&self) -> String {
+        let millis = self.duration.as_millis();
+        let mut transition = String::new();
+        for property in &self.properties {
+            if !transition.is_empty() {
+                transition.push_str(", ");
+            }
+            let _ = write!(transition, "{property} {millis}ms");
+        }
+        format!("* {{\n  transition: {transition};\n}}\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::ThemeTransition;
+
+    #[test]
+    fn css() {
+        let transition = ThemeTransition::new(Duration::from_millis(200));
+        assert_eq!(
+            transition.css(),
+            "* {\n  transition: background-color 200ms, border-color 200ms, color 200ms;\n}\n"
+        );
+
+        let transition = transition.properties(["fill"]);
+        assert_eq!(transition.css(), "* {\n  transition: fill 200ms;\n}\n");
+    }
+}
@@ -0,0 +1,926 @@
+//! Typed builders for CSS values. All of them implement [Display], so they
+//! can be used in value position of [make_styles!](crate::make_styles).
+//!
+//! Shorthand properties are easy to get wrong when written as plain strings:
+//! A missing or superfluous value silently invalidates the whole declaration.
+//! The builders in this module only offer constructors with a valid number of
+//! arguments. For enumerated properties like `display`, see [keywords]. Values
+//! consisting of several parts, like the ones of `transition` and `transform`,
+//! are assembled by [Transition] and [Transform].
+//!
+//! # Example
+//! ```
+//! # use css_in_rs::{make_styles, testing::render_classes_to_string, EmptyTheme};
+//! use css_in_rs::values::{AspectRatio, Gap, Inset, PlaceItems};
+//!
+//! make_styles! {
+//!     (_theme: EmptyTheme) -> MyClasses {
+//!         overlay {
+//!             position: "absolute",
+//!             inset: Inset::symmetric("0", "10px"),
+//!             aspect_ratio: AspectRatio::new(16, 9),
+//!             gap: Gap::new("1em", "2em"),
+//!             place_items: PlaceItems::all("center"),
+//!         },
+//!     }
+//! }
+//!
+//! let css = render_classes_to_string::<MyClasses>(&EmptyTheme);
+//! assert_eq!(
+//!     css,
+//!     ".css-0 {\n  position: absolute;\n  inset: 0 10px;\n  aspect-ratio: 16 / 9;\n  \
+//!      gap: 1em 2em;\n  place-items: center;\n}\n"
+//! );
+//! ```
+
+use core::fmt::{Display, Formatter, Result};
+
+pub mod keywords;
+
+/// Value for the `inset` shorthand, consisting of the top, right, bottom and
+/// left edges. The shortest equivalent form is emitted. This works for other
+/// box shorthands like `margin` or `padding` as well.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Inset {
+    top: String,
+    right: String,
+    bottom: String,
+    left: String,
+}
+
+impl Inset {
+    /// Uses the same value for all edges
+    pub fn all(value: impl Display) -> Self {
+        let value = value.to_string();
+        Self::symmetric(value.clone(), value)
+    }
+
+    /// Uses `vertical` for the top and bottom edges and `horizontal` for the
+    /// left and right edges
+    pub fn symmetric(vertical: impl Display, horizontal: impl Display) -> Self {
+        let vertical = vertical.to_string();
+        let horizontal = horizontal.to_string();
+        Self::new(vertical.clone(), horizontal.clone(), vertical, horizontal)
+    }
+
+    pub fn new(
+        top: impl Display,
+        right: impl Display,
+        bottom: impl Display,
+        left: impl Display,
+    ) -> Self {
+        Self {
+            top: top.to_string(),
+            right: right.to_string(),
+            bottom: bottom.to_string(),
+            left: left.to_string(),
+        }
+    }
+}
+
+impl Display for Inset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let Inset {
+            top,
+            right,
+            bottom,
+            left,
+        } = self;
+
+        if left != right {
+            write!(f, "{top} {right} {bottom} {left}")
+        } else if top != bottom {
+            write!(f, "{top} {right} {bottom}")
+        } else if top != right {
+            write!(f, "{top} {right}")
+        } else {
+            write!(f, "{top}")
+        }
+    }
+}
+
+/// Value for the `aspect-ratio` property
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AspectRatio {
+    ratio: Option<(f64, f64)>,
+    auto: bool,
+}
+
+impl AspectRatio {
+    /// A preferred aspect ratio of `width / height`
+    ///
+    /// # Panics
+    /// Panics if `width` or `height` is not positive
+    pub fn new(width: impl Into<f64>, height: impl Into<f64>) -> Self {
+        let width = width.into();
+        let height = height.into();
+        assert!(
+            width > 0.0 && height > 0.0,
+            "aspect-ratio must be positive, got {width} / {height}"
+        );
+
+        Self {
+            ratio: Some((width, height)),
+            auto: false,
+        }
+    }
+
+    /// No preferred aspect ratio
+    pub fn auto() -> Self {
+        Self {
+            ratio: None,
+            auto: true,
+        }
+    }
+
+    /// Use the natural aspect ratio of replaced elements (like images) and
+    /// this ratio otherwise
+    pub fn or_auto(self) -> Self {
+        Self { auto: true, ..self }
+    }
+}
+
+impl Display for AspectRatio {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match (self.auto, self.ratio) {
+            (true, Some((width, height))) => write!(f, "auto {width} / {height}"),
+            (false, Some((width, height))) => write!(f, "{width} / {height}"),
+            (_, None) => write!(f, "auto"),
+        }
+    }
+}
+
+macro_rules! pair {
+    ($(#[$meta:meta])* $name:ident, $first:ident, $second:ident) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct $name {
+            $first: String,
+            $second: String,
+        }
+
+        impl $name {
+            /// Uses the same value for both components
+            pub fn all(value: impl Display) -> Self {
+                let value = value.to_string();
+                Self::new(value.clone(), value)
+            }
+
+            pub fn new($first: impl Display, $second: impl Display) -> Self {
+                Self {
+                    $first: $first.to_string(),
+                    $second: $second.to_string(),
+                }
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+                if self.$first == self.$second {
+                    write!(f, "{}", self.$first)
+                } else {
+                    write!(f, "{} {}", self.$first, self.$second)
+                }
+            }
+        }
+    };
+}
+
+pair! {
+    /// Value for the `gap` shorthand: the gap between rows and between columns
+    Gap, row, column
+}
+
+pair! {
+    /// Value for the `place-items` shorthand: `align-items` and `justify-items`
+    PlaceItems, align, justify
+}
+
+pair! {
+    /// Value for the `place-content` shorthand: `align-content` and `justify-content`
+    PlaceContent, align, justify
+}
+
+pair! {
+    /// Value for the `place-self` shorthand: `align-self` and `justify-self`
+    PlaceSelf, align, justify
+}
+
+/// Value for the SVG `fill` and `stroke` properties: `none`, a color, or a
+/// reference to a paint server like a gradient, optionally with a fallback color
+///
+/// # Example
+/// ```
+/// # use css_in_rs::{make_styles, testing::render_classes_to_string, Theme};
+/// use css_in_rs::values::{DashArray, Paint};
+///
+/// #[derive(Clone)]
+/// struct MyTheme {
+///     primary: String,
+/// }
+///
+/// impl Theme for MyTheme {
+///     fn fast_cmp(&self, other: &Self) -> bool {
+///         self.primary == other.primary
+///     }
+/// }
+///
+/// make_styles! {
+///     (theme: MyTheme) -> IconClasses {
+///         "svg.icon > path" {
+///             fill: Paint::url("gradient").or(Paint::color(&theme.primary)),
+///             stroke: Paint::current_color(),
+///             stroke_dasharray: DashArray::new(vec![4, 2]),
+///         },
+///     }
+/// }
+///
+/// let theme = MyTheme { primary: "#1976d2".to_owned() };
+/// let css = render_classes_to_string::<IconClasses>(&theme);
+/// assert_eq!(
+///     css,
+///     "svg.css-0 > path {\n  fill: url(#gradient) #1976d2;\n  stroke: currentColor;\n  \
+///      stroke-dasharray: 4 2;\n}\n"
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Paint {
+    /// Id of the referenced paint server
+    server: Option<String>,
+    /// A color, `none`, or (together with `server`) the fallback
+    color: Option<String>,
+}
+
+impl Paint {
+    pub fn none() -> Self {
+        Self::color("none")
+    }
+
+    /// Uses the value of the `color` property, i.e. to match the surrounding text
+    pub fn current_color() -> Self {
+        Self::color("currentColor")
+    }
+
+    pub fn color(color: impl Display) -> Self {
+        Self {
+            server: None,
+            color: Some(color.to_string()),
+        }
+    }
+
+    /// References a paint server (like a `linearGradient`) by the id of its element
+    pub fn url(id: impl Display) -> Self {
+        Self {
+            server: Some(id.to_string()),
+            color: None,
+        }
+    }
+
+    /// Sets the color to use if the referenced paint server cannot be used
+    ///
+    /// # Panics
+    /// Panics if `self` does not reference a paint server or if `fallback` does
+    pub fn or(self, fallback: Paint) -> Self {
+        assert!(
+            self.server.is_some(),
+            "Only paint servers can have a fallback"
+        );
+        assert!(
+            fallback.server.is_none(),
+            "The fallback cannot reference a paint server"
+        );
+
+        Self {
+            color: fallback.color,
+            ..self
+        }
+    }
+}
+
+impl Display for Paint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match (&self.server, &self.color) {
+            (Some(id), Some(color)) => write!(f, "url(#{id}) {color}"),
+            (Some(id), None) => write!(f, "url(#{id})"),
+            (None, Some(color)) => write!(f, "{color}"),
+            (None, None) => write!(f, "none"),
+        }
+    }
+}
+
+/// Value for the SVG `stroke-dasharray` property: alternating lengths of
+/// dashes and gaps. Without any lengths, the stroke is solid (`none`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DashArray {
+    lengths: Vec<String>,
+}
+
+impl DashArray {
+    /// # Panics
+    /// Panics if a length is negative
+    pub fn new<L: Display>(lengths: impl IntoIterator<Item = L>) -> Self {
+        let lengths = lengths
+            .into_iter()
+            .map(|length| length.to_string())
+            .collect::<Vec<_>>();
+
+        for length in &lengths {
+            assert!(
+                !length.trim_start().starts_with('-'),
+                "stroke-dasharray must not be negative, got {length}"
+            );
+        }
+
+        Self { lengths }
+    }
+
+    pub fn none() -> Self {
+        Self {
+            lengths: Vec::new(),
+        }
+    }
+}
+
+impl Display for DashArray {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.lengths.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", self.lengths.join(" "))
+        }
+    }
+}
+
+/// Value for the `content` property of `::before` and `::after`, starting
+/// with the given text. The text is quoted and escaped, so quotes, backslashes
+/// and line breaks are rendered as they are. Further text, attributes and
+/// counters can be appended.
+///
+/// # Example
+/// ```
+/// # use css_in_rs::{make_styles, testing::render_classes_to_string, EmptyTheme};
+/// use css_in_rs::values::content;
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> LinkClasses {
+///         "a.link::after" {
+///             content: content(" (").attr("href").text(")"),
+///         },
+///         "li.item::before" {
+///             content: content("\"Step\" ").counter("step").text(": "),
+///         },
+///     }
+/// }
+///
+/// let css = render_classes_to_string::<LinkClasses>(&EmptyTheme);
+/// assert_eq!(
+///     css,
+///     "a.css-1::after {\n  content: \" (\" attr(href) \")\";\n}\n\
+///      li.css-0::before {\n  content: \"\\\"Step\\\" \" counter(step) \": \";\n}\n"
+/// );
+/// ```
+pub fn content(text: impl Display) -> Content {
+    Content { items: Vec::new() }.text(text)
+}
+
+/// A value for the `content` property, see [content]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Content {
+    items: Vec<String>,
+}
+
+impl Content {
+    /// Nothing is rendered, and the pseudo-element is not created
+    pub fn none() -> Self {
+        Self {
+            items: vec!["none".to_owned()],
+        }
+    }
+
+    /// Appends quoted and escaped text
+    pub fn text(mut self, text: impl Display) -> Self {
+        self.items.push(quote(&text.to_string()));
+        self
+    }
+
+    /// Appends the value of an attribute of the element, i.e. `data-label`
+    ///
+    /// # Panics
+    /// Panics if `name` is not a valid attribute name
+    pub fn attr(mut self, name: &str) -> Self {
+        assert_ident(name, "attribute");
+        self.items.push(format!("attr({name})"));
+        self
+    }
+
+    /// Appends the value of a counter, i.e. for numbered headings
+    ///
+    /// # Panics
+    /// Panics if `name` is not a valid counter name
+    pub fn counter(mut self, name: &str) -> Self {
+        assert_ident(name, "counter");
+        self.items.push(format!("counter({name})"));
+        self
+    }
+
+    /// Appends the values of all nested counters with the given name, joined
+    /// by `separator`, i.e. `1.2.3` for nested lists
+    ///
+    /// # Panics
+    /// Panics if `name` is not a valid counter name
+    pub fn counters(mut self, name: &str, separator: &str) -> Self {
+        assert_ident(name, "counter");
+        self.items
+            .push(format!("counters({name}, {})", quote(separator)));
+        self
+    }
+}
+
+impl Display for Content {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.items.join(" "))
+    }
+}
+
+/// Quotes `text` as a CSS string. Line breaks and other control characters
+/// are written as escape sequences.
+pub(crate) fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(ch);
+            }
+            ch if ch.is_control() => quoted.push_str(&format!("\\{:x} ", ch as u32)),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn assert_ident(name: &str, kind: &str) {
+    let valid = !name.is_empty()
+        && !name.starts_with(|ch: char| ch.is_ascii_digit())
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_');
+    assert!(valid, "Invalid {kind} name: {name:?}");
+}
+
+/// A `url()` value, i.e. for `background-image` or the `src` of a
+/// `@font-face`. The URL is quoted and escaped. See [url!](crate::url) for
+/// assets which are embedded into the binary.
+///
+/// Bundlers which hash the file names of assets, like the `asset!` macro of
+/// dioxus, return the final path at runtime. It can be passed as it is:
+/// `Url::new(asset!("/assets/background.png"))`.
+///
+/// # Example
+/// ```
+/// # use css_in_rs::{make_styles, testing::render_classes_to_string, EmptyTheme};
+/// use css_in_rs::values::Url;
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> HeroClasses {
+///         hero {
+///             background_image: Url::new("/assets/hero (1).png"),
+///             mask_image: Url::data("image/svg+xml", b"<svg/>"),
+///         },
+///     }
+/// }
+///
+/// let css = render_classes_to_string::<HeroClasses>(&EmptyTheme);
+/// assert_eq!(
+///     css,
+///     ".css-0 {\n  background-image: url(\"/assets/hero (1).png\");\n  \
+///      mask-image: url(\"data:image/svg+xml;base64,PHN2Zy8+\");\n}\n"
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Url {
+    url: String,
+}
+
+impl Url {
+    pub fn new(url: impl Display) -> Self {
+        Self {
+            url: url.to_string(),
+        }
+    }
+
+    /// Embeds the data as a `data:` URL, so no request is needed. Only
+    /// suitable for small files like icons, since the data is part of the
+    /// stylesheet.
+    pub fn data(mime_type: &str, data: &[u8]) -> Self {
+        Self {
+            url: format!("data:{mime_type};base64,{}", base64(data)),
+        }
+    }
+}
+
+impl Display for Url {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "url({})", quote(&self.url))
+    }
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (idx, &byte)| {
+            bits | (byte as u32) << (16 - 8 * idx)
+        });
+        for idx in 0..4 {
+            match idx <= chunk.len() {
+                true => encoded.push(ALPHABET[((bits >> (18 - 6 * idx)) & 0x3f) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+    encoded
+}
+
+/// Creates a [Url](crate::values::Url) value. With `include`, the file is embedded
+/// into the binary using `include_bytes!` and referenced as a `data:` URL, so
+/// it resolves without any bundler; the path is relative to the current file.
+///
+/// # Example
+/// ```
+/// # use css_in_rs::{make_styles, url, EmptyTheme};
+/// make_styles! {
+///     (_theme: EmptyTheme) -> IconClasses {
+///         icon {
+///             background_image: url!("/assets/icon.svg"),
+///         },
+///         license {
+///             background_image: url!(include "../../LICENSE", "text/plain"),
+///         },
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! url {
+    (include $path:literal, $mime_type:expr) => {
+        $crate::values::Url::data($mime_type, ::core::include_bytes!($path))
+    };
+    ($url:expr) => {
+        $crate::values::Url::new($url)
+    };
+}
+
+/// Starts a `calc()` expression, which combines values of different units
+/// or of the theme. The operators get the spaces `calc()` requires, and sums
+/// are put in parentheses before they are multiplied or divided. String
+/// literals containing `calc()` are checked by [make_styles!](crate::make_styles)
+/// instead.
+///
+/// # Example
+/// ```
+/// # use css_in_rs::{make_styles, testing::render_classes_to_string, Theme};
+/// use css_in_rs::values::calc;
+///
+/// #[derive(Clone)]
+/// struct MyTheme {
+///     header_height: String,
+/// }
+///
+/// impl Theme for MyTheme {
+///     fn fast_cmp(&self, other: &Self) -> bool {
+///         self.header_height == other.header_height
+///     }
+/// }
+///
+/// make_styles! {
+///     (theme: MyTheme) -> LayoutClasses {
+///         content {
+///             height: calc("100vh").minus(&theme.header_height),
+///             width: calc("100%").minus("2rem").divided_by(3),
+///         },
+///     }
+/// }
+///
+/// let theme = MyTheme { header_height: "64px".to_owned() };
+/// let css = render_classes_to_string::<LayoutClasses>(&theme);
+/// assert_eq!(
+///     css,
+///     ".css-0 {\n  height: calc(100vh - 64px);\n  width: calc((100% - 2rem) / 3);\n}\n"
+/// );
+/// ```
+pub fn calc(value: impl Display) -> Calc {
+    Calc {
+        expression: value.to_string(),
+        is_sum: false,
+    }
+}
+
+/// A `calc()` expression, see [calc]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Calc {
+    expression: String,
+    /// Whether the expression has to be put in parentheses before it is
+    /// multiplied or divided
+    is_sum: bool,
+}
+
+impl Calc {
+    pub fn plus(self, value: impl Display) -> Self {
+        self.sum('+', value)
+    }
+
+    pub fn minus(self, value: impl Display) -> Self {
+        self.sum('-', value)
+    }
+
+    pub fn times(self, value: impl Display) -> Self {
+        self.product('*', value)
+    }
+
+    pub fn divided_by(self, value: impl Display) -> Self {
+        self.product('/', value)
+    }
+
+    fn sum(self, operator: char, value: impl Display) -> Self {
+        Self {
+            expression: format!("{} {operator} {value}", self.expression),
+            is_sum: true,
+        }
+    }
+
+    fn product(self, operator: char, value: impl Display) -> Self {
+        let expression = match self.is_sum {
+            true => format!("({}) {operator} {value}", self.expression),
+            false => format!("{} {operator} {value}", self.expression),
+        };
+        Self {
+            expression,
+            is_sum: false,
+        }
+    }
+}
+
+impl Display for Calc {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "calc({})", self.expression)
+    }
+}
+
+/// Value for the `transition` shorthand. Each transition starts with
+/// [Transition::new]; several of them are combined with [Transition::and].
+///
+/// # Example
+/// ```
+/// # use css_in_rs::{make_styles, testing::render_classes_to_string, EmptyTheme};
+/// use css_in_rs::values::{Transform, Transition};
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> CardClasses {
+///         card {
+///             transition: Transition::new()
+///                 .prop("opacity")
+///                 .duration_ms(200)
+///                 .ease_out()
+///                 .and(Transition::new().prop("transform").duration_ms(300).delay_ms(50)),
+///             transform: Transform::new().translate("-50%", "0").scale(1.5),
+///         },
+///     }
+/// }
+///
+/// let css = render_classes_to_string::<CardClasses>(&EmptyTheme);
+/// assert_eq!(
+///     css,
+///     ".css-0 {\n  transition: opacity 200ms ease-out, transform 300ms 50ms;\n  \
+///      transform: translate(-50%, 0) scale(1.5);\n}\n"
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transition {
+    /// The transitions combined by [Transition::and]. The methods configure
+    /// the last one.
+    layers: Vec<TransitionLayer>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct TransitionLayer {
+    property: String,
+    duration_ms: u32,
+    timing_function: Option<String>,
+    delay_ms: Option<u32>,
+}
+
+impl Transition {
+    /// Transitions all properties, until [Transition::prop] is used
+    pub fn new() -> Self {
+        Self {
+            layers: vec![TransitionLayer {
+                property: "all".to_owned(),
+                duration_ms: 0,
+                timing_function: None,
+                delay_ms: None,
+            }],
+        }
+    }
+
+    /// The property to transition, like `opacity`
+    pub fn prop(mut self, property: impl Display) -> Self {
+        self.layer().property = property.to_string();
+        self
+    }
+
+    pub fn duration_ms(mut self, duration: u32) -> Self {
+        self.layer().duration_ms = duration;
+        self
+    }
+
+    pub fn delay_ms(mut self, delay: u32) -> Self {
+        self.layer().delay_ms = Some(delay);
+        self
+    }
+
+    /// Uses the given easing function, like `cubic-bezier(0.4, 0, 0.2, 1)`
+    pub fn timing(mut self, timing_function: impl Display) -> Self {
+        self.layer().timing_function = Some(timing_function.to_string());
+        self
+    }
+
+    pub fn linear(self) -> Self {
+        self.timing("linear")
+    }
+
+    pub fn ease(self) -> Self {
+        self.timing("ease")
+    }
+
+    pub fn ease_in(self) -> Self {
+        self.timing("ease-in")
+    }
+
+    pub fn ease_out(self) -> Self {
+        self.timing("ease-out")
+    }
+
+    pub fn ease_in_out(self) -> Self {
+        self.timing("ease-in-out")
+    }
+
+    /// Adds the transitions of `other`, so several properties are transitioned
+    /// differently
+    pub fn and(mut self, other: Transition) -> Self {
+        self.layers.extend(other.layers);
+        self
+    }
+
+    fn layer(&mut self) -> &mut TransitionLayer {
+        self.layers.last_mut().unwrap()
+    }
+}
+
+impl Default for Transition {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for Transition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        for (index, layer) in self.layers.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} {}ms", layer.property, layer.duration_ms)?;
+            if let Some(timing_function) = &layer.timing_function {
+                write!(f, " {timing_function}")?;
+            }
+            if let Some(delay) = layer.delay_ms {
+                write!(f, " {delay}ms")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Value for the `transform` property: transform functions which are applied
+/// from left to right. Without any function, the element is not transformed
+/// (`none`). See [Transition] for an example.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Transform {
+    functions: Vec<String>,
+}
+
+impl Transform {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn translate(self, x: impl Display, y: impl Display) -> Self {
+        self.function(format_args!("translate({x}, {y})"))
+    }
+
+    pub fn translate_x(self, x: impl Display) -> Self {
+        self.function(format_args!("translateX({x})"))
+    }
+
+    pub fn translate_y(self, y: impl Display) -> Self {
+        self.function(format_args!("translateY({y})"))
+    }
+
+    /// Scales both axes by the same factor
+    pub fn scale(self, factor: impl Display) -> Self {
+        self.function(format_args!("scale({factor})"))
+    }
+
+    pub fn scale_xy(self, x: impl Display, y: impl Display) -> Self {
+        self.function(format_args!("scale({x}, {y})"))
+    }
+
+    /// Rotates clockwise by the given number of degrees
+    pub fn rotate_deg(self, angle: impl Display) -> Self {
+        self.function(format_args!("rotate({angle}deg)"))
+    }
+
+    pub fn skew_deg(self, x: impl Display, y: impl Display) -> Self {
+        self.function(format_args!("skew({x}deg, {y}deg)"))
+    }
+
+    /// Appends any other transform function, like `perspective(500px)`
+    pub fn function(mut self, function: impl Display) -> Self {
+        self.functions.push(function.to_string());
+        self
+    }
+}
+
+impl Display for Transform {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.functions.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", self.functions.join(" "))
+        }
+    }
+}
+
+/// Marks a declaration as `!important`, so it overrides declarations of
+/// selectors with a higher specificity. Use it sparingly; the
+/// `#[deny_important]` option of [make_styles!](crate::make_styles) forbids it
+/// entirely.
+///
+/// # Panics
+/// Panics if `value` is empty or already marked as `!important`
+///
+/// # Example
+/// ```
+/// # use css_in_rs::{make_styles, testing::render_classes_to_string, EmptyTheme};
+/// use css_in_rs::values::important;
+///
+/// make_styles! {
+///     (_theme: EmptyTheme) -> UtilityClasses {
+///         hidden {
+///             display: important("none"),
+///         },
+///     }
+/// }
+///
+/// let css = render_classes_to_string::<UtilityClasses>(&EmptyTheme);
+/// assert_eq!(css, ".css-0 {\n  display: none !important;\n}\n");
+/// ```
+pub fn important(value: impl Display) -> Important {
+    let value = value.to_string();
+    assert!(
+        !value.trim().is_empty(),
+        "!important requires a value, got an empty one"
+    );
+    assert!(
+        !value.contains("!important"),
+        "The value is already marked as !important: {value}"
+    );
+
+    Important { value }
+}
+
+/// A value marked as `!important`, see [important]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Important {
+    value: String,
+}
+
+impl Display for Important {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{} !important", self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn base64() {
+        assert_eq!(super::base64(b""), "");
+        assert_eq!(super::base64(b"f"), "Zg==");
+        assert_eq!(super::base64(b"fo"), "Zm8=");
+        assert_eq!(super::base64(b"foo"), "Zm9v");
+        assert_eq!(super::base64(&[0xfb, 0xff]), "+/8=");
+    }
+}
@@ -0,0 +1,235 @@
+//! Typed keywords for the most common enumerated properties, i.e.
+//! `display: Display::Flex` instead of `display: "flex"`. A typo in a plain
+//! string silently drops the declaration, whereas a typo in a keyword does not
+//! compile. Keywords and strings can be mixed freely.
+//!
+//! Every keyword type has the CSS-wide keywords `inherit`, `initial`, `unset`
+//! and `revert` as well.
+//!
+//! # Example
+//! ```
+//! # use css_in_rs::{make_styles, testing::render_classes_to_string, EmptyTheme};
+//! use css_in_rs::values::keywords::{AlignItems, Display, FlexDirection, Position};
+//!
+//! make_styles! {
+//!     (_theme: EmptyTheme) -> ToolbarClasses {
+//!         toolbar {
+//!             position: Position::Sticky,
+//!             display: Display::InlineFlex,
+//!             flex_direction: FlexDirection::RowReverse,
+//!             align_items: AlignItems::Center,
+//!             top: "0px",
+//!         },
+//!     }
+//! }
+//!
+//! let css = render_classes_to_string::<ToolbarClasses>(&EmptyTheme);
+//! assert_eq!(
+//!     css,
+//!     ".css-0 {\n  position: sticky;\n  display: inline-flex;\n  \
+//!      flex-direction: row-reverse;\n  align-items: center;\n  top: 0px;\n}\n"
+//! );
+//! assert_eq!(Display::Initial.keyword(), "initial");
+//! ```
+
+use core::fmt::{Formatter, Result};
+
+macro_rules! keywords {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident => $keyword:literal,)* }) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant,)*
+            Inherit,
+            Initial,
+            Unset,
+            Revert,
+        }
+
+        impl $name {
+            /// The keyword as written in CSS
+            pub const fn keyword(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $keyword,)*
+                    Self::Inherit => "inherit",
+                    Self::Initial => "initial",
+                    Self::Unset => "unset",
+                    Self::Revert => "revert",
+                }
+            }
+        }
+
+        impl core::fmt::Display for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+                f.write_str(self.keyword())
+            }
+        }
+    };
+}
+
+keywords! {
+    /// Values of `display`
+    Display {
+        None => "none",
+        Block => "block",
+        Inline => "inline",
+        InlineBlock => "inline-block",
+        Flex => "flex",
+        InlineFlex => "inline-flex",
+        Grid => "grid",
+        InlineGrid => "inline-grid",
+        Contents => "contents",
+        FlowRoot => "flow-root",
+        Table => "table",
+        ListItem => "list-item",
+    }
+}
+
+keywords! {
+    /// Values of `position`
+    Position {
+        Static => "static",
+        Relative => "relative",
+        Absolute => "absolute",
+        Fixed => "fixed",
+        Sticky => "sticky",
+    }
+}
+
+keywords! {
+    /// Values of `flex-direction`
+    FlexDirection {
+        Row => "row",
+        RowReverse => "row-reverse",
+        Column => "column",
+        ColumnReverse => "column-reverse",
+    }
+}
+
+keywords! {
+    /// Values of `flex-wrap`
+    FlexWrap {
+        Nowrap => "nowrap",
+        Wrap => "wrap",
+        WrapReverse => "wrap-reverse",
+    }
+}
+
+keywords! {
+    /// Values of `justify-content`
+    JustifyContent {
+        Normal => "normal",
+        Start => "start",
+        End => "end",
+        FlexStart => "flex-start",
+        FlexEnd => "flex-end",
+        Center => "center",
+        SpaceBetween => "space-between",
+        SpaceAround => "space-around",
+        SpaceEvenly => "space-evenly",
+        Stretch => "stretch",
+    }
+}
+
+keywords! {
+    /// Values of `align-items` and `align-self`
+    AlignItems {
+        Normal => "normal",
+        Stretch => "stretch",
+        Start => "start",
+        End => "end",
+        FlexStart => "flex-start",
+        FlexEnd => "flex-end",
+        Center => "center",
+        Baseline => "baseline",
+    }
+}
+
+keywords! {
+    /// Values of `overflow`, `overflow-x` and `overflow-y`
+    Overflow {
+        Visible => "visible",
+        Hidden => "hidden",
+        Clip => "clip",
+        Scroll => "scroll",
+        Auto => "auto",
+    }
+}
+
+keywords! {
+    /// Values of `visibility`
+    Visibility {
+        Visible => "visible",
+        Hidden => "hidden",
+        Collapse => "collapse",
+    }
+}
+
+keywords! {
+    /// Values of `box-sizing`
+    BoxSizing {
+        ContentBox => "content-box",
+        BorderBox => "border-box",
+    }
+}
+
+keywords! {
+    /// Values of `text-align`
+    TextAlign {
+        Start => "start",
+        End => "end",
+        Left => "left",
+        Right => "right",
+        Center => "center",
+        Justify => "justify",
+    }
+}
+
+keywords! {
+    /// Values of `white-space`
+    WhiteSpace {
+        Normal => "normal",
+        Nowrap => "nowrap",
+        Pre => "pre",
+        PreWrap => "pre-wrap",
+        PreLine => "pre-line",
+        BreakSpaces => "break-spaces",
+    }
+}
+
+keywords! {
+    /// Values of `cursor`, except for images
+    Cursor {
+        Auto => "auto",
+        Default => "default",
+        None => "none",
+        Pointer => "pointer",
+        Text => "text",
+        Move => "move",
+        Grab => "grab",
+        Grabbing => "grabbing",
+        NotAllowed => "not-allowed",
+        Wait => "wait",
+        Progress => "progress",
+        Help => "help",
+        Crosshair => "crosshair",
+    }
+}
+
+keywords! {
+    /// Values of `pointer-events`, except for the SVG ones
+    PointerEvents {
+        Auto => "auto",
+        None => "none",
+    }
+}
+
+keywords! {
+    /// Values of `user-select`
+    UserSelect {
+        Auto => "auto",
+        None => "none",
+        Text => "text",
+        All => "all",
+    }
+}
@@ -0,0 +1,108 @@
+//! Custom elements with bundled styles, so components can be shipped as
+//! framework-agnostic web components. Every element gets a shadow root with
+//! a [StyleProvider] of its own, so its styles neither leak into the page nor
+//! are affected by the styles of the page.
+//!
+//! # Example
+//! ```no_run
+//! # use css_in_rs::{make_styles, web_components::define_custom_element, EmptyTheme};
+//! make_styles! {
+//!     (_theme: EmptyTheme) -> BadgeClasses {
+//!         badge {
+//!             padding: "2px 6px",
+//!             border_radius: "4px",
+//!             background_color: "teal",
+//!         },
+//!     }
+//! }
+//!
+//! define_custom_element::<BadgeClasses>("my-badge", EmptyTheme, |element| {
+//!     let badge = element.document().create_element("span").unwrap();
+//!     badge.set_class_name(&element.classes.badge);
+//!     badge.set_text_content(Some("new"));
+//!     element.shadow_root.append_child(&badge).unwrap();
+//! })
+//! .unwrap();
+//! ```
+
+use wasm_bindgen::{closure::Closure, prelude::wasm_bindgen, JsCast};
+
+use crate::{backend::web::WebSysBackend, Classes, Error, StyleProvider};
+
+#[wasm_bindgen(inline_js = "
+export function custom_element_class(setup) {
+    return class extends HTMLElement {
+        constructor() {
+            super();
+            setup(this, this.attachShadow({ mode: 'open' }));
+        }
+    };
+}")]
+extern "C" {
+    fn custom_element_class(setup: &js_sys::Function) -> js_sys::Function;
+}
+
+/// A new instance of a custom element, passed to the setup function of
+/// [define_custom_element]
+pub struct CustomElement<C: Classes> {
+    /// The custom element itself, i.e. to read its attributes
+    pub host: web_sys::HtmlElement,
+    /// The open shadow root of the element, which the content goes into
+    pub shadow_root: web_sys::ShadowRoot,
+    /// Mounts the styles in the shadow root. Keep a clone to mount further
+    /// [Classes] or to update the theme later.
+    pub provider: StyleProvider<C::Theme>,
+    /// The classnames of the styles mounted in the shadow root
+    pub classes: C,
+}
+
+impl<C: Classes> CustomElement<C> {
+    /// The document of the element, i.e. to create the content
+    pub fn document(&self) -> web_sys::Document {
+        self.host
+            .owner_document()
+            .expect("Custom elements have a document")
+    }
+}
+
+/// Registers a custom element with the given name, which has to contain a
+/// hyphen. Whenever an element is created, it gets an open shadow root with a
+/// new [StyleProvider] for `theme`, which mounts the styles of `C`. Then,
+/// `setup` is called to create the content of the shadow root.
+///
+/// The styles are written to the shadow root right after `setup` returns.
+/// Returns an error if there is no window or if the name is invalid or
+/// already taken.
+pub fn define_custom_element<C>(
+    name: &str,
+    theme: C::Theme,
+    setup: impl Fn(CustomElement<C>) + 'static,
+) -> Result<(), Error>
+where
+    C: Classes,
+{
+    let window = crate::backend::web::active_window().ok_or(Error::NoWindow)?;
+    let registry = window.custom_elements();
+
+    let setup = move |host: web_sys::HtmlElement, shadow_root: web_sys::ShadowRoot| {
+        let backend = WebSysBackend::try_new_in_shadow_root(&shadow_root)
+            .unwrap_or_else(|err| panic!("{err}"));
+        let provider = StyleProvider::new_with_backend(backend, theme.clone());
+        let classes = provider.add_classes::<C>();
+        setup(CustomElement {
+            host,
+            shadow_root,
+            provider: provider.clone(),
+            classes,
+        });
+        provider.flush();
+    };
+    let setup = Closure::<dyn Fn(web_sys::HtmlElement, web_sys::ShadowRoot)>::new(setup);
+    let class = custom_element_class(setup.as_ref().unchecked_ref());
+    // Elements can be created as long as the class is registered, which is forever
+    setup.forget();
+
+    registry
+        .define(name, &class)
+        .map_err(|err| Error::Dom(format!("{err:?}")))
+}
@@ -8,7 +8,7 @@ mod sample;
 
 fn main() {
     // launch the web app
-    dioxus_web::launch(App);
+    dioxus::launch(App);
 }
 
 make_styles! {
@@ -22,14 +22,14 @@ make_styles! {
     }
 }
 
-fn App(cx: Scope) -> Element {
-    use_style_provider_quickstart(cx, MuiTheme::default);
-    CssClasses::use_style(cx);
+fn App() -> Element {
+    use_style_provider_quickstart(MuiTheme::default);
+    CssClasses::use_style();
 
-    render!(
+    rsx! {
         mui_dioxus_demopanel::Panel {
             source: include_str!("sample.rs"),
             sample::Sample {}
         }
-    )
+    }
 }
@@ -1,3 +1,5 @@
+use std::rc::Rc;
+
 use css_in_rs::{make_styles, Classes};
 // toggle_hidden!
 use dioxus::prelude::*;
@@ -5,7 +7,7 @@ use mui_dioxus::theme::MuiTheme;
 
 fn main() {
     // launch the web app
-    dioxus_web::launch(App);
+    dioxus::launch(App);
 }
 // toggle_hidden!
 
@@ -48,32 +50,34 @@ fn shake(elem: &web_sys::Element, classes: &CssClasses) {
 }
 
 #[allow(non_snake_case)]
-pub fn Sample(cx: Scope) -> Element {
-    let classes = CssClasses::use_style(cx);
+pub fn Sample() -> Element {
+    let classes = Rc::new(CssClasses::use_style());
 
-    let elem_ref = use_ref(cx, || None::<web_sys::Element>);
+    let mut elem_ref = use_signal(|| None::<web_sys::Element>);
 
-    render!(
+    rsx! {
         div {
-            class: &classes.container as &str,
+            class: "{classes.container}",
             div {
-                class: &classes.item as &str,
-                onclick: move |_| shake(elem_ref.read().as_ref().unwrap(), classes),
+                class: "{classes.item}",
+                onclick: {
+                    let classes = classes.clone();
+                    move |_| shake(elem_ref.read().as_ref().unwrap(), &classes)
+                },
                 onmounted: move |ev| {
-                    let elem = ev.inner().get_raw_element().unwrap();
-                    let elem = elem.downcast_ref::<web_sys::Element>().unwrap();
+                    let elem = ev.downcast::<web_sys::Element>().unwrap();
                     elem_ref.set(Some(elem.clone()));
                 },
                 "Click me"
             }
         }
-    )
+    }
 }
 // toggle_hidden!
 
-fn App(cx: Scope) -> Element {
-    css_in_rs::use_style_provider_quickstart(cx, MuiTheme::default);
-    CssClasses::use_style(cx);
+fn App() -> Element {
+    css_in_rs::use_style_provider_quickstart(MuiTheme::default);
+    CssClasses::use_style();
 
-    render!(Sample {})
+    rsx! { Sample {} }
 }
@@ -5,7 +5,7 @@ use dioxus::prelude::*;
 
 fn main() {
     // launch the web app
-    dioxus_web::launch(App);
+    dioxus::launch(App);
 }
 
 make_styles! {
@@ -37,37 +37,37 @@ make_styles! {
     }
 }
 
-fn RedText(cx: Scope) -> Element {
-    let class1 = &SharedClasses::use_style(cx).left_margin;
-    let class2 = &RedClass::use_style(cx).text;
+fn RedText() -> Element {
+    let class1 = SharedClasses::use_style().left_margin;
+    let class2 = RedClass::use_style().text;
 
-    cx.render(rsx! {
+    rsx! {
         div {
             class: "{class1} {class2}",
             "This text is supposed to be red!",
         }
-    })
+    }
 }
 
-fn BlueText(cx: Scope) -> Element {
-    let class1 = &SharedClasses::use_style(cx).left_margin;
-    let class2 = &BlueClass::use_style(cx).text;
+fn BlueText() -> Element {
+    let class1 = SharedClasses::use_style().left_margin;
+    let class2 = BlueClass::use_style().text;
 
-    cx.render(rsx! {
+    rsx! {
         div {
             class: "{class1} {class2}",
             "This text is supposed to be blue!",
         }
-    })
+    }
 }
 
-fn App(cx: Scope) -> Element {
-    use_style_provider_quickstart(cx, || EmptyTheme);
+fn App() -> Element {
+    use_style_provider_quickstart(|| EmptyTheme);
 
-    cx.render(rsx! {
+    rsx! {
         RedText {}
         BlueText {}
         RedText {}
         BlueText {}
-    })
+    }
 }